@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use redis_cache::commands::{process_incr, process_set};
+use redis_cache::models::{KvStore, RedisData, RedisValue, Store, DEFAULT_SHARD_COUNT};
+use redis_cache::utils::encoder::{encode_bulk_bytes, encode_null_string};
+
+const THREADS: usize = 8;
+const READS_PER_THREAD: usize = 10_000;
+const WRITES_PER_THREAD: usize = 5_000;
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+// Every thread reads the same key, so they all land on one shard: the case
+// sharding can't help with and only shared read locks can
+fn hammer(kv_store: &KvStore, read: fn(&KvStore)) {
+    let handles: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let store = Arc::clone(kv_store);
+            thread::spawn(move || {
+                for _ in 0..READS_PER_THREAD {
+                    read(&store);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+fn encode_hot(value: Option<&RedisValue>) -> Vec<u8> {
+    match value.map(|value| &value.data) {
+        Some(RedisData::String(s)) => encode_bulk_bytes(s),
+        _ => encode_null_string(),
+    }
+}
+
+fn hot_key_reads(c: &mut Criterion) {
+    let kv_store: KvStore = Arc::new(Store::new());
+    process_set(&parts(&["SET", "hot", &"x".repeat(64)]), &kv_store).unwrap();
+
+    let mut group = c.benchmark_group("hot_key_reads");
+    group.throughput(Throughput::Elements((THREADS * READS_PER_THREAD) as u64));
+
+    // Both variants do the same work as GET; only the lock differs
+    group.bench_function("shared", |b| {
+        b.iter(|| hammer(&kv_store, |store| {
+            black_box(encode_hot(store.shard_read("hot").get("hot")));
+        }))
+    });
+    group.bench_function("exclusive", |b| {
+        b.iter(|| hammer(&kv_store, |store| {
+            black_box(encode_hot(store.shard("hot").get("hot")));
+        }))
+    });
+    group.finish();
+}
+
+// Hot key reads from tokio tasks, as connections make them, with one map under
+// std's RwLock (what each shard is) and one under tokio's. Nothing else differs,
+// so this is the cost of the lock alone. No handler holds a shard across an
+// .await, so std's lock is never held by a parked task
+fn hot_key_reads_from_tasks(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(THREADS).build().unwrap();
+    let hot_map = || HashMap::from([("hot".to_string(), RedisValue::new(RedisData::String(vec![b'x'; 64]), None))]);
+    let std_map = Arc::new(std::sync::RwLock::new(hot_map()));
+    let tokio_map = Arc::new(tokio::sync::RwLock::new(hot_map()));
+
+    let mut group = c.benchmark_group("hot_key_reads_from_tasks");
+    group.throughput(Throughput::Elements((THREADS * READS_PER_THREAD) as u64));
+
+    group.bench_function("std_rwlock", |b| {
+        b.iter(|| runtime.block_on(async {
+            let tasks: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let map = Arc::clone(&std_map);
+                    tokio::spawn(async move {
+                        for _ in 0..READS_PER_THREAD {
+                            black_box(encode_hot(map.read().unwrap().get("hot")));
+                        }
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap();
+            }
+        }))
+    });
+    group.bench_function("tokio_rwlock", |b| {
+        b.iter(|| runtime.block_on(async {
+            let tasks: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let map = Arc::clone(&tokio_map);
+                    tokio::spawn(async move {
+                        for _ in 0..READS_PER_THREAD {
+                            black_box(encode_hot(map.read().await.get("hot")));
+                        }
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap();
+            }
+        }))
+    });
+    group.finish();
+}
+
+// Each thread INCRs its own keys, so on the sharded store they mostly land on
+// different locks while on a single shard they all queue for the one
+fn independent_writes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("independent_writes");
+    group.throughput(Throughput::Elements((THREADS * WRITES_PER_THREAD) as u64));
+
+    for (name, shards) in [("sharded", DEFAULT_SHARD_COUNT), ("single_shard", 1)] {
+        let kv_store: KvStore = Arc::new(Store::with_shards(shards));
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let handles: Vec<_> = (0..THREADS)
+                    .map(|t| {
+                        let store = Arc::clone(&kv_store);
+                        thread::spawn(move || {
+                            for i in 0..WRITES_PER_THREAD {
+                                let key = format!("counter:{}:{}", t, i % 64);
+                                process_incr(&parts(&["INCR", &key]), &store).unwrap();
+                            }
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    handle.join().unwrap();
+                }
+            })
+        });
+        let latencies = write_latencies(&kv_store);
+        println!(
+            "independent_writes/{:<12} latency: p50 {:?}  p99 {:?}",
+            name, percentile(&latencies, 0.50), percentile(&latencies, 0.99)
+        );
+    }
+    group.finish();
+}
+
+// The same workload with every INCR timed on its own, since criterion only
+// reports the time for a whole run. Sorted, for percentile
+fn write_latencies(kv_store: &KvStore) -> Vec<Duration> {
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let store = Arc::clone(kv_store);
+            thread::spawn(move || {
+                (0..WRITES_PER_THREAD)
+                    .map(|i| {
+                        let key = format!("counter:{}:{}", t, i % 64);
+                        let started = Instant::now();
+                        process_incr(&parts(&["INCR", &key]), &store).unwrap();
+                        started.elapsed()
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let mut latencies: Vec<Duration> = handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect();
+    latencies.sort();
+    latencies
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+criterion_group!(benches, hot_key_reads, hot_key_reads_from_tasks, independent_writes);
+criterion_main!(benches);
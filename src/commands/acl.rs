@@ -0,0 +1,78 @@
+use crate::models::{Acl, AclRule, ClientState, RespResult};
+use crate::utils::encoder::*;
+use crate::utils::keys::command_keys;
+
+pub fn process_acl(
+    parts: &[String],
+    client: &ClientState,
+    acl: &Acl
+) -> RespResult {
+    // parts[0] = "ACL", parts[1] = subcommand, parts[2..] = arguments
+    if parts.len() < 2 {
+        return Err("Malformed ACL".to_string());
+    }
+    match parts[1].to_uppercase().as_str() {
+        "SETUSER" => acl_setuser(&parts[2..], acl),
+        "LIST" if parts.len() == 2 => acl_list(acl),
+        "WHOAMI" if parts.len() == 2 => Ok(encode_bulk_string(&client.username)),
+        "LIST" | "WHOAMI" => Ok(encode_error_string(&format!(
+            "ERR wrong number of arguments for 'acl|{}' command", parts[1].to_lowercase()
+        ))),
+        _ => Ok(encode_error_string(&format!(
+            "ERR unknown subcommand '{}'. Try ACL HELP.", parts[1]
+        ))),
+    }
+}
+
+// ACL SETUSER username [rule ...]: creates the user if needed, then applies every rule or none
+fn acl_setuser(
+    args: &[String],
+    acl: &Acl
+) -> RespResult {
+    let Some((username, rules)) = args.split_first() else {
+        return Ok(encode_error_string("ERR wrong number of arguments for 'acl|setuser' command"));
+    };
+    // Applied to a copy first so one bad rule leaves the user unchanged
+    let applied = acl.update_user(username, |user| {
+        rules.iter().try_for_each(|rule| user.apply(rule))
+    });
+    match applied {
+        Ok(()) => Ok(encode_simple_string("OK")),
+        Err(e) => Ok(encode_error_string(&e)),
+    }
+}
+
+// ACL LIST: one "user <name> <rules>" line per user, by name
+fn acl_list(acl: &Acl) -> RespResult {
+    let users = acl.users();
+    let mut users: Vec<(&String, &AclRule)> = users.iter().collect();
+    users.sort_by_key(|(name, _)| *name);
+    let lines: Vec<String> = users.into_iter()
+        .map(|(name, user)| format!("user {} {}", name, user.describe()))
+        .collect();
+    Ok(encode_array(&lines))
+}
+
+/// Checks the connection's ACL user may run `command` on the keys in `parts`,
+/// returning the NOPERM error to reply with if not.
+pub fn check_permissions(
+    command: &str,
+    parts: &[String],
+    client: &ClientState,
+    acl: &Acl
+) -> Result<(), Vec<u8>> {
+    if acl.is_unrestricted() {
+        return Ok(());
+    }
+    let users = acl.users();
+    // A user deleted or disabled since this connection logged in keeps nothing
+    let Some(user) = users.get(&client.username).filter(|user| user.enabled && user.can_run(command)) else {
+        return Err(encode_error_string(&format!(
+            "NOPERM User {} has no permissions to run the '{}' command", client.username, command.to_lowercase()
+        )));
+    };
+    if !command_keys(command, parts).into_iter().all(|key| user.can_access(key)) {
+        return Err(encode_error_string("NOPERM No permissions to access a key"));
+    }
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use std::time::{Duration, Instant};
+
+use crate::models::{ClientInfo, ClientPause, ClientState, Clients, ReplyMode, RespResult};
+use crate::utils::encoder::*;
+
+pub fn process_client(
+    parts: &[String],
+    client: &mut ClientState,
+    clients: &Clients,
+    client_pause: &ClientPause
+) -> RespResult {
+    // parts[0] = "CLIENT", parts[1] = subcommand, parts[2..] = arguments
+    if parts.len() < 2 {
+        return Err("Malformed CLIENT".to_string());
+    }
+    let subcommand = parts[1].to_uppercase();
+    match (subcommand.as_str(), parts.len()) {
+        ("SETNAME", 3) => client_setname(&parts[2], client),
+        ("GETNAME", 2) => Ok(client.name.as_deref().map_or_else(encode_null_string, encode_bulk_string)),
+        ("ID", 2) => Ok(encode_integer(client.id as i64)),
+        ("LIST", _) => client_list(&parts[2..], clients),
+        ("KILL", 3) => client_kill_addr(&parts[2], clients),
+        ("KILL", len) if len >= 4 => client_kill_filters(&parts[2..], client, clients),
+        ("NO-EVICT", 3) => set_flag(&parts[2], "on", "off", &mut client.no_evict),
+        ("NO-TOUCH", 3) => set_flag(&parts[2], "on", "off", &mut client.no_touch),
+        ("PAUSE", 3 | 4) => client_pause_for(&parts[2..], client_pause),
+        ("UNPAUSE", 2) => {
+            client_pause.unpause();
+            Ok(encode_simple_string("OK"))
+        },
+        ("REPLY", 3) => client_reply(&parts[2], client),
+        ("CACHING", 3) => set_flag(&parts[2], "yes", "no", &mut client.caching),
+        ("SETNAME" | "GETNAME" | "ID" | "KILL" | "NO-EVICT" | "NO-TOUCH" | "PAUSE" | "UNPAUSE" | "REPLY" | "CACHING", _) => Ok(encode_error_string(&format!(
+            "ERR wrong number of arguments for 'client|{}' command", subcommand.to_lowercase()
+        ))),
+        _ => Ok(encode_error_string(&format!(
+            "ERR unknown subcommand '{}'. Try CLIENT HELP.", parts[1]
+        ))),
+    }
+}
+
+// CLIENT SETNAME name: an empty name clears it
+fn client_setname(name: &str, client: &mut ClientState) -> RespResult {
+    if !is_valid_client_name(name) {
+        return Ok(encode_error_string("ERR Client names cannot contain spaces, newlines or special characters."));
+    }
+    client.name = Some(name.to_string()).filter(|name| !name.is_empty());
+    Ok(encode_simple_string("OK"))
+}
+
+// Sets `flag` from a yes/no style argument, case-insensitively
+fn set_flag(value: &str, on: &str, off: &str, flag: &mut bool) -> RespResult {
+    if value.eq_ignore_ascii_case(on) {
+        *flag = true;
+    } else if value.eq_ignore_ascii_case(off) {
+        *flag = false;
+    } else {
+        return Ok(encode_error_string("ERR syntax error"));
+    }
+    Ok(encode_simple_string("OK"))
+}
+
+// CLIENT PAUSE timeout [WRITE | ALL]: holds back every client's commands, or
+// only their writes, for `timeout` milliseconds
+fn client_pause_for(args: &[String], client_pause: &ClientPause) -> RespResult {
+    let Ok(timeout) = args[0].parse::<u64>() else {
+        return Ok(encode_error_string("ERR timeout is not an integer or out of range"));
+    };
+    let writes_only = match args.get(1).map(|mode| mode.to_uppercase()).as_deref() {
+        None | Some("ALL") => false,
+        Some("WRITE") => true,
+        Some(_) => return Ok(encode_error_string("ERR syntax error")),
+    };
+    client_pause.pause(Duration::from_millis(timeout), writes_only);
+    Ok(encode_simple_string("OK"))
+}
+
+// CLIENT REPLY ON | OFF | SKIP: only ON is answered, the others take effect silently
+fn client_reply(mode: &str, client: &mut ClientState) -> RespResult {
+    match mode.to_uppercase().as_str() {
+        "ON" => {
+            client.reply_mode = ReplyMode::On;
+            Ok(encode_simple_string("OK"))
+        },
+        "OFF" => {
+            client.reply_mode = ReplyMode::Off;
+            Ok(Vec::new())
+        },
+        // Meaningless while replies are already off
+        "SKIP" => {
+            if client.reply_mode == ReplyMode::On {
+                client.reply_mode = ReplyMode::Skip;
+            }
+            Ok(Vec::new())
+        },
+        _ => Ok(encode_error_string("ERR syntax error")),
+    }
+}
+
+// CLIENT LIST [ID id [id ...]]: one line per connection, oldest first
+fn client_list(args: &[String], clients: &Clients) -> RespResult {
+    let ids: Option<Vec<u64>> = match args.split_first() {
+        None => None,
+        Some((filter, ids)) if filter.eq_ignore_ascii_case("ID") && !ids.is_empty() => {
+            match ids.iter().map(|id| id.parse::<u64>()).collect() {
+                Ok(ids) => Some(ids),
+                Err(_) => return Ok(encode_error_string("ERR Invalid client ID")),
+            }
+        },
+        Some(_) => return Ok(encode_error_string("ERR syntax error")),
+    };
+    let clients = clients.lock().unwrap();
+    let mut listed: Vec<&ClientInfo> = clients.values()
+        .filter(|info| ids.as_ref().is_none_or(|ids| ids.contains(&info.id)))
+        .collect();
+    listed.sort_by_key(|info| info.id);
+
+    let now = Instant::now();
+    let lines: String = listed.into_iter().map(|info| info.describe(now) + "\n").collect();
+    Ok(encode_bulk_string(&lines))
+}
+
+// Tells a connection, already taken out of the registry, to close
+fn kill(info: ClientInfo) {
+    info.kill.notify_one();
+}
+
+// CLIENT KILL ip:port: the old form, which replies OK or an error
+fn client_kill_addr(addr: &str, clients: &Clients) -> RespResult {
+    let mut clients = clients.lock().unwrap();
+    let Some(id) = clients.values().find(|info| info.addr == addr).map(|info| info.id) else {
+        return Ok(encode_error_string("ERR No such client"));
+    };
+    if let Some(info) = clients.remove(&id) {
+        kill(info);
+    }
+    Ok(encode_simple_string("OK"))
+}
+
+// CLIENT KILL <filter> <value> ...: every connection matching all of ID, ADDR
+// and USER, never the caller unless SKIPME no. Replies with how many were closed
+fn client_kill_filters(args: &[String], client: &ClientState, clients: &Clients) -> RespResult {
+    if !args.len().is_multiple_of(2) {
+        return Ok(encode_error_string("ERR syntax error"));
+    }
+    let mut id = None;
+    let mut addr = None;
+    let mut user = None;
+    let mut skip_me = true;
+    for pair in args.chunks(2) {
+        let value = &pair[1];
+        match pair[0].to_uppercase().as_str() {
+            "ID" => match value.parse::<u64>() {
+                Ok(parsed) if parsed > 0 => id = Some(parsed),
+                _ => return Ok(encode_error_string("ERR client-id should be greater than 0")),
+            },
+            "ADDR" => addr = Some(value.as_str()),
+            "USER" => user = Some(value.as_str()),
+            "SKIPME" => match value.to_lowercase().as_str() {
+                "yes" => skip_me = true,
+                "no" => skip_me = false,
+                _ => return Ok(encode_error_string("ERR syntax error")),
+            },
+            _ => return Ok(encode_error_string("ERR syntax error")),
+        }
+    }
+
+    let mut clients = clients.lock().unwrap();
+    let matching: Vec<u64> = clients.values()
+        .filter(|info| id.is_none_or(|id| info.id == id))
+        .filter(|info| addr.is_none_or(|addr| info.addr == addr))
+        .filter(|info| user.is_none_or(|user| info.status.lock().unwrap().username == user))
+        .filter(|info| !(skip_me && info.id == client.id))
+        .map(|info| info.id)
+        .collect();
+    for id in &matching {
+        if let Some(info) = clients.remove(id) {
+            kill(info);
+        }
+    }
+    Ok(encode_integer(matching.len() as i64))
+}
+
+/// Whether `name` can be given to a connection with CLIENT SETNAME or HELLO SETNAME.
+pub fn is_valid_client_name(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_graphic())
+}
@@ -0,0 +1,133 @@
+use std::sync::LazyLock;
+
+use crate::constants::{BLOCKING_COMMANDS, COMMAND_ARITY, COMMAND_DOCS, DENY_OOM_COMMANDS, WRITE_COMMANDS};
+use crate::models::RespResult;
+use crate::utils::encoder::*;
+use crate::utils::keys::{command_keys, key_spec, KeySpec};
+
+/// What COMMAND reports about one command.
+pub struct CommandMeta {
+    pub name: &'static str,
+    pub arity: i64,
+    pub flags: Vec<&'static str>,
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+    pub group: &'static str,
+    pub summary: &'static str,
+}
+
+/// Every supported command, in COMMAND_ARITY order. Flags and key positions
+/// come from the tables the executor itself uses, so they can't drift apart.
+pub static COMMAND_TABLE: LazyLock<Vec<CommandMeta>> = LazyLock::new(|| {
+    COMMAND_ARITY.iter()
+        .map(|&(name, arity)| {
+            let spec = key_spec(name);
+            let (first_key, last_key, step) = spec.legacy_range();
+            let (group, summary) = COMMAND_DOCS.iter()
+                .find(|(doc_name, _, _)| *doc_name == name)
+                .map_or(("generic", ""), |&(_, group, summary)| (group, summary));
+
+            let mut flags = Vec::new();
+            if WRITE_COMMANDS.contains(&name) {
+                flags.push("write");
+            } else if spec != KeySpec::None {
+                flags.push("readonly");
+            }
+            if DENY_OOM_COMMANDS.contains(&name) {
+                flags.push("denyoom");
+            }
+            if BLOCKING_COMMANDS.contains(&name) {
+                flags.push("blocking");
+            }
+            if spec.is_movable() {
+                flags.push("movablekeys");
+            }
+            CommandMeta { name, arity, flags, first_key, last_key, step, group, summary }
+        })
+        .collect()
+});
+
+fn find_command(name: &str) -> Option<&'static CommandMeta> {
+    COMMAND_TABLE.iter().find(|meta| meta.name.eq_ignore_ascii_case(name))
+}
+
+pub fn process_command(parts: &[String]) -> RespResult {
+    // parts[0] = "COMMAND", [parts[1] = subcommand, parts[2..] = arguments]
+    let Some(subcommand) = parts.get(1) else {
+        return Ok(encode_raw_array(COMMAND_TABLE.iter().map(encode_info).collect()));
+    };
+    let args = &parts[2..];
+    match subcommand.to_uppercase().as_str() {
+        "COUNT" if args.is_empty() => Ok(encode_integer(COMMAND_TABLE.len() as i64)),
+        "LIST" if args.is_empty() => {
+            let names: Vec<String> = COMMAND_TABLE.iter().map(|meta| meta.name.to_lowercase()).collect();
+            Ok(encode_array(&names))
+        },
+        "INFO" => Ok(command_info(args)),
+        "DOCS" => Ok(command_docs(args)),
+        "GETKEYS" => Ok(command_getkeys(args)),
+        "COUNT" | "LIST" => Ok(encode_error_string(&format!(
+            "ERR wrong number of arguments for 'command|{}' command", subcommand.to_lowercase()
+        ))),
+        _ => Ok(encode_error_string(&format!(
+            "ERR unknown subcommand '{}'. Try COMMAND HELP.", subcommand
+        ))),
+    }
+}
+
+// COMMAND INFO [name ...]: every command if none are named, a null for each unknown one
+fn command_info(names: &[String]) -> Vec<u8> {
+    if names.is_empty() {
+        return encode_raw_array(COMMAND_TABLE.iter().map(encode_info).collect());
+    }
+    encode_raw_array(names.iter()
+        .map(|name| find_command(name).map_or_else(encode_null_array, encode_info))
+        .collect())
+}
+
+// [name, arity, [flags], first key, last key, step]
+fn encode_info(meta: &CommandMeta) -> Vec<u8> {
+    encode_raw_array(vec![
+        encode_bulk_string(&meta.name.to_lowercase()),
+        encode_integer(meta.arity),
+        encode_raw_array(meta.flags.iter().map(|flag| encode_simple_string(flag)).collect()),
+        encode_integer(meta.first_key),
+        encode_integer(meta.last_key),
+        encode_integer(meta.step),
+    ])
+}
+
+// COMMAND DOCS [name ...]: name, then its summary and group, for each known command
+fn command_docs(names: &[String]) -> Vec<u8> {
+    let metas: Vec<&CommandMeta> = if names.is_empty() {
+        COMMAND_TABLE.iter().collect()
+    } else {
+        names.iter().filter_map(|name| find_command(name)).collect()
+    };
+    encode_raw_array(metas.into_iter()
+        .flat_map(|meta| [
+            encode_bulk_string(&meta.name.to_lowercase()),
+            encode_array(&[
+                "summary".to_string(), meta.summary.to_string(),
+                "group".to_string(), meta.group.to_string(),
+            ]),
+        ])
+        .collect())
+}
+
+// COMMAND GETKEYS command [arg ...]: the keys that command would touch
+fn command_getkeys(command: &[String]) -> Vec<u8> {
+    let Some(meta) = command.first().and_then(|name| find_command(name)) else {
+        return encode_error_string("ERR Invalid command specified");
+    };
+    let argc = command.len() as i64;
+    if (meta.arity >= 0 && argc != meta.arity) || (meta.arity < 0 && argc < -meta.arity) {
+        return encode_error_string("ERR Invalid number of arguments specified for command");
+    }
+    let keys: Vec<String> = command_keys(meta.name, command).into_iter().map(String::from).collect();
+    if keys.is_empty() {
+        return encode_error_string("ERR The command has no key arguments");
+    }
+    encode_array(&keys)
+}
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+
+use crate::models::{Acl, RespResult, ServerConfig};
+use crate::utils::encoder::*;
+use crate::utils::glob::glob_match;
+
+pub fn process_config(
+    parts: &[String],
+    server_config: &Arc<Mutex<ServerConfig>>,
+    acl: &Acl
+) -> RespResult {
+    // parts[0] = "CONFIG", parts[1] = subcommand, parts[2..] = arguments
+    if parts.len() < 2 {
+        return Err("Malformed CONFIG".to_string());
+    }
+    match parts[1].to_uppercase().as_str() {
+        "GET" => config_get(&parts[2..], server_config),
+        "SET" => config_set(&parts[2..], server_config, acl),
+        // There are no stats to reset yet
+        "RESETSTAT" if parts.len() == 2 => Ok(encode_simple_string("OK")),
+        // Settings only come from the command line, so there's no file to rewrite
+        "REWRITE" if parts.len() == 2 => Ok(encode_error_string("ERR The server is running without a config file")),
+        "RESETSTAT" | "REWRITE" => Ok(encode_error_string(&format!(
+            "ERR wrong number of arguments for 'config|{}' command", parts[1].to_lowercase()
+        ))),
+        _ => Ok(encode_error_string(&format!(
+            "ERR unknown subcommand '{}'. Try CONFIG HELP.", parts[1]
+        ))),
+    }
+}
+
+// CONFIG GET pattern [pattern ...]: flat name/value pairs for every matching parameter
+fn config_get(
+    patterns: &[String],
+    server_config: &Arc<Mutex<ServerConfig>>
+) -> RespResult {
+    if patterns.is_empty() {
+        return Ok(encode_error_string("ERR wrong number of arguments for 'config|get' command"));
+    }
+    let config = server_config.lock().unwrap();
+    let mut response = Vec::new();
+    for (name, value) in config.params() {
+        if patterns.iter().any(|pattern| glob_match(&pattern.to_lowercase(), name)) {
+            response.push(name.to_string());
+            response.push(value);
+        }
+    }
+    Ok(encode_array(&response))
+}
+
+// CONFIG SET param value [param value ...]: all or nothing
+fn config_set(
+    args: &[String],
+    server_config: &Arc<Mutex<ServerConfig>>,
+    acl: &Acl
+) -> RespResult {
+    if args.is_empty() || !args.len().is_multiple_of(2) {
+        return Ok(encode_error_string("ERR wrong number of arguments for 'config|set' command"));
+    }
+    let mut config = server_config.lock().unwrap();
+    // Applied to a copy first so one bad pair leaves everything unchanged
+    let mut updated = config.clone();
+    for pair in args.chunks_exact(2) {
+        if let Err(e) = updated.set(&pair[0], &pair[1]) {
+            return Ok(encode_error_string(&e));
+        }
+    }
+    // requirepass is the default user's password, which AUTH checks against the ACL
+    if updated.requirepass != config.requirepass {
+        acl.set_requirepass(updated.requirepass.clone());
+    }
+    log::set_max_level(updated.log_level());
+    *config = updated;
+    Ok(encode_simple_string("OK"))
+}
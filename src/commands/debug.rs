@@ -0,0 +1,117 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::constants::BLOCKING_COMMANDS;
+use crate::models::{ClientState, RespResult, ServerConfig, ServerState, Slowlog, SlowlogEntry};
+use crate::utils::encoder::*;
+
+pub async fn process_debug(
+    parts: &[String],
+    server_config: &Arc<Mutex<ServerConfig>>
+) -> RespResult {
+    // parts[0] = "DEBUG", parts[1] = subcommand, parts[2..] = arguments
+    if parts.len() < 2 {
+        return Err("Malformed DEBUG".to_string());
+    }
+    match (parts[1].to_uppercase().as_str(), parts.len()) {
+        // Only this connection waits; Redis would stall every client, but test
+        // suites use it to hold a connection busy, which this still does
+        ("SLEEP", 3) => {
+            let seconds = match parts[2].parse::<f64>() {
+                Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => seconds,
+                _ => return Ok(encode_error_string("ERR value is not a valid float")),
+            };
+            tokio::time::sleep(Duration::from_secs_f64(seconds)).await;
+            Ok(encode_simple_string("OK"))
+        },
+        ("SET-ACTIVE-EXPIRE", 3) => {
+            let enabled = match parts[2].as_str() {
+                "0" => false,
+                "1" => true,
+                _ => return Ok(encode_error_string("ERR value is out of range, must be 0 or 1")),
+            };
+            server_config.lock().unwrap().active_expire = enabled;
+            Ok(encode_simple_string("OK"))
+        },
+        // Nothing to dump or tune here, but harnesses expect them to succeed
+        ("JMAP", 2) | ("QUICKLIST-PACKED-THRESHOLD", 3) => Ok(encode_simple_string("OK")),
+        _ => Ok(encode_error_string(&format!(
+            "ERR unknown subcommand or wrong number of arguments for '{}'. Try DEBUG HELP.", parts[1]
+        ))),
+    }
+}
+
+pub fn process_slowlog(parts: &[String], slowlog: &Slowlog) -> RespResult {
+    // parts[0] = "SLOWLOG", parts[1] = subcommand, [parts[2] = count]
+    if parts.len() < 2 {
+        return Err("Malformed SLOWLOG".to_string());
+    }
+    match (parts[1].to_uppercase().as_str(), parts.len()) {
+        ("GET", 2 | 3) => slowlog_get(parts.get(2), slowlog),
+        ("LEN", 2) => Ok(encode_integer(slowlog.lock().unwrap().entries.len() as i64)),
+        ("RESET", 2) => {
+            slowlog.lock().unwrap().entries.clear();
+            Ok(encode_simple_string("OK"))
+        },
+        ("GET" | "LEN" | "RESET", _) => Ok(encode_error_string(&format!(
+            "ERR wrong number of arguments for 'slowlog|{}' command", parts[1].to_lowercase()
+        ))),
+        _ => Ok(encode_error_string(&format!(
+            "ERR unknown subcommand '{}'. Try SLOWLOG HELP.", parts[1]
+        ))),
+    }
+}
+
+// SLOWLOG GET [count]: the newest `count` entries (10 by default, -1 for all)
+fn slowlog_get(count: Option<&String>, slowlog: &Slowlog) -> RespResult {
+    let count = match count.map(|raw| raw.parse::<i64>()) {
+        None => 10,
+        Some(Ok(count)) if count < -1 => {
+            return Ok(encode_error_string("ERR count should be greater than or equal to -1"));
+        },
+        Some(Ok(-1)) => usize::MAX,
+        Some(Ok(count)) => count as usize,
+        Some(Err(_)) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+    };
+    let slowlog = slowlog.lock().unwrap();
+    Ok(encode_raw_array(slowlog.entries.iter().take(count).map(encode_slowlog_entry).collect()))
+}
+
+// [id, timestamp, microseconds, [args], client address, client name]
+fn encode_slowlog_entry(entry: &SlowlogEntry) -> Vec<u8> {
+    encode_raw_array(vec![
+        encode_integer(entry.id as i64),
+        encode_integer(entry.timestamp as i64),
+        encode_integer(entry.duration.as_micros() as i64),
+        encode_array(&entry.args),
+        encode_bulk_string(&entry.client_addr),
+        encode_bulk_string(&entry.client_name),
+    ])
+}
+
+/// Logs a command that ran for `elapsed` if that's at least slowlog-log-slower-than.
+/// EXEC is left to the commands it ran, blocking commands would log their wait
+/// rather than their work, and AUTH and friends could leak a password.
+pub fn log_if_slow(
+    command: &str,
+    parts: &[String],
+    elapsed: Duration,
+    client: &ClientState,
+    state: &ServerState
+) {
+    if matches!(command, "EXEC" | "AUTH" | "HELLO" | "ACL") || BLOCKING_COMMANDS.contains(&command) {
+        return;
+    }
+    let (threshold, max_len) = {
+        let config = state.server_config.lock().unwrap();
+        (config.slowlog_log_slower_than, config.slowlog_max_len)
+    };
+    if threshold < 0 || elapsed.as_micros() < threshold as u128 {
+        return;
+    }
+    let client_addr = state.clients.lock().unwrap()
+        .get(&client.id)
+        .map_or_else(String::new, |info| info.addr.clone());
+    let client_name = client.name.clone().unwrap_or_default();
+    state.slowlog.lock().unwrap().push(parts, elapsed, client_addr, client_name, max_len);
+}
@@ -1,12 +1,57 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use crate::models::{RedisData, RedisValue, RespResult};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::constants::{OBJECT_HELP, SERVER_VERSION};
+use crate::models::{Acl, ClientState, Databases, ExpireOptions, ExpireUnit, KvStore, RedisValue, RespResult};
+use crate::utils::async_helpers::free_in_background;
 use crate::utils::encoder::*;
+use crate::utils::glob::glob_match;
+use crate::utils::scan::{parse_scan_args, ScanArgs};
 
-pub fn process_ping() -> RespResult {
-    Ok(encode_simple_string("PONG"))
+pub fn process_ping(
+    parts: &[String],
+    client: &ClientState
+) -> RespResult {
+    // parts[0] = "PING", [parts[1] = message]
+    if parts.len() > 2 {
+        return Err("Malformed PING".to_string());
+    }
+    // A RESP2 subscriber can only read push-shaped replies, so it gets the
+    // pong as a two-element array like a channel message
+    if client.subscription_count() > 0 && client.protocol == 2 {
+        let message = parts.get(1).map(String::as_str).unwrap_or("");
+        return Ok(encode_array(&["pong".to_string(), message.to_string()]));
+    }
+    match parts.get(1) {
+        Some(message) => Ok(encode_bulk_string(message)),
+        None => Ok(encode_simple_string("PONG")),
+    }
+}
+
+pub fn process_auth(
+    parts: &[String],
+    client: &mut ClientState,
+    acl: &Acl
+) -> RespResult {
+    // parts[0] = "AUTH", [parts[1] = username], parts[-1] = password
+    let (username, password) = match parts.len() {
+        2 => ("default", &parts[1]),
+        3 => (parts[1].as_str(), &parts[2]),
+        _ => return Err("Malformed AUTH".to_string()),
+    };
+    if parts.len() == 2 && acl.users().get("default").is_some_and(|default| default.nopass) {
+        return Ok(encode_error_string(
+            "ERR AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?"
+        ));
+    }
+    if !acl.check_password(username, password) {
+        return Ok(encode_error_string("WRONGPASS invalid username-password pair or user is disabled."));
+    }
+    client.authenticated = true;
+    client.username = username.to_string();
+    Ok(encode_simple_string("OK"))
 }
 
 pub fn process_echo(parts: &[String]) -> RespResult {
@@ -17,36 +62,564 @@ pub fn process_echo(parts: &[String]) -> RespResult {
     Ok(encode_bulk_string(&parts[1]))
 }
 
+// QUIT: anything after it is ignored
+pub fn process_quit(client: &mut ClientState) -> RespResult {
+    client.close_after_reply = true;
+    Ok(encode_simple_string("OK"))
+}
+
+// LOLWUT draws SCHOTTER_ROWS rows of SCHOTTER_COLUMNS squares, each
+// SCHOTTER_CELL_WIDTH characters wide
+const SCHOTTER_ROWS: usize = 12;
+const SCHOTTER_COLUMNS: usize = 8;
+const SCHOTTER_CELL_WIDTH: usize = 4;
+// What a square looks like once it has come loose
+const TUMBLED_SQUARES: &[&str] = &["<>", "][", "/\\", "\\/", "()"];
+
+pub fn process_lolwut(parts: &[String]) -> RespResult {
+    // parts[0] = "LOLWUT", [parts[1] = "VERSION", parts[2] = version]
+    let version = match parts.len() {
+        1 => 5,
+        3 if parts[1].eq_ignore_ascii_case("VERSION") => match parts[2].parse::<i64>() {
+            Ok(version) => version,
+            Err(_) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+        },
+        _ => return Ok(encode_error_string("ERR syntax error")),
+    };
+    // Like Redis, only some versions come with art; the rest just say which version this is
+    let mut output = String::new();
+    if version == 5 {
+        output.push_str(&schotter());
+        output.push_str("\nGeorg Nees - schotter, plotter on paper, 1968. ");
+    }
+    output.push_str(&format!("Redis ver. {}\n", SERVER_VERSION));
+    Ok(encode_bulk_string(&output))
+}
+
+// After Georg Nees' Schotter: a grid of squares, neat at the top and coming
+// loose further down, each row more likely to have tumbled and shifted squares
+fn schotter() -> String {
+    let mut rng = rand::thread_rng();
+    let mut art = String::new();
+    for row in 0..SCHOTTER_ROWS {
+        let disorder = row as f64 / (SCHOTTER_ROWS - 1) as f64;
+        let mut line = String::new();
+        for _ in 0..SCHOTTER_COLUMNS {
+            let (square, offset) = if rng.gen_bool(disorder) {
+                let square = *TUMBLED_SQUARES.choose(&mut rng).unwrap();
+                (square, rng.gen_range(0..=SCHOTTER_CELL_WIDTH - square.len()))
+            } else {
+                ("[]", 1)
+            };
+            line.push_str(&" ".repeat(offset));
+            line.push_str(square);
+            line.push_str(&" ".repeat(SCHOTTER_CELL_WIDTH - offset - square.len()));
+        }
+        art.push_str(line.trim_end());
+        art.push('\n');
+    }
+    art
+}
+
 pub fn process_type(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    kv_store: &KvStore
 ) -> RespResult {
     // parts[0] = "TYPE", parts[1] = key
     if parts.len() < 2 {
         return Err("Malformed TYPE".to_string());
     }
-    let key = &parts[1];
-    let mut map = kv_store.lock().unwrap();
+    let type_name = kv_store.read_live(&parts[1], |value| value.map_or("none", |value| value.data.type_name()));
+    Ok(encode_simple_string(type_name))
+}
+
+/// KEYS pattern: every live key matching the glob `pattern`.
+///
+/// This walks the whole keyspace with every shard locked, so nothing else can
+/// touch the store until it finishes. Fine for debugging and small datasets,
+/// but prefer SCAN-style iteration on anything large.
+pub fn process_keys(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "KEYS", parts[1] = pattern
+    if parts.len() != 2 {
+        return Err("Malformed KEYS".to_string());
+    }
+    let pattern = &parts[1];
+    let now = Instant::now();
+
+    let mut map = kv_store.lock_all();
+    let mut keys = Vec::new();
+    let mut expired = Vec::new();
+    for (key, value) in map.iter() {
+        if value.is_expired(now) {
+            expired.push(key.clone());
+        } else if glob_match(pattern, key) {
+            keys.push(key.clone());
+        }
+    }
+    // Already holding every shard, so drop the dead keys we walked past
+    for key in &expired {
+        map.remove(key);
+    }
+    Ok(encode_array(&keys))
+}
 
-    let is_expired = match map.get(key) {
-        Some(redis_value) => {
-            match redis_value.expires_at {
-                Some(expiry) => Instant::now() > expiry,
-                None => false
+pub fn process_randomkey(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "RANDOMKEY"
+    if parts.len() != 1 {
+        return Err("Malformed RANDOMKEY".to_string());
+    }
+    let now = Instant::now();
+    let mut map = kv_store.lock_all();
+    let (live, expired): (Vec<_>, Vec<_>) = map.iter()
+        .map(|(key, value)| (key.clone(), value.is_expired(now)))
+        .partition(|(_, is_expired)| !is_expired);
+    // Expired keys found on the way are dropped, as any other read would
+    for (key, _) in &expired {
+        map.remove(key);
+    }
+
+    match live.choose(&mut rand::thread_rng()) {
+        Some((key, _)) => Ok(encode_bulk_string(key)),
+        None => Ok(encode_null_string()),
+    }
+}
+
+pub fn process_del(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "DEL", parts[1..] = keys
+    if parts.len() < 2 {
+        return Err("Malformed DEL".to_string());
+    }
+    let mut map = kv_store.lock_keys(&parts[1..]);
+    let deleted = parts[1..].iter()
+        .filter_map(|key| map.remove(key))
+        .count();
+    Ok(encode_integer(deleted as i64))
+}
+
+pub fn process_unlink(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "UNLINK", parts[1..] = keys
+    if parts.len() < 2 {
+        return Err("Malformed UNLINK".to_string());
+    }
+    let removed: Vec<RedisValue> = {
+        let mut map = kv_store.lock_keys(&parts[1..]);
+        parts[1..].iter().filter_map(|key| map.remove(key)).collect()
+    };
+    let unlinked = removed.len();
+
+    // The keys are already gone; only freeing the values is left
+    if !removed.is_empty() {
+        free_in_background(removed);
+    }
+    Ok(encode_integer(unlinked as i64))
+}
+
+pub fn process_exists(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "EXISTS", parts[1..] = keys
+    if parts.len() < 2 {
+        return Err("Malformed EXISTS".to_string());
+    }
+    Ok(encode_integer(count_existing(&parts[1..], kv_store, false)))
+}
+
+pub fn process_touch(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "TOUCH", parts[1..] = keys
+    if parts.len() < 2 {
+        return Err("Malformed TOUCH".to_string());
+    }
+    Ok(encode_integer(count_existing(&parts[1..], kv_store, true)))
+}
+
+// Counts the keys that exist and, for TOUCH, resets their idle time.
+// Repeated keys are counted every time they appear, as Redis does
+fn count_existing(keys: &[String], kv_store: &KvStore, mark_accessed: bool) -> i64 {
+    let map = kv_store.lock_keys(keys);
+    let mut count = 0;
+    for value in keys.iter().filter_map(|key| map.get(key)) {
+        if mark_accessed {
+            value.mark_accessed();
+        }
+        count += 1;
+    }
+    count
+}
+
+pub fn process_scan(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SCAN", parts[1] = cursor, [MATCH pattern] [COUNT count] [TYPE type]
+    if parts.len() < 2 {
+        return Err("Malformed SCAN".to_string());
+    }
+    let (scan, type_filter) = match parse_keyspace_scan_args(&parts[1..]) {
+        Ok(args) => args,
+        Err(reply) => return Ok(reply),
+    };
+    // The cursor packs a shard and a position in that shard's own order, so
+    // each call only read-locks the shards it walks, one at a time. A shard
+    // that grows or shrinks between calls may reorder, so a key can be
+    // returned twice or, if added or removed mid-scan, not at all, which
+    // SCAN's guarantees allow for.
+    let shard_count = kv_store.shard_count();
+    let mut shard = (scan.cursor % shard_count as u64) as usize;
+    let mut position = (scan.cursor / shard_count as u64) as usize;
+    let mut visited = 0;
+    let mut keys = Vec::new();
+    while shard < shard_count && visited < scan.count {
+        let map = kv_store.shard_read_at(shard);
+        // Like Redis, MATCH and TYPE filter a page after it's taken, so a page can come back short or empty
+        for (key, value) in map.iter().skip(position).take(scan.count - visited) {
+            visited += 1;
+            position += 1;
+            if let Some(value) = value
+                && scan.matches(key)
+                && type_filter.as_ref().is_none_or(|wanted| wanted == value.data.type_name())
+            {
+                keys.push(key.clone());
             }
-        },
-        None => return Ok(encode_simple_string("none")),
+        }
+        if position >= map.len() {
+            shard += 1;
+            position = 0;
+        }
+    }
+    let next_cursor = if shard == shard_count { 0 } else { (position * shard_count + shard) as u64 };
+    Ok(encode_raw_array(vec![encode_bulk_string(&next_cursor.to_string()), encode_array(&keys)]))
+}
+
+// SCAN takes TYPE on top of the options shared with the *SCAN commands
+fn parse_keyspace_scan_args(args: &[String]) -> Result<(ScanArgs, Option<String>), Vec<u8>> {
+    let mut shared = vec![args[0].clone()];
+    let mut type_filter = None;
+    for option in args[1..].chunks(2) {
+        match (option[0].to_uppercase().as_str(), option.get(1)) {
+            ("TYPE", Some(name)) => type_filter = Some(name.to_lowercase()),
+            _ => shared.extend_from_slice(option),
+        }
+    }
+    Ok((parse_scan_args(&shared)?, type_filter))
+}
+
+pub fn process_expire(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "EXPIRE", parts[1] = key, parts[2] = seconds, [NX|XX|GT|LT]
+    set_expiry(parts, ExpireUnit::Seconds, false, kv_store)
+}
+
+pub fn process_pexpire(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "PEXPIRE", parts[1] = key, parts[2] = milliseconds, [NX|XX|GT|LT]
+    set_expiry(parts, ExpireUnit::Milliseconds, false, kv_store)
+}
+
+pub fn process_expireat(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "EXPIREAT", parts[1] = key, parts[2] = unix time in seconds, [NX|XX|GT|LT]
+    set_expiry(parts, ExpireUnit::Seconds, true, kv_store)
+}
+
+pub fn process_pexpireat(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "PEXPIREAT", parts[1] = key, parts[2] = unix time in milliseconds, [NX|XX|GT|LT]
+    set_expiry(parts, ExpireUnit::Milliseconds, true, kv_store)
+}
+
+// Shared body of the EXPIRE family; `absolute` means the time is a unix timestamp
+fn set_expiry(
+    parts: &[String],
+    unit: ExpireUnit,
+    absolute: bool,
+    kv_store: &KvStore
+) -> RespResult {
+    if parts.len() < 3 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let invalid_time = || encode_error_string(&format!(
+        "ERR invalid expire time in '{}' command", parts[0].to_lowercase()
+    ));
+    let Ok(time) = parts[2].parse::<i64>() else {
+        return Ok(encode_error_string("ERR value is not an integer or out of range"));
+    };
+    let Some(mut millis) = (match unit {
+        ExpireUnit::Seconds => time.checked_mul(1000),
+        ExpireUnit::Milliseconds => Some(time),
+    }) else {
+        return Ok(invalid_time());
+    };
+    if absolute {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64;
+        millis = millis.saturating_sub(now);
+    }
+
+    let mut options = ExpireOptions::default();
+    for flag in &parts[3..] {
+        match flag.to_uppercase().as_str() {
+            "NX" => options.nx = true,
+            "XX" => options.xx = true,
+            "GT" => options.gt = true,
+            "LT" => options.lt = true,
+            _ => return Ok(encode_error_string(&format!("ERR Unsupported option {}", flag))),
+        }
+    }
+    if options.nx && (options.xx || options.gt || options.lt) {
+        return Ok(encode_error_string("ERR NX and XX, GT or LT options at the same time are not compatible"));
+    }
+    if options.gt && options.lt {
+        return Ok(encode_error_string("ERR GT and LT options at the same time are not compatible"));
+    }
+
+    let now = Instant::now();
+    let key = &parts[1];
+    let mut map = kv_store.shard(key);
+    let Some(value) = map.get_mut(key) else {
+        return Ok(encode_integer(0));
     };
 
-    if is_expired {
+    // A key with no expiry counts as living forever when comparing with GT/LT
+    let current = value.expires_at.map(|expiry| expiry.saturating_duration_since(now).as_millis() as i64);
+    let allowed = match current {
+        None => !options.xx && !options.gt,
+        Some(current) => {
+            !options.nx && (!options.gt || millis > current) && (!options.lt || millis < current)
+        }
+    };
+    if !allowed {
+        return Ok(encode_integer(0));
+    }
+
+    // A deadline that has already passed deletes the key straight away
+    if millis <= 0 {
         map.remove(key);
-        Ok(encode_simple_string("none"))
-    } else {
-        let val = map.get(key).unwrap();
-        match &val.data {
-            RedisData::String(_) => Ok(encode_simple_string("string")),
-            RedisData::List(_) => Ok(encode_simple_string("list")),
-            RedisData::Stream(_) => Ok(encode_simple_string("stream")),
+        return Ok(encode_integer(1));
+    }
+    let Some(deadline) = now.checked_add(Duration::from_millis(millis as u64)) else {
+        return Ok(invalid_time());
+    };
+    value.expires_at = Some(deadline);
+    value.touch();
+    Ok(encode_integer(1))
+}
+
+pub fn process_ttl(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "TTL", parts[1] = key
+    remaining_ttl(parts, ExpireUnit::Seconds, kv_store)
+}
+
+pub fn process_pttl(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "PTTL", parts[1] = key
+    remaining_ttl(parts, ExpireUnit::Milliseconds, kv_store)
+}
+
+pub fn process_persist(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "PERSIST", parts[1] = key
+    if parts.len() != 2 {
+        return Err("Malformed PERSIST".to_string());
+    }
+    let key = &parts[1];
+    let mut map = kv_store.shard(key);
+    let Some(value) = map.get_mut(key) else {
+        return Ok(encode_integer(0));
+    };
+    match value.expires_at.take() {
+        Some(_) => {
+            value.touch();
+            Ok(encode_integer(1))
+        },
+        None => Ok(encode_integer(0))
+    }
+}
+
+// Shared body of TTL and PTTL: -2 for a missing key, -1 for one without an expiry
+fn remaining_ttl(
+    parts: &[String],
+    unit: ExpireUnit,
+    kv_store: &KvStore
+) -> RespResult {
+    if parts.len() != 2 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let now = Instant::now();
+    let key = &parts[1];
+    let map = kv_store.shard_read(key);
+    let Some(value) = map.get(key) else {
+        return Ok(encode_integer(-2));
+    };
+    let Some(expiry) = value.expires_at else {
+        return Ok(encode_integer(-1));
+    };
+
+    // saturating_duration_since rather than `-`, which panics if the deadline passed since the check above
+    let millis = expiry.saturating_duration_since(now).as_millis() as i64;
+    match unit {
+        ExpireUnit::Seconds => Ok(encode_integer((millis + 500) / 1000)),
+        ExpireUnit::Milliseconds => Ok(encode_integer(millis)),
+    }
+}
+
+pub fn process_rename(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "RENAME", parts[1] = source, parts[2] = destination
+    rename(parts, false, kv_store)
+}
+
+pub fn process_renamenx(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "RENAMENX", parts[1] = source, parts[2] = destination
+    rename(parts, true, kv_store)
+}
+
+// Shared body of RENAME and RENAMENX; the value moves with its expiry intact
+fn rename(
+    parts: &[String],
+    only_if_new: bool,
+    kv_store: &KvStore
+) -> RespResult {
+    if parts.len() != 3 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let (source, destination) = (&parts[1], &parts[2]);
+    let mut map = kv_store.lock_keys(&parts[1..3]);
+
+    if !map.contains_key(source) {
+        return Ok(encode_error_string("ERR no such key"));
+    }
+    if only_if_new && map.contains_key(destination) {
+        return Ok(encode_integer(0));
+    }
+    // Renaming a key onto itself leaves it as it was
+    if source != destination {
+        let mut value = map.remove(source).unwrap();
+        value.touch();
+        map.insert(destination.clone(), value);
+    }
+    if only_if_new { Ok(encode_integer(1)) } else { Ok(encode_simple_string("OK")) }
+}
+
+pub fn process_copy(
+    parts: &[String],
+    db_index: usize,
+    databases: &Databases
+) -> RespResult {
+    // parts[0] = "COPY", parts[1] = source, parts[2] = destination, [DB destination-db] [REPLACE]
+    if parts.len() < 3 {
+        return Err("Malformed COPY".to_string());
+    }
+    let (source, destination) = (&parts[1], &parts[2]);
+    let mut target_db = db_index;
+    let mut replace = false;
+    let mut idx = 3;
+    while let Some(option) = parts.get(idx) {
+        match (option.to_uppercase().as_str(), parts.get(idx + 1)) {
+            ("REPLACE", _) => replace = true,
+            ("DB", Some(raw)) => {
+                target_db = match raw.parse::<usize>() {
+                    Ok(db) if db < databases.len() => db,
+                    Ok(_) => return Ok(encode_error_string("ERR DB index is out of range")),
+                    Err(_) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+                };
+                idx += 1;
+            },
+            _ => return Ok(encode_error_string("ERR syntax error")),
+        }
+        idx += 1;
+    }
+    if target_db == db_index && source == destination {
+        return Ok(encode_error_string("ERR source and destination objects are the same"));
+    }
+
+    // Cloned under the source's lock, then written under the destination's, so
+    // a copy between databases never holds two stores' locks at once
+    let copy = {
+        let map = databases[db_index].shard_read(source);
+        match map.get(source) {
+            Some(value) => value.clone(),
+            None => return Ok(encode_integer(0)),
         }
+    };
+    let mut map = databases[target_db].shard(destination);
+    if !replace && map.contains_key(destination) {
+        return Ok(encode_integer(0));
+    }
+    map.insert(destination.clone(), copy);
+    Ok(encode_integer(1))
+}
+
+pub fn process_object(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "OBJECT", parts[1] = subcommand, [parts[2] = key]
+    let Some(subcommand) = parts.get(1) else {
+        return Ok(encode_error_string("ERR wrong number of arguments for 'object' command"));
+    };
+    let subcommand = subcommand.to_uppercase();
+    if subcommand == "HELP" {
+        return Ok(encode_array(&OBJECT_HELP.iter().map(|line| line.to_string()).collect::<Vec<_>>()));
+    }
+    if !matches!(subcommand.as_str(), "ENCODING" | "REFCOUNT" | "IDLETIME" | "FREQ") {
+        return Ok(encode_error_string(&format!(
+            "ERR unknown subcommand '{}'. Try OBJECT HELP.", parts[1]
+        )));
+    }
+    if parts.len() != 3 {
+        return Ok(encode_error_string(&format!(
+            "ERR wrong number of arguments for 'object|{}' command", subcommand.to_lowercase()
+        )));
+    }
+
+    let key = &parts[2];
+    let map = kv_store.shard_read(key);
+    let Some(value) = map.get(key) else {
+        return Ok(encode_null_string());
+    };
+
+    match subcommand.as_str() {
+        "ENCODING" => Ok(encode_bulk_string(value.data.encoding_name())),
+        // Values are never shared between keys here
+        "REFCOUNT" => Ok(encode_integer(1)),
+        "IDLETIME" => Ok(encode_integer(value.idle_time().as_secs() as i64)),
+        _ => Ok(encode_error_string(
+            "ERR An LFU maxmemory policy is not selected, access frequency not tracked."
+        )),
     }
 }
@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+
+use crate::models::{KvStore, RedisData, RedisValue, RespResult};
+use crate::utils::encoder::*;
+use crate::utils::random::{random_sample, MAX_SAMPLE_COUNT};
+use crate::utils::scan::parse_scan_args;
+
+pub fn process_hset(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HSET", parts[1] = key, parts[2..] = field value pairs
+    if parts.len() < 4 || !parts.len().is_multiple_of(2) {
+        return Err("Malformed HSET".to_string());
+    }
+    match set_fields(&parts[1], &parts[2..], kv_store) {
+        Ok(added) => Ok(encode_integer(added)),
+        Err(reply) => Ok(reply),
+    }
+}
+
+pub fn process_hmset(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HMSET", parts[1] = key, parts[2..] = field value pairs
+    if parts.len() < 4 || !parts.len().is_multiple_of(2) {
+        return Err("Malformed HMSET".to_string());
+    }
+    match set_fields(&parts[1], &parts[2..], kv_store) {
+        Ok(_) => Ok(encode_simple_string("OK")),
+        Err(reply) => Ok(reply),
+    }
+}
+
+pub fn process_hget(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HGET", parts[1] = key, parts[2] = field
+    if parts.len() < 3 {
+        return Err("Malformed HGET".to_string());
+    }
+    let key = &parts[1];
+    let field = &parts[2];
+
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(key) {
+        Some(value) => match &value.data {
+            RedisData::Hash(hash) => {
+                value.mark_accessed();
+                match hash.get(field) {
+                    Some(v) => Ok(encode_bulk_string(v)),
+                    None => Ok(encode_null_string()),
+                }
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_null_string())
+    }
+}
+
+pub fn process_hdel(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HDEL", parts[1] = key, parts[2..] = fields
+    if parts.len() < 3 {
+        return Err("Malformed HDEL".to_string());
+    }
+    let key = &parts[1];
+    let mut map = kv_store.shard(&parts[1]);
+    let mut should_remove = false;
+
+    let response = match map.get_mut(key) {
+        Some(value) => match &mut value.data {
+            RedisData::Hash(hash) => {
+                let removed = parts[2..].iter()
+                    .filter(|field| hash.remove(*field).is_some())
+                    .count();
+                should_remove = hash.is_empty();
+                if removed > 0 {
+                    value.touch();
+                }
+                Ok(encode_integer(removed as i64))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    };
+
+    // Redis never keeps empty hashes around
+    if should_remove {
+        map.remove(key);
+    }
+    response
+}
+
+pub fn process_hexists(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HEXISTS", parts[1] = key, parts[2] = field
+    if parts.len() < 3 {
+        return Err("Malformed HEXISTS".to_string());
+    }
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::Hash(hash) => Ok(encode_integer(hash.contains_key(&parts[2]) as i64)),
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    }
+}
+
+pub fn process_hlen(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HLEN", parts[1] = key
+    if parts.len() < 2 {
+        return Err("Malformed HLEN".to_string());
+    }
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::Hash(hash) => Ok(encode_integer(hash.len() as i64)),
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    }
+}
+
+pub fn process_hkeys(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HKEYS", parts[1] = key
+    if parts.len() < 2 {
+        return Err("Malformed HKEYS".to_string());
+    }
+    read_hash(&parts[1], kv_store, |hash| hash.keys().cloned().collect())
+}
+
+pub fn process_hvals(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HVALS", parts[1] = key
+    if parts.len() < 2 {
+        return Err("Malformed HVALS".to_string());
+    }
+    read_hash(&parts[1], kv_store, |hash| hash.values().cloned().collect())
+}
+
+pub fn process_hgetall(
+    parts: &[String],
+    kv_store: &KvStore,
+    protocol: u8
+) -> RespResult {
+    // parts[0] = "HGETALL", parts[1] = key
+    if parts.len() < 2 {
+        return Err("Malformed HGETALL".to_string());
+    }
+    if protocol == 3 {
+        let map = kv_store.shard_read(&parts[1]);
+        return match map.get(&parts[1]) {
+            Some(value) => match &value.data {
+                RedisData::Hash(hash) => {
+                    value.mark_accessed();
+                    let pairs: Vec<(String, String)> = hash.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                    Ok(encode_map(&pairs))
+                },
+                _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+            },
+            None => Ok(encode_map(&[])),
+        };
+    }
+    read_hash(&parts[1], kv_store, |hash| {
+        hash.iter().flat_map(|(k, v)| [k.clone(), v.clone()]).collect()
+    })
+}
+
+// Encodes whatever `extract` pulls out of the hash at `key`; a missing key is an empty array
+fn read_hash(
+    key: &str,
+    kv_store: &KvStore,
+    extract: impl Fn(&HashMap<String, String>) -> Vec<String>
+) -> RespResult {
+    let map = kv_store.shard_read(key);
+    match map.get(key) {
+        Some(value) => match &value.data {
+            RedisData::Hash(hash) => {
+                value.mark_accessed();
+                Ok(encode_array(&extract(hash)))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_array(&[]))
+    }
+}
+
+pub fn process_hmget(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HMGET", parts[1] = key, parts[2..] = fields
+    if parts.len() < 3 {
+        return Err("Malformed HMGET".to_string());
+    }
+    let map = kv_store.shard_read(&parts[1]);
+    let hash = match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::Hash(hash) => {
+                value.mark_accessed();
+                Some(hash)
+            },
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => None
+    };
+
+    let values = parts[2..].iter()
+        .map(|field| match hash.and_then(|h| h.get(field)) {
+            Some(v) => encode_bulk_string(v),
+            None => encode_null_string(),
+        })
+        .collect();
+    Ok(encode_raw_array(values))
+}
+
+pub fn process_hincrby(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HINCRBY", parts[1] = key, parts[2] = field, parts[3] = increment
+    if parts.len() < 4 {
+        return Err("Malformed HINCRBY".to_string());
+    }
+    let Ok(increment) = parts[3].parse::<i64>() else {
+        return Ok(encode_error_string("ERR value is not an integer or out of range"));
+    };
+
+    let mut map = kv_store.shard(&parts[1]);
+    let entry = map.entry(parts[1].clone()).or_insert(RedisValue::new(
+        RedisData::Hash(HashMap::new()),
+        None
+    ));
+
+    match &mut entry.data {
+        RedisData::Hash(hash) => {
+            let current = match hash.get(&parts[2]) {
+                Some(v) => match v.parse::<i64>() {
+                    Ok(n) => n,
+                    Err(_) => return Ok(encode_error_string("ERR hash value is not an integer")),
+                },
+                None => 0
+            };
+            let Some(new_value) = current.checked_add(increment) else {
+                return Ok(encode_error_string("ERR increment or decrement would overflow"));
+            };
+            hash.insert(parts[2].clone(), new_value.to_string());
+            entry.touch();
+            Ok(encode_integer(new_value))
+        },
+        _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"))
+    }
+}
+
+pub fn process_hincrbyfloat(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HINCRBYFLOAT", parts[1] = key, parts[2] = field, parts[3] = increment
+    if parts.len() < 4 {
+        return Err("Malformed HINCRBYFLOAT".to_string());
+    }
+    let increment = match parts[3].parse::<f64>() {
+        Ok(f) if f.is_finite() => f,
+        _ => return Ok(encode_error_string("ERR value is not a valid float")),
+    };
+
+    let mut map = kv_store.shard(&parts[1]);
+    let entry = map.entry(parts[1].clone()).or_insert(RedisValue::new(
+        RedisData::Hash(HashMap::new()),
+        None
+    ));
+
+    match &mut entry.data {
+        RedisData::Hash(hash) => {
+            let current = match hash.get(&parts[2]) {
+                Some(v) => match v.parse::<f64>() {
+                    Ok(f) => f,
+                    Err(_) => return Ok(encode_error_string("ERR hash value is not a float")),
+                },
+                None => 0.0
+            };
+            let new_value = current + increment;
+            if !new_value.is_finite() {
+                return Ok(encode_error_string("ERR increment would produce NaN or Infinity"));
+            }
+            let formatted = format_double(new_value);
+            hash.insert(parts[2].clone(), formatted.clone());
+            entry.touch();
+            Ok(encode_bulk_string(&formatted))
+        },
+        _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"))
+    }
+}
+
+pub fn process_hsetnx(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HSETNX", parts[1] = key, parts[2] = field, parts[3] = value
+    if parts.len() < 4 {
+        return Err("Malformed HSETNX".to_string());
+    }
+    let mut map = kv_store.shard(&parts[1]);
+    let entry = map.entry(parts[1].clone()).or_insert(RedisValue::new(
+        RedisData::Hash(HashMap::new()),
+        None
+    ));
+
+    match &mut entry.data {
+        RedisData::Hash(hash) => {
+            if hash.contains_key(&parts[2]) {
+                return Ok(encode_integer(0));
+            }
+            hash.insert(parts[2].clone(), parts[3].clone());
+            entry.touch();
+            Ok(encode_integer(1))
+        },
+        _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"))
+    }
+}
+
+pub fn process_hrandfield(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HRANDFIELD", parts[1] = key, [parts[2] = count, [parts[3] = WITHVALUES]]
+    if parts.len() < 2 {
+        return Err("Malformed HRANDFIELD".to_string());
+    }
+    let count: Option<i64> = match parts.get(2) {
+        Some(raw) => match raw.parse::<i64>() {
+            Ok(n) if n.unsigned_abs() > MAX_SAMPLE_COUNT as u64 => {
+                return Ok(encode_error_string("ERR value is out of range"));
+            },
+            Ok(n) => Some(n),
+            Err(_) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+        },
+        None => None
+    };
+    let with_values = match parts.get(3) {
+        Some(option) if option.to_uppercase() == "WITHVALUES" => true,
+        Some(_) => return Ok(encode_error_string("ERR syntax error")),
+        None => false
+    };
+
+    let map = kv_store.shard_read(&parts[1]);
+    let hash = match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::Hash(hash) => hash,
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => return match count {
+            Some(_) => Ok(encode_array(&[])),
+            None => Ok(encode_null_string()),
+        }
+    };
+
+    let pairs: Vec<(&String, &String)> = hash.iter().collect();
+    match count {
+        None => match random_sample(&pairs, 1).first() {
+            Some((field, _)) => Ok(encode_bulk_string(field)),
+            None => Ok(encode_null_string()),
+        },
+        Some(n) => {
+            let mut response = Vec::new();
+            for (field, value) in random_sample(&pairs, n) {
+                response.push(field.clone());
+                if with_values {
+                    response.push(value.clone());
+                }
+            }
+            Ok(encode_array(&response))
+        }
+    }
+}
+
+pub fn process_hscan(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "HSCAN", parts[1] = key, parts[2] = cursor, [MATCH pattern] [COUNT count]
+    if parts.len() < 3 {
+        return Err("Malformed HSCAN".to_string());
+    }
+    let scan = match parse_scan_args(&parts[2..]) {
+        Ok(scan) => scan,
+        Err(reply) => return Ok(reply),
+    };
+
+    let map = kv_store.shard_read(&parts[1]);
+    let mut elements = Vec::new();
+    // The store can't resume a scan, so everything is returned for cursor 0 and
+    // any other cursor is treated as already finished; COUNT is only a hint
+    if let (0, Some(value)) = (scan.cursor, map.get(&parts[1])) {
+        match &value.data {
+            RedisData::Hash(hash) => {
+                for (field, value) in hash {
+                    if scan.matches(field) {
+                        elements.push(field.clone());
+                        elements.push(value.clone());
+                    }
+                }
+            },
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        }
+    }
+    Ok(encode_raw_array(vec![encode_bulk_string("0"), encode_array(&elements)]))
+}
+
+// Writes `pairs` (field, value, field, value, ...) into the hash at `key`, returning how many
+// fields are new, or the WRONGTYPE reply
+fn set_fields(
+    key: &str,
+    pairs: &[String],
+    kv_store: &KvStore
+) -> Result<i64, Vec<u8>> {
+    let mut map = kv_store.shard(key);
+    let entry = map.entry(key.to_string()).or_insert(RedisValue::new(
+        RedisData::Hash(HashMap::new()),
+        None
+    ));
+
+    match &mut entry.data {
+        RedisData::Hash(hash) => {
+            let mut added = 0;
+            for chunk in pairs.chunks_exact(2) {
+                if hash.insert(chunk[0].clone(), chunk[1].clone()).is_none() {
+                    added += 1;
+                }
+            }
+            entry.touch();
+            Ok(added)
+        },
+        _ => Err(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"))
+    }
+}
@@ -1,28 +1,50 @@
-
 use std::sync::{Arc, Mutex};
-use crate::models::{InfoOption, ServerInfo, RespResult};
+use std::time::Instant;
+
+use crate::models::{Databases, InfoOption, ServerInfo, RespResult};
 use crate::utils::encoder::encode_bulk_string;
 
 pub fn process_info(
     parts: &[String],
-    server_info: &Arc<Mutex<ServerInfo>>
+    server_info: &Arc<Mutex<ServerInfo>>,
+    databases: &Databases
 ) -> RespResult {
-    // Don't need length check because can only pass INFO 
-    let mut info_option: Option<InfoOption> = None;
-    if parts.len() > 1 {
-        info_option = match parts[1].to_uppercase().as_str() {
-            "REPLICATION" => {
-                Some(InfoOption::Replication)
-            },
-            _ => None //todo: maybe throw err
+    // parts[0] = "INFO", [parts[1..] = sections]
+    let mut sections: Vec<InfoOption> = Vec::new();
+    if parts.len() == 1 {
+        sections.extend(InfoOption::ALL);
+    }
+    for name in &parts[1..] {
+        for &section in InfoOption::parse(name) {
+            if !sections.contains(&section) {
+                sections.push(section);
+            }
         }
     }
 
-    let info = server_info.lock().unwrap();
+    let mut info = server_info.lock().unwrap();
+    // A replica that hung up since the last write is still registered
+    info.prune_replicas();
 
-    match info_option {
-        //todo: make work for all infooption since all can implement the string
-        Some(InfoOption::Replication) => Ok(encode_bulk_string(&info.replication_info.to_info_string())), 
-        None => Ok(encode_bulk_string(&info.replication_info.to_info_string())) //todo: update
+    let output: Vec<String> = sections.into_iter()
+        .map(|section| match section {
+            InfoOption::Server => info.server_info_string(),
+            InfoOption::Replication => info.replication_info.to_info_string(),
+            InfoOption::Keyspace => keyspace_info_string(databases),
+        })
+        .collect();
+    Ok(encode_bulk_string(&output.join("\r\n")))
+}
+
+// "# Keyspace", then a db<N> line for every database holding keys
+fn keyspace_info_string(databases: &Databases) -> String {
+    let now = Instant::now();
+    let mut output = "# Keyspace\r\n".to_string();
+    for (index, kv_store) in databases.iter().enumerate() {
+        let keys = kv_store.len();
+        if keys > 0 {
+            output.push_str(&format!("db{}:keys={},expires={}\r\n", index, keys, kv_store.expiring_count(now)));
+        }
     }
-}
\ No newline at end of file
+    output
+}
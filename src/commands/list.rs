@@ -1,15 +1,13 @@
-use std::sync::{Arc, Mutex};
-use std::collections::{VecDeque, HashMap};
-use tokio::sync::mpsc;
+use std::collections::VecDeque;
 
-use crate::models::{ListDir, RedisData, RedisValue, RespResult};
+use crate::models::{KvStore, ListDir, RedisData, RedisValue, RespResult, WaitingRoom};
 use crate::utils::async_helpers::*;
 use crate::utils::encoder::*;
 
 pub fn process_push(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>,
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom,
     push_type: ListDir
 ) -> RespResult {
     // parts[0] = "RPUSH"/"LPUSH", parts[1] = key, parts[2..] = values
@@ -17,7 +15,7 @@ pub fn process_push(
         return Err("Incomplete RPUSH/LPUSH command".to_string());
     }
     let key = parts[1].clone();
-    let mut map = kv_store.lock().unwrap();
+    let mut map = kv_store.shard(&parts[1]);
 
     // Collect all values to push
     let new_elements: Vec<String> = parts[2..].to_vec();
@@ -31,32 +29,28 @@ pub fn process_push(
         RedisData::List(list) => {
             let mut room = waiting_room.lock().unwrap();
             let total_new_elements = new_elements.len();
-            let mut remaining_elements = new_elements.into_iter();
+            let mut remaining_elements: VecDeque<String> = new_elements.into();
 
             if let Some(queue) = room.get_mut(&key) {
-                println!("DEBUG: PUSH found {} waiters for {}", queue.len(), key);
                 // First, clean up any dead waiters
                 queue.retain(|sender| !sender.is_closed());
-                println!("DEBUG: PUSH after cleanup: {} live waiters for {}", queue.len(), key);
+                log::debug!("PUSH found {} live waiters for {}", queue.len(), key);
 
-                while let Some(tx) = queue.pop_front() {
-                    let Some(next_val) = remaining_elements.next() else {
-                        println!("DEBUG: PUSH ran out of elements for waiters");
+                // Only take a waiter off the queue when there is an element to hand it,
+                // otherwise it would be dropped and never woken by a later push
+                while let Some(next_val) = remaining_elements.pop_front() {
+                    let Some(tx) = queue.pop_front() else {
+                        remaining_elements.push_front(next_val);
                         break;
                     };
-                    if tx.try_send(next_val.clone()).is_ok() {
-                        println!("DEBUG: PUSH successfully handed off element");
-                    } else {
+                    if tx.try_send(next_val.clone()).is_err() {
                         // Send failed, put element back for next waiter or list
-                        println!("DEBUG: PUSH send failed, will retry with element");
-                        remaining_elements = std::iter::once(next_val).chain(remaining_elements).collect::<Vec<_>>().into_iter();
+                        remaining_elements.push_front(next_val);
                     }
                 }
-            } else {
-                println!("DEBUG: PUSH found NO waiters in room for {}", key);
             }
 
-            let leftovers: Vec<String> = remaining_elements.collect();
+            let leftovers: Vec<String> = remaining_elements.into();
             let leftovers_count = leftovers.len();
             if !leftovers.is_empty() {
                 match push_type {
@@ -66,6 +60,7 @@ pub fn process_push(
             }
 
             let final_len = list.len() + (total_new_elements - leftovers_count);
+            entry.touch();
             Ok(encode_integer(final_len as i64))
         },
         _ => Err("WRONGTYPE Operation against a key that is not a list".to_string())
@@ -74,7 +69,7 @@ pub fn process_push(
 
 pub fn process_lrange(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    kv_store: &KvStore
 ) -> RespResult {
     // parts[0] = "LRANGE", parts[1] = key, parts[2] = start, parts[3] = end
     if parts.len() < 4 {
@@ -84,16 +79,16 @@ pub fn process_lrange(
     let mut start: i64 = parts[2].parse().map_err(|_| "Invalid start index")?;
     let mut end: i64 = parts[3].parse().map_err(|_| "Invalid end index")?;
 
-    let map = kv_store.lock().unwrap();
-    match map.get(key) {
+    kv_store.read_live(key, |value| match value {
         Some(value) => {
             match &value.data {
                 RedisData::List(list) => {
+                    value.mark_accessed();
                     if start < 0 {
-                        start = list.len() as i64 + start;
+                        start += list.len() as i64;
                     }
                     if end < 0 {
-                        end = list.len() as i64 + end;
+                        end += list.len() as i64;
                     }
                     let start_idx = start.max(0) as usize;
                     let mut end_idx = end.max(0) as usize;
@@ -111,33 +106,35 @@ pub fn process_lrange(
             }
         },
         None => Ok(encode_array(&[]))
-    }
+    })
 }
 
 pub fn process_llen(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    kv_store: &KvStore
 ) -> RespResult {
     // parts[0] = "LLEN", parts[1] = key
     if parts.len() < 2 {
         return Err("Incomplete LLEN command".to_string());
     }
     let key = &parts[1];
-    let map = kv_store.lock().unwrap();
-    match map.get(key) {
+    kv_store.read_live(key, |value| match value {
         Some(value) => {
             match &value.data {
-                RedisData::List(list) => Ok(encode_integer(list.len() as i64)),
+                RedisData::List(list) => {
+                    value.mark_accessed();
+                    Ok(encode_integer(list.len() as i64))
+                },
                 _ => Err("WRONGTYPE Operation against a key not holding a list".to_string()),
             }
         },
         None => Ok(encode_integer(0))
-    }
+    })
 }
 
 pub fn process_pop(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,
+    kv_store: &KvStore,
     push_type: ListDir
 ) -> RespResult {
     // parts[0] = "LPOP"/"RPOP", parts[1] = key, [parts[2] = count]
@@ -151,7 +148,7 @@ pub fn process_pop(
     }
 
     let key = &parts[1];
-    let mut map = kv_store.lock().unwrap();
+    let mut map = kv_store.shard(&parts[1]);
     let mut should_remove = false;
 
     let response = match map.get_mut(key) {
@@ -174,6 +171,7 @@ pub fn process_pop(
                         if list.is_empty() {
                             should_remove = true;
                         }
+                        value.touch();
                         if dropped_items.len() > 1 {
                             Ok(encode_array(&dropped_items))
                         } else {
@@ -195,8 +193,8 @@ pub fn process_pop(
 
 pub async fn process_blpop(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
 ) -> RespResult {
     // parts[0] = "BLPOP", parts[1] = key, parts[2] = timeout
     if parts.len() < 3 {
@@ -204,48 +202,80 @@ pub async fn process_blpop(
     }
 
     let key = parts[1].clone();
-    println!("DEBUG: BLPOP checking kv_store for {}", key);
-    let timeout_val: f64 = parts.last().unwrap().parse().unwrap_or(0.0);
+    // Checked before registering as a waiter; 0 means block forever
+    let timeout_val = match parse_timeout(&parts[parts.len() - 1]) {
+        Ok(timeout) => timeout,
+        Err(reply) => return Ok(reply),
+    };
 
     // If list exists and has items, return immediately
-    {
-        let mut map = kv_store.lock().unwrap();
-        if let Some(val) = map.get_mut(&key) {
-            if let RedisData::List(list) = &mut val.data {
-                if !list.is_empty() {
-                    let item = list.remove(0);
-                    return Ok(encode_array(&[key, item]));
-                }
-            }
-        }
+    if let Some(item) = pop_front_now(&key, kv_store) {
+        return Ok(encode_array(&[key, item]));
     }
-    println!("DEBUG: BLPOP blocking on key: {}", key);
+    log::debug!("BLPOP blocking on key: {}", key);
 
     // List empty/didn't exist, block
-    let (_tx, mut rx) = init_waiting_room(&vec![key.to_string()], &waiting_room);
+    let mut waiter = init_waiting_room(std::slice::from_ref(&key), waiting_room);
 
     let result = if timeout_val > 0.0 {
         let duration = tokio::time::Duration::from_secs_f64(timeout_val);
-        match tokio::time::timeout(duration, rx.recv()).await {
+        match tokio::time::timeout(duration, waiter.rx.recv()).await {
             Ok(maybe_data) => maybe_data,
             Err(_) => {
-                let mut room = waiting_room.lock().unwrap();
-                if let Some(queue) = room.get_mut(&key) {
-                    queue.retain(|sender| !sender.is_closed());
-                }
+                waiter.leave();
                 // One last look to check if data was sent during the timeout transition
-                rx.try_recv().ok()
+                waiter.rx.try_recv().ok()
             },
         }
     } else {
-        rx.recv().await
+        waiter.rx.recv().await
     };
 
     match result {
         Some(data) => {
-            println!("DEBUG: BLPOP Woke up! Received: {}", data);
+            log::debug!("BLPOP woke up with {}", data);
             Ok(encode_array(&[key, data]))
         },
         None => Ok(encode_null_array()),
     }
 }
+
+// BLPOP as run from EXEC: transactions never block, so an empty list is just a nil reply
+pub fn process_blpop_nowait(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "BLPOP", parts[1] = key, parts[2] = timeout
+    if parts.len() < 3 {
+        return Err("Incomplete BLPOP command".to_string());
+    }
+    if let Err(reply) = parse_timeout(&parts[parts.len() - 1]) {
+        return Ok(reply);
+    }
+    match pop_front_now(&parts[1], kv_store) {
+        Some(item) => Ok(encode_array(&[parts[1].clone(), item])),
+        None => Ok(encode_null_array()),
+    }
+}
+
+// Pops the head of the list at `key` if there is one, dropping the key once it's empty
+fn pop_front_now(
+    key: &str,
+    kv_store: &KvStore
+) -> Option<String> {
+    let mut map = kv_store.shard(key);
+    let value = map.get_mut(key)?;
+    let RedisData::List(list) = &mut value.data else {
+        return None;
+    };
+    if list.is_empty() {
+        return None;
+    }
+    let item = list.remove(0);
+    let emptied = list.is_empty();
+    value.touch();
+    if emptied {
+        map.remove(key);
+    }
+    Some(item)
+}
@@ -4,10 +4,32 @@ pub mod list;
 pub mod stream;
 pub mod transaction;
 pub mod info;
+pub mod set;
+pub mod zset;
+pub mod hash;
+pub mod config;
+pub mod server;
+pub mod pubsub;
+pub mod replication;
+pub mod acl;
+pub mod client;
+pub mod command;
+pub mod debug;
 
 pub use generic::*;
 pub use string::*;
 pub use list::*;
 pub use stream::*;
 pub use transaction::*;
-pub use info::*;
\ No newline at end of file
+pub use info::*;
+pub use set::*;
+pub use zset::*;
+pub use hash::*;
+pub use config::*;
+pub use server::*;
+pub use pubsub::*;
+pub use replication::*;
+pub use acl::*;
+pub use client::*;
+pub use command::*;
+pub use debug::*;
\ No newline at end of file
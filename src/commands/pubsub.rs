@@ -0,0 +1,262 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{ClientState, PubSub, PushSender, RespResult, SubscriptionKind};
+use crate::utils::encoder::*;
+use crate::utils::glob::glob_match;
+
+pub fn process_subscribe(
+    parts: &[String],
+    client: &mut ClientState,
+    pubsub: &PubSub
+) -> RespResult {
+    // parts[0] = "SUBSCRIBE", parts[1..] = channels
+    subscribe(parts, SubscriptionKind::Channel, client, pubsub)
+}
+
+pub fn process_psubscribe(
+    parts: &[String],
+    client: &mut ClientState,
+    pubsub: &PubSub
+) -> RespResult {
+    // parts[0] = "PSUBSCRIBE", parts[1..] = glob patterns
+    subscribe(parts, SubscriptionKind::Pattern, client, pubsub)
+}
+
+pub fn process_unsubscribe(
+    parts: &[String],
+    client: &mut ClientState,
+    pubsub: &PubSub
+) -> RespResult {
+    // parts[0] = "UNSUBSCRIBE", [parts[1..] = channels], no channels means all of them
+    unsubscribe(parts, SubscriptionKind::Channel, client, pubsub)
+}
+
+pub fn process_punsubscribe(
+    parts: &[String],
+    client: &mut ClientState,
+    pubsub: &PubSub
+) -> RespResult {
+    // parts[0] = "PUNSUBSCRIBE", [parts[1..] = patterns], no patterns means all of them
+    unsubscribe(parts, SubscriptionKind::Pattern, client, pubsub)
+}
+
+pub fn process_publish(
+    parts: &[String],
+    pubsub: &PubSub
+) -> RespResult {
+    // parts[0] = "PUBLISH", parts[1] = channel, parts[2] = message
+    if parts.len() != 3 {
+        return Err("Malformed PUBLISH".to_string());
+    }
+    let channel = &parts[1];
+    let message = &parts[2];
+    let mut registry = pubsub.lock().unwrap();
+    let mut delivered = 0;
+
+    if let Some(subscribers) = registry.channels.get_mut(channel) {
+        let payload = encode_array(&["message".to_string(), channel.clone(), message.clone()]);
+        delivered += deliver(subscribers, &payload);
+    }
+    // Pattern subscribers get the pattern that matched as well as the channel
+    for (pattern, subscribers) in registry.patterns.iter_mut() {
+        if glob_match(pattern, channel) {
+            let payload = encode_array(&["pmessage".to_string(), pattern.clone(), channel.clone(), message.clone()]);
+            delivered += deliver(subscribers, &payload);
+        }
+    }
+    registry.channels.retain(|_, subscribers| !subscribers.is_empty());
+    registry.patterns.retain(|_, subscribers| !subscribers.is_empty());
+    Ok(encode_integer(delivered as i64))
+}
+
+pub fn process_pubsub(
+    parts: &[String],
+    pubsub: &PubSub
+) -> RespResult {
+    // parts[0] = "PUBSUB", parts[1] = subcommand, parts[2..] = arguments
+    let Some(subcommand) = parts.get(1) else {
+        return Ok(encode_error_string("ERR wrong number of arguments for 'pubsub' command"));
+    };
+    let subcommand = subcommand.to_uppercase();
+    let args = &parts[2..];
+    let wrong_arity = || Ok(encode_error_string(&format!(
+        "ERR wrong number of arguments for 'pubsub|{}' command", subcommand.to_lowercase()
+    )));
+    let registry = pubsub.lock().unwrap();
+
+    match subcommand.as_str() {
+        "CHANNELS" => {
+            if args.len() > 1 {
+                return wrong_arity();
+            }
+            let mut channels: Vec<String> = registry.channels.iter()
+                .filter(|(channel, subscribers)| {
+                    live_count(subscribers) > 0 && args.first().is_none_or(|pattern| glob_match(pattern, channel))
+                })
+                .map(|(channel, _)| channel.clone())
+                .collect();
+            channels.sort();
+            Ok(encode_array(&channels))
+        },
+        "NUMSUB" => {
+            let pairs = args.iter()
+                .flat_map(|channel| {
+                    let count = registry.channels.get(channel).map_or(0, |subscribers| live_count(subscribers));
+                    [encode_bulk_string(channel), encode_integer(count as i64)]
+                })
+                .collect();
+            Ok(encode_raw_array(pairs))
+        },
+        "NUMPAT" => {
+            if !args.is_empty() {
+                return wrong_arity();
+            }
+            let patterns = registry.patterns.values().filter(|subscribers| live_count(subscribers) > 0).count();
+            Ok(encode_integer(patterns as i64))
+        },
+        // Shard channels only exist in cluster mode, so there are never any
+        "SHARDCHANNELS" => {
+            if args.len() > 1 {
+                return wrong_arity();
+            }
+            Ok(encode_array(&[]))
+        },
+        "SHARDNUMSUB" => {
+            let pairs = args.iter()
+                .flat_map(|channel| [encode_bulk_string(channel), encode_integer(0)])
+                .collect();
+            Ok(encode_raw_array(pairs))
+        },
+        _ => Ok(encode_error_string(&format!(
+            "ERR unknown subcommand '{}'. Try PUBSUB HELP.", parts[1]
+        ))),
+    }
+}
+
+// Shared body of SUBSCRIBE and PSUBSCRIBE
+fn subscribe(
+    parts: &[String],
+    kind: SubscriptionKind,
+    client: &mut ClientState,
+    pubsub: &PubSub
+) -> RespResult {
+    if parts.len() < 2 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let Some(sender) = client.push_sender.clone() else {
+        return Err("SUBSCRIBE needs a connection that can receive pushed messages".to_string());
+    };
+
+    let mut registry = pubsub.lock().unwrap();
+    let mut response = Vec::new();
+    // One confirmation per name, each carrying the running subscription count
+    for name in &parts[1..] {
+        let (joined, subscribers) = match kind {
+            SubscriptionKind::Channel => (client.subscriptions.insert(name.clone()), &mut registry.channels),
+            SubscriptionKind::Pattern => (client.pattern_subscriptions.insert(name.clone()), &mut registry.patterns),
+        };
+        if joined {
+            subscribers.entry(name.clone()).or_default().push(sender.clone());
+        }
+        response.extend(subscription_reply(reply_kind(kind, true), Some(name), client.subscription_count()));
+    }
+    Ok(response)
+}
+
+// Shared body of UNSUBSCRIBE and PUNSUBSCRIBE
+fn unsubscribe(
+    parts: &[String],
+    kind: SubscriptionKind,
+    client: &mut ClientState,
+    pubsub: &PubSub
+) -> RespResult {
+    let names: Vec<String> = if parts.len() > 1 {
+        parts[1..].to_vec()
+    } else {
+        let mut all: Vec<String> = subscribed(kind, client).iter().cloned().collect();
+        all.sort();
+        all
+    };
+    if names.is_empty() {
+        return Ok(subscription_reply(reply_kind(kind, false), None, client.subscription_count()));
+    }
+
+    let mut registry = pubsub.lock().unwrap();
+    let mut response = Vec::new();
+    for name in &names {
+        let subscribers = match kind {
+            SubscriptionKind::Channel => &mut registry.channels,
+            SubscriptionKind::Pattern => &mut registry.patterns,
+        };
+        if subscribed(kind, client).remove(name) {
+            remove_subscriber(subscribers, name, client);
+        }
+        response.extend(subscription_reply(reply_kind(kind, false), Some(name), client.subscription_count()));
+    }
+    Ok(response)
+}
+
+/// Drops every channel and pattern subscription `client` has, without
+/// sending any confirmations.
+pub fn unsubscribe_all(client: &mut ClientState, pubsub: &PubSub) {
+    let mut registry = pubsub.lock().unwrap();
+    for channel in std::mem::take(&mut client.subscriptions) {
+        remove_subscriber(&mut registry.channels, &channel, client);
+    }
+    for pattern in std::mem::take(&mut client.pattern_subscriptions) {
+        remove_subscriber(&mut registry.patterns, &pattern, client);
+    }
+}
+
+fn subscribed(kind: SubscriptionKind, client: &mut ClientState) -> &mut HashSet<String> {
+    match kind {
+        SubscriptionKind::Channel => &mut client.subscriptions,
+        SubscriptionKind::Pattern => &mut client.pattern_subscriptions,
+    }
+}
+
+fn reply_kind(kind: SubscriptionKind, subscribing: bool) -> &'static str {
+    match (kind, subscribing) {
+        (SubscriptionKind::Channel, true) => "subscribe",
+        (SubscriptionKind::Channel, false) => "unsubscribe",
+        (SubscriptionKind::Pattern, true) => "psubscribe",
+        (SubscriptionKind::Pattern, false) => "punsubscribe",
+    }
+}
+
+// Subscribers whose connection is still open; closed ones linger until the next PUBLISH
+fn live_count(subscribers: &[PushSender]) -> usize {
+    subscribers.iter().filter(|sender| !sender.is_closed()).count()
+}
+
+// Sends `payload` to every subscriber still connected, dropping the ones that went away
+fn deliver(subscribers: &mut Vec<PushSender>, payload: &[u8]) -> usize {
+    subscribers.retain(|sender| !sender.is_closed());
+    subscribers.iter()
+        .filter(|sender| sender.try_send(payload.to_vec()).is_ok())
+        .count()
+}
+
+// The [kind, name, count] confirmation sent for every (un)subscribed channel or pattern
+fn subscription_reply(kind: &str, name: Option<&String>, count: usize) -> Vec<u8> {
+    encode_raw_array(vec![
+        encode_bulk_string(kind),
+        name.map_or_else(encode_null_string, |name| encode_bulk_string(name)),
+        encode_integer(count as i64),
+    ])
+}
+
+// Drops this connection's sender from `name`, forgetting it once nobody is left
+fn remove_subscriber(
+    registry: &mut HashMap<String, Vec<PushSender>>,
+    name: &str,
+    client: &ClientState
+) {
+    let Some(sender) = &client.push_sender else { return };
+    if let Some(subscribers) = registry.get_mut(name) {
+        subscribers.retain(|other| !other.same_channel(sender));
+        if subscribers.is_empty() {
+            registry.remove(name);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::{Arc, Mutex};
+
+use crate::models::{ClientState, Databases, ReplicationGate, RespResult, ServerInfo};
+use crate::utils::encoder::*;
+use crate::utils::rdb::{encode, snapshot};
+
+pub fn process_replconf(parts: &[String]) -> RespResult {
+    // parts[0] = "REPLCONF", parts[1..] = option value pairs (listening-port <port>, capa <capability>, ...)
+    if parts.len().is_multiple_of(2) {
+        return Ok(encode_error_string("ERR syntax error"));
+    }
+    for pair in parts[1..].chunks_exact(2) {
+        match pair[0].to_lowercase().as_str() {
+            "listening-port" if pair[1].parse::<u16>().is_err() => {
+                return Ok(encode_error_string("ERR value is not an integer or out of range"));
+            },
+            // Every replica understands the same stream, so capabilities change nothing yet
+            "listening-port" | "capa" => {},
+            _ => return Ok(encode_error_string(&format!("ERR Unrecognized REPLCONF option: {}", pair[0]))),
+        }
+    }
+    Ok(encode_simple_string("OK"))
+}
+
+pub async fn process_psync(
+    parts: &[String],
+    client: &mut ClientState,
+    databases: &Databases,
+    server_info: &Arc<Mutex<ServerInfo>>,
+    replication: &ReplicationGate
+) -> RespResult {
+    // parts[0] = "PSYNC", parts[1] = replid, parts[2] = offset
+    if parts.len() != 3 {
+        return Err("Malformed PSYNC".to_string());
+    }
+    let Some(sender) = client.push_sender.clone() else {
+        return Ok(encode_error_string("ERR PSYNC needs a connection that can receive a write stream"));
+    };
+    // There's no backlog to continue from, so every replica gets a full resync:
+    // the header, then the snapshot as a bulk payload without a trailing CRLF.
+    // No write is in progress while the snapshot is taken and the replica
+    // attached, so each one lands in exactly one of the two.
+    let _sync = replication.sync().await;
+    let mut info = server_info.lock().unwrap();
+    let payload = encode(&snapshot(databases));
+    let mut reply = format!(
+        "+FULLRESYNC {} {}\r\n${}\r\n",
+        info.replication_info.master_replid, info.replication_info.master_repl_offset, payload.len()
+    ).into_bytes();
+    reply.extend(payload);
+
+    info.attach_replica(sender);
+    replication.set_attached(true);
+    Ok(reply)
+}
@@ -0,0 +1,274 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::constants::{SERVER_NAME, SERVER_VERSION};
+use crate::models::{Acl, ClientState, Databases, KvStore, PubSub, ReplyMode, RespResult, ServerConfig, ServerInfo};
+use crate::utils::async_helpers::free_in_background;
+use crate::utils::rdb::{rdb_path, snapshot, write_snapshot};
+use crate::utils::encoder::*;
+use super::client::is_valid_client_name;
+use super::pubsub::unsubscribe_all;
+
+pub fn process_dbsize(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "DBSIZE"
+    if parts.len() != 1 {
+        return Err("Malformed DBSIZE".to_string());
+    }
+    let now = Instant::now();
+    // Keys past their deadline may not have been swept yet, so they're skipped here
+    let map = kv_store.lock_all();
+    let live = map.iter().filter(|(_, value)| !value.is_expired(now)).count();
+    Ok(encode_integer(live as i64))
+}
+
+pub fn process_flushdb(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "FLUSHDB", [parts[1] = ASYNC/SYNC]
+    let Some(lazy) = parse_flush_mode(parts) else {
+        return Ok(encode_error_string("ERR syntax error"));
+    };
+    flush(kv_store, lazy);
+    Ok(encode_simple_string("OK"))
+}
+
+pub fn process_flushall(
+    parts: &[String],
+    databases: &Databases
+) -> RespResult {
+    // parts[0] = "FLUSHALL", [parts[1] = ASYNC/SYNC]
+    let Some(lazy) = parse_flush_mode(parts) else {
+        return Ok(encode_error_string("ERR syntax error"));
+    };
+    for kv_store in databases.iter() {
+        flush(kv_store, lazy);
+    }
+    Ok(encode_simple_string("OK"))
+}
+
+// Whether the flush should free the old keys in the background. None means
+// the arguments didn't parse.
+fn parse_flush_mode(parts: &[String]) -> Option<bool> {
+    if parts.len() > 2 {
+        return None;
+    }
+    match parts.get(1).map(|mode| mode.to_uppercase()).as_deref() {
+        None | Some("SYNC") => Some(false),
+        Some("ASYNC") => Some(true),
+        Some(_) => None,
+    }
+}
+
+// Either way the keys are gone once this returns; ASYNC only moves the cost
+// of freeing them off the caller.
+fn flush(kv_store: &KvStore, lazy: bool) {
+    let mut map = kv_store.lock_all();
+    if lazy {
+        let old = map.take_all();
+        drop(map);
+        free_in_background(old);
+    } else {
+        map.clear();
+    }
+}
+
+pub fn process_save(
+    parts: &[String],
+    databases: &Databases,
+    server_config: &Arc<Mutex<ServerConfig>>
+) -> RespResult {
+    // parts[0] = "SAVE"
+    if parts.len() != 1 {
+        return Err("Malformed SAVE".to_string());
+    }
+    let path = rdb_path(&server_config.lock().unwrap());
+    match write_snapshot(&snapshot(databases), &path) {
+        Ok(()) => Ok(encode_simple_string("OK")),
+        Err(e) => {
+            log::warn!("SAVE to {} failed: {}", path.display(), e);
+            Ok(encode_error_string(&format!("ERR {}", e)))
+        },
+    }
+}
+
+pub fn process_bgsave(
+    parts: &[String],
+    databases: &Databases,
+    server_config: &Arc<Mutex<ServerConfig>>,
+    bgsave_in_progress: &Arc<AtomicBool>
+) -> RespResult {
+    // parts[0] = "BGSAVE", [parts[1] = SCHEDULE]
+    if parts.len() > 2 {
+        return Ok(encode_error_string("ERR syntax error"));
+    }
+    if bgsave_in_progress.swap(true, Ordering::AcqRel) {
+        return Ok(encode_error_string("ERR Background save already in progress"));
+    }
+    // The copy is taken now, under the locks; only encoding and writing it
+    // happen off this connection
+    let path = rdb_path(&server_config.lock().unwrap());
+    let snapshot = snapshot(databases);
+    let bgsave_in_progress = Arc::clone(bgsave_in_progress);
+    std::thread::spawn(move || {
+        if let Err(e) = write_snapshot(&snapshot, &path) {
+            log::warn!("BGSAVE to {} failed: {}", path.display(), e);
+        }
+        bgsave_in_progress.store(false, Ordering::Release);
+    });
+    Ok(encode_simple_string("Background saving started"))
+}
+
+pub fn process_select(
+    parts: &[String],
+    client: &mut ClientState,
+    databases: &Databases
+) -> RespResult {
+    // parts[0] = "SELECT", parts[1] = index
+    if parts.len() != 2 {
+        return Err("Malformed SELECT".to_string());
+    }
+    let index: i64 = match parts[1].parse() {
+        Ok(index) => index,
+        Err(_) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+    };
+    if index < 0 || index as usize >= databases.len() {
+        return Ok(encode_error_string("ERR DB index is out of range"));
+    }
+    client.db_index = index as usize;
+    Ok(encode_simple_string("OK"))
+}
+
+pub fn process_hello(
+    parts: &[String],
+    client: &mut ClientState,
+    acl: &Acl,
+    server_info: &Arc<Mutex<ServerInfo>>
+) -> RespResult {
+    // parts[0] = "HELLO", [parts[1] = protover, [AUTH username password] [SETNAME clientname]]
+    let mut protocol = client.protocol;
+    if let Some(raw) = parts.get(1) {
+        match raw.parse::<i64>() {
+            Ok(version @ (2 | 3)) => protocol = version as u8,
+            Ok(_) => return Ok(encode_error_string("NOPROTO unsupported protocol version")),
+            Err(_) => return Ok(encode_error_string("ERR Protocol version is not an integer or out of range")),
+        }
+    }
+
+    // Every option is checked before any takes effect
+    let mut name = None;
+    let mut user = None;
+    let mut options = parts.iter().skip(2);
+    while let Some(option) = options.next() {
+        match option.to_uppercase().as_str() {
+            "AUTH" => {
+                let (Some(username), Some(password)) = (options.next(), options.next()) else {
+                    return Ok(encode_error_string(&format!("ERR Syntax error in HELLO option '{}'", option)));
+                };
+                if !acl.check_password(username, password) {
+                    return Ok(encode_error_string("WRONGPASS invalid username-password pair or user is disabled."));
+                }
+                user = Some(username.clone());
+            },
+            "SETNAME" => {
+                let Some(clientname) = options.next() else {
+                    return Ok(encode_error_string(&format!("ERR Syntax error in HELLO option '{}'", option)));
+                };
+                if !is_valid_client_name(clientname) {
+                    return Ok(encode_error_string("ERR Client names cannot contain spaces, newlines or special characters."));
+                }
+                name = Some(clientname.clone());
+            },
+            _ => return Ok(encode_error_string(&format!("ERR Syntax error in HELLO option '{}'", option))),
+        }
+    }
+    if user.is_none() && !client.authenticated && acl.login_required() {
+        return Ok(encode_error_string(
+            "NOAUTH HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the client and select the RESP protocol version at the same time"
+        ));
+    }
+    if let Some(user) = user {
+        client.authenticated = true;
+        client.username = user;
+    }
+    client.protocol = protocol;
+    if name.is_some() {
+        client.name = name;
+    }
+
+    let role = server_info.lock().unwrap().replication_info.role.clone();
+    let fields = vec![
+        ("server", encode_bulk_string(SERVER_NAME)),
+        ("version", encode_bulk_string(SERVER_VERSION)),
+        ("proto", encode_integer(client.protocol as i64)),
+        ("mode", encode_bulk_string("standalone")),
+        ("role", encode_bulk_string(&role)),
+        ("modules", encode_array(&[])),
+    ];
+    // The reply already uses the protocol that was just negotiated
+    if client.protocol == 3 {
+        Ok(encode_raw_map(fields.into_iter().map(|(name, value)| (encode_bulk_string(name), value)).collect()))
+    } else {
+        Ok(encode_raw_array(fields.into_iter().flat_map(|(name, value)| [encode_bulk_string(name), value]).collect()))
+    }
+}
+
+/// RESET puts the connection back as it was when it connected: out of any
+/// transaction with nothing watched, unsubscribed from everything, on database 0
+/// and RESP2, with replies on, no name and no NO-EVICT, NO-TOUCH or CACHING
+/// flags, and logged in as the default user only if the server doesn't
+/// require a password. Its ID is kept.
+pub fn process_reset(
+    parts: &[String],
+    client: &mut ClientState,
+    pubsub: &PubSub,
+    acl: &Acl
+) -> RespResult {
+    // parts[0] = "RESET"
+    if parts.len() != 1 {
+        return Err("Malformed RESET".to_string());
+    }
+    client.command_queue = None;
+    client.transaction_dirty = false;
+    client.watched_keys.clear();
+    unsubscribe_all(client, pubsub);
+    client.db_index = 0;
+    client.protocol = 2;
+    client.name = None;
+    client.no_evict = false;
+    client.no_touch = false;
+    client.reply_mode = ReplyMode::On;
+    client.caching = false;
+    client.username = "default".to_string();
+    client.authenticated = !acl.login_required();
+    Ok(encode_simple_string("RESET"))
+}
+
+pub fn process_wait(
+    parts: &[String],
+    server_info: &Arc<Mutex<ServerInfo>>
+) -> RespResult {
+    // parts[0] = "WAIT", parts[1] = numreplicas, parts[2] = timeout
+    if parts.len() != 3 {
+        return Err("Malformed WAIT".to_string());
+    }
+    if parts[1].parse::<i64>().is_err() {
+        return Ok(encode_error_string("ERR value is not an integer or out of range"));
+    }
+    match parts[2].parse::<i64>() {
+        Ok(timeout) if timeout < 0 => return Ok(encode_error_string("ERR timeout is negative")),
+        Ok(_) => {},
+        Err(_) => return Ok(encode_error_string("ERR timeout is not an integer or out of range")),
+    }
+    // Replicas don't send REPLCONF ACK <offset> yet, so there's no way to know
+    // which have caught up: report every connected replica straight away instead
+    // of blocking. Full WAIT hooks in here once process_replconf records each
+    // replica's acked offset, waiting until enough reach master_repl_offset
+    let mut info = server_info.lock().unwrap();
+    info.prune_replicas();
+    Ok(encode_integer(info.replication_info.connected_slaves as i64))
+}
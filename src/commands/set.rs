@@ -0,0 +1,500 @@
+use std::collections::HashSet;
+
+use crate::models::{KvStore, RedisData, RedisValue, RespResult, SetOp, StoreGuard};
+use crate::utils::encoder::*;
+use crate::utils::random::{random_sample, MAX_SAMPLE_COUNT};
+use crate::utils::scan::parse_scan_args;
+
+pub fn process_sadd(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SADD", parts[1] = key, parts[2..] = members
+    if parts.len() < 3 {
+        return Err("Malformed SADD".to_string());
+    }
+    let key = parts[1].clone();
+    let mut map = kv_store.shard(&parts[1]);
+    let entry = map.entry(key).or_insert(RedisValue::new(
+        RedisData::Set(HashSet::new()),
+        None
+    ));
+
+    match &mut entry.data {
+        RedisData::Set(set) => {
+            let added = parts[2..].iter()
+                .filter(|member| set.insert(member.to_string()))
+                .count();
+            if added > 0 {
+                entry.touch();
+            }
+            Ok(encode_integer(added as i64))
+        },
+        _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"))
+    }
+}
+
+pub fn process_srem(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SREM", parts[1] = key, parts[2..] = members
+    if parts.len() < 3 {
+        return Err("Malformed SREM".to_string());
+    }
+    let key = &parts[1];
+    let mut map = kv_store.shard(&parts[1]);
+    let mut should_remove = false;
+
+    let response = match map.get_mut(key) {
+        Some(value) => match &mut value.data {
+            RedisData::Set(set) => {
+                let removed = parts[2..].iter()
+                    .filter(|member| set.remove(*member))
+                    .count();
+                should_remove = set.is_empty();
+                if removed > 0 {
+                    value.touch();
+                }
+                Ok(encode_integer(removed as i64))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    };
+
+    // Redis never keeps empty sets around
+    if should_remove {
+        map.remove(key);
+    }
+    response
+}
+
+pub fn process_smembers(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SMEMBERS", parts[1] = key
+    if parts.len() < 2 {
+        return Err("Malformed SMEMBERS".to_string());
+    }
+    let map = kv_store.read_keys(&parts[1..2]);
+    let members = match compute_set_op(&parts[1..2], &map, SetOp::Union) {
+        Ok(members) => members,
+        Err(reply) => return Ok(reply),
+    };
+    Ok(encode_array(&members.into_iter().collect::<Vec<String>>()))
+}
+
+pub fn process_scard(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SCARD", parts[1] = key
+    if parts.len() < 2 {
+        return Err("Malformed SCARD".to_string());
+    }
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::Set(set) => Ok(encode_integer(set.len() as i64)),
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    }
+}
+
+pub fn process_sismember(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SISMEMBER", parts[1] = key, parts[2] = member
+    if parts.len() < 3 {
+        return Err("Malformed SISMEMBER".to_string());
+    }
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::Set(set) => {
+                value.mark_accessed();
+                Ok(encode_integer(set.contains(&parts[2]) as i64))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    }
+}
+
+pub fn process_smismember(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SMISMEMBER", parts[1] = key, parts[2..] = members
+    if parts.len() < 3 {
+        return Err("Malformed SMISMEMBER".to_string());
+    }
+    let map = kv_store.shard_read(&parts[1]);
+    let set = match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::Set(set) => Some(set),
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => None
+    };
+
+    let flags = parts[2..].iter()
+        .map(|member| encode_integer(set.is_some_and(|s| s.contains(member)) as i64))
+        .collect();
+    Ok(encode_raw_array(flags))
+}
+
+pub fn process_srandmember(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SRANDMEMBER", parts[1] = key, [parts[2] = count]
+    if parts.len() < 2 {
+        return Err("Malformed SRANDMEMBER".to_string());
+    }
+    let count = match parse_count(parts.get(2)) {
+        Ok(Some(n)) if n.unsigned_abs() > MAX_SAMPLE_COUNT as u64 => {
+            return Ok(encode_error_string("ERR value is out of range"));
+        },
+        Ok(count) => count,
+        Err(reply) => return Ok(reply),
+    };
+
+    let map = kv_store.shard_read(&parts[1]);
+    let members: Vec<&String> = match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::Set(set) => set.iter().collect(),
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Vec::new()
+    };
+
+    match count {
+        None => match random_sample(&members, 1).first() {
+            Some(member) => Ok(encode_bulk_string(member)),
+            None => Ok(encode_null_string()),
+        },
+        Some(n) => {
+            let picked: Vec<String> = random_sample(&members, n).into_iter().cloned().collect();
+            Ok(encode_array(&picked))
+        }
+    }
+}
+
+pub fn process_spop(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SPOP", parts[1] = key, [parts[2] = count]
+    if parts.len() < 2 {
+        return Err("Malformed SPOP".to_string());
+    }
+    let count = match parse_count(parts.get(2)) {
+        Ok(Some(n)) if n < 0 => return Ok(encode_error_string("ERR value is out of range, must be positive")),
+        Ok(count) => count,
+        Err(reply) => return Ok(reply),
+    };
+    let key = &parts[1];
+    let mut map = kv_store.shard(&parts[1]);
+    let mut should_remove = false;
+
+    let popped = match map.get_mut(key) {
+        Some(value) => match &mut value.data {
+            RedisData::Set(set) => {
+                let members: Vec<String> = set.iter().cloned().collect();
+                let popped = random_sample(&members, count.unwrap_or(1));
+                for member in &popped {
+                    set.remove(member);
+                }
+                should_remove = set.is_empty();
+                if !popped.is_empty() {
+                    value.touch();
+                }
+                popped
+            },
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Vec::new()
+    };
+
+    // Redis never keeps empty sets around
+    if should_remove {
+        map.remove(key);
+    }
+    match count {
+        None => match popped.first() {
+            Some(member) => Ok(encode_bulk_string(member)),
+            None => Ok(encode_null_string()),
+        },
+        Some(_) => Ok(encode_array(&popped)),
+    }
+}
+
+pub fn process_smove(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SMOVE", parts[1] = source, parts[2] = destination, parts[3] = member
+    if parts.len() < 4 {
+        return Err("Malformed SMOVE".to_string());
+    }
+    let (source, destination, member) = (&parts[1], &parts[2], &parts[3]);
+    let mut map = kv_store.lock_keys(&[source, destination]);
+
+    // Both keys must be sets (or missing) before anything is touched
+    for key in [source, destination] {
+        if map.get(key).is_some_and(|value| !matches!(value.data, RedisData::Set(_))) {
+            return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"));
+        }
+    }
+
+    let source_emptied = match map.get_mut(source) {
+        Some(value) => match &mut value.data {
+            RedisData::Set(set) if set.contains(member) => {
+                // Moving within the same set changes nothing
+                if source == destination {
+                    return Ok(encode_integer(1));
+                }
+                set.remove(member);
+                let emptied = set.is_empty();
+                value.touch();
+                emptied
+            },
+            _ => return Ok(encode_integer(0)),
+        },
+        None => return Ok(encode_integer(0)),
+    };
+    if source_emptied {
+        map.remove(source);
+    }
+
+    let entry = map.entry(destination.clone()).or_insert(RedisValue::new(
+        RedisData::Set(HashSet::new()),
+        None
+    ));
+    if let RedisData::Set(set) = &mut entry.data {
+        set.insert(member.clone());
+        entry.touch();
+    }
+    Ok(encode_integer(1))
+}
+
+pub fn process_sscan(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SSCAN", parts[1] = key, parts[2] = cursor, [MATCH pattern] [COUNT count]
+    if parts.len() < 3 {
+        return Err("Malformed SSCAN".to_string());
+    }
+    let scan = match parse_scan_args(&parts[2..]) {
+        Ok(scan) => scan,
+        Err(reply) => return Ok(reply),
+    };
+
+    let map = kv_store.shard_read(&parts[1]);
+    let mut members = Vec::new();
+    // Same single-page approach as HSCAN: cursor 0 returns everything, others are finished
+    if let (0, Some(value)) = (scan.cursor, map.get(&parts[1])) {
+        match &value.data {
+            RedisData::Set(set) => members.extend(set.iter().filter(|m| scan.matches(m)).cloned()),
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        }
+    }
+    Ok(encode_raw_array(vec![encode_bulk_string("0"), encode_array(&members)]))
+}
+
+pub fn process_sinter(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SINTER", parts[1..] = keys
+    if parts.len() < 2 {
+        return Err("Incomplete SINTER command".to_string());
+    }
+    let map = kv_store.read_keys(&parts[1..]);
+    let members = match compute_set_op(&parts[1..], &map, SetOp::Inter) {
+        Ok(members) => members,
+        Err(reply) => return Ok(reply),
+    };
+    Ok(encode_array(&members.into_iter().collect::<Vec<String>>()))
+}
+
+pub fn process_sunion(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SUNION", parts[1..] = keys
+    if parts.len() < 2 {
+        return Err("Incomplete SUNION command".to_string());
+    }
+    let map = kv_store.read_keys(&parts[1..]);
+    let members = match compute_set_op(&parts[1..], &map, SetOp::Union) {
+        Ok(members) => members,
+        Err(reply) => return Ok(reply),
+    };
+    Ok(encode_array(&members.into_iter().collect::<Vec<String>>()))
+}
+
+pub fn process_sdiff(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SDIFF", parts[1] = first key, parts[2..] = keys to subtract
+    if parts.len() < 2 {
+        return Err("Incomplete SDIFF command".to_string());
+    }
+    let map = kv_store.read_keys(&parts[1..]);
+    let members = match compute_set_op(&parts[1..], &map, SetOp::Diff) {
+        Ok(members) => members,
+        Err(reply) => return Ok(reply),
+    };
+    Ok(encode_array(&members.into_iter().collect::<Vec<String>>()))
+}
+
+pub fn process_sinterstore(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SINTERSTORE", parts[1] = destination, parts[2..] = keys
+    store_set_op(parts, kv_store, SetOp::Inter)
+}
+
+pub fn process_sunionstore(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SUNIONSTORE", parts[1] = destination, parts[2..] = keys
+    store_set_op(parts, kv_store, SetOp::Union)
+}
+
+pub fn process_sdiffstore(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SDIFFSTORE", parts[1] = destination, parts[2] = first key, parts[3..] = keys to subtract
+    store_set_op(parts, kv_store, SetOp::Diff)
+}
+
+pub fn process_sintercard(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SINTERCARD", parts[1] = numkeys, parts[2..2+numkeys] = keys, [LIMIT n]
+    if parts.len() < 2 {
+        return Err("Malformed SINTERCARD".to_string());
+    }
+    let numkeys: usize = match parts[1].parse() {
+        Ok(n) if n > 0 => n,
+        _ => return Ok(encode_error_string("ERR numkeys should be greater than 0")),
+    };
+    if numkeys > parts.len().saturating_sub(2) {
+        return Ok(encode_error_string("ERR Number of keys can't be greater than number of args"));
+    }
+    let keys = &parts[2..2 + numkeys];
+
+    // LIMIT 0 means no limit
+    let limit = match &parts[2 + numkeys..] {
+        [] => 0,
+        [option, value] if option.to_uppercase() == "LIMIT" => match value.parse::<usize>() {
+            Ok(limit) => limit,
+            Err(_) => return Ok(encode_error_string("ERR LIMIT can't be negative")),
+        },
+        _ => return Ok(encode_error_string("ERR syntax error")),
+    };
+
+    let map = kv_store.read_keys(keys);
+    let cardinality = match compute_set_op(keys, &map, SetOp::Inter) {
+        Ok(members) => members.len(),
+        Err(reply) => return Ok(reply),
+    };
+    let cardinality = if limit > 0 { cardinality.min(limit) } else { cardinality };
+    Ok(encode_integer(cardinality as i64))
+}
+
+// Shared body of the *STORE variants: computes the result under a single lock and
+// overwrites the destination, which is deleted instead when the result is empty
+fn store_set_op(
+    parts: &[String],
+    kv_store: &KvStore,
+    op: SetOp
+) -> RespResult {
+    if parts.len() < 3 {
+        return Err(format!("Incomplete {} command", parts[0].to_uppercase()));
+    }
+    let destination = parts[1].clone();
+    let mut map = kv_store.lock_keys(&parts[1..]);
+    let members = match compute_set_op(&parts[2..], &map, op) {
+        Ok(members) => members,
+        Err(reply) => return Ok(reply),
+    };
+    let cardinality = members.len() as i64;
+
+    if members.is_empty() {
+        map.remove(&destination);
+    } else {
+        map.insert(destination, RedisValue::new(RedisData::Set(members), None));
+    }
+    Ok(encode_integer(cardinality))
+}
+
+// Parses the optional count argument, handing back an error reply for non-integers
+fn parse_count(raw: Option<&String>) -> Result<Option<i64>, Vec<u8>> {
+    match raw {
+        Some(raw) => raw.parse()
+            .map(Some)
+            .map_err(|_| encode_error_string("ERR value is not an integer or out of range")),
+        None => Ok(None),
+    }
+}
+
+/// Combines the sets stored at `keys` according to `op`.
+///
+/// Missing keys behave like empty sets; any key holding another type
+/// fails the whole operation with the WRONGTYPE reply.
+fn compute_set_op(
+    keys: &[String],
+    map: &StoreGuard,
+    op: SetOp
+) -> Result<HashSet<String>, Vec<u8>> {
+    let mut sets: Vec<Option<&HashSet<String>>> = Vec::new();
+    for key in keys {
+        match map.get(key) {
+            Some(value) => match &value.data {
+                RedisData::Set(set) => {
+                    value.mark_accessed();
+                    sets.push(Some(set));
+                },
+                _ => return Err(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+            },
+            None => sets.push(None),
+        }
+    }
+
+    let (first, rest) = match sets.split_first() {
+        Some((first, rest)) => (*first, rest),
+        None => return Ok(HashSet::new()),
+    };
+
+    let result = match op {
+        SetOp::Union => sets.iter()
+            .flatten()
+            .flat_map(|s| s.iter().cloned())
+            .collect(),
+        SetOp::Inter => first.into_iter()
+            .flatten()
+            .filter(|member| rest.iter().all(|s| s.is_some_and(|s| s.contains(*member))))
+            .cloned()
+            .collect(),
+        SetOp::Diff => first.into_iter()
+            .flatten()
+            .filter(|member| !rest.iter().flatten().any(|s| s.contains(*member)))
+            .cloned()
+            .collect(),
+    };
+    Ok(result)
+}
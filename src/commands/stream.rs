@@ -1,16 +1,14 @@
-use std::sync::{Arc, Mutex};
-use std::collections::{VecDeque, HashMap};
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::mpsc;
 
-use crate::models::{RedisData, RedisValue, StreamEntry, RespResult};
+use crate::models::{KvStore, RedisData, RedisValue, Stream, StreamEntry, RespResult, WaitingRoom};
 use crate::utils::async_helpers::*;
 use crate::utils::encoder::*;
 
 pub fn process_xadd(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
 ) -> RespResult {
     // parts[0] = "XADD", parts[1] = key, parts[2] = entry_id, parts[3..] = field value pairs
     if parts.len() < 5 {
@@ -25,81 +23,117 @@ pub fn process_xadd(
         .map(|chunk| (chunk[0].clone(), chunk[1].clone()))
         .collect();
 
-    let stream_entry = StreamEntry { id: entity_id.clone(), fields: map_elements };
-
-    let mut map = kv_store.lock().unwrap();
+    let mut map = kv_store.shard(&parts[1]);
+    // A rejected ID mustn't leave behind the empty stream made to hold it
+    let created = !map.contains_key(&key);
 
     let entry = map.entry(key.clone()).or_insert(RedisValue::new(
-        RedisData::Stream(Vec::new()),
+        RedisData::Stream(Stream::new()),
         None
     ));
 
     match &mut entry.data {
-        RedisData::Stream(stream) => {
-            let (initial_ms, initial_seq) = parse_entity_id(&entity_id);
-
-            // Handle sequence auto-generation if the ID was "1234-*"
-            let (new_ms, new_seq) = if parts[2].ends_with("-*") {
-                if let Some(last_entry) = stream.last() {
-                    let (last_ms, last_seq) = parse_entity_id(&last_entry.id);
-
-                    if initial_ms == last_ms {
-                        (initial_ms, last_seq + 1)
-                    } else if initial_ms == 0 {
-                        (initial_ms, 1)
-                    } else {
-                        (initial_ms, 0)
-                    }
-                } else {
-                    let seq = if initial_ms == 0 { 1 } else { 0 };
-                    (initial_ms, seq)
+        RedisData::Stream(stream) => match append_entry(stream, &entity_id, map_elements) {
+            Ok(resolved_id) => {
+                entry.touch();
+
+                // Unlike BLPOP, every XREAD blocked on the stream gets to see the new entry
+                wake_all_waiters(&key, &resolved_id, waiting_room);
+                Ok(encode_bulk_string(&resolved_id))
+            },
+            Err(reply) => {
+                if created {
+                    map.remove(&key);
                 }
-            } else {
-                (initial_ms, initial_seq)
-            };
+                Ok(reply)
+            },
+        },
+        _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"))
+    }
+}
 
-            if new_ms == 0 && new_seq == 0 {
-                return Ok("-ERR The ID specified in XADD must be greater than 0-0\r\n".as_bytes().to_vec());
-            }
+// Resolves `entity_id` against the stream's last ID and appends the entry,
+// returning the ID it was stored under or the error reply to send
+fn append_entry(
+    stream: &mut Stream,
+    entity_id: &str,
+    fields: HashMap<String, String>
+) -> Result<String, Vec<u8>> {
+    let (mut initial_ms, initial_seq) = parse_entity_id(entity_id);
+
+    // Handle sequence auto-generation if the ID was "*" or "1234-*"
+    let (new_ms, new_seq) = if entity_id == "*" || entity_id.ends_with("-*") {
+        let (last_ms, last_seq) = parse_entity_id(&stream.last_id);
+
+        // A fully generated ID never goes backwards, even if the clock
+        // is behind the last ID (e.g. after XSETID or clock skew)
+        if entity_id == "*" {
+            initial_ms = initial_ms.max(last_ms);
+        }
 
-            let resolved_id = format!("{}-{}", new_ms, new_seq);
-            println!("{} RESOLVED ID", resolved_id);
-
-            let is_valid = valid_entity_id(stream, &resolved_id);
-            match is_valid {
-                true => {
-                    let mut room = waiting_room.lock().unwrap();
-                    let mut finalized_entry = stream_entry;
-                    finalized_entry.id = resolved_id.clone();
-                    stream.push(finalized_entry);
-
-                    if let Some(queue) = room.get_mut(&key) {
-                        while let Some(tx) = queue.pop_front() {
-                            // Send the ID to wake up the XREAD thread
-                            if tx.try_send(resolved_id.clone()).is_ok() {
-                                println!("DEBUG: XADD successfully notified a waiter");
-                                // In Redis, XREAD BLOCK usually wakes up ALL waiters, 
-                                // but BLPOP only wakes up one. For XREAD, empty full queue
-                            } else {
-                                println!("DEBUG: Found a dead waiter, moving to next");
-                            }
-                        }
-                    }
-                    Ok(encode_bulk_string(&resolved_id))
+        if initial_ms == last_ms {
+            match last_seq.checked_add(1) {
+                Some(seq) => (initial_ms, seq),
+                // "*" moves on to the next millisecond; "ms-*" is pinned to its own
+                None if entity_id == "*" => match last_ms.checked_add(1) {
+                    Some(ms) => (ms, 0),
+                    None => return Err(encode_error_string(
+                        "ERR The stream has exhausted the last possible ID, unable to add more items"
+                    )),
                 },
-                false => Ok("-ERR The ID specified in XADD is equal or smaller than the target stream top item\r\n".as_bytes().to_vec())
+                None => return Err(encode_error_string(
+                    "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+                )),
             }
-        },
-        _ => Err("WRONGTYPE Operation against a key that is not a stream".to_string())
+        } else if initial_ms == 0 {
+            (initial_ms, 1)
+        } else {
+            (initial_ms, 0)
+        }
+    } else {
+        (initial_ms, initial_seq)
+    };
+
+    if new_ms == 0 && new_seq == 0 {
+        return Err(encode_error_string("ERR The ID specified in XADD must be greater than 0-0"));
+    }
+
+    let resolved_id = format!("{}-{}", new_ms, new_seq);
+    log::debug!("XADD resolved ID {}", resolved_id);
+
+    if !valid_entity_id(stream, &resolved_id) {
+        return Err(encode_error_string("ERR The ID specified in XADD is equal or smaller than the target stream top item"));
     }
+    // Always store the resolved "ms-seq" so range comparisons never see a wildcard
+    stream.entries.push(StreamEntry { id: resolved_id.clone(), fields });
+    stream.last_id = resolved_id.clone();
+    stream.entries_added += 1;
+    Ok(resolved_id)
 }
 
 pub async fn process_xread(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    read_streams(parts, kv_store, Some(waiting_room)).await
+}
+
+// XREAD as run from EXEC: transactions never block, so BLOCK is ignored
+pub async fn process_xread_nowait(
+    parts: &[String],
+    kv_store: &KvStore
 ) -> RespResult {
-    // parts[0] = "XREAD", optionally [BLOCK ms], then "STREAMS", then keys..., then ids...
+    read_streams(parts, kv_store, None).await
+}
+
+// Shared XREAD body; only blocks when BLOCK is given and there is a waiting room to wait in
+async fn read_streams(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: Option<&WaitingRoom>
+) -> RespResult {
+    // parts[0] = "XREAD", optionally [COUNT n] [BLOCK ms], then "STREAMS", then keys..., then ids...
     if parts.len() < 4 {
         return Err("Malformed XREAD".to_string());
     }
@@ -109,37 +143,64 @@ pub async fn process_xread(
         .position(|r| r.to_uppercase() == "STREAMS")
         .ok_or_else(|| "Missing STREAMS keyword".to_string())?;
 
-    // Check for BLOCK option
-    let block_ms: Option<f64> = parts.iter()
-        .position(|r| r.to_uppercase() == "BLOCK")
-        .and_then(|idx| parts.get(idx + 1))
-        .and_then(|v| v.parse().ok());
+    // Check for BLOCK option; STREAMS always follows, so the value is there
+    let block_ms = match parts[..streams_idx].iter().position(|r| r.to_uppercase() == "BLOCK") {
+        Some(idx) => match parse_block_ms(&parts[idx + 1]) {
+            Ok(ms) => Some(ms),
+            Err(reply) => return Ok(reply),
+        },
+        None => None,
+    };
+
+    // Optional COUNT caps how many entries are returned per stream; COUNT 0 is no cap
+    let count: usize = match parts[..streams_idx].iter().position(|r| r.to_uppercase() == "COUNT") {
+        Some(idx) => match parts.get(idx + 1).and_then(|v| v.parse().ok()) {
+            Some(0) => usize::MAX,
+            Some(count) => count,
+            None => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+        },
+        None => usize::MAX,
+    };
 
     let remaining = &parts[streams_idx + 1..];
+    if remaining.is_empty() || !remaining.len().is_multiple_of(2) {
+        return Ok(encode_error_string(
+            "ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified."
+        ));
+    }
     let num_streams = remaining.len() / 2;
     let keys = &remaining[..num_streams];
     let ids = &remaining[num_streams..];
 
+    // A key of another type fails the whole read, before anything can block
+    if holds_non_stream(keys, kv_store) {
+        return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"));
+    }
+
     // handle dollar sign inputs
-    let effective_ids = get_effective_ids_for_xread(&keys, &ids, &kv_store);
+    let effective_ids = get_effective_ids_for_xread(keys, ids, kv_store);
+
+    // Register before the first read so an XADD landing between the read
+    // and the wait still wakes us instead of leaving us blocked. The waiter
+    // leaves the room when dropped, whether we're served, time out or return early
+    let waiter = block_ms.and(waiting_room).map(|room| init_waiting_room(keys, room));
 
     // Try to read stream immediately 
-    let mut result = perform_xread(&keys, &effective_ids, &kv_store);
+    let mut result = perform_xread(keys, &effective_ids, count, kv_store);
 
     if !result.is_empty() {
         return Ok(encode_raw_array(result));
     }
 
-    if let Some(timeout_val) = block_ms {
-        let (_tx, mut rx) = init_waiting_room(&keys, &waiting_room);
-        if timeout_val > 0.0 {
-            let duration = tokio::time::Duration::from_millis(timeout_val as u64);
-            let _ = tokio::time::timeout(duration, rx.recv()).await;
+    if let (Some(timeout_val), Some(mut waiter)) = (block_ms, waiter) {
+        if timeout_val > 0 {
+            let duration = tokio::time::Duration::from_millis(timeout_val);
+            let _ = tokio::time::timeout(duration, waiter.rx.recv()).await;
         } else {
-            rx.recv().await;
+            waiter.rx.recv().await;
         }
         // Wake up and try to read again (Second pass)
-        result = perform_xread(&keys, &effective_ids, &kv_store);
+        result = perform_xread(keys, &effective_ids, count, kv_store);
     }
 
     if result.is_empty() {
@@ -149,24 +210,26 @@ pub async fn process_xread(
     }
 }
 
+// Whether any of `keys` is live and holds something other than a stream
+fn holds_non_stream(keys: &[String], kv_store: &KvStore) -> bool {
+    let map = kv_store.read_keys(keys);
+    keys.iter().any(|key| map.get(key).is_some_and(|value| !matches!(value.data, RedisData::Stream(_))))
+}
+
 fn get_effective_ids_for_xread(
     keys: &[String],
     ids: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    kv_store: &KvStore
 ) -> Vec<String> {
     let mut effective_ids = ids.to_vec();
     // scope the map lock
     {
-        let map = kv_store.lock().unwrap();
+        let map = kv_store.read_keys(keys);
         for i in 0..keys.len() {
             if ids[i] == "$" {
                 if let Some(RedisValue { data: RedisData::Stream(stream), .. }) = map.get(&keys[i]) {
-                    // If the stream exists, $ becomes the last ID currently in it
-                    if let Some(last_entry) = stream.last() {
-                        effective_ids[i] = last_entry.id.clone();
-                    } else {
-                        effective_ids[i] = "0-0".to_string();
-                    }
+                    // If the stream exists, $ becomes the last ID it has handed out
+                    effective_ids[i] = stream.last_id.clone();
                 } else {
                     // If key doesn't exist, $ is effectively 0-0
                     effective_ids[i] = "0-0".to_string();
@@ -180,21 +243,26 @@ fn get_effective_ids_for_xread(
 fn perform_xread(
     keys: &[String], 
     ids: &[String], 
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    count: usize,
+    kv_store: &KvStore
 ) -> Vec<Vec<u8>> {
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.read_keys(keys);
     let mut result = Vec::new();
 
     for i in 0..keys.len() {
         let key = &keys[i];
         let filter_id = parse_entity_id(&ids[i]);
 
-        if let Some(RedisValue { data: RedisData::Stream(stream), .. }) = map.get(key.as_str()) {
+        if let Some(value @ RedisValue { data: RedisData::Stream(stream), .. }) = map.get(key.as_str()) {
+            value.mark_accessed();
             let mut results_for_stream: Vec<Vec<u8>> = Vec::new();
-            for entry in stream {
+            for entry in &stream.entries {
+                if results_for_stream.len() >= count {
+                    break;
+                }
                 let entity_id_in_stream = parse_entity_id(&entry.id);
                 if entity_id_in_stream > filter_id {
-                    results_for_stream.push(encode_stream_entry(&entry));
+                    results_for_stream.push(encode_stream_entry(entry));
                 }
             }
             if !results_for_stream.is_empty() {
@@ -211,9 +279,9 @@ fn perform_xread(
 
 pub fn process_xrange(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    kv_store: &KvStore
 ) -> RespResult {
-    // parts[0] = "XRANGE", parts[1] = key, parts[2] = start, parts[3] = end
+    // parts[0] = "XRANGE", parts[1] = key, parts[2] = start, parts[3] = end, [parts[4] = COUNT, parts[5] = n]
     if parts.len() < 4 {
         return Err("Malformed XRANGE".to_string());
     }
@@ -221,51 +289,263 @@ pub fn process_xrange(
     let start_raw = &parts[2];
     let end_raw = &parts[3];
 
-    let start_bound = if start_raw == "-" {
-        (0, 0)
-    } else {
-        parse_entity_id(start_raw)
+    let start_bound = range_start_bound(start_raw);
+    let end_bound = range_end_bound(end_raw);
+
+    // Optional COUNT caps how many entries are returned
+    let count = match range_count(parts) {
+        Ok(count) => count,
+        Err(reply) => return Ok(reply),
     };
 
-    let end_bound = if end_raw == "+" {
-        (u64::MAX, u64::MAX)
-    } else {
-        let id_parts: Vec<&str> = end_raw.split('-').collect();
-        let ms = id_parts[0].parse::<u64>().unwrap_or(u64::MAX);
-        let seq = if id_parts.len() > 1 {
-            id_parts[1].parse::<u64>().unwrap_or(u64::MAX)
-        } else {
-            u64::MAX
-        };
-        (ms, seq)
+    kv_store.read_live(key, |value| match value {
+        Some(entry) => match &entry.data {
+            RedisData::Stream(stream) => {
+                entry.mark_accessed();
+                let mut entries_resp = Vec::new();
+
+                for entry in &stream.entries {
+                    if entries_resp.len() >= count {
+                        break;
+                    }
+                    let entry_id = parse_entity_id(&entry.id);
+                    if entry_id >= start_bound && entry_id <= end_bound {
+                        entries_resp.push(encode_stream_entry(entry))
+                    }
+                }
+                Ok(encode_raw_array(entries_resp))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_array(&[])),
+    })
+}
+
+pub fn process_xrevrange(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "XREVRANGE", parts[1] = key, parts[2] = end, parts[3] = start, [parts[4] = COUNT, parts[5] = n]
+    if parts.len() < 4 {
+        return Err("Malformed XREVRANGE".to_string());
+    }
+    let key = &parts[1];
+    let end_bound = range_end_bound(&parts[2]);
+    let start_bound = range_start_bound(&parts[3]);
+
+    let count = match range_count(parts) {
+        Ok(count) => count,
+        Err(reply) => return Ok(reply),
     };
 
-    let map = kv_store.lock().unwrap();
-    match map.get(key) {
+    kv_store.read_live(key, |value| match value {
         Some(entry) => match &entry.data {
             RedisData::Stream(stream) => {
+                entry.mark_accessed();
                 let mut entries_resp = Vec::new();
 
-                for entry in stream {
+                for entry in stream.entries.iter().rev() {
+                    if entries_resp.len() >= count {
+                        break;
+                    }
                     let entry_id = parse_entity_id(&entry.id);
                     if entry_id >= start_bound && entry_id <= end_bound {
-                        entries_resp.push(encode_stream_entry(&entry))
+                        entries_resp.push(encode_stream_entry(entry))
                     }
                 }
                 Ok(encode_raw_array(entries_resp))
             },
-            _ => Err("WRONGTYPE ...".to_string()),
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
         },
         None => Ok(encode_array(&[])),
+    })
+}
+
+pub fn process_xinfo(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "XINFO", parts[1] = sub-command, parts[2] = key
+    if parts.len() < 3 {
+        return Err("Malformed XINFO".to_string());
+    }
+    match parts[1].to_uppercase().as_str() {
+        "STREAM" => xinfo_stream(&parts[2], kv_store),
+        "GROUPS" | "CONSUMERS" | "FULL" => Ok(encode_error_string("ERR not implemented")),
+        other => Ok(encode_error_string(&format!("ERR unknown subcommand '{}'", other))),
     }
 }
 
-fn valid_entity_id(stream: &Vec<StreamEntry>, entity_id: &str) -> bool {
-    let (last_ms, last_seq): (u64, u64) = if let Some(last_entry) = stream.last() {
-        parse_entity_id(&last_entry.id)
-    } else {
-        (0, 0)
+fn xinfo_stream(
+    key: &str,
+    kv_store: &KvStore
+) -> RespResult {
+    let map = kv_store.shard_read(key);
+    let stream = match map.get(key) {
+        Some(RedisValue { data: RedisData::Stream(stream), .. }) => stream,
+        Some(_) => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        None => return Ok(encode_error_string("ERR no such key")),
+    };
+
+    // Entries live in a flat Vec rather than a radix tree, so report them as a single node
+    let radix_tree_keys = if stream.entries.is_empty() { 0 } else { 1 };
+    let first_entry = stream.entries.first().map_or_else(encode_null_string, encode_stream_entry);
+    let last_entry = stream.entries.last().map_or_else(encode_null_string, encode_stream_entry);
+
+    Ok(encode_raw_array(vec![
+        encode_bulk_string("length"),
+        encode_integer(stream.entries.len() as i64),
+        encode_bulk_string("radix-tree-keys"),
+        encode_integer(radix_tree_keys),
+        encode_bulk_string("radix-tree-nodes"),
+        encode_integer(radix_tree_keys + 1),
+        encode_bulk_string("last-generated-id"),
+        encode_bulk_string(&stream.last_id),
+        encode_bulk_string("max-deleted-entry-id"),
+        encode_bulk_string(&stream.max_deleted_entry_id),
+        encode_bulk_string("entries-added"),
+        encode_integer(stream.entries_added as i64),
+        encode_bulk_string("groups"),
+        encode_integer(0), // Consumer groups are not supported yet
+        encode_bulk_string("first-entry"),
+        first_entry,
+        encode_bulk_string("last-entry"),
+        last_entry,
+    ]))
+}
+
+pub fn process_xsetid(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "XSETID", parts[1] = key, parts[2] = last_id,
+    // then optionally [ENTRIESADDED n] [MAXDELETEDENTRYID id]
+    if parts.len() < 3 {
+        return Err("Malformed XSETID".to_string());
+    }
+    let key = &parts[1];
+    let Some(new_id) = parse_explicit_id(&parts[2]) else {
+        return Ok(encode_error_string("ERR Invalid stream ID specified as stream command argument"));
+    };
+
+    let mut entries_added: Option<u64> = None;
+    let mut max_deleted_id: Option<(u64, u64)> = None;
+    for option in parts[3..].chunks(2) {
+        let [name, value] = option else {
+            return Ok(encode_error_string("ERR syntax error"));
+        };
+        match name.to_uppercase().as_str() {
+            "ENTRIESADDED" => match value.parse() {
+                Ok(n) => entries_added = Some(n),
+                Err(_) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+            },
+            "MAXDELETEDENTRYID" => match parse_explicit_id(value) {
+                Some(id) => max_deleted_id = Some(id),
+                None => return Ok(encode_error_string("ERR Invalid stream ID specified as stream command argument")),
+            },
+            _ => return Ok(encode_error_string("ERR syntax error")),
+        }
+    }
+
+    // Everything is checked before anything changes, so a rejected XSETID
+    // leaves the stream (or the lack of one) exactly as it was. A missing
+    // key is checked as an empty stream and only stored once it passes.
+    let mut map = kv_store.shard(key);
+    let mut created = Stream::new();
+    let stream = match map.get_mut(key) {
+        Some(RedisValue { data: RedisData::Stream(stream), .. }) => stream,
+        Some(_) => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        None => &mut created,
     };
+    let top_id = stream.entries.last().map_or((0, 0), |e| parse_entity_id(&e.id));
+    if new_id < top_id {
+        return Ok(encode_error_string("ERR The ID specified in XSETID is smaller than the target stream top item"));
+    }
+    // Without the explicit bookkeeping options the last ID may only move forward
+    let has_options = entries_added.is_some() || max_deleted_id.is_some();
+    if !has_options && new_id < parse_entity_id(&stream.last_id) {
+        return Ok(encode_error_string("ERR The ID specified in XSETID is smaller than the current last ID"));
+    }
+    if entries_added.is_some_and(|n| n < stream.entries.len() as u64) {
+        return Ok(encode_error_string("ERR The entries_added specified in XSETID is smaller than the target stream length"));
+    }
+    if max_deleted_id.is_some_and(|id| new_id < id) {
+        return Ok(encode_error_string("ERR The ID specified in XSETID is smaller than the provided max_deleted_entry_id"));
+    }
+
+    if let Some(n) = entries_added {
+        stream.entries_added = n;
+    }
+    if let Some(id) = max_deleted_id {
+        stream.max_deleted_entry_id = format!("{}-{}", id.0, id.1);
+    }
+    stream.last_id = format!("{}-{}", new_id.0, new_id.1);
+    match map.get_mut(key) {
+        Some(entry) => entry.touch(),
+        None => {
+            map.insert(key.clone(), RedisValue::new(RedisData::Stream(created), None));
+        },
+    }
+    Ok(encode_simple_string("OK"))
+}
+
+pub fn process_xdel(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "XDEL", parts[1] = key, parts[2..] = ids
+    if parts.len() < 3 {
+        return Err("Malformed XDEL".to_string());
+    }
+    let mut ids = Vec::new();
+    for raw in &parts[2..] {
+        match parse_explicit_id(raw) {
+            Some(id) => ids.push(id),
+            None => return Ok(encode_error_string("ERR Invalid stream ID specified as stream command argument")),
+        }
+    }
+
+    let mut map = kv_store.shard(&parts[1]);
+    match map.get_mut(&parts[1]) {
+        Some(value) => match &mut value.data {
+            RedisData::Stream(stream) => {
+                let before = stream.entries.len();
+                let mut max_deleted = parse_entity_id(&stream.max_deleted_entry_id);
+                stream.entries.retain(|entry| {
+                    let entry_id = parse_entity_id(&entry.id);
+                    if ids.contains(&entry_id) {
+                        max_deleted = max_deleted.max(entry_id);
+                        false
+                    } else {
+                        true
+                    }
+                });
+                stream.max_deleted_entry_id = format!("{}-{}", max_deleted.0, max_deleted.1);
+                let deleted = before - stream.entries.len();
+                if deleted > 0 {
+                    value.touch();
+                }
+                // An emptied stream is kept so its last ID keeps guarding future XADDs
+                Ok(encode_integer(deleted as i64))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    }
+}
+
+// The COUNT of XRANGE/XREVRANGE key a b [COUNT n], or the error reply for a bad one
+fn range_count(parts: &[String]) -> Result<usize, Vec<u8>> {
+    match &parts[4..] {
+        [] => Ok(usize::MAX),
+        [option, count] if option.to_uppercase() == "COUNT" => count.parse()
+            .map_err(|_| encode_error_string("ERR value is not an integer or out of range")),
+        _ => Err(encode_error_string("ERR syntax error")),
+    }
+}
+
+fn valid_entity_id(stream: &Stream, entity_id: &str) -> bool {
+    let (last_ms, last_seq) = parse_entity_id(&stream.last_id);
 
     let (new_ms, new_seq) = parse_entity_id(entity_id);
     if (new_ms < last_ms) || (new_ms == last_ms && new_seq <= last_seq) {
@@ -296,3 +576,37 @@ fn parse_entity_id(entity_id: &str) -> (u64, u64) {
     };
     (ms, seq)
 }
+
+// Lower range bound: a bare "ms" means "ms-0"; either sentinel maps to its extreme
+fn range_start_bound(raw: &str) -> (u64, u64) {
+    match raw {
+        "-" => (0, 0),
+        "+" => (u64::MAX, u64::MAX),
+        _ => parse_entity_id(raw),
+    }
+}
+
+// Upper range bound: a bare "ms" covers every sequence in that ms; either sentinel maps to its extreme
+fn range_end_bound(raw: &str) -> (u64, u64) {
+    match raw {
+        "+" => return (u64::MAX, u64::MAX),
+        "-" => return (0, 0),
+        _ => {}
+    }
+    let id_parts: Vec<&str> = raw.split('-').collect();
+    let ms = id_parts[0].parse::<u64>().unwrap_or(u64::MAX);
+    let seq = if id_parts.len() > 1 {
+        id_parts[1].parse::<u64>().unwrap_or(u64::MAX)
+    } else {
+        u64::MAX
+    };
+    (ms, seq)
+}
+
+// Parses a fully specified "ms-seq" (or bare "ms") ID, rejecting wildcards and garbage
+fn parse_explicit_id(raw: &str) -> Option<(u64, u64)> {
+    match raw.split_once('-') {
+        Some((ms, seq)) => Some((ms.parse().ok()?, seq.parse().ok()?)),
+        None => Some((raw.parse().ok()?, 0)),
+    }
+}
@@ -1,68 +1,230 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::borrow::Cow;
 use std::time::Instant;
 
-use crate::models::{RedisData, RedisValue, RespResult};
+use crate::models::{KvStore, RedisData, RedisValue, RespResult};
 use crate::utils::encoder::*;
 
+// String commands take their arguments as the bytes the client sent, so a
+// value is stored exactly as given. Keys and options are still read as text
+fn text(arg: &impl AsRef<[u8]>) -> Cow<'_, str> {
+    String::from_utf8_lossy(arg.as_ref())
+}
+
 pub fn process_set(
-    parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore
 ) -> RespResult {
     // parts[0] = "SET", parts[1] = key, parts[2] = value, [parts[3] = EX/PX, parts[4] = time]
     if parts.len() < 3 {
         return Err("Incomplete SET command".to_string());
     }
 
-    let key = parts[1].clone();
-    let value = parts[2].clone();
+    let key = text(&parts[1]).into_owned();
+    let value = parts[2].as_ref().to_vec();
     let mut expires_at = None;
 
     // Handle expiry if present: SET key value EX 10 or SET key value PX 1000
     if parts.len() >= 5 {
-        let time_val = parts[4].parse::<u64>().unwrap_or(0);
-        match parts[3].to_uppercase().as_str() {
+        let time_val = text(&parts[4]).parse::<u64>().unwrap_or(0);
+        match text(&parts[3]).to_uppercase().as_str() {
             "EX" => expires_at = Some(Instant::now() + std::time::Duration::from_secs(time_val)),
             "PX" => expires_at = Some(Instant::now() + std::time::Duration::from_millis(time_val)),
             _ => return Err("Invalid expiry flag".to_string()),
         }
     }
 
-    let mut map = kv_store.lock().unwrap();
+    let mut map = kv_store.shard(&key);
     map.insert(key, RedisValue::new(RedisData::String(value), expires_at));
 
     Ok(encode_simple_string("OK"))
 }
 
 pub fn process_get(
-    parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore
+) -> RespResult {
+    get(parts, kv_store, true)
+}
+
+/// GET for a CLIENT NO-TOUCH connection, which leaves the key's idle time alone.
+pub fn process_get_no_touch(
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore
+) -> RespResult {
+    get(parts, kv_store, false)
+}
+
+fn get(
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore,
+    touch: bool
 ) -> RespResult {
     // parts[0] = "GET", parts[1] = key
     if parts.len() < 2 {
         return Err("Malformed GET".to_string());
     }
-    let key = &parts[1];
-    let mut map = kv_store.lock().unwrap();
-
-    let is_expired = match map.get(key) {
-        Some(redis_value) => {
-            match redis_value.expires_at {
-                Some(expiry) => Instant::now() > expiry,
-                None => false
-            }
+    kv_store.read_live(&text(&parts[1]), |value| match value {
+        Some(value) => match &value.data {
+            RedisData::String(s) => {
+                if touch {
+                    value.mark_accessed();
+                }
+                Ok(encode_bulk_bytes(s))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key not holding a string")),
         },
-        None => return Ok(encode_null_string()),
+        None => Ok(encode_null_string()),
+    })
+}
+
+pub fn process_setnx(
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SETNX", parts[1] = key, parts[2] = value
+    if parts.len() < 3 {
+        return Err("Malformed SETNX".to_string());
+    }
+    let key = text(&parts[1]).into_owned();
+    let mut map = kv_store.shard(&key);
+
+    if map.contains_key(&key) {
+        return Ok(encode_integer(0));
+    }
+    map.insert(key, RedisValue::new(RedisData::String(parts[2].as_ref().to_vec()), None));
+    Ok(encode_integer(1))
+}
+
+pub fn process_getset(
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "GETSET", parts[1] = key, parts[2] = value
+    if parts.len() < 3 {
+        return Err("Malformed GETSET".to_string());
+    }
+    let key = text(&parts[1]).into_owned();
+    let mut map = kv_store.shard(&key);
+
+    let old = match map.get(&key) {
+        Some(value) => match &value.data {
+            RedisData::String(s) => encode_bulk_bytes(s),
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key not holding a string")),
+        },
+        None => encode_null_string(),
+    };
+    // Like SET, the new value replaces any TTL the old one had
+    map.insert(key, RedisValue::new(RedisData::String(parts[2].as_ref().to_vec()), None));
+    Ok(old)
+}
+
+pub fn process_getrange(
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore
+) -> RespResult {
+    getrange(parts, kv_store, true)
+}
+
+/// GETRANGE for a CLIENT NO-TOUCH connection, which leaves the key's idle time alone.
+pub fn process_getrange_no_touch(
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore
+) -> RespResult {
+    getrange(parts, kv_store, false)
+}
+
+fn getrange(
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore,
+    touch: bool
+) -> RespResult {
+    // parts[0] = "GETRANGE", parts[1] = key, parts[2] = start, parts[3] = end
+    if parts.len() < 4 {
+        return Err("Malformed GETRANGE".to_string());
+    }
+    let (Ok(mut start), Ok(mut end)) = (text(&parts[2]).parse::<i64>(), text(&parts[3]).parse::<i64>()) else {
+        return Ok(encode_error_string("ERR value is not an integer or out of range"));
     };
 
-    if is_expired {
-        map.remove(key);
-        Ok(encode_null_string())
-    } else {
-        let val = map.get(key).unwrap();
-        match &val.data {
-            RedisData::String(s) => Ok(encode_bulk_string(s)),
-            _ => Err("WRONGTYPE Operation against a key not holding a string".to_string()),
+    kv_store.read_live(&text(&parts[1]), |value| {
+        let bytes = match value {
+            Some(value) => match &value.data {
+                RedisData::String(s) => {
+                    if touch {
+                        value.mark_accessed();
+                    }
+                    s.as_slice()
+                },
+                _ => return Ok(encode_error_string("WRONGTYPE Operation against a key not holding a string")),
+            },
+            None => return Ok(encode_bulk_string("")),
+        };
+
+        // Offsets are inclusive byte positions; negatives count back from the end
+        let len = bytes.len() as i64;
+        if start < 0 && end < 0 && start > end {
+            return Ok(encode_bulk_string(""));
+        }
+        if start < 0 {
+            start += len;
         }
+        if end < 0 {
+            end += len;
+        }
+        let start = start.max(0);
+        let end = end.max(0).min(len - 1);
+        if len == 0 || start > end {
+            return Ok(encode_bulk_string(""));
+        }
+        Ok(encode_bulk_bytes(&bytes[start as usize..=end as usize]))
+    })
+}
+
+// Same cap Redis puts on proto-max-bulk-len
+const MAX_STRING_LENGTH: usize = 512 * 1024 * 1024;
+
+pub fn process_setrange(
+    parts: &[impl AsRef<[u8]>],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "SETRANGE", parts[1] = key, parts[2] = offset, parts[3] = value
+    if parts.len() < 4 {
+        return Err("Malformed SETRANGE".to_string());
+    }
+    let key = text(&parts[1]).into_owned();
+    let value = parts[3].as_ref();
+    let offset = match text(&parts[2]).parse::<i64>() {
+        Ok(offset) if offset < 0 => return Ok(encode_error_string("ERR offset is out of range")),
+        Ok(offset) => offset as usize,
+        Err(_) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+    };
+    if offset.saturating_add(value.len()) > MAX_STRING_LENGTH {
+        return Ok(encode_error_string("ERR string exceeds maximum allowed size (proto-max-bulk-len)"));
+    }
+
+    let mut map = kv_store.shard(&key);
+    let Some(existing) = map.get_mut(&key) else {
+        // Nothing to write means nothing to create
+        if value.is_empty() {
+            return Ok(encode_integer(0));
+        }
+        let mut bytes = vec![0; offset];
+        bytes.extend_from_slice(value);
+        map.insert(key, RedisValue::new(RedisData::String(bytes), None));
+        return Ok(encode_integer((offset + value.len()) as i64));
+    };
+    let RedisData::String(s) = &mut existing.data else {
+        return Ok(encode_error_string("WRONGTYPE Operation against a key not holding a string"));
+    };
+    if value.is_empty() {
+        return Ok(encode_integer(s.len() as i64));
+    }
+
+    if s.len() < offset + value.len() {
+        s.resize(offset + value.len(), 0);
     }
+    s[offset..offset + value.len()].copy_from_slice(value);
+    let len = s.len();
+    existing.touch();
+    Ok(encode_integer(len as i64))
 }
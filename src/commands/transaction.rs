@@ -1,30 +1,32 @@
 use std::collections::{HashMap, VecDeque};
-use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
 use async_recursion::async_recursion;
 use crate::utils::encoder::*;
 use crate::models::*;
 use crate::executor::*;
+use crate::parser::lossy_parts;
 
 pub fn process_incr(
     parts: &[String],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>
+    kv_store: &KvStore
 ) -> RespResult {
     if parts.len() < 2 {
         return Err("Incomplete INCR command".to_string());
     }
 
     let key = &parts[1];
-    let mut map = kv_store.lock().unwrap();
+    let mut map = kv_store.shard(&parts[1]);
     let entry = map.get_mut(key.as_str());
 
     match entry {
         Some(value) => {
             match &mut value.data {
                 RedisData::String(item) => {
-                    if let Ok(num) = item.parse::<i64>() {
-                        let new_num = num + 1;
-                        *item = new_num.to_string(); 
+                    if let Some(num) = parse_integer(item) {
+                        let Some(new_num) = num.checked_add(1) else {
+                            return Ok(encode_error_string("ERR increment or decrement would overflow"));
+                        };
+                        *item = new_num.to_string().into_bytes();
+                        value.touch();
                         Ok(encode_integer(new_num))
                     } else {
                         Ok(encode_error_string("ERR value is not an integer or out of range"))
@@ -34,65 +36,118 @@ pub fn process_incr(
             }
         },
         None => {
-            map.insert(key.clone(), RedisValue::new(RedisData::String("1".to_string()), None));
+            map.insert(key.clone(), RedisValue::new(RedisData::String(b"1".to_vec()), None));
             Ok(encode_integer(1))
         },
     }
 }
 
 pub fn process_multi(
-    command_queue: &mut Option<VecDeque<Vec<String>>>
+    client: &mut ClientState
 ) -> RespResult {
-    if command_queue.is_some() {
+    if client.command_queue.is_some() {
         return Ok(encode_error_string("ERR MULTI calls can not be nested"));
     }
-    *command_queue = Some(VecDeque::new());
+    client.command_queue = Some(VecDeque::new());
+    client.transaction_dirty = false;
     Ok(encode_simple_string("OK"))
 }
 
 #[async_recursion]
 pub async fn process_exec(
-    command_queue: &mut Option<VecDeque<Vec<String>>>,
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>,
-    server_info: &Arc<Mutex<ServerInfo>>
+    client: &mut ClientState,
+    state: &ServerState
 ) -> RespResult {
-    let queue = match command_queue.take() {
+    let queue = match client.command_queue.take() {
         Some(q) => q,
         None => return Ok(encode_error_string("ERR EXEC without MULTI")),
     };
+    // EXEC always ends the watch, whether or not the transaction runs
+    let watched_keys = std::mem::take(&mut client.watched_keys);
+    if std::mem::take(&mut client.transaction_dirty) {
+        return Ok(encode_error_string("EXECABORT Transaction discarded because of previous errors."));
+    }
+    if watched_keys_changed(&watched_keys, &state.databases) {
+        return Ok(encode_null_array());
+    }
     if queue.is_empty() {
-        return Ok(encode_array(&vec![]));
+        return Ok(encode_array(&[]));
     }
     let mut responses: Vec<Vec<u8>> = Vec::new();
-    for parts in queue {
+    client.in_exec = true;
+    for args in queue {
+        let parts = lossy_parts(&args);
         let command_result = execute_commands(
             parts[0].to_uppercase(), 
             &parts, 
-            kv_store, 
-            waiting_room, 
-            &mut None, // MULTI/EXEC can't be nested so null command queue
-            server_info
+            &args,
+            state, 
+            client // queue was taken above, so queued commands run immediately
         ).await;
         responses.push(command_result);
     }
+    client.in_exec = false;
     Ok(encode_raw_array(responses))
 }
 
 pub fn process_discard(
-    command_queue: &mut Option<VecDeque<Vec<String>>>,
+    client: &mut ClientState,
 ) -> RespResult {
-    match command_queue.take() {
-        Some(_) => Ok(encode_simple_string("OK")),
+    match client.command_queue.take() {
+        Some(_) => {
+            client.watched_keys.clear();
+            client.transaction_dirty = false;
+            Ok(encode_simple_string("OK"))
+        },
         None => Ok(encode_error_string("ERR DISCARD without MULTI"))
     }
 }
 
-pub fn handle_push_command_queue(
+pub fn process_watch(
     parts: &[String],
-    command_queue: &mut VecDeque<Vec<String>>
+    client: &mut ClientState,
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "WATCH", parts[1..] = keys
+    if parts.len() < 2 {
+        return Err("Incomplete WATCH command".to_string());
+    }
+    if client.command_queue.is_some() {
+        return Ok(encode_error_string("ERR WATCH inside MULTI is not allowed"));
+    }
+    let map = kv_store.read_keys(&parts[1..]);
+    for key in &parts[1..] {
+        // Watching a key twice keeps the version from the first WATCH
+        client.watched_keys
+            .entry((client.db_index, key.clone()))
+            .or_insert_with(|| map.get(key).map(|value| value.version));
+    }
+    Ok(encode_simple_string("OK"))
+}
+
+pub fn process_unwatch(
+    client: &mut ClientState
+) -> RespResult {
+    client.watched_keys.clear();
+    Ok(encode_simple_string("OK"))
+}
+
+// A watched key counts as changed if it was written, created or deleted since WATCH
+fn watched_keys_changed(
+    watched_keys: &HashMap<(usize, String), Option<u64>>,
+    databases: &Databases
+) -> bool {
+    // Keys are checked against the database they were watched in, even if the client has since SELECTed another
+    watched_keys.iter().any(|((db, key), version)| {
+        databases[*db].shard_read(key).get(key).map(|value| value.version) != *version
+    })
+}
+
+pub fn handle_push_command_queue(
+    args: &[Vec<u8>],
+    command_queue: &mut VecDeque<Vec<Vec<u8>>>
 ) -> RespResult {
-    command_queue.push_back(parts.to_vec());
+    command_queue.push_back(args.to_vec());
     Ok(encode_simple_string("QUEUED"))
 }
 
@@ -0,0 +1,1181 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use tokio::time::{Duration, Instant};
+
+use crate::models::{
+    KvStore, LexBound, RedisData, RedisValue, RespResult, ScoreBound, SetOp, StoreGuard, WaitingRoom, ZAddOptions,
+    ZAggregate, ZPopSide, ZRangeBy, ZRangeOptions, ZSetOpOptions
+};
+use crate::utils::async_helpers::*;
+use crate::utils::encoder::*;
+use crate::utils::random::{random_sample, MAX_SAMPLE_COUNT};
+use crate::utils::scan::parse_scan_args;
+
+pub fn process_zadd(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    // parts[0] = "ZADD", parts[1] = key, [NX|XX] [GT|LT] [CH], then score member pairs
+    if parts.len() < 4 {
+        return Err("Malformed ZADD".to_string());
+    }
+    let key = parts[1].clone();
+
+    let mut options = ZAddOptions::default();
+    let mut idx = 2;
+    while let Some(flag) = parts.get(idx) {
+        match flag.to_uppercase().as_str() {
+            "NX" => options.nx = true,
+            "XX" => options.xx = true,
+            "GT" => options.gt = true,
+            "LT" => options.lt = true,
+            "CH" => options.ch = true,
+            _ => break,
+        }
+        idx += 1;
+    }
+    if options.nx && options.xx {
+        return Ok(encode_error_string("ERR XX and NX options at the same time are not compatible"));
+    }
+    if (options.gt && options.lt) || ((options.gt || options.lt) && options.nx) {
+        return Ok(encode_error_string("ERR GT, LT, and/or NX options at the same time are not compatible"));
+    }
+    let pair_args = &parts[idx..];
+    if pair_args.is_empty() || !pair_args.len().is_multiple_of(2) {
+        return Err("Malformed ZADD".to_string());
+    }
+
+    // Validate every score before touching the store so a bad pair adds nothing
+    let mut pairs: Vec<(f64, String)> = Vec::new();
+    for chunk in pair_args.chunks_exact(2) {
+        match parse_score(&chunk[0]) {
+            Ok(score) => pairs.push((score, chunk[1].clone())),
+            Err(reply) => return Ok(reply),
+        }
+    }
+    // A member repeated within the call is only counted once, with its last score
+    let mut seen = HashSet::new();
+    let mut pairs: Vec<(f64, String)> = pairs.into_iter().rev().filter(|(_, m)| seen.insert(m.clone())).collect();
+    pairs.reverse();
+
+    let mut map = kv_store.shard(&parts[1]);
+    // XX never creates members, so don't leave an empty key behind
+    if options.xx && !map.contains_key(&key) {
+        return Ok(encode_integer(0));
+    }
+    let entry = map.entry(key).or_insert(RedisValue::new(
+        RedisData::ZSet(Vec::new()),
+        None
+    ));
+
+    match &mut entry.data {
+        RedisData::ZSet(zset) => {
+            let (mut added, mut updated) = (0, 0);
+            for (score, member) in pairs {
+                let current = zset.iter().find(|(_, m)| *m == member).map(|(s, _)| *s);
+                let apply = match current {
+                    None => !options.xx,
+                    Some(old) => !options.nx
+                        && (!options.gt || score > old)
+                        && (!options.lt || score < old),
+                };
+                if !apply {
+                    continue;
+                }
+                match current {
+                    None => added += 1,
+                    Some(old) if old != score => updated += 1,
+                    Some(_) => continue,
+                }
+                remove_member(zset, &member);
+                insert_sorted(zset, score, member);
+            }
+            if added + updated > 0 {
+                entry.touch();
+            }
+            if added > 0 {
+                wake_all_waiters(&parts[1], &parts[1], waiting_room);
+            }
+            Ok(encode_integer(if options.ch { added + updated } else { added }))
+        },
+        _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"))
+    }
+}
+
+pub fn process_zcard(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZCARD", parts[1] = key
+    if parts.len() < 2 {
+        return Err("Malformed ZCARD".to_string());
+    }
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::ZSet(zset) => Ok(encode_integer(zset.len() as i64)),
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    }
+}
+
+pub fn process_zrank(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZRANK", parts[1] = key, parts[2] = member, [parts[3] = WITHSCORE]
+    if parts.len() < 3 {
+        return Err("Malformed ZRANK".to_string());
+    }
+    let with_score = match parts.get(3) {
+        Some(option) if option.to_uppercase() == "WITHSCORE" => true,
+        Some(_) => return Ok(encode_error_string("ERR syntax error")),
+        None => false
+    };
+    let null_reply = if with_score { encode_null_array() } else { encode_null_string() };
+
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::ZSet(zset) => match zset.iter().position(|(_, m)| *m == parts[2]) {
+                Some(rank) if with_score => Ok(encode_raw_array(vec![
+                    encode_integer(rank as i64),
+                    encode_bulk_string(&format_double(zset[rank].0)),
+                ])),
+                Some(rank) => Ok(encode_integer(rank as i64)),
+                None => Ok(null_reply),
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(null_reply)
+    }
+}
+
+pub fn process_zscore(
+    parts: &[String],
+    kv_store: &KvStore,
+    protocol: u8
+) -> RespResult {
+    // parts[0] = "ZSCORE", parts[1] = key, parts[2] = member
+    if parts.len() < 3 {
+        return Err("Malformed ZSCORE".to_string());
+    }
+    let key = &parts[1];
+    let member = &parts[2];
+
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(key) {
+        Some(value) => match &value.data {
+            RedisData::ZSet(zset) => {
+                value.mark_accessed();
+                match zset.iter().find(|(_, m)| m == member) {
+                    Some((score, _)) if protocol == 3 => Ok(encode_double(*score)),
+                    Some((score, _)) => Ok(encode_bulk_string(&format_double(*score))),
+                    None if protocol == 3 => Ok(encode_null()),
+                    None => Ok(encode_null_string()),
+                }
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None if protocol == 3 => Ok(encode_null()),
+        None => Ok(encode_null_string())
+    }
+}
+
+pub fn process_zrange(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZRANGE", parts[1] = key, parts[2] = start, parts[3] = stop,
+    // [BYSCORE|BYLEX] [REV] [LIMIT offset count] [WITHSCORES]
+    if parts.len() < 4 {
+        return Err("Malformed ZRANGE".to_string());
+    }
+    let mut options = ZRangeOptions::default();
+    let mut idx = 4;
+    while let Some(option) = parts.get(idx) {
+        match option.to_uppercase().as_str() {
+            "BYSCORE" => options.by = ZRangeBy::Score,
+            "BYLEX" => options.by = ZRangeBy::Lex,
+            "REV" => options.rev = true,
+            "WITHSCORES" => options.with_scores = true,
+            "LIMIT" => {
+                options.limit = match parse_limit(&parts[idx + 1..]) {
+                    Ok(limit) => Some(limit),
+                    Err(reply) => return Ok(reply),
+                };
+                idx += 2;
+            },
+            _ => return Ok(encode_error_string("ERR syntax error")),
+        }
+        idx += 1;
+    }
+    if options.limit.is_some() && options.by == ZRangeBy::Rank {
+        return Ok(encode_error_string("ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"));
+    }
+    if options.with_scores && options.by == ZRangeBy::Lex {
+        return Ok(encode_error_string("ERR syntax error, WITHSCORES not supported in combination with BYLEX"));
+    }
+    range_reply(&parts[1], &parts[2], &parts[3], &options, kv_store)
+}
+
+pub fn process_zrevrange(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZREVRANGE", parts[1] = key, parts[2] = start, parts[3] = stop, [parts[4] = WITHSCORES]
+    if parts.len() < 4 {
+        return Err("Malformed ZREVRANGE".to_string());
+    }
+    let with_scores = match parts.get(4) {
+        Some(option) if option.to_uppercase() == "WITHSCORES" => true,
+        Some(_) => return Ok(encode_error_string("ERR syntax error")),
+        None => false
+    };
+    let options = ZRangeOptions { rev: true, with_scores, ..Default::default() };
+    range_reply(&parts[1], &parts[2], &parts[3], &options, kv_store)
+}
+
+pub fn process_zrangebyscore(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZRANGEBYSCORE", parts[1] = key, parts[2] = min, parts[3] = max, [WITHSCORES] [LIMIT offset count]
+    legacy_range(parts, ZRangeBy::Score, false, kv_store)
+}
+
+pub fn process_zrevrangebyscore(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZREVRANGEBYSCORE", parts[1] = key, parts[2] = max, parts[3] = min, [WITHSCORES] [LIMIT offset count]
+    legacy_range(parts, ZRangeBy::Score, true, kv_store)
+}
+
+pub fn process_zrangebylex(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZRANGEBYLEX", parts[1] = key, parts[2] = min, parts[3] = max, [LIMIT offset count]
+    legacy_range(parts, ZRangeBy::Lex, false, kv_store)
+}
+
+pub fn process_zrevrangebylex(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZREVRANGEBYLEX", parts[1] = key, parts[2] = max, parts[3] = min, [LIMIT offset count]
+    legacy_range(parts, ZRangeBy::Lex, true, kv_store)
+}
+
+// Shared body of the ZRANGEBYSCORE/ZRANGEBYLEX family; only the score variants take WITHSCORES
+fn legacy_range(
+    parts: &[String],
+    by: ZRangeBy,
+    rev: bool,
+    kv_store: &KvStore
+) -> RespResult {
+    if parts.len() < 4 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let mut options = ZRangeOptions { by, rev, ..Default::default() };
+    let mut idx = 4;
+    while let Some(option) = parts.get(idx) {
+        match option.to_uppercase().as_str() {
+            "WITHSCORES" if options.by == ZRangeBy::Score => options.with_scores = true,
+            "LIMIT" => {
+                options.limit = match parse_limit(&parts[idx + 1..]) {
+                    Ok(limit) => Some(limit),
+                    Err(reply) => return Ok(reply),
+                };
+                idx += 2;
+            },
+            _ => return Ok(encode_error_string("ERR syntax error")),
+        }
+        idx += 1;
+    }
+    range_reply(&parts[1], &parts[2], &parts[3], &options, kv_store)
+}
+
+pub fn process_zrem(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZREM", parts[1] = key, parts[2..] = members
+    if parts.len() < 3 {
+        return Err("Malformed ZREM".to_string());
+    }
+    let key = &parts[1];
+    let mut map = kv_store.shard(key);
+    let mut should_remove = false;
+
+    let response = match map.get_mut(key) {
+        Some(value) => match &mut value.data {
+            RedisData::ZSet(zset) => {
+                let removed = parts[2..].iter()
+                    .filter(|member| remove_member(zset, member).is_some())
+                    .count();
+                should_remove = zset.is_empty();
+                if removed > 0 {
+                    value.touch();
+                }
+                Ok(encode_integer(removed as i64))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    };
+
+    if should_remove {
+        map.remove(key);
+    }
+    response
+}
+
+pub fn process_zincrby(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    // parts[0] = "ZINCRBY", parts[1] = key, parts[2] = increment, parts[3] = member
+    if parts.len() < 4 {
+        return Err("Malformed ZINCRBY".to_string());
+    }
+    let increment = match parse_score(&parts[2]) {
+        Ok(increment) => increment,
+        Err(reply) => return Ok(reply),
+    };
+    let member = &parts[3];
+
+    let mut map = kv_store.shard(&parts[1]);
+    let entry = map.entry(parts[1].clone()).or_insert(RedisValue::new(
+        RedisData::ZSet(Vec::new()),
+        None
+    ));
+
+    match &mut entry.data {
+        RedisData::ZSet(zset) => {
+            let current = zset.iter().find(|(_, m)| m == member).map(|(score, _)| *score);
+            let score = current.unwrap_or(0.0) + increment;
+            // inf + -inf
+            if score.is_nan() {
+                return Ok(encode_error_string("ERR resulting score is not a number (NaN)"));
+            }
+            remove_member(zset, member);
+            insert_sorted(zset, score, member.clone());
+            entry.touch();
+            if current.is_none() {
+                wake_all_waiters(&parts[1], &parts[1], waiting_room);
+            }
+            Ok(encode_bulk_string(&format_double(score)))
+        },
+        _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"))
+    }
+}
+
+pub fn process_zcount(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZCOUNT", parts[1] = key, parts[2] = min, parts[3] = max
+    if parts.len() < 4 {
+        return Err("Malformed ZCOUNT".to_string());
+    }
+    let (min, max) = match (ScoreBound::parse(&parts[2]), ScoreBound::parse(&parts[3])) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return Ok(encode_error_string("ERR min or max is not a float")),
+    };
+
+    let map = kv_store.shard_read(&parts[1]);
+    match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::ZSet(zset) => {
+                let count = zset.iter()
+                    .filter(|(score, _)| min.allows_above(*score) && max.allows_below(*score))
+                    .count();
+                Ok(encode_integer(count as i64))
+            },
+            _ => Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => Ok(encode_integer(0))
+    }
+}
+
+pub fn process_zpopmin(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZPOPMIN", parts[1] = key, [parts[2] = count]
+    zpop(parts, ZPopSide::Min, kv_store)
+}
+
+pub fn process_zpopmax(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZPOPMAX", parts[1] = key, [parts[2] = count]
+    zpop(parts, ZPopSide::Max, kv_store)
+}
+
+// Shared body of ZPOPMIN and ZPOPMAX: a flat member, score, member, score... reply
+fn zpop(
+    parts: &[String],
+    side: ZPopSide,
+    kv_store: &KvStore
+) -> RespResult {
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let count = match parts.get(2).map(|raw| raw.parse::<i64>()) {
+        None => 1,
+        Some(Ok(count)) if count >= 0 => count as usize,
+        Some(Ok(_)) => return Ok(encode_error_string("ERR value is out of range, must be positive")),
+        Some(Err(_)) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+    };
+    match pop_first_nonempty(&parts[1..2], &side, count, kv_store) {
+        Ok(Some((_, popped))) => Ok(encode_array(&flatten_entries(&popped))),
+        Ok(None) => Ok(encode_array(&[])),
+        Err(reply) => Ok(reply),
+    }
+}
+
+pub async fn process_bzpopmin(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    // parts[0] = "BZPOPMIN", parts[1..n-1] = keys, parts[n-1] = timeout
+    bzpop(parts, ZPopSide::Min, kv_store, Some(waiting_room)).await
+}
+
+pub async fn process_bzpopmax(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    // parts[0] = "BZPOPMAX", parts[1..n-1] = keys, parts[n-1] = timeout
+    bzpop(parts, ZPopSide::Max, kv_store, Some(waiting_room)).await
+}
+
+// BZPOPMIN as run from EXEC: transactions never block, so an empty set is just a nil reply
+pub async fn process_bzpopmin_nowait(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    bzpop(parts, ZPopSide::Min, kv_store, None).await
+}
+
+pub async fn process_bzpopmax_nowait(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    bzpop(parts, ZPopSide::Max, kv_store, None).await
+}
+
+// Shared body of BZPOPMIN and BZPOPMAX: replies with [key, member, score]
+async fn bzpop(
+    parts: &[String],
+    side: ZPopSide,
+    kv_store: &KvStore,
+    waiting_room: Option<&WaitingRoom>
+) -> RespResult {
+    if parts.len() < 3 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let timeout = match parse_timeout(&parts[parts.len() - 1]) {
+        Ok(timeout) => timeout,
+        Err(reply) => return Ok(reply),
+    };
+    let keys = &parts[1..parts.len() - 1];
+    match blocking_pop(keys, &side, 1, timeout, kv_store, waiting_room).await {
+        Ok(Some((key, popped))) => {
+            let mut response = vec![key];
+            response.extend(flatten_entries(&popped));
+            Ok(encode_array(&response))
+        },
+        Ok(None) => Ok(encode_null_array()),
+        Err(reply) => Ok(reply),
+    }
+}
+
+pub fn process_zmpop(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZMPOP", parts[1] = numkeys, parts[2..2+numkeys] = keys, MIN|MAX, [COUNT count]
+    if parts.len() < 4 {
+        return Err("Malformed ZMPOP".to_string());
+    }
+    let (keys, side, count) = match parse_mpop_args(&parts[1..]) {
+        Ok(args) => args,
+        Err(reply) => return Ok(reply),
+    };
+    match pop_first_nonempty(keys, &side, count, kv_store) {
+        Ok(popped) => Ok(encode_mpop(popped)),
+        Err(reply) => Ok(reply),
+    }
+}
+
+pub async fn process_bzmpop(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    // parts[0] = "BZMPOP", parts[1] = timeout, parts[2] = numkeys, keys..., MIN|MAX, [COUNT count]
+    bzmpop(parts, kv_store, Some(waiting_room)).await
+}
+
+// BZMPOP as run from EXEC: behaves like ZMPOP
+pub async fn process_bzmpop_nowait(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    bzmpop(parts, kv_store, None).await
+}
+
+async fn bzmpop(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: Option<&WaitingRoom>
+) -> RespResult {
+    if parts.len() < 5 {
+        return Err("Malformed BZMPOP".to_string());
+    }
+    let timeout = match parse_timeout(&parts[1]) {
+        Ok(timeout) => timeout,
+        Err(reply) => return Ok(reply),
+    };
+    let (keys, side, count) = match parse_mpop_args(&parts[2..]) {
+        Ok(args) => args,
+        Err(reply) => return Ok(reply),
+    };
+    match blocking_pop(keys, &side, count, timeout, kv_store, waiting_room).await {
+        Ok(popped) => Ok(encode_mpop(popped)),
+        Err(reply) => Ok(reply),
+    }
+}
+
+// The key a pop came from and the (score, member) entries taken from it
+type Popped = Option<(String, Vec<(f64, String)>)>;
+
+/// Pops from the first non-empty sorted set among `keys`, waiting up to
+/// `timeout` seconds (0 waits forever) for one to be filled.
+///
+/// Every wake-up only means "something was added": another client may get
+/// there first, so the pop is retried and the wait resumed until the deadline.
+/// Without a waiting room (inside EXEC) it never blocks.
+async fn blocking_pop(
+    keys: &[String],
+    side: &ZPopSide,
+    count: usize,
+    timeout: f64,
+    kv_store: &KvStore,
+    waiting_room: Option<&WaitingRoom>
+) -> Result<Popped, Vec<u8>> {
+    // A deadline past what the clock can represent is as good as none
+    let deadline = (timeout > 0.0).then(|| Instant::now().checked_add(Duration::from_secs_f64(timeout))).flatten();
+    loop {
+        // Register before looking so a ZADD in between still wakes us
+        let waiter = waiting_room.map(|room| init_waiting_room(keys, room));
+        if let Some(popped) = pop_first_nonempty(keys, side, count, kv_store)? {
+            return Ok(Some(popped));
+        }
+        // Dropping the waiter on any way out of here leaves the room
+        let Some(mut waiter) = waiter else {
+            return Ok(None);
+        };
+        match deadline {
+            Some(deadline) => {
+                if tokio::time::timeout_at(deadline, waiter.rx.recv()).await.is_err() {
+                    return Ok(None);
+                }
+            },
+            None => {
+                waiter.rx.recv().await;
+            },
+        }
+    }
+}
+
+/// Pops up to `count` entries from the first of `keys` holding a sorted set,
+/// deleting the key once it's empty. Missing keys are skipped; any other type
+/// is the WRONGTYPE reply.
+fn pop_first_nonempty(
+    keys: &[String],
+    side: &ZPopSide,
+    count: usize,
+    kv_store: &KvStore
+) -> Result<Popped, Vec<u8>> {
+    for key in keys {
+        let mut map = kv_store.shard(key);
+        let Some(value) = map.get_mut(key) else {
+            continue;
+        };
+        let RedisData::ZSet(zset) = &mut value.data else {
+            return Err(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"));
+        };
+        let take = count.min(zset.len());
+        if take == 0 {
+            return Ok(Some((key.clone(), Vec::new())));
+        }
+        let popped: Vec<(f64, String)> = match side {
+            ZPopSide::Min => zset.drain(..take).collect(),
+            ZPopSide::Max => {
+                let split = zset.len() - take;
+                zset.drain(split..).rev().collect()
+            },
+        };
+        let emptied = zset.is_empty();
+        value.touch();
+        if emptied {
+            map.remove(key);
+        }
+        return Ok(Some((key.clone(), popped)));
+    }
+    Ok(None)
+}
+
+// Parses `numkeys key [key ...] MIN|MAX [COUNT count]`
+fn parse_mpop_args(args: &[String]) -> Result<(&[String], ZPopSide, usize), Vec<u8>> {
+    let numkeys = match args[0].parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => return Err(encode_error_string("ERR numkeys should be greater than 0")),
+    };
+    if numkeys > args.len().saturating_sub(2) {
+        return Err(encode_error_string("ERR syntax error"));
+    }
+    let keys = &args[1..=numkeys];
+    let side = match args[numkeys + 1].to_uppercase().as_str() {
+        "MIN" => ZPopSide::Min,
+        "MAX" => ZPopSide::Max,
+        _ => return Err(encode_error_string("ERR syntax error")),
+    };
+    let count = match &args[numkeys + 2..] {
+        [] => 1,
+        [option, value] if option.to_uppercase() == "COUNT" => match value.parse::<usize>() {
+            Ok(count) if count > 0 => count,
+            _ => return Err(encode_error_string("ERR count should be greater than 0")),
+        },
+        _ => return Err(encode_error_string("ERR syntax error")),
+    };
+    Ok((keys, side, count))
+}
+
+// ZMPOP/BZMPOP reply: [key, [[member, score], ...]], or nil when nothing was popped
+fn encode_mpop(popped: Popped) -> Vec<u8> {
+    match popped {
+        Some((key, entries)) => encode_raw_array(vec![
+            encode_bulk_string(&key),
+            encode_raw_array(entries.iter()
+                .map(|(score, member)| encode_array(&[member.clone(), format_double(*score)]))
+                .collect()),
+        ]),
+        None => encode_null_array(),
+    }
+}
+
+fn flatten_entries(entries: &[(f64, String)]) -> Vec<String> {
+    entries.iter()
+        .flat_map(|(score, member)| [member.clone(), format_double(*score)])
+        .collect()
+}
+
+pub fn process_zunion(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZUNION", parts[1] = numkeys, keys..., [WEIGHTS w ...] [AGGREGATE SUM|MIN|MAX] [WITHSCORES]
+    zset_op(parts, SetOp::Union, kv_store)
+}
+
+pub fn process_zinter(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZINTER", parts[1] = numkeys, keys..., [WEIGHTS w ...] [AGGREGATE SUM|MIN|MAX] [WITHSCORES]
+    zset_op(parts, SetOp::Inter, kv_store)
+}
+
+pub fn process_zdiff(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZDIFF", parts[1] = numkeys, keys..., [WITHSCORES]
+    zset_op(parts, SetOp::Diff, kv_store)
+}
+
+pub fn process_zunionstore(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    // parts[0] = "ZUNIONSTORE", parts[1] = destination, parts[2] = numkeys, keys..., [WEIGHTS w ...] [AGGREGATE ...]
+    store_zset_op(parts, SetOp::Union, kv_store, waiting_room)
+}
+
+pub fn process_zinterstore(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    // parts[0] = "ZINTERSTORE", parts[1] = destination, parts[2] = numkeys, keys..., [WEIGHTS w ...] [AGGREGATE ...]
+    store_zset_op(parts, SetOp::Inter, kv_store, waiting_room)
+}
+
+pub fn process_zdiffstore(
+    parts: &[String],
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    // parts[0] = "ZDIFFSTORE", parts[1] = destination, parts[2] = numkeys, keys...
+    store_zset_op(parts, SetOp::Diff, kv_store, waiting_room)
+}
+
+pub fn process_zintercard(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZINTERCARD", parts[1] = numkeys, parts[2..2+numkeys] = keys, [LIMIT n]
+    if parts.len() < 3 {
+        return Err("Malformed ZINTERCARD".to_string());
+    }
+    let numkeys: usize = match parts[1].parse() {
+        Ok(n) if n > 0 => n,
+        _ => return Ok(encode_error_string("ERR numkeys should be greater than 0")),
+    };
+    if numkeys > parts.len().saturating_sub(2) {
+        return Ok(encode_error_string("ERR Number of keys can't be greater than number of args"));
+    }
+    let keys = &parts[2..2 + numkeys];
+
+    // LIMIT 0 means no limit
+    let limit = match &parts[2 + numkeys..] {
+        [] => 0,
+        [option, value] if option.to_uppercase() == "LIMIT" => match value.parse::<usize>() {
+            Ok(limit) => limit,
+            Err(_) => return Ok(encode_error_string("ERR LIMIT can't be negative")),
+        },
+        _ => return Ok(encode_error_string("ERR syntax error")),
+    };
+
+    let map = kv_store.read_keys(keys);
+    let cardinality = match compute_zset_op(keys, &map, SetOp::Inter, &ZSetOpOptions::default()) {
+        Ok(result) => result.len(),
+        Err(reply) => return Ok(reply),
+    };
+    let cardinality = if limit > 0 { cardinality.min(limit) } else { cardinality };
+    Ok(encode_integer(cardinality as i64))
+}
+
+pub fn process_zrandmember(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZRANDMEMBER", parts[1] = key, [parts[2] = count, [parts[3] = WITHSCORES]]
+    if parts.len() < 2 {
+        return Err("Malformed ZRANDMEMBER".to_string());
+    }
+    let count: Option<i64> = match parts.get(2) {
+        Some(raw) => match raw.parse::<i64>() {
+            Ok(n) if n.unsigned_abs() > MAX_SAMPLE_COUNT as u64 => {
+                return Ok(encode_error_string("ERR value is out of range"));
+            },
+            Ok(n) => Some(n),
+            Err(_) => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+        },
+        None => None
+    };
+    let with_scores = match parts.get(3) {
+        Some(option) if option.to_uppercase() == "WITHSCORES" => true,
+        Some(_) => return Ok(encode_error_string("ERR syntax error")),
+        None => false
+    };
+
+    let map = kv_store.shard_read(&parts[1]);
+    let zset = match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::ZSet(zset) => zset,
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => return match count {
+            Some(_) => Ok(encode_array(&[])),
+            None => Ok(encode_null_string()),
+        }
+    };
+
+    match count {
+        None => match random_sample(zset, 1).first() {
+            Some((_, member)) => Ok(encode_bulk_string(member)),
+            None => Ok(encode_null_string()),
+        },
+        Some(n) => {
+            let picked = random_sample(zset, n);
+            if with_scores {
+                Ok(encode_array(&flatten_entries(&picked)))
+            } else {
+                let members: Vec<String> = picked.into_iter().map(|(_, member)| member).collect();
+                Ok(encode_array(&members))
+            }
+        }
+    }
+}
+
+pub fn process_zscan(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZSCAN", parts[1] = key, parts[2] = cursor, [MATCH pattern] [COUNT count]
+    if parts.len() < 3 {
+        return Err("Malformed ZSCAN".to_string());
+    }
+    let scan = match parse_scan_args(&parts[2..]) {
+        Ok(scan) => scan,
+        Err(reply) => return Ok(reply),
+    };
+
+    let map = kv_store.shard_read(&parts[1]);
+    let mut elements = Vec::new();
+    // Same single-page approach as HSCAN: cursor 0 returns everything, others are finished
+    if let (0, Some(value)) = (scan.cursor, map.get(&parts[1])) {
+        match &value.data {
+            RedisData::ZSet(zset) => {
+                for (score, member) in zset {
+                    if scan.matches(member) {
+                        elements.push(member.clone());
+                        elements.push(format_double(*score));
+                    }
+                }
+            },
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        }
+    }
+    Ok(encode_raw_array(vec![encode_bulk_string("0"), encode_array(&elements)]))
+}
+
+pub fn process_zmscore(
+    parts: &[String],
+    kv_store: &KvStore
+) -> RespResult {
+    // parts[0] = "ZMSCORE", parts[1] = key, parts[2..] = members
+    if parts.len() < 3 {
+        return Err("Malformed ZMSCORE".to_string());
+    }
+    let map = kv_store.shard_read(&parts[1]);
+    let zset = match map.get(&parts[1]) {
+        Some(value) => match &value.data {
+            RedisData::ZSet(zset) => Some(zset),
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => None
+    };
+
+    let scores = parts[2..].iter()
+        .map(|member| match zset.and_then(|z| z.iter().find(|(_, m)| m == member)) {
+            Some((score, _)) => encode_bulk_string(&format_double(*score)),
+            None => encode_null_string(),
+        })
+        .collect();
+    Ok(encode_raw_array(scores))
+}
+
+// Shared body of ZUNION, ZINTER and ZDIFF
+fn zset_op(
+    parts: &[String],
+    op: SetOp,
+    kv_store: &KvStore
+) -> RespResult {
+    if parts.len() < 3 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let (keys, options) = match parse_zset_op_args(&parts[0], &parts[1..], &op, true) {
+        Ok(args) => args,
+        Err(reply) => return Ok(reply),
+    };
+    let map = kv_store.read_keys(keys);
+    let result = match compute_zset_op(keys, &map, op, &options) {
+        Ok(result) => result,
+        Err(reply) => return Ok(reply),
+    };
+
+    let mut response = Vec::new();
+    for (score, member) in result {
+        response.push(member);
+        if options.with_scores {
+            response.push(format_double(score));
+        }
+    }
+    Ok(encode_array(&response))
+}
+
+// Shared body of the *STORE variants: overwrites the destination with the result,
+// or deletes it when the result is empty, and replies with the cardinality
+fn store_zset_op(
+    parts: &[String],
+    op: SetOp,
+    kv_store: &KvStore,
+    waiting_room: &WaitingRoom
+) -> RespResult {
+    if parts.len() < 4 {
+        return Err(format!("Malformed {}", parts[0].to_uppercase()));
+    }
+    let (keys, options) = match parse_zset_op_args(&parts[0], &parts[2..], &op, false) {
+        Ok(args) => args,
+        Err(reply) => return Ok(reply),
+    };
+    let destination = parts[1].clone();
+    let mut map = kv_store.lock_keys(&parts[1..]);
+    let result = match compute_zset_op(keys, &map, op, &options) {
+        Ok(result) => result,
+        Err(reply) => return Ok(reply),
+    };
+    let cardinality = result.len() as i64;
+
+    if result.is_empty() {
+        map.remove(&destination);
+    } else {
+        map.insert(destination.clone(), RedisValue::new(RedisData::ZSet(result), None));
+        wake_all_waiters(&destination, &destination, waiting_room);
+    }
+    Ok(encode_integer(cardinality))
+}
+
+// Parses `numkeys key [key ...]` and the trailing options. ZDIFF takes neither
+// WEIGHTS nor AGGREGATE, and the STORE variants don't take WITHSCORES.
+fn parse_zset_op_args<'a>(
+    command: &str,
+    args: &'a [String],
+    op: &SetOp,
+    allow_with_scores: bool
+) -> Result<(&'a [String], ZSetOpOptions), Vec<u8>> {
+    let numkeys = match args[0].parse::<i64>() {
+        Ok(n) if n > 0 => n as usize,
+        Ok(_) => return Err(encode_error_string(&format!(
+            "ERR at least 1 input key is needed for '{}' command", command.to_lowercase()
+        ))),
+        Err(_) => return Err(encode_error_string("ERR value is not an integer or out of range")),
+    };
+    if args.len() < numkeys + 1 {
+        return Err(encode_error_string("ERR syntax error"));
+    }
+    let keys = &args[1..=numkeys];
+    let combines_scores = !matches!(op, SetOp::Diff);
+
+    let mut options = ZSetOpOptions::default();
+    let mut idx = numkeys + 1;
+    while let Some(option) = args.get(idx) {
+        match option.to_uppercase().as_str() {
+            "WEIGHTS" if combines_scores => {
+                let raw = args.get(idx + 1..idx + 1 + numkeys)
+                    .ok_or_else(|| encode_error_string("ERR syntax error"))?;
+                let weights: Option<Vec<f64>> = raw.iter()
+                    .map(|w| w.parse::<f64>().ok().filter(|w| !w.is_nan()))
+                    .collect();
+                options.weights = Some(weights.ok_or_else(|| encode_error_string("ERR weight value is not a float"))?);
+                idx += numkeys;
+            },
+            "AGGREGATE" if combines_scores => {
+                options.aggregate = match args.get(idx + 1).map(|a| a.to_uppercase()).as_deref() {
+                    Some("SUM") => ZAggregate::Sum,
+                    Some("MIN") => ZAggregate::Min,
+                    Some("MAX") => ZAggregate::Max,
+                    _ => return Err(encode_error_string("ERR syntax error")),
+                };
+                idx += 1;
+            },
+            "WITHSCORES" if allow_with_scores => options.with_scores = true,
+            _ => return Err(encode_error_string("ERR syntax error")),
+        }
+        idx += 1;
+    }
+    Ok((keys, options))
+}
+
+/// Combines the sorted sets stored at `keys` according to `op`, returning
+/// the result in sorted-set order.
+///
+/// Missing keys behave like empty sets and plain sets count every member
+/// with a score of 1; any other type fails with the WRONGTYPE reply.
+fn compute_zset_op(
+    keys: &[String],
+    map: &StoreGuard,
+    op: SetOp,
+    options: &ZSetOpOptions
+) -> Result<Vec<(f64, String)>, Vec<u8>> {
+    let mut inputs: Vec<HashMap<&str, f64>> = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        let weight = options.weights.as_ref().map_or(1.0, |weights| weights[i]);
+        // 0 * inf would be NaN; Redis treats it as 0
+        let weigh = |score: f64| {
+            let weighted = score * weight;
+            if weighted.is_nan() { 0.0 } else { weighted }
+        };
+        let input = match map.get(key).map(|value| &value.data) {
+            Some(RedisData::ZSet(zset)) => zset.iter().map(|(score, m)| (m.as_str(), weigh(*score))).collect(),
+            Some(RedisData::Set(set)) => set.iter().map(|m| (m.as_str(), weigh(1.0))).collect(),
+            Some(_) => return Err(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+            None => HashMap::new(),
+        };
+        inputs.push(input);
+    }
+
+    let (first, rest) = match inputs.split_first() {
+        Some(split) => split,
+        None => return Ok(Vec::new()),
+    };
+    let combined: HashMap<&str, f64> = match op {
+        SetOp::Union => {
+            let mut union: HashMap<&str, f64> = HashMap::new();
+            for input in &inputs {
+                for (member, score) in input {
+                    union.entry(member)
+                        .and_modify(|acc| *acc = options.aggregate.combine(*acc, *score))
+                        .or_insert(*score);
+                }
+            }
+            union
+        },
+        SetOp::Inter => first.iter()
+            .filter_map(|(member, score)| {
+                rest.iter().try_fold(*score, |acc, input| {
+                    input.get(member).map(|s| options.aggregate.combine(acc, *s))
+                }).map(|score| (*member, score))
+            })
+            .collect(),
+        SetOp::Diff => first.iter()
+            .filter(|(member, _)| !rest.iter().any(|input| input.contains_key(*member)))
+            .map(|(member, score)| (*member, *score))
+            .collect(),
+    };
+
+    let mut result: Vec<(f64, String)> = combined.into_iter()
+        .map(|(member, score)| (score, member.to_string()))
+        .collect();
+    result.sort_by(|a, b| compare_entries(a, b.0, &b.1));
+    Ok(result)
+}
+
+// Parses the `offset count` that follows LIMIT
+fn parse_limit(args: &[String]) -> Result<(i64, i64), Vec<u8>> {
+    match args {
+        [offset, count, ..] => match (offset.parse(), count.parse()) {
+            (Ok(offset), Ok(count)) => Ok((offset, count)),
+            _ => Err(encode_error_string("ERR value is not an integer or out of range")),
+        },
+        _ => Err(encode_error_string("ERR syntax error")),
+    }
+}
+
+// Parsed start/stop arguments of a range query
+enum RangeBounds {
+    Rank(i64, i64),
+    Score(ScoreBound, ScoreBound),
+    Lex(LexBound, LexBound),
+}
+
+/// Runs a range query against the sorted set at `key`.
+///
+/// `start`/`stop` are indexes for rank ranges; for score and lex ranges they
+/// are the min/max bounds, given as max/min when `options.rev` is set.
+fn range_reply(
+    key: &str,
+    start: &str,
+    stop: &str,
+    options: &ZRangeOptions,
+    kv_store: &KvStore
+) -> RespResult {
+    let (min_raw, max_raw) = if options.rev { (stop, start) } else { (start, stop) };
+    let bounds = match options.by {
+        ZRangeBy::Rank => match (start.parse(), stop.parse()) {
+            (Ok(start), Ok(stop)) => RangeBounds::Rank(start, stop),
+            _ => return Ok(encode_error_string("ERR value is not an integer or out of range")),
+        },
+        ZRangeBy::Score => match (ScoreBound::parse(min_raw), ScoreBound::parse(max_raw)) {
+            (Some(min), Some(max)) => RangeBounds::Score(min, max),
+            _ => return Ok(encode_error_string("ERR min or max is not a float")),
+        },
+        ZRangeBy::Lex => match (LexBound::parse(min_raw), LexBound::parse(max_raw)) {
+            (Some(min), Some(max)) => RangeBounds::Lex(min, max),
+            _ => return Ok(encode_error_string("ERR min or max not valid string range item")),
+        },
+    };
+
+    let map = kv_store.shard_read(key);
+    let zset = match map.get(key) {
+        Some(value) => match &value.data {
+            RedisData::ZSet(zset) => {
+                value.mark_accessed();
+                zset
+            },
+            _ => return Ok(encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value")),
+        },
+        None => return Ok(encode_array(&[]))
+    };
+
+    let mut selected: Vec<&(f64, String)> = match &bounds {
+        RangeBounds::Rank(..) => zset.iter().collect(),
+        RangeBounds::Score(min, max) => zset.iter()
+            .filter(|(score, _)| min.allows_above(*score) && max.allows_below(*score))
+            .collect(),
+        RangeBounds::Lex(min, max) => zset.iter()
+            .filter(|(_, member)| min.allows_above(member) && max.allows_below(member))
+            .collect(),
+    };
+    if options.rev {
+        selected.reverse();
+    }
+
+    let selected: Vec<&(f64, String)> = match bounds {
+        RangeBounds::Rank(start, stop) => {
+            let len = selected.len() as i64;
+            let start_idx = if start < 0 { len + start } else { start }.max(0);
+            let stop_idx = if stop < 0 { len + stop } else { stop }.min(len - 1);
+            if start_idx > stop_idx {
+                return Ok(encode_array(&[]));
+            }
+            selected[start_idx as usize..=stop_idx as usize].to_vec()
+        },
+        _ => match options.limit {
+            Some((offset, _)) if offset < 0 => Vec::new(),
+            Some((offset, count)) => selected.into_iter()
+                .skip(offset as usize)
+                .take(if count < 0 { usize::MAX } else { count as usize })
+                .collect(),
+            None => selected,
+        },
+    };
+
+    let mut response = Vec::new();
+    for (score, member) in selected {
+        response.push(member.clone());
+        if options.with_scores {
+            response.push(format_double(*score));
+        }
+    }
+    Ok(encode_array(&response))
+}
+
+// A score or increment, or the error reply for one that isn't a number
+fn parse_score(raw: &str) -> Result<f64, Vec<u8>> {
+    match raw.parse::<f64>() {
+        Ok(score) if !score.is_nan() => Ok(score),
+        _ => Err(encode_error_string("ERR value is not a valid float")),
+    }
+}
+
+fn compare_entries(a: &(f64, String), score: f64, member: &str) -> Ordering {
+    a.0.total_cmp(&score).then_with(|| a.1.as_str().cmp(member))
+}
+
+fn insert_sorted(zset: &mut Vec<(f64, String)>, score: f64, member: String) {
+    let idx = zset
+        .binary_search_by(|entry| compare_entries(entry, score, &member))
+        .unwrap_or_else(|idx| idx);
+    zset.insert(idx, (score, member));
+}
+
+fn remove_member(zset: &mut Vec<(f64, String)>, member: &str) -> Option<f64> {
+    let idx = zset.iter().position(|(_, m)| m == member)?;
+    Some(zset.remove(idx).0)
+}
@@ -1,2 +1,10 @@
 pub const PORT: &str = "--port";
-pub const REPLICA_OF: &str = "--replicaof";
\ No newline at end of file
+pub const REPLICA_OF: &str = "--replicaof";
+pub const HZ: &str = "--hz";
+pub const DIR: &str = "--dir";
+pub const DB_FILENAME: &str = "--dbfilename";
+pub const MAXMEMORY_KEYS: &str = "--maxmemory-keys";
+pub const APPEND_ONLY: &str = "--appendonly";
+pub const REQUIRE_PASS: &str = "--requirepass";
+pub const DATABASES: &str = "--databases";
+pub const LOG_LEVEL: &str = "--loglevel";
@@ -0,0 +1,324 @@
+// Arity of every supported command, using the Redis convention: a positive
+// value is the exact number of arguments (command name included), a negative
+// value is the minimum number of arguments.
+pub const COMMAND_ARITY: &[(&str, i64)] = &[
+    ("PING", -1),
+    ("ECHO", 2),
+    ("QUIT", -1),
+    ("LOLWUT", -1),
+    ("SET", -3),
+    ("GET", 2),
+    ("SETNX", 3),
+    ("GETSET", 3),
+    ("GETRANGE", 4),
+    ("SETRANGE", 4),
+    ("INCR", 2),
+    ("TYPE", 2),
+    ("KEYS", 2),
+    ("DEL", -2),
+    ("UNLINK", -2),
+    ("EXISTS", -2),
+    ("TOUCH", -2),
+    ("RANDOMKEY", 1),
+    ("SCAN", -2),
+    ("EXPIRE", -3),
+    ("PEXPIRE", -3),
+    ("EXPIREAT", -3),
+    ("PEXPIREAT", -3),
+    ("TTL", 2),
+    ("PTTL", 2),
+    ("PERSIST", 2),
+    ("RENAME", 3),
+    ("RENAMENX", 3),
+    ("COPY", -3),
+    ("OBJECT", -2),
+    ("DBSIZE", 1),
+    ("FLUSHDB", -1),
+    ("FLUSHALL", -1),
+    ("SAVE", 1),
+    ("BGSAVE", -1),
+    ("SELECT", 2),
+    ("INFO", -1),
+    ("RPUSH", -3),
+    ("LPUSH", -3),
+    ("LRANGE", 4),
+    ("LLEN", 2),
+    ("LPOP", -2),
+    ("BLPOP", -3),
+    ("XADD", -5),
+    ("XRANGE", -4),
+    ("XREVRANGE", -4),
+    ("XREAD", -4),
+    ("XINFO", -2),
+    ("XSETID", -3),
+    ("XDEL", -3),
+    ("MULTI", 1),
+    ("EXEC", 1),
+    ("DISCARD", 1),
+    ("WATCH", -2),
+    ("UNWATCH", 1),
+    ("SADD", -3),
+    ("SREM", -3),
+    ("SMEMBERS", 2),
+    ("SCARD", 2),
+    ("SISMEMBER", 3),
+    ("SMISMEMBER", -3),
+    ("SRANDMEMBER", -2),
+    ("SPOP", -2),
+    ("SMOVE", 4),
+    ("SSCAN", -3),
+    ("SINTER", -2),
+    ("SUNION", -2),
+    ("SDIFF", -2),
+    ("SINTERSTORE", -3),
+    ("SUNIONSTORE", -3),
+    ("SDIFFSTORE", -3),
+    ("SINTERCARD", -3),
+    ("ZADD", -4),
+    ("ZSCORE", 3),
+    ("ZCARD", 2),
+    ("ZRANK", -3),
+    ("ZRANGE", -4),
+    ("ZREVRANGE", -4),
+    ("ZRANGEBYSCORE", -4),
+    ("ZREVRANGEBYSCORE", -4),
+    ("ZRANGEBYLEX", -4),
+    ("ZREVRANGEBYLEX", -4),
+    ("ZREM", -3),
+    ("ZINCRBY", 4),
+    ("ZCOUNT", 4),
+    ("ZPOPMIN", -2),
+    ("ZPOPMAX", -2),
+    ("BZPOPMIN", -3),
+    ("BZPOPMAX", -3),
+    ("ZMPOP", -4),
+    ("BZMPOP", -5),
+    ("ZUNION", -3),
+    ("ZINTER", -3),
+    ("ZDIFF", -3),
+    ("ZUNIONSTORE", -4),
+    ("ZINTERSTORE", -4),
+    ("ZDIFFSTORE", -4),
+    ("ZINTERCARD", -3),
+    ("ZRANDMEMBER", -2),
+    ("ZSCAN", -3),
+    ("ZMSCORE", -3),
+    ("CONFIG", -2),
+    ("COMMAND", -1),
+    ("HELLO", -1),
+    ("RESET", 1),
+    ("AUTH", -2),
+    ("ACL", -2),
+    ("CLIENT", -2),
+    ("DEBUG", -2),
+    ("SLOWLOG", -2),
+    ("REPLCONF", -3),
+    ("PSYNC", 3),
+    ("WAIT", 3),
+    ("SUBSCRIBE", -2),
+    ("UNSUBSCRIBE", -1),
+    ("PSUBSCRIBE", -2),
+    ("PUNSUBSCRIBE", -1),
+    ("PUBLISH", 3),
+    ("PUBSUB", -2),
+    ("HSET", -4),
+    ("HGET", 3),
+    ("HDEL", -3),
+    ("HEXISTS", 3),
+    ("HLEN", 2),
+    ("HKEYS", 2),
+    ("HVALS", 2),
+    ("HGETALL", 2),
+    ("HMSET", -4),
+    ("HMGET", -3),
+    ("HINCRBY", 4),
+    ("HINCRBYFLOAT", 4),
+    ("HSETNX", 4),
+    ("HRANDFIELD", -2),
+    ("HSCAN", -3),
+];
+
+// Reply to OBJECT HELP, one bulk string per line
+pub const OBJECT_HELP: &[&str] = &[
+    "OBJECT <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+    "ENCODING <key>",
+    "    Return the kind of internal representation used in order to store the value",
+    "    associated with a <key>.",
+    "FREQ <key>",
+    "    Return the access frequency index of the <key>. The returned integer is",
+    "    proportional to the logarithm of the recent access frequency of the key.",
+    "IDLETIME <key>",
+    "    Return the idle time of the <key>, that is the approximated number of",
+    "    seconds elapsed since the last access to the key.",
+    "REFCOUNT <key>",
+    "    Return the number of references of the value associated with the specified",
+    "    <key>.",
+    "HELP",
+    "    Print this help.",
+];
+
+// Commands that can add keys, so under maxmemory-keys they first make room
+// (Redis flags these `denyoom`)
+pub const DENY_OOM_COMMANDS: &[&str] = &[
+    "SET", "SETNX", "GETSET", "SETRANGE", "INCR", "COPY",
+    "RPUSH", "LPUSH",
+    "XADD",
+    "SADD", "SMOVE", "SINTERSTORE", "SUNIONSTORE", "SDIFFSTORE",
+    "ZADD", "ZINCRBY", "ZUNIONSTORE", "ZINTERSTORE", "ZDIFFSTORE",
+    "HSET", "HMSET", "HSETNX", "HINCRBY", "HINCRBYFLOAT",
+];
+
+// Commands that can change the keyspace, so they go to the append-only file
+// (Redis flags these `write`)
+pub const WRITE_COMMANDS: &[&str] = &[
+    "SET", "SETNX", "GETSET", "SETRANGE", "INCR",
+    "DEL", "UNLINK", "EXPIRE", "PEXPIRE", "EXPIREAT", "PEXPIREAT", "PERSIST",
+    "RENAME", "RENAMENX", "COPY", "FLUSHDB", "FLUSHALL",
+    "RPUSH", "LPUSH", "LPOP", "BLPOP",
+    "XADD", "XSETID", "XDEL",
+    "SADD", "SREM", "SPOP", "SMOVE", "SINTERSTORE", "SUNIONSTORE", "SDIFFSTORE",
+    "ZADD", "ZREM", "ZINCRBY", "ZPOPMIN", "ZPOPMAX", "BZPOPMIN", "BZPOPMAX", "ZMPOP", "BZMPOP",
+    "ZUNIONSTORE", "ZINTERSTORE", "ZDIFFSTORE",
+    "HSET", "HDEL", "HMSET", "HSETNX", "HINCRBY", "HINCRBYFLOAT",
+];
+
+// Commands that can wait for data to arrive (Redis flags these `blocking`)
+pub const BLOCKING_COMMANDS: &[&str] = &["BLPOP", "BZPOPMIN", "BZPOPMAX", "BZMPOP", "XREAD"];
+
+// (name, group, summary) for COMMAND DOCS, in the same order as COMMAND_ARITY
+pub const COMMAND_DOCS: &[(&str, &str, &str)] = &[
+    ("PING", "connection", "Returns the server's liveliness response."),
+    ("ECHO", "connection", "Returns the given string."),
+    ("QUIT", "connection", "Closes the connection."),
+    ("LOLWUT", "server", "Displays computer art and the Redis version."),
+    ("SET", "string", "Sets the string value of a key, ignoring its type. The key is created if it doesn't exist."),
+    ("GET", "string", "Returns the string value of a key."),
+    ("SETNX", "string", "Set the string value of a key only when the key doesn't exist."),
+    ("GETSET", "string", "Returns the previous string value of a key after setting it to a new value."),
+    ("GETRANGE", "string", "Returns a substring of the string stored at a key."),
+    ("SETRANGE", "string", "Overwrites a part of a string value with another by an offset. Creates the key if it doesn't exist."),
+    ("INCR", "string", "Increments the integer value of a key by one. Uses 0 as initial value if the key doesn't exist."),
+    ("TYPE", "generic", "Determines the type of value stored at a key."),
+    ("KEYS", "generic", "Returns all key names that match a pattern."),
+    ("DEL", "generic", "Deletes one or more keys."),
+    ("UNLINK", "generic", "Asynchronously deletes one or more keys."),
+    ("EXISTS", "generic", "Determines whether one or more keys exist."),
+    ("TOUCH", "generic", "Returns the number of existing keys out of those specified after updating the time they were last accessed."),
+    ("RANDOMKEY", "generic", "Returns a random key name from the database."),
+    ("SCAN", "generic", "Iterates over the key names in the database."),
+    ("EXPIRE", "generic", "Sets the expiration time of a key in seconds."),
+    ("PEXPIRE", "generic", "Sets the expiration time of a key in milliseconds."),
+    ("EXPIREAT", "generic", "Sets the expiration time of a key to a Unix timestamp."),
+    ("PEXPIREAT", "generic", "Sets the expiration time of a key to a Unix milliseconds timestamp."),
+    ("TTL", "generic", "Returns the expiration time in seconds of a key."),
+    ("PTTL", "generic", "Returns the expiration time in milliseconds of a key."),
+    ("PERSIST", "generic", "Removes the expiration time of a key."),
+    ("RENAME", "generic", "Renames a key and overwrites the destination."),
+    ("RENAMENX", "generic", "Renames a key only when the target key name doesn't exist."),
+    ("COPY", "generic", "Copies the value of a key to a new key."),
+    ("OBJECT", "generic", "A container for object introspection commands."),
+    ("DBSIZE", "server", "Returns the number of keys in the database."),
+    ("FLUSHDB", "server", "Removes all keys from the current database."),
+    ("FLUSHALL", "server", "Removes all keys from all databases."),
+    ("SAVE", "server", "Synchronously saves the database(s) to disk."),
+    ("BGSAVE", "server", "Asynchronously saves the database(s) to disk."),
+    ("SELECT", "connection", "Changes the selected database."),
+    ("INFO", "server", "Returns information and statistics about the server."),
+    ("RPUSH", "list", "Appends one or more elements to a list. Creates the key if it doesn't exist."),
+    ("LPUSH", "list", "Prepends one or more elements to a list. Creates the key if it doesn't exist."),
+    ("LRANGE", "list", "Returns a range of elements from a list."),
+    ("LLEN", "list", "Returns the length of a list."),
+    ("LPOP", "list", "Returns the first elements in a list after removing it. Deletes the list if the last element was popped."),
+    ("BLPOP", "list", "Removes and returns the first element in a list. Blocks until an element is available otherwise."),
+    ("XADD", "stream", "Appends a new message to a stream. Creates the key if it doesn't exist."),
+    ("XRANGE", "stream", "Returns the messages from a stream within a range of IDs."),
+    ("XREVRANGE", "stream", "Returns the messages from a stream within a range of IDs in reverse order."),
+    ("XREAD", "stream", "Returns messages from multiple streams with IDs greater than the ones requested. Blocks until a message is available otherwise."),
+    ("XINFO", "stream", "A container for stream introspection commands."),
+    ("XSETID", "stream", "An internal command for replicating stream values."),
+    ("XDEL", "stream", "Returns the number of messages after removing them from a stream."),
+    ("MULTI", "transactions", "Starts a transaction."),
+    ("EXEC", "transactions", "Executes all commands in a transaction."),
+    ("DISCARD", "transactions", "Discards a transaction."),
+    ("WATCH", "transactions", "Monitors changes to keys to determine the execution of a transaction."),
+    ("UNWATCH", "transactions", "Forgets about watched keys of a transaction."),
+    ("SADD", "set", "Adds one or more members to a set. Creates the key if it doesn't exist."),
+    ("SREM", "set", "Removes one or more members from a set. Deletes the set if the last member was removed."),
+    ("SMEMBERS", "set", "Returns all members of a set."),
+    ("SCARD", "set", "Returns the number of members in a set."),
+    ("SISMEMBER", "set", "Determines whether a member belongs to a set."),
+    ("SMISMEMBER", "set", "Determines whether multiple members belong to a set."),
+    ("SRANDMEMBER", "set", "Get one or multiple random members from a set."),
+    ("SPOP", "set", "Returns one or more random members from a set after removing them. Deletes the set if the last member was popped."),
+    ("SMOVE", "set", "Moves a member from one set to another."),
+    ("SSCAN", "set", "Iterates over members of a set."),
+    ("SINTER", "set", "Returns the intersect of multiple sets."),
+    ("SUNION", "set", "Returns the union of multiple sets."),
+    ("SDIFF", "set", "Returns the difference of multiple sets."),
+    ("SINTERSTORE", "set", "Stores the intersect of multiple sets in a key."),
+    ("SUNIONSTORE", "set", "Stores the union of multiple sets in a key."),
+    ("SDIFFSTORE", "set", "Stores the difference of multiple sets in a key."),
+    ("SINTERCARD", "set", "Returns the number of members of the intersect of multiple sets."),
+    ("ZADD", "sorted-set", "Adds one or more members to a sorted set, or updates their scores. Creates the key if it doesn't exist."),
+    ("ZSCORE", "sorted-set", "Returns the score of a member in a sorted set."),
+    ("ZCARD", "sorted-set", "Returns the number of members in a sorted set."),
+    ("ZRANK", "sorted-set", "Returns the index of a member in a sorted set ordered by ascending scores."),
+    ("ZRANGE", "sorted-set", "Returns members in a sorted set within a range of indexes."),
+    ("ZREVRANGE", "sorted-set", "Returns members in a sorted set within a range of indexes in reverse order."),
+    ("ZRANGEBYSCORE", "sorted-set", "Returns members in a sorted set within a range of scores."),
+    ("ZREVRANGEBYSCORE", "sorted-set", "Returns members in a sorted set within a range of scores in reverse order."),
+    ("ZRANGEBYLEX", "sorted-set", "Returns members in a sorted set within a lexicographical range."),
+    ("ZREVRANGEBYLEX", "sorted-set", "Returns members in a sorted set within a lexicographical range in reverse order."),
+    ("ZREM", "sorted-set", "Removes one or more members from a sorted set. Deletes the sorted set if all members were removed."),
+    ("ZINCRBY", "sorted-set", "Increments the score of a member in a sorted set."),
+    ("ZCOUNT", "sorted-set", "Returns the count of members in a sorted set that have scores within a range."),
+    ("ZPOPMIN", "sorted-set", "Returns the lowest-scoring members from a sorted set after removing them."),
+    ("ZPOPMAX", "sorted-set", "Returns the highest-scoring members from a sorted set after removing them."),
+    ("BZPOPMIN", "sorted-set", "Removes and returns the member with the lowest score from one or more sorted sets. Blocks until a member is available otherwise."),
+    ("BZPOPMAX", "sorted-set", "Removes and returns the member with the highest score from one or more sorted sets. Blocks until a member is available otherwise."),
+    ("ZMPOP", "sorted-set", "Returns the highest- or lowest-scoring members from one or more sorted sets after removing them."),
+    ("BZMPOP", "sorted-set", "Removes and returns a member by score from one or more sorted sets. Blocks until a member is available otherwise."),
+    ("ZUNION", "sorted-set", "Returns the union of multiple sorted sets."),
+    ("ZINTER", "sorted-set", "Returns the intersect of multiple sorted sets."),
+    ("ZDIFF", "sorted-set", "Returns the difference between multiple sorted sets."),
+    ("ZUNIONSTORE", "sorted-set", "Stores the union of multiple sorted sets in a key."),
+    ("ZINTERSTORE", "sorted-set", "Stores the intersect of multiple sorted sets in a key."),
+    ("ZDIFFSTORE", "sorted-set", "Stores the difference of multiple sorted sets in a key."),
+    ("ZINTERCARD", "sorted-set", "Returns the number of members of the intersect of multiple sorted sets."),
+    ("ZRANDMEMBER", "sorted-set", "Returns one or more random members from a sorted set."),
+    ("ZSCAN", "sorted-set", "Iterates over members and scores of a sorted set."),
+    ("ZMSCORE", "sorted-set", "Returns the score of one or more members in a sorted set."),
+    ("CONFIG", "server", "A container for server configuration commands."),
+    ("COMMAND", "server", "Returns detailed information about all commands."),
+    ("HELLO", "connection", "Handshakes with the server."),
+    ("RESET", "connection", "Resets the connection."),
+    ("AUTH", "connection", "Authenticates the connection."),
+    ("ACL", "server", "A container for Access List Control commands."),
+    ("CLIENT", "connection", "A container for client connection commands."),
+    ("DEBUG", "server", "A container for debugging commands."),
+    ("SLOWLOG", "server", "A container for slow log commands."),
+    ("REPLCONF", "server", "An internal command for configuring the replication stream."),
+    ("PSYNC", "server", "An internal command used in replication."),
+    ("WAIT", "generic", "Blocks until the asynchronous replication of all preceding write commands sent by the connection is completed."),
+    ("SUBSCRIBE", "pubsub", "Listens for messages published to channels."),
+    ("UNSUBSCRIBE", "pubsub", "Stops listening to messages posted to channels."),
+    ("PSUBSCRIBE", "pubsub", "Listens for messages published to channels that match one or more patterns."),
+    ("PUNSUBSCRIBE", "pubsub", "Stops listening to messages published to channels that match one or more patterns."),
+    ("PUBLISH", "pubsub", "Posts a message to a channel."),
+    ("PUBSUB", "pubsub", "A container for Pub/Sub commands."),
+    ("HSET", "hash", "Creates or modifies the value of a field in a hash."),
+    ("HGET", "hash", "Returns the value of a field in a hash."),
+    ("HDEL", "hash", "Deletes one or more fields and their values from a hash. Deletes the hash if no fields remain."),
+    ("HEXISTS", "hash", "Determines whether a field exists in a hash."),
+    ("HLEN", "hash", "Returns the number of fields in a hash."),
+    ("HKEYS", "hash", "Returns all fields in a hash."),
+    ("HVALS", "hash", "Returns all values in a hash."),
+    ("HGETALL", "hash", "Returns all fields and values in a hash."),
+    ("HMSET", "hash", "Sets the values of multiple fields."),
+    ("HMGET", "hash", "Returns the values of all fields in a hash."),
+    ("HINCRBY", "hash", "Increments the integer value of a field in a hash by a number. Uses 0 as initial value if the field doesn't exist."),
+    ("HINCRBYFLOAT", "hash", "Increments the floating point value of a field by a number. Uses 0 as initial value if the field doesn't exist."),
+    ("HSETNX", "hash", "Sets the value of a field in a hash only when the field doesn't exist."),
+    ("HRANDFIELD", "hash", "Returns one or more random fields from a hash."),
+    ("HSCAN", "hash", "Iterates over fields and values of a hash."),
+];
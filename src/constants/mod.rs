@@ -1,3 +1,7 @@
 pub mod cmd_line_const;
+pub mod command_const;
+pub mod server_const;
 
-pub use cmd_line_const::*;
\ No newline at end of file
+pub use cmd_line_const::*;
+pub use command_const::*;
+pub use server_const::*;
@@ -0,0 +1,19 @@
+// What HELLO and INFO report this server as
+pub const SERVER_NAME: &str = "redis";
+pub const SERVER_VERSION: &str = "7.2.0";
+
+// Pub/sub messages a connection can have pending before PUBLISH starts dropping them
+pub const PUSH_CHANNEL_CAPACITY: usize = 1024;
+
+// Bytes read from a socket at a time; a command longer than this takes several reads
+pub const READ_CHUNK_SIZE: usize = 4096;
+// A connection whose unfinished command outgrows this is dropped, like Redis'
+// client-query-buffer-limit
+pub const MAX_QUERY_BUFFER_SIZE: usize = 1024 * 1024 * 1024;
+
+// Keys compared per shard when picking one to evict, like Redis' maxmemory-samples.
+// A shard has no random access, so these are the first keys in iteration order
+pub const EVICTION_SAMPLES: usize = 5;
+
+// Written to `dir` when running with --appendonly yes
+pub const AOF_FILENAME: &str = "appendonly.aof";
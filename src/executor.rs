@@ -1,43 +1,222 @@
-use std::sync::{Arc, Mutex};
-use std::collections::{VecDeque, HashMap};
-use tokio::sync::mpsc;
 use async_recursion::async_recursion;
 
-use crate::models::{ListDir, ServerInfo, RedisValue, RespResult};
+use crate::models::{ClientState, ListDir, RespResult, ServerState};
 use crate::commands::*;
+use crate::constants::{BLOCKING_COMMANDS, DENY_OOM_COMMANDS, WRITE_COMMANDS};
+use crate::replication::propagate;
+use crate::utils::aof::propagated;
+use crate::utils::encoder::encode_error_string;
+use crate::utils::eviction::evict_for_write;
 
 #[async_recursion]
 pub async fn execute_commands(
     command: String,
-    parts: &Vec<String>, 
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>,
-    command_queue: &mut Option<VecDeque<Vec<String>>>,
-    server_info: &Arc<Mutex<ServerInfo>>
+    parts: &[String],
+    args: &[Vec<u8>], // `parts` as the client sent them, for handlers that store values
+    state: &ServerState,
+    client: &mut ClientState
 ) -> Vec<u8> {
+    // Looked up per command, so a SELECT queued inside MULTI affects what follows it
+    let db_index = client.db_index;
+    let kv_store = &state.databases[db_index];
+    let waiting_rooms = &state.waiting_rooms[db_index];
+    let server_info = &state.server_info;
+    let server_config = &state.server_config;
+
+    // Logging in is open to everyone; what comes after depends on who logged in
+    if !matches!(command.as_str(), "AUTH" | "HELLO" | "QUIT" | "RESET")
+        && let Err(denied) = check_permissions(&command, parts, client, &state.acl)
+    {
+        return denied;
+    }
+    if DENY_OOM_COMMANDS.contains(&command.as_str()) {
+        let max_keys = server_config.lock().unwrap().maxmemory_keys;
+        if !evict_for_write(&state.databases, max_keys) {
+            return encode_error_string("OOM command not allowed when used memory > 'maxmemory'.");
+        }
+    }
+    // A write holds the replication gate until it's propagated, so a PSYNC
+    // snapshot can't catch it halfway. A blocking command waiting for data
+    // would keep PSYNC out for as long as it waits, so it goes without
+    let is_write = WRITE_COMMANDS.contains(&command.as_str());
+    let _replication = if is_write && (client.in_exec || !BLOCKING_COMMANDS.contains(&command.as_str())) {
+        Some(state.replication.write().await)
+    } else {
+        None
+    };
+    // Tokio's clock rather than std's, so tests can fast-forward a slow command
+    let started = tokio::time::Instant::now();
     let result = match command.as_str() {
-        "PING" => process_ping(),
-        "ECHO" => process_echo(&parts),
-        "SET" => process_set(&parts, &kv_store),
-        "GET" => process_get(&parts, &kv_store),
-        "RPUSH" => process_push(&parts, &kv_store, &waiting_room, ListDir::R),
-        "LRANGE" => process_lrange(&parts, &kv_store),
-        "LPUSH" => process_push(&parts, &kv_store, &waiting_room, ListDir::L),
-        "LLEN" => process_llen(&parts, &kv_store),
-        "LPOP" => process_pop(&parts, &kv_store, ListDir::L),
-        "BLPOP" => process_blpop(&parts, &kv_store, &waiting_room).await,
-        "TYPE" => process_type(&parts, &kv_store),
-        "XADD" => process_xadd(&parts, &kv_store, &waiting_room),
-        "XRANGE" => process_xrange(&parts, &kv_store),
-        "XREAD" => process_xread(&parts, &kv_store, &waiting_room).await,
-        "INCR" => process_incr(&parts, &kv_store),
-        "MULTI" => process_multi(command_queue),
-        "EXEC" => process_exec(command_queue, &kv_store, &waiting_room, server_info).await,
-        "DISCARD" => process_discard(command_queue),
-        "INFO" => process_info(&parts, &server_info),
+        "PING" => process_ping(parts, client),
+        "AUTH" => process_auth(parts, client, &state.acl),
+        "ACL" => process_acl(parts, client, &state.acl),
+        "CLIENT" => process_client(parts, client, &state.clients, &state.client_pause),
+        "DEBUG" => process_debug(parts, server_config).await,
+        "SLOWLOG" => process_slowlog(parts, &state.slowlog),
+        "ECHO" => process_echo(parts),
+        "QUIT" => process_quit(client),
+        "LOLWUT" => process_lolwut(parts),
+        "SET" => process_set(args, kv_store),
+        "GET" if client.no_touch => process_get_no_touch(args, kv_store),
+        "GET" => process_get(args, kv_store),
+        "SETNX" => process_setnx(args, kv_store),
+        "GETSET" => process_getset(args, kv_store),
+        "GETRANGE" if client.no_touch => process_getrange_no_touch(args, kv_store),
+        "GETRANGE" => process_getrange(args, kv_store),
+        "SETRANGE" => process_setrange(args, kv_store),
+        "RPUSH" => process_push(parts, kv_store, &waiting_rooms.lists, ListDir::R),
+        "LRANGE" => process_lrange(parts, kv_store),
+        "LPUSH" => process_push(parts, kv_store, &waiting_rooms.lists, ListDir::L),
+        "LLEN" => process_llen(parts, kv_store),
+        "LPOP" => process_pop(parts, kv_store, ListDir::L),
+        "BLPOP" if client.in_exec => process_blpop_nowait(parts, kv_store),
+        "BLPOP" => process_blpop(parts, kv_store, &waiting_rooms.lists).await,
+        "TYPE" => process_type(parts, kv_store),
+        "KEYS" => process_keys(parts, kv_store),
+        "DEL" => process_del(parts, kv_store),
+        "UNLINK" => process_unlink(parts, kv_store),
+        "EXISTS" => process_exists(parts, kv_store),
+        "TOUCH" => process_touch(parts, kv_store),
+        "RANDOMKEY" => process_randomkey(parts, kv_store),
+        "SCAN" => process_scan(parts, kv_store),
+        "EXPIRE" => process_expire(parts, kv_store),
+        "PEXPIRE" => process_pexpire(parts, kv_store),
+        "EXPIREAT" => process_expireat(parts, kv_store),
+        "PEXPIREAT" => process_pexpireat(parts, kv_store),
+        "TTL" => process_ttl(parts, kv_store),
+        "PTTL" => process_pttl(parts, kv_store),
+        "PERSIST" => process_persist(parts, kv_store),
+        "RENAME" => process_rename(parts, kv_store),
+        "RENAMENX" => process_renamenx(parts, kv_store),
+        "COPY" => process_copy(parts, client.db_index, &state.databases),
+        "OBJECT" => process_object(parts, kv_store),
+        "DBSIZE" => process_dbsize(parts, kv_store),
+        "FLUSHDB" => process_flushdb(parts, kv_store),
+        "FLUSHALL" => process_flushall(parts, &state.databases),
+        "SAVE" => process_save(parts, &state.databases, server_config),
+        "BGSAVE" => process_bgsave(parts, &state.databases, server_config, &state.bgsave_in_progress),
+        "SELECT" => process_select(parts, client, &state.databases),
+        "XADD" => process_xadd(parts, kv_store, &waiting_rooms.streams),
+        "XRANGE" => process_xrange(parts, kv_store),
+        "XREVRANGE" => process_xrevrange(parts, kv_store),
+        "XREAD" if client.in_exec => process_xread_nowait(parts, kv_store).await,
+        "XREAD" => process_xread(parts, kv_store, &waiting_rooms.streams).await,
+        "XINFO" => process_xinfo(parts, kv_store),
+        "XSETID" => process_xsetid(parts, kv_store),
+        "XDEL" => process_xdel(parts, kv_store),
+        "INCR" => process_incr(parts, kv_store),
+        "MULTI" => process_multi(client),
+        "EXEC" => process_exec(client, state).await,
+        "DISCARD" => process_discard(client),
+        "WATCH" => process_watch(parts, client, kv_store),
+        "UNWATCH" => process_unwatch(client),
+        "INFO" => process_info(parts, server_info, &state.databases),
+        "CONFIG" => process_config(parts, server_config, &state.acl),
+        "COMMAND" => process_command(parts),
+        "REPLCONF" => process_replconf(parts),
+        "PSYNC" => process_psync(parts, client, &state.databases, server_info, &state.replication).await,
+        "HELLO" => process_hello(parts, client, &state.acl, server_info),
+        "RESET" => process_reset(parts, client, &state.pubsub, &state.acl),
+        "WAIT" => process_wait(parts, server_info),
+        "SUBSCRIBE" => process_subscribe(parts, client, &state.pubsub),
+        "UNSUBSCRIBE" => process_unsubscribe(parts, client, &state.pubsub),
+        "PSUBSCRIBE" => process_psubscribe(parts, client, &state.pubsub),
+        "PUNSUBSCRIBE" => process_punsubscribe(parts, client, &state.pubsub),
+        "PUBLISH" => process_publish(parts, &state.pubsub),
+        "PUBSUB" => process_pubsub(parts, &state.pubsub),
+        "SADD" => process_sadd(parts, kv_store),
+        "SREM" => process_srem(parts, kv_store),
+        "SMEMBERS" => process_smembers(parts, kv_store),
+        "SCARD" => process_scard(parts, kv_store),
+        "SISMEMBER" => process_sismember(parts, kv_store),
+        "SMISMEMBER" => process_smismember(parts, kv_store),
+        "SRANDMEMBER" => process_srandmember(parts, kv_store),
+        "SPOP" => process_spop(parts, kv_store),
+        "SMOVE" => process_smove(parts, kv_store),
+        "SSCAN" => process_sscan(parts, kv_store),
+        "SINTER" => process_sinter(parts, kv_store),
+        "SUNION" => process_sunion(parts, kv_store),
+        "SDIFF" => process_sdiff(parts, kv_store),
+        "SINTERSTORE" => process_sinterstore(parts, kv_store),
+        "SUNIONSTORE" => process_sunionstore(parts, kv_store),
+        "SDIFFSTORE" => process_sdiffstore(parts, kv_store),
+        "SINTERCARD" => process_sintercard(parts, kv_store),
+        "ZADD" => process_zadd(parts, kv_store, &waiting_rooms.zsets),
+        "ZSCORE" => process_zscore(parts, kv_store, client.protocol),
+        "ZCARD" => process_zcard(parts, kv_store),
+        "ZRANK" => process_zrank(parts, kv_store),
+        "ZRANGE" => process_zrange(parts, kv_store),
+        "ZREVRANGE" => process_zrevrange(parts, kv_store),
+        "ZRANGEBYSCORE" => process_zrangebyscore(parts, kv_store),
+        "ZREVRANGEBYSCORE" => process_zrevrangebyscore(parts, kv_store),
+        "ZRANGEBYLEX" => process_zrangebylex(parts, kv_store),
+        "ZREVRANGEBYLEX" => process_zrevrangebylex(parts, kv_store),
+        "ZREM" => process_zrem(parts, kv_store),
+        "ZINCRBY" => process_zincrby(parts, kv_store, &waiting_rooms.zsets),
+        "ZCOUNT" => process_zcount(parts, kv_store),
+        "ZPOPMIN" => process_zpopmin(parts, kv_store),
+        "ZPOPMAX" => process_zpopmax(parts, kv_store),
+        "BZPOPMIN" if client.in_exec => process_bzpopmin_nowait(parts, kv_store).await,
+        "BZPOPMIN" => process_bzpopmin(parts, kv_store, &waiting_rooms.zsets).await,
+        "BZPOPMAX" if client.in_exec => process_bzpopmax_nowait(parts, kv_store).await,
+        "BZPOPMAX" => process_bzpopmax(parts, kv_store, &waiting_rooms.zsets).await,
+        "ZMPOP" => process_zmpop(parts, kv_store),
+        "ZUNION" => process_zunion(parts, kv_store),
+        "ZINTER" => process_zinter(parts, kv_store),
+        "ZDIFF" => process_zdiff(parts, kv_store),
+        "ZUNIONSTORE" => process_zunionstore(parts, kv_store, &waiting_rooms.zsets),
+        "ZINTERSTORE" => process_zinterstore(parts, kv_store, &waiting_rooms.zsets),
+        "ZDIFFSTORE" => process_zdiffstore(parts, kv_store, &waiting_rooms.zsets),
+        "ZINTERCARD" => process_zintercard(parts, kv_store),
+        "ZRANDMEMBER" => process_zrandmember(parts, kv_store),
+        "ZSCAN" => process_zscan(parts, kv_store),
+        "ZMSCORE" => process_zmscore(parts, kv_store),
+        "BZMPOP" if client.in_exec => process_bzmpop_nowait(parts, kv_store).await,
+        "BZMPOP" => process_bzmpop(parts, kv_store, &waiting_rooms.zsets).await,
+        "HSET" => process_hset(parts, kv_store),
+        "HGET" => process_hget(parts, kv_store),
+        "HDEL" => process_hdel(parts, kv_store),
+        "HEXISTS" => process_hexists(parts, kv_store),
+        "HLEN" => process_hlen(parts, kv_store),
+        "HKEYS" => process_hkeys(parts, kv_store),
+        "HVALS" => process_hvals(parts, kv_store),
+        "HGETALL" => process_hgetall(parts, kv_store, client.protocol),
+        "HMSET" => process_hmset(parts, kv_store),
+        "HMGET" => process_hmget(parts, kv_store),
+        "HINCRBY" => process_hincrby(parts, kv_store),
+        "HINCRBYFLOAT" => process_hincrbyfloat(parts, kv_store),
+        "HSETNX" => process_hsetnx(parts, kv_store),
+        "HRANDFIELD" => process_hrandfield(parts, kv_store),
+        "HSCAN" => process_hscan(parts, kv_store),
         _ => Err("Not supported".to_string()),
     };
-    match_result(result)
+    log_if_slow(&command, parts, started.elapsed(), client, state);
+    let reply = match result {
+        // Every command EXEC runs owes its array an element, so an Err can't be dropped there
+        Err(e) if client.in_exec => error_reply(&e),
+        result => match_result(result),
+    };
+
+    if is_write {
+        let commands = propagated(args, &reply);
+        if let Some(aof) = &state.aof
+            && let Err(e) = aof.append(db_index, &commands)
+        {
+            eprintln!("Could not append to the AOF: {}", e);
+        }
+        propagate(server_info, &state.replication, db_index, &commands);
+    }
+    reply
+}
+
+// A handler's Err as an error reply, keeping its code (e.g. WRONGTYPE) if it has one
+fn error_reply(e: &str) -> Vec<u8> {
+    let code = e.split(' ').next().unwrap_or_default();
+    if code.len() > 1 && code.chars().all(|c| c.is_ascii_uppercase()) {
+        encode_error_string(e)
+    } else {
+        encode_error_string(&format!("ERR {}", e))
+    }
 }
 
 pub fn match_result(result: RespResult) -> Vec<u8> {
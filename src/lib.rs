@@ -3,4 +3,5 @@ pub mod models;
 pub mod commands;
 pub mod utils;
 pub mod executor;
-pub mod constants;
\ No newline at end of file
+pub mod constants;
+pub mod replication;
\ No newline at end of file
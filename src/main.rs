@@ -4,14 +4,23 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use std::sync::{Arc, Mutex};
 use std::collections::{HashMap, VecDeque};
 use std::env;
+use std::path::Path;
 use tokio::sync::mpsc;
 
-use redis_cache::models::{ServerInfo, ReplicationInfo, RedisValue};
+use redis_cache::models::{new_databases, ClientInfo, ClientState, ServerConfig, ServerInfo, ReplicationInfo, ServerState};
 use redis_cache::parser;
+use redis_cache::replication;
 use redis_cache::constants::*;
+use redis_cache::utils::aof::{self, AppendOnlyFile};
+use redis_cache::utils::rdb;
+use redis_cache::utils::expiry::spawn_configured_expiry;
 
 #[tokio::main]
 async fn main() {
+    // RUST_LOG=debug brings back the per-command tracing. Without it, the
+    // loglevel setting decides what's logged, so CONFIG SET loglevel can change it
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("trace")).init();
+
     // You can use print statements as follows for debugging, they'll be visible when running tests.
     println!("Logs from your program will appear here!");
 
@@ -24,22 +33,95 @@ async fn main() {
     let role = args.iter()
         .position(|arg| arg == REPLICA_OF)
         .map_or("master", |_| "slave");
+    let master = args.iter()
+        .position(|arg| arg == REPLICA_OF)
+        .and_then(|idx| replication::parse_replicaof(&args[idx + 1..]));
+
+    let appendonly = args.iter()
+        .position(|arg| arg == APPEND_ONLY)
+        .and_then(|idx| args.get(idx + 1))
+        .is_some_and(|flag| flag.eq_ignore_ascii_case("yes"));
+
+    let mut config = ServerConfig::default();
+    if let Some(dir) = args.iter().position(|arg| arg == DIR).and_then(|idx| args.get(idx + 1)) {
+        config.dir = dir.clone();
+    }
+    if let Some(name) = args.iter().position(|arg| arg == DB_FILENAME).and_then(|idx| args.get(idx + 1)) {
+        config.dbfilename = name.clone();
+    }
+    if let Some(max_keys) = args.iter()
+        .position(|arg| arg == MAXMEMORY_KEYS)
+        .and_then(|idx| args.get(idx + 1))
+        .and_then(|raw| raw.parse().ok())
+    {
+        config.maxmemory_keys = max_keys;
+    }
+    if let Some(databases) = args.iter()
+        .position(|arg| arg == DATABASES)
+        .and_then(|idx| args.get(idx + 1))
+        .and_then(|raw| raw.parse().ok())
+        .filter(|databases| *databases > 0)
+    {
+        config.databases = databases;
+    }
+    // Checked the same way CONFIG SET checks them
+    for (flag, param) in [(HZ, "hz"), (REQUIRE_PASS, "requirepass"), (LOG_LEVEL, "loglevel")] {
+        if let Some(value) = args.iter().position(|arg| arg == flag).and_then(|idx| args.get(idx + 1))
+            && let Err(e) = config.set(param, value)
+        {
+            eprintln!("Ignoring {} {}: {}", flag, value, e);
+        }
+    }
+    if env::var_os("RUST_LOG").is_none() {
+        log::set_max_level(config.log_level());
+    }
     
     let listener = TcpListener::bind(format!("127.0.0.1:{}", port_num)).await.unwrap();
 
-    let store = Arc::new(Mutex::new(HashMap::new()));
-    let waiting_room: Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>> = Arc::new(Mutex::new(HashMap::new()));
+    // Whatever the last SAVE left behind is in place before the first client
+    // connects. With an AOF that's the more complete record, so it wins.
+    let databases = new_databases(config.databases as usize);
+    let path = rdb::rdb_path(&config);
+    if !appendonly {
+        match rdb::load(&databases, &path) {
+            Ok(0) => {},
+            Ok(loaded) => log::info!("Loaded {} keys from {}", loaded, path.display()),
+            Err(e) => eprintln!("Could not load {}: {}", path.display(), e),
+        }
+    }
+    let aof_path = Path::new(&config.dir).join(AOF_FILENAME);
+
     //todo: update for more info
-    let server_info: Arc<Mutex<ServerInfo>> = Arc::new(Mutex::new(ServerInfo{replication_info: ReplicationInfo::new(format!("{}", role))}));
+    let mut server_info = ServerInfo::new(ReplicationInfo::new(role.to_string()));
+    server_info.tcp_port = port_num.parse().unwrap_or(6379);
+    let requirepass = config.requirepass.clone();
+    let mut state = ServerState::new(databases, server_info, config);
+    state.acl.set_requirepass(requirepass);
+    if appendonly {
+        if let Err(e) = aof::replay(&aof_path, &state).await {
+            eprintln!("Could not replay {}: {}", aof_path.display(), e);
+        }
+        match AppendOnlyFile::open(&aof_path) {
+            Ok(aof) => state.aof = Some(Arc::new(aof)),
+            Err(e) => eprintln!("Could not open {}, running without an AOF: {}", aof_path.display(), e),
+        }
+    }
+    spawn_configured_expiry(Arc::clone(&state.databases), Arc::clone(&state.server_config));
+    if let Some((host, port)) = master {
+        let listening_port = port_num.parse().unwrap_or(6379);
+        tokio::spawn(replication::run_replica(host, port, listening_port, Arc::clone(&state.server_info)));
+    }
     
+    // CLIENT ID of the next connection; ids are never reused
+    let mut next_client_id: u64 = 1;
     loop {
         match listener.accept().await {
-            Ok((stream, _)) => {
-                let kv_store = Arc::clone(&store);
-                let room_clone = Arc::clone(&waiting_room);
-                let info_clone = Arc::clone(&server_info);
+            Ok((stream, addr)) => {
+                let state = state.clone();
+                let info = ClientInfo::new(next_client_id, addr.to_string());
+                next_client_id += 1;
                 tokio::spawn(async move { 
-                    handle_client(stream, kv_store, room_clone, info_clone).await;
+                    handle_client(stream, state, info).await;
                 });
             },
             Err(e) => eprintln!("Connection error: {}", e)
@@ -49,49 +131,64 @@ async fn main() {
 
 async fn handle_client(
     mut stream: tokio::net::TcpStream, 
-    kv_store: Arc<Mutex<HashMap<String, RedisValue>>>,           
-    waiting_room: Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>,
-    server_info: Arc<Mutex<ServerInfo>>
+    state: ServerState,
+    info: ClientInfo
 ) {
-    let mut buffer = [0; 512];
-    // For MULTI will keep track of pending commands by client, None
-    // should signal MULTI is not on
-    let mut command_queue: Option<VecDeque<Vec<String>>> = None;
+    // Bytes read but not yet run: the tail of a command that takes several reads
+    let mut buffer: Vec<u8> = Vec::with_capacity(READ_CHUNK_SIZE);
+    // MULTI queue, watched keys, selected database, etc. for this connection
+    let mut client = ClientState::new();
+    client.id = info.id;
+    // Listed by CLIENT LIST until the connection closes
+    let kill = Arc::clone(&info.kill);
+    client.status = Some(Arc::clone(&info.status));
+    state.clients.lock().unwrap().insert(info.id, info);
+    // Without a password there's nothing to log in to
+    client.authenticated = !state.acl.login_required();
+    // PUBLISH hands messages for this connection to `push_rx`, to be written between commands
+    let (push_tx, mut push_rx) = mpsc::channel(PUSH_CHANNEL_CAPACITY);
+    client.push_sender = Some(push_tx);
     loop {
-        match run_command(&mut stream, &mut buffer, &kv_store, &waiting_room, &mut command_queue, &server_info).await {
-            Ok(alive) if !alive => break, // EOF reached
-            Ok(_) => (),                 // Command handled, keep going
-            Err(e) => {
-                eprintln!("Connection error: {}", e);
-                break;
-            }
+        buffer.reserve(READ_CHUNK_SIZE);
+        let result = tokio::select! {
+            read = stream.read_buf(&mut buffer) => match read {
+                Ok(0) => break, // EOF reached
+                Ok(_) => run_command(&mut stream, &mut buffer, &state, &mut client).await,
+                Err(e) => Err(e.into()),
+            },
+            // The client holds a sender, so the channel never closes while we're here
+            Some(message) = push_rx.recv() => stream.write_all(&message).await.map_err(|e| e.into()),
+            _ = kill.notified() => break, // CLIENT KILL
+        };
+        if let Err(e) = result {
+            eprintln!("Connection error: {}", e);
+            break;
+        }
+        if client.close_after_reply {
+            break;
         }
-        
     }
+    state.clients.lock().unwrap().remove(&client.id);
 }
 
+// Runs every command that has fully arrived and writes their replies in one
+// go. Whatever follows the last whole command stays in `buffer` for the next read.
 async fn run_command(
     stream: &mut tokio::net::TcpStream, // Use &mut here
-    buffer: &mut [u8],
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,           
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>,
-    command_queue: &mut Option<VecDeque<Vec<String>>>, // Mutable ref to the state
-    server_info: &Arc<Mutex<ServerInfo>>
-) -> Result<bool, Box<dyn std::error::Error>> {
-    match stream.read(buffer).await? {
-        0 => return Ok(false), // Signal disconnect
-        bytes_read => {
-            let parsed_bytes = parser::parse_resp(
-                buffer, 
-                bytes_read, 
-                kv_store, 
-                waiting_room, 
-                command_queue,
-                server_info
-            ).await;
-            
-            stream.write_all(&parsed_bytes).await?;
-            Ok(true) // Keep loop alive
-        }
-    }   
-}
\ No newline at end of file
+    buffer: &mut Vec<u8>,
+    state: &ServerState,
+    client: &mut ClientState // Mutable ref to the state
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (replies, consumed) = parser::parse_pipeline(
+        buffer, 
+        state, 
+        client
+    ).await;
+    buffer.drain(..consumed);
+    if buffer.len() > MAX_QUERY_BUFFER_SIZE {
+        return Err("query buffer limit exceeded".into());
+    }
+    
+    stream.write_all(&replies).await?;
+    Ok(())
+}
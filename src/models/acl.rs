@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{RwLock, RwLockReadGuard};
+
+use crate::utils::glob::glob_match;
+
+/// Every ACL user by name. `default` always exists; it's who every connection
+/// starts as. Every command is checked against this, while only ACL SETUSER
+/// and CONFIG SET requirepass change it, so readers share the lock.
+pub struct Acl {
+    users: RwLock<HashMap<String, AclRule>>,
+    // Set while `default` is the only user and may run anything on any key,
+    // so checking a command needs no lock at all
+    unrestricted: AtomicBool,
+}
+
+impl Acl {
+    pub fn new() -> Self {
+        Self {
+            users: RwLock::new(HashMap::from([("default".to_string(), AclRule::default_user())])),
+            unrestricted: AtomicBool::new(true),
+        }
+    }
+
+    pub fn users(&self) -> RwLockReadGuard<'_, HashMap<String, AclRule>> {
+        self.users.read().unwrap()
+    }
+
+    /// Whether every connection may run every command, so there's nothing to check.
+    pub fn is_unrestricted(&self) -> bool {
+        self.unrestricted.load(Ordering::Acquire)
+    }
+
+    /// Applies `update` to a copy of `username`, creating the user if needed,
+    /// and keeps the result only if it succeeds.
+    pub fn update_user(
+        &self,
+        username: &str,
+        update: impl FnOnce(&mut AclRule) -> Result<(), String>
+    ) -> Result<(), String> {
+        let mut users = self.users.write().unwrap();
+        let mut user = users.get(username).cloned().unwrap_or_default();
+        update(&mut user)?;
+        users.insert(username.to_string(), user);
+        let unrestricted = users.len() == 1 && users.get("default").is_some_and(AclRule::is_unrestricted);
+        self.unrestricted.store(unrestricted, Ordering::Release);
+        Ok(())
+    }
+
+    /// Gives the default user `password`, as --requirepass does, or takes its password away.
+    pub fn set_requirepass(&self, password: Option<String>) {
+        let _ = self.update_user("default", |default| {
+            default.passwords.clear();
+            default.nopass = password.is_none();
+            default.passwords.extend(password);
+            Ok(())
+        });
+    }
+
+    /// Whether a new connection has to AUTH before it can run anything, which
+    /// it doesn't if the default user is enabled and needs no password.
+    pub fn login_required(&self) -> bool {
+        !self.users().get("default").is_some_and(|default| default.enabled && default.nopass)
+    }
+
+    /// Whether `username` and `password` log in.
+    pub fn check_password(&self, username: &str, password: &str) -> bool {
+        self.users().get(username).is_some_and(|user| user.check_password(password))
+    }
+}
+
+impl Default for Acl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One ACL user: whether it may log in, with which passwords, and which
+/// commands and keys it may then use. Built up from ACL SETUSER rules.
+#[derive(Clone)]
+pub struct AclRule {
+    pub enabled: bool,
+    // Logs in with any password, or none at all
+    pub nopass: bool,
+    pub passwords: HashSet<String>,
+    pub key_patterns: Vec<String>,
+    // +@all: every command except those in `denied_commands`; otherwise only
+    // those in `allowed_commands`
+    pub all_commands: bool,
+    pub allowed_commands: HashSet<String>,
+    pub denied_commands: HashSet<String>,
+}
+
+impl AclRule {
+    /// A user as ACL SETUSER first creates it: disabled, no passwords, no
+    /// keys and no commands.
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            nopass: false,
+            passwords: HashSet::new(),
+            key_patterns: Vec::new(),
+            all_commands: false,
+            allowed_commands: HashSet::new(),
+            denied_commands: HashSet::new(),
+        }
+    }
+
+    /// The `default` user every connection starts as: enabled, no password,
+    /// every key and every command.
+    pub fn default_user() -> Self {
+        Self {
+            enabled: true,
+            nopass: true,
+            key_patterns: vec!["*".to_string()],
+            all_commands: true,
+            ..Self::new()
+        }
+    }
+
+    /// Applies one ACL SETUSER rule, or explains (as a reply error) why it can't.
+    pub fn apply(&mut self, rule: &str) -> Result<(), String> {
+        match rule.to_lowercase().as_str() {
+            "on" | "enabled" => self.enabled = true,
+            "off" | "disabled" => self.enabled = false,
+            "nopass" => {
+                self.nopass = true;
+                self.passwords.clear();
+            },
+            "resetpass" => {
+                self.nopass = false;
+                self.passwords.clear();
+            },
+            "allkeys" => self.key_patterns = vec!["*".to_string()],
+            "resetkeys" => self.key_patterns.clear(),
+            "allcommands" | "+@all" => self.set_all_commands(true),
+            "nocommands" | "-@all" => self.set_all_commands(false),
+            "reset" => *self = Self::new(),
+            _ => {
+                if let Some(password) = rule.strip_prefix('>') {
+                    self.nopass = false;
+                    self.passwords.insert(password.to_string());
+                } else if let Some(password) = rule.strip_prefix('<') {
+                    if !self.passwords.remove(password) {
+                        return Err("ERR Error in ACL SETUSER modifier '<...>': no such password".to_string());
+                    }
+                } else if let Some(pattern) = rule.strip_prefix('~') {
+                    self.key_patterns.push(pattern.to_string());
+                } else if let Some(command) = rule.strip_prefix('+').filter(|command| is_command_name(command)) {
+                    let command = command.to_uppercase();
+                    self.denied_commands.remove(&command);
+                    self.allowed_commands.insert(command);
+                } else if let Some(command) = rule.strip_prefix('-').filter(|command| is_command_name(command)) {
+                    let command = command.to_uppercase();
+                    self.allowed_commands.remove(&command);
+                    self.denied_commands.insert(command);
+                } else {
+                    return Err(format!("ERR Error in ACL SETUSER modifier '{}': Syntax error", rule));
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn set_all_commands(&mut self, all: bool) {
+        self.all_commands = all;
+        self.allowed_commands.clear();
+        self.denied_commands.clear();
+    }
+
+    pub fn check_password(&self, password: &str) -> bool {
+        self.enabled && (self.nopass || self.passwords.contains(password))
+    }
+
+    /// Whether this user may run `command`, given in upper case.
+    pub fn can_run(&self, command: &str) -> bool {
+        if self.all_commands {
+            !self.denied_commands.contains(command)
+        } else {
+            self.allowed_commands.contains(command)
+        }
+    }
+
+    pub fn can_access(&self, key: &str) -> bool {
+        self.key_patterns.iter().any(|pattern| glob_match(pattern, key))
+    }
+
+    // Enabled, with every command and every key. Passwords don't matter here,
+    // since they only decide who logs in
+    fn is_unrestricted(&self) -> bool {
+        self.enabled && self.all_commands && self.denied_commands.is_empty()
+            && self.key_patterns.iter().any(|pattern| pattern == "*")
+    }
+
+    /// The rules that rebuild this user, as ACL LIST shows them. Passwords
+    /// are never listed.
+    pub fn describe(&self) -> String {
+        let mut rules = vec![if self.enabled { "on" } else { "off" }.to_string()];
+        if self.nopass {
+            rules.push("nopass".to_string());
+        }
+        if self.key_patterns.is_empty() {
+            rules.push("resetkeys".to_string());
+        }
+        rules.extend(self.key_patterns.iter().map(|pattern| format!("~{}", pattern)));
+        rules.push(if self.all_commands { "+@all" } else { "-@all" }.to_string());
+
+        let mut commands: Vec<String> = self.allowed_commands.iter().map(|command| format!("+{}", command.to_lowercase()))
+            .chain(self.denied_commands.iter().map(|command| format!("-{}", command.to_lowercase())))
+            .collect();
+        commands.sort();
+        rules.extend(commands);
+        rules.join(" ")
+    }
+}
+
+impl Default for AclRule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Categories other than @all aren't supported, so +@read is a syntax error rather than a no-op
+fn is_command_name(command: &str) -> bool {
+    !command.is_empty() && command.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
@@ -0,0 +1,268 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+
+use super::types::PushSender;
+
+/// Per-connection state that lives for as long as the client stays connected.
+pub struct ClientState {
+    // For MULTI will keep track of pending commands, None signals MULTI is not on
+    pub command_queue: Option<VecDeque<Vec<Vec<u8>>>>,
+    // Set when a command is rejected while queuing, so EXEC aborts the transaction
+    pub transaction_dirty: bool,
+    // True while EXEC runs the queued commands, which must never block
+    pub in_exec: bool,
+    // (database, key) -> version seen at WATCH time, None if the key didn't exist
+    pub watched_keys: HashMap<(usize, String), Option<u64>>,
+    // RESP version negotiated with HELLO; every connection starts on RESP2
+    pub protocol: u8,
+    // Assigned when the connection is accepted, unique and increasing; 0 for internal clients
+    pub id: u64,
+    // Set by CLIENT SETNAME or HELLO SETNAME
+    pub name: Option<String>,
+    // Set by CLIENT NO-EVICT; only reported, as clients are never evicted
+    pub no_evict: bool,
+    // Set by CLIENT NO-TOUCH: reads leave the keys' idle time alone
+    pub no_touch: bool,
+    // Which replies CLIENT REPLY lets through, applied by parse_pipeline
+    pub reply_mode: ReplyMode,
+    // Set by QUIT: handle_client closes the connection once the reply is written
+    pub close_after_reply: bool,
+    // Set by CLIENT CACHING yes; there's no client-side caching to apply it to yet
+    pub caching: bool,
+    // Whether AUTH (or HELLO AUTH) succeeded; only checked while the server requires a password
+    pub authenticated: bool,
+    // ACL user whose permissions apply, `default` until AUTH picks another
+    pub username: String,
+    // Database picked with SELECT, an index into the server's Databases
+    pub db_index: usize,
+    // Channels and patterns this connection is (P)SUBSCRIBEd to; while it has any
+    // it only accepts pub/sub commands
+    pub subscriptions: HashSet<String>,
+    pub pattern_subscriptions: HashSet<String>,
+    // Where PUBLISH sends messages for this connection, forwarded to the socket by handle_client
+    pub push_sender: Option<PushSender>,
+    // What CLIENT LIST shows for this connection, shared with its ClientInfo; None for internal clients
+    pub status: Option<Arc<Mutex<ClientStatus>>>,
+}
+
+impl ClientState {
+    pub fn new() -> Self {
+        Self {
+            command_queue: None,
+            transaction_dirty: false,
+            in_exec: false,
+            watched_keys: HashMap::new(),
+            protocol: 2,
+            id: 0,
+            name: None,
+            no_evict: false,
+            no_touch: false,
+            reply_mode: ReplyMode::On,
+            close_after_reply: false,
+            caching: false,
+            authenticated: false,
+            username: "default".to_string(),
+            db_index: 0,
+            subscriptions: HashSet::new(),
+            pattern_subscriptions: HashSet::new(),
+            push_sender: None,
+            status: None,
+        }
+    }
+
+    /// Channels plus patterns, the count reported by every (un)subscribe reply.
+    pub fn subscription_count(&self) -> usize {
+        self.subscriptions.len() + self.pattern_subscriptions.len()
+    }
+}
+
+/// What CLIENT REPLY lets through.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplyMode {
+    On,
+    Off,
+    // Drop the reply to the next command, then go back to On
+    Skip,
+}
+
+impl Default for ClientState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What CLIENT LIST reports about one connection, kept in the server's client
+/// registry. The parts its commands change are in `status`, which the
+/// connection updates through its own handle, so running a command never
+/// locks the registry itself.
+pub struct ClientInfo {
+    pub id: u64,
+    pub addr: String, // Peer address, ip:port
+    pub connected_at: Instant,
+    pub status: Arc<Mutex<ClientStatus>>,
+    // Notified by CLIENT KILL; handle_client closes the connection when it fires
+    pub kill: Arc<Notify>,
+}
+
+/// The parts of a CLIENT LIST line that change as the connection runs
+/// commands. Only the connection itself and CLIENT LIST lock it.
+pub struct ClientStatus {
+    pub last_interaction: Instant,
+    pub name: Option<String>,
+    pub username: String,
+    pub db_index: usize,
+    pub subscriptions: usize,
+    pub pattern_subscriptions: usize,
+    // Commands queued so far while in MULTI
+    pub multi: Option<usize>,
+    pub no_evict: bool,
+    pub no_touch: bool,
+    pub last_command: String,
+}
+
+impl ClientInfo {
+    pub fn new(id: u64, addr: String) -> Self {
+        let now = Instant::now();
+        Self {
+            id,
+            addr,
+            connected_at: now,
+            status: Arc::new(Mutex::new(ClientStatus::new(now))),
+            kill: Arc::new(Notify::new()),
+        }
+    }
+
+    /// One CLIENT LIST line, without the trailing newline.
+    pub fn describe(&self, now: Instant) -> String {
+        let status = self.status.lock().unwrap();
+        format!(
+            "id={} addr={} name={} age={} idle={} flags={} db={} sub={} psub={} multi={} cmd={} user={}",
+            self.id,
+            self.addr,
+            status.name.as_deref().unwrap_or(""),
+            now.duration_since(self.connected_at).as_secs(),
+            now.duration_since(status.last_interaction).as_secs(),
+            status.flags(),
+            status.db_index,
+            status.subscriptions,
+            status.pattern_subscriptions,
+            status.multi.map_or(-1, |queued| queued as i64),
+            status.last_command,
+            status.username,
+        )
+    }
+}
+
+impl ClientStatus {
+    fn new(now: Instant) -> Self {
+        Self {
+            last_interaction: now,
+            name: None,
+            username: "default".to_string(),
+            db_index: 0,
+            subscriptions: 0,
+            pattern_subscriptions: 0,
+            multi: None,
+            no_evict: false,
+            no_touch: false,
+            last_command: "NULL".to_string(),
+        }
+    }
+
+    /// Notes that `command` (given in upper case) has arrived.
+    pub fn record_command(&mut self, command: &str) {
+        self.last_interaction = Instant::now();
+        self.last_command = command.to_lowercase();
+    }
+
+    /// Catches up with whatever the last command changed in `client`.
+    pub fn refresh(&mut self, client: &ClientState) {
+        self.name = client.name.clone();
+        self.username = client.username.clone();
+        self.db_index = client.db_index;
+        self.subscriptions = client.subscriptions.len();
+        self.pattern_subscriptions = client.pattern_subscriptions.len();
+        self.multi = client.command_queue.as_ref().map(VecDeque::len);
+        self.no_evict = client.no_evict;
+        self.no_touch = client.no_touch;
+    }
+
+    // The flags= field: x in MULTI, P subscribed, e NO-EVICT, T NO-TOUCH, N for none
+    fn flags(&self) -> String {
+        let mut flags = String::new();
+        if self.multi.is_some() {
+            flags.push('x');
+        }
+        if self.subscriptions + self.pattern_subscriptions > 0 {
+            flags.push('P');
+        }
+        if self.no_evict {
+            flags.push('e');
+        }
+        if self.no_touch {
+            flags.push('T');
+        }
+        if flags.is_empty() {
+            flags.push('N');
+        }
+        flags
+    }
+}
+
+// A CLIENT PAUSE in effect
+struct Pause {
+    until: Instant,
+    // WRITE mode: reads carry on, only writes wait
+    writes_only: bool,
+}
+
+/// Server-wide CLIENT PAUSE state. Commands it covers wait before running
+/// until the pause runs out or CLIENT UNPAUSE lifts it.
+#[derive(Default)]
+pub struct ClientPause {
+    pause: Mutex<Option<Pause>>,
+    resumed: Notify,
+}
+
+impl ClientPause {
+    /// Pauses for `timeout`. Overlapping pauses keep the later end and the
+    /// stricter mode.
+    pub fn pause(&self, timeout: Duration, writes_only: bool) {
+        let until = Instant::now() + timeout;
+        let mut pause = self.pause.lock().unwrap();
+        *pause = Some(match pause.take().filter(|current| current.until > Instant::now()) {
+            Some(current) => Pause {
+                until: current.until.max(until),
+                writes_only: current.writes_only && writes_only,
+            },
+            None => Pause { until, writes_only },
+        });
+    }
+
+    pub fn unpause(&self) {
+        *self.pause.lock().unwrap() = None;
+        self.resumed.notify_waiters();
+    }
+
+    fn held_until(&self, is_write: bool) -> Option<Instant> {
+        self.pause.lock().unwrap().as_ref()
+            .filter(|pause| pause.until > Instant::now() && (is_write || !pause.writes_only))
+            .map(|pause| pause.until)
+    }
+
+    /// Waits out any pause covering a command, a write or not.
+    pub async fn wait(&self, is_write: bool) {
+        loop {
+            // Registered before checking, so an UNPAUSE in between isn't missed
+            let resumed = self.resumed.notified();
+            let Some(until) = self.held_until(is_write) else { return };
+            tokio::select! {
+                _ = tokio::time::sleep_until(until.into()) => {},
+                _ = resumed => {},
+            }
+        }
+    }
+}
@@ -1,17 +1,75 @@
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
-use super::stream::StreamEntry;
+use super::stream::Stream;
 
+// Longest string Redis keeps in a single allocation with its object header
+const EMBSTR_SIZE_LIMIT: usize = 44;
+
+#[derive(Clone)]
 pub enum RedisData {
-    String(String),
+    String(Vec<u8>), // Binary safe: whatever bytes the client sent
     List(Vec<String>),
-    Stream(Vec<StreamEntry>)
-    // Future: Set(HashSet<String>), Hash(HashMap<String, String>)
+    Stream(Stream),
+    Set(HashSet<String>),
+    ZSet(Vec<(f64, String)>), // Kept sorted by score, then member
+    Hash(HashMap<String, String>)
+}
+
+impl RedisData {
+    /// The name TYPE reports for this kind of value.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RedisData::String(_) => "string",
+            RedisData::List(_) => "list",
+            RedisData::Stream(_) => "stream",
+            RedisData::Set(_) => "set",
+            RedisData::ZSet(_) => "zset",
+            RedisData::Hash(_) => "hash",
+        }
+    }
+
+    /// The name OBJECT ENCODING reports, i.e. how Redis itself would hold
+    /// this value. Strings follow Redis' rules; the rest name the encoding
+    /// closest to how they're stored here.
+    pub fn encoding_name(&self) -> &'static str {
+        match self {
+            // Only canonical integers ("12", not "012" or "+12") are stored as ints
+            RedisData::String(s) if parse_integer(s).is_some_and(|n| n.to_string().as_bytes() == s) => "int",
+            RedisData::String(s) if s.len() <= EMBSTR_SIZE_LIMIT => "embstr",
+            RedisData::String(_) => "raw",
+            RedisData::List(_) => "quicklist",
+            RedisData::Stream(_) => "stream",
+            RedisData::Set(_) => "hashtable",
+            RedisData::ZSet(_) => "skiplist",
+            RedisData::Hash(_) => "hashtable",
+        }
+    }
+}
+
+/// A string value read as an i64, as INCR and OBJECT ENCODING see it.
+pub fn parse_integer(bytes: &[u8]) -> Option<i64> {
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+// Shared across every key so a deleted and re-created key never reuses a version
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+// Access times are kept as milliseconds since this instant so they fit in an atomic
+static CLOCK_START: OnceLock<Instant> = OnceLock::new();
+
+fn clock_ms() -> u64 {
+    CLOCK_START.get_or_init(Instant::now).elapsed().as_millis() as u64
 }
 
 pub struct RedisValue {
     pub data: RedisData,
     pub expires_at: Option<Instant>, // None means it never expires
+    pub version: u64, // Changes on every write, used by WATCH to detect modifications
+    // Atomic so reads holding only a shared shard lock can still record themselves
+    last_accessed: AtomicU64,
 }
 
 impl RedisValue {
@@ -19,6 +77,36 @@ impl RedisValue {
         Self {
             data,
             expires_at,
+            version: NEXT_VERSION.fetch_add(1, Ordering::Relaxed),
+            last_accessed: AtomicU64::new(clock_ms()),
         }
     }
+
+    /// Records a read, resetting the idle time OBJECT IDLETIME reports.
+    pub fn mark_accessed(&self) {
+        self.last_accessed.store(clock_ms(), Ordering::Relaxed);
+    }
+
+    /// How long since the value was last read or written.
+    pub fn idle_time(&self) -> Duration {
+        Duration::from_millis(clock_ms().saturating_sub(self.last_accessed.load(Ordering::Relaxed)))
+    }
+
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|expiry| now > expiry)
+    }
+
+    /// Marks the value as modified in place.
+    pub fn touch(&mut self) {
+        self.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+        self.mark_accessed();
+    }
+}
+
+// A clone is a new write as far as WATCH is concerned, so it gets its own
+// version instead of sharing the original's
+impl Clone for RedisValue {
+    fn clone(&self) -> Self {
+        Self::new(self.data.clone(), self.expires_at)
+    }
 }
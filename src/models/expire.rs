@@ -0,0 +1,14 @@
+/// Conditions accepted by EXPIRE and friends after the time argument.
+#[derive(Default)]
+pub struct ExpireOptions {
+    pub nx: bool, // Only when the key has no expiry
+    pub xx: bool, // Only when the key already has an expiry
+    pub gt: bool, // Only when the new expiry is later than the current one
+    pub lt: bool, // Only when the new expiry is earlier than the current one
+}
+
+/// How the time argument of an EXPIRE-family command is read.
+pub enum ExpireUnit {
+    Seconds,
+    Milliseconds,
+}
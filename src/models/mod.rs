@@ -3,9 +3,27 @@ mod data;
 mod list;
 mod stream;
 mod server;
+mod set;
+mod client;
+mod zset;
+mod store;
+mod expire;
+mod state;
+mod pubsub;
+mod acl;
+mod slowlog;
 
 pub use types::*;
 pub use data::*;
 pub use list::*;
 pub use stream::*;
 pub use server::*;
+pub use set::*;
+pub use client::*;
+pub use zset::*;
+pub use store::*;
+pub use expire::*;
+pub use state::*;
+pub use pubsub::*;
+pub use acl::*;
+pub use slowlog::*;
@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+
+use super::types::PushSender;
+
+/// Every live subscription, by exact channel name and by glob pattern.
+#[derive(Default)]
+pub struct Subscribers {
+    pub channels: HashMap<String, Vec<PushSender>>,
+    pub patterns: HashMap<String, Vec<PushSender>>,
+}
+
+/// Whether a (un)subscribe command works on channels or patterns.
+#[derive(Clone, Copy)]
+pub enum SubscriptionKind {
+    Channel,
+    Pattern,
+}
@@ -1,22 +1,139 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use super::store::DEFAULT_DATABASES;
+use crate::constants::SERVER_VERSION;
+use crate::utils::expiry::DEFAULT_HZ;
+use super::slowlog::{DEFAULT_SLOWLOG_LOG_SLOWER_THAN, DEFAULT_SLOWLOG_MAX_LEN};
+use super::types::PushSender;
+
+/// A section of INFO output, in the order plain INFO lists them.
+#[derive(Clone, Copy, PartialEq)]
 pub enum InfoOption {
-    Replication
+    Server,
+    Replication,
+    Keyspace,
+}
+
+impl InfoOption {
+    pub const ALL: [InfoOption; 3] = [InfoOption::Server, InfoOption::Replication, InfoOption::Keyspace];
+
+    /// The sections an INFO argument asks for; none if it names no section.
+    pub fn parse(name: &str) -> &'static [InfoOption] {
+        match name.to_lowercase().as_str() {
+            "server" => &[InfoOption::Server],
+            "replication" => &[InfoOption::Replication],
+            "keyspace" => &[InfoOption::Keyspace],
+            "all" | "everything" | "default" => &Self::ALL,
+            _ => &[],
+        }
+    }
 }
 
 pub struct ServerInfo {
-    pub replication_info: ReplicationInfo
+    pub replication_info: ReplicationInfo,
+    pub replicas: Replicas,
+    pub tcp_port: u16,
+    pub started_at: Instant,
+}
+
+impl ServerInfo {
+    pub fn new(replication_info: ReplicationInfo) -> Self {
+        Self {
+            replication_info,
+            replicas: Replicas::default(),
+            tcp_port: 6379,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// The `# Server` section of INFO.
+    pub fn server_info_string(&self) -> String {
+        let uptime = self.started_at.elapsed().as_secs();
+        format!(
+            "# Server\r\nredis_version:{}\r\nredis_mode:standalone\r\nos:{} {}\r\narch_bits:{}\r\n\
+            process_id:{}\r\ntcp_port:{}\r\nuptime_in_seconds:{}\r\nuptime_in_days:{}\r\n",
+            SERVER_VERSION, std::env::consts::OS, std::env::consts::ARCH, usize::BITS,
+            std::process::id(), self.tcp_port, uptime, uptime / (24 * 60 * 60)
+        )
+    }
+
+    /// Registers a replica that just took a full resync. The first one starts
+    /// the backlog, as every later write is now part of a stream to follow.
+    pub fn attach_replica(&mut self, sender: PushSender) {
+        self.replicas.senders.push(sender);
+        // The replica starts from a snapshot of every database, so the next write has to say which one it's for
+        self.replicas.db_index = None;
+        self.replication_info.activate_backlog();
+        self.replication_info.connected_slaves = self.replicas.senders.len() as u64;
+    }
+
+    /// Forgets replicas whose connection has closed.
+    pub fn prune_replicas(&mut self) {
+        self.replicas.senders.retain(|sender| !sender.is_closed());
+        self.replication_info.connected_slaves = self.replicas.senders.len() as u64;
+    }
+}
+
+// Connections that attached with PSYNC. Writes reach each one through its
+// connection's push channel, the same way PUBLISH messages do
+#[derive(Default)]
+pub struct Replicas {
+    pub senders: Vec<PushSender>,
+    // Database the write stream last SELECTed, so it's only re-sent on a change
+    pub db_index: Option<usize>,
+}
+
+/// Keeps PSYNC's snapshot in step with the write stream. A write holds it
+/// shared from when it runs until it has been propagated, and PSYNC holds it
+/// alone while it snapshots and attaches the replica, so every write is
+/// either in the snapshot or sent to the replica afterwards, never both.
+#[derive(Default)]
+pub struct ReplicationGate {
+    lock: RwLock<()>,
+    // Cleared only by propagation finding no replica left, under server_info,
+    // so writes with no replica to feed can skip locking server_info
+    attached: AtomicBool,
+}
+
+impl ReplicationGate {
+    pub async fn write(&self) -> RwLockReadGuard<'_, ()> {
+        self.lock.read().await
+    }
+
+    pub async fn sync(&self) -> RwLockWriteGuard<'_, ()> {
+        self.lock.write().await
+    }
+
+    /// False once no replica is attached; true may still find none.
+    pub fn may_have_replicas(&self) -> bool {
+        self.attached.load(Ordering::Acquire)
+    }
+
+    // Only changed while holding server_info, which orders it with the replica list
+    pub fn set_attached(&self, attached: bool) {
+        self.attached.store(attached, Ordering::Release);
+    }
 }
 
+// Same as Redis' default repl-backlog-size, 1mb
+pub const DEFAULT_REPL_BACKLOG_SIZE: u64 = 1024 * 1024;
+
 pub struct ReplicationInfo {
     pub info_type_name: String, //todo: maybe use enum and interface
     pub role: String,
-    // pub connected_slaves: u64,
+    pub connected_slaves: u64,
     pub master_replid: String,
     pub master_repl_offset: u64,
-    // pub second_repl_offset: i64,
-    // pub repl_backlog_active: u64,
-    // pub repl_backlog_size: u64,
-    // pub repl_backlog_first_byte_offset: i64,
-    // pub repl_backlog_histlen: u64
+    pub second_repl_offset: i64, // -1 until a failover leaves a previous replid to continue from
+    // Only what a backlog of the write stream would hold is tracked; the bytes
+    // aren't kept, since there is no partial resync to serve from them
+    pub repl_backlog_active: bool,
+    pub repl_backlog_size: u64,
+    pub repl_backlog_first_byte_offset: u64,
+    pub repl_backlog_histlen: u64,
 }
 
 impl ReplicationInfo {
@@ -24,17 +141,186 @@ impl ReplicationInfo {
         Self {
             info_type_name: "Replication".to_string(),
             role,
+            connected_slaves: 0,
             master_replid: Self::generate_replid(),
-            master_repl_offset: 0
+            master_repl_offset: 0,
+            second_repl_offset: -1,
+            repl_backlog_active: false,
+            repl_backlog_size: DEFAULT_REPL_BACKLOG_SIZE,
+            repl_backlog_first_byte_offset: 0,
+            repl_backlog_histlen: 0,
         }
     }
+
+    fn activate_backlog(&mut self) {
+        if !self.repl_backlog_active {
+            self.repl_backlog_active = true;
+            self.repl_backlog_first_byte_offset = self.master_repl_offset + 1;
+            self.repl_backlog_histlen = 0;
+        }
+    }
+
+    /// Advances the offset past `bytes` of write stream. The backlog keeps the
+    /// most recent `repl_backlog_size` of them, so once full its start moves too.
+    pub fn record_written(&mut self, bytes: u64) {
+        self.master_repl_offset += bytes;
+        if self.repl_backlog_active {
+            self.repl_backlog_histlen = (self.repl_backlog_histlen + bytes).min(self.repl_backlog_size);
+            self.repl_backlog_first_byte_offset = self.master_repl_offset - self.repl_backlog_histlen + 1;
+        }
+    }
+
     pub fn to_info_string(&self) -> String {
         format!(
-            "# {}\r\nrole:{}\r\nmaster_replid:{}\r\nmaster_repl_offset:{}\r\n",
-            self.info_type_name, self.role, self.master_replid, self.master_repl_offset
+            "# {}\r\nrole:{}\r\nconnected_slaves:{}\r\nmaster_replid:{}\r\nmaster_repl_offset:{}\r\n\
+            second_repl_offset:{}\r\nrepl_backlog_active:{}\r\nrepl_backlog_size:{}\r\n\
+            repl_backlog_first_byte_offset:{}\r\nrepl_backlog_histlen:{}\r\n",
+            self.info_type_name, self.role, self.connected_slaves, self.master_replid, self.master_repl_offset,
+            self.second_repl_offset, self.repl_backlog_active as u8, self.repl_backlog_size,
+            self.repl_backlog_first_byte_offset, self.repl_backlog_histlen
         )
     }
     fn generate_replid() -> String {
         "8371b4fb1155b71f4a04d3e1bc3e18c4a990aeeb".to_string()
     }
 }
+
+// Runtime-tunable parameters, read and written through CONFIG GET/SET
+#[derive(Clone)]
+pub struct ServerConfig {
+    pub dir: String,
+    pub dbfilename: String,
+    pub maxmemory_keys: u64, // Cap on keys across all databases, 0 means no cap
+    // Reported and settable for compatibility, but memory isn't measured;
+    // maxmemory-keys is the limit that's enforced
+    pub maxmemory: u64,
+    pub hz: u32, // Active expiry sweeps per second
+    pub requirepass: Option<String>,
+    // (seconds, changes) pairs for `save`. Nothing saves on a schedule yet,
+    // only SAVE and BGSAVE write snapshots
+    pub save_intervals: Vec<(u64, u64)>,
+    pub loglevel: String,
+    pub databases: u8, // Fixed once the server starts
+    // Microseconds a command must take to be logged; negative turns SLOWLOG off
+    pub slowlog_log_slower_than: i64,
+    pub slowlog_max_len: usize,
+    // Whether the background sweep removes expired keys; DEBUG SET-ACTIVE-EXPIRE
+    // turns it off so tests can watch lazy expiry alone. Not a CONFIG parameter
+    pub active_expire: bool,
+}
+
+impl ServerConfig {
+    pub fn new(dir: String, dbfilename: String) -> Self {
+        Self {
+            dir,
+            dbfilename,
+            maxmemory_keys: 0,
+            maxmemory: 0,
+            hz: DEFAULT_HZ as u32,
+            requirepass: None,
+            // Redis' defaults
+            save_intervals: vec![(3600, 1), (300, 100), (60, 10000)],
+            loglevel: "notice".to_string(),
+            databases: DEFAULT_DATABASES as u8,
+            slowlog_log_slower_than: DEFAULT_SLOWLOG_LOG_SLOWER_THAN,
+            slowlog_max_len: DEFAULT_SLOWLOG_MAX_LEN,
+            active_expire: true,
+        }
+    }
+
+    /// Every parameter as (name, value), in the order CONFIG GET lists them.
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        let save: Vec<String> = self.save_intervals.iter().map(|(seconds, changes)| format!("{} {}", seconds, changes)).collect();
+        vec![
+            ("dir", self.dir.clone()),
+            ("dbfilename", self.dbfilename.clone()),
+            ("maxmemory-keys", self.maxmemory_keys.to_string()),
+            ("maxmemory", self.maxmemory.to_string()),
+            ("hz", self.hz.to_string()),
+            ("requirepass", self.requirepass.clone().unwrap_or_default()),
+            ("save", save.join(" ")),
+            ("loglevel", self.loglevel.clone()),
+            ("databases", self.databases.to_string()),
+            ("slowlog-log-slower-than", self.slowlog_log_slower_than.to_string()),
+            ("slowlog-max-len", self.slowlog_max_len.to_string()),
+        ]
+    }
+
+    /// Updates `name`, or explains (as a reply error) why it can't.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let failed = |reason: &str| format!("ERR CONFIG SET failed (possibly related to argument '{}') - {}", name, reason);
+        let not_an_integer = || failed("argument couldn't be parsed into an integer");
+        match name.to_lowercase().as_str() {
+            "dir" => self.dir = value.to_string(),
+            "dbfilename" => self.dbfilename = value.to_string(),
+            "maxmemory-keys" => self.maxmemory_keys = value.parse().map_err(|_| not_an_integer())?,
+            "maxmemory" => self.maxmemory = parse_memory(value).ok_or_else(|| failed("argument must be a memory value"))?,
+            // Like Redis, out of range values are clamped rather than refused
+            "hz" => self.hz = value.parse::<i64>().map_err(|_| not_an_integer())?.clamp(1, 500) as u32,
+            "requirepass" => self.requirepass = Some(value.to_string()).filter(|password| !password.is_empty()),
+            "save" => self.save_intervals = parse_save(value).ok_or_else(|| failed("Invalid save parameters"))?,
+            "loglevel" => {
+                let level = value.to_lowercase();
+                if log_level_filter(&level).is_none() {
+                    return Err(failed("argument(s) must be one of the following: debug, verbose, notice, warning"));
+                }
+                self.loglevel = level;
+            },
+            "databases" => return Err(failed("can't set immutable config")),
+            "slowlog-log-slower-than" => self.slowlog_log_slower_than = value.parse().map_err(|_| not_an_integer())?,
+            "slowlog-max-len" => self.slowlog_max_len = value.parse().map_err(|_| not_an_integer())?,
+            _ => return Err(format!("ERR Unknown option or number of arguments for CONFIG SET - '{}'", name)),
+        }
+        Ok(())
+    }
+
+    /// How much `loglevel` lets through, as a `log` filter.
+    pub fn log_level(&self) -> log::LevelFilter {
+        log_level_filter(&self.loglevel).unwrap_or(log::LevelFilter::Warn)
+    }
+}
+
+// Redis' levels, from most to least verbose. notice is the default, and
+// matches what's logged when RUST_LOG isn't set
+fn log_level_filter(level: &str) -> Option<log::LevelFilter> {
+    match level {
+        "debug" => Some(log::LevelFilter::Debug),
+        "verbose" => Some(log::LevelFilter::Info),
+        "notice" => Some(log::LevelFilter::Warn),
+        "warning" => Some(log::LevelFilter::Error),
+        _ => None,
+    }
+}
+
+// A byte count with an optional unit: 100, 100b, 1k, 1kb, 5mb, 2gb...
+fn parse_memory(value: &str) -> Option<u64> {
+    let value = value.to_lowercase();
+    let digits = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(digits);
+    let multiplier = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+// "<seconds> <changes> ..." pairs; an empty value turns saving off
+fn parse_save(value: &str) -> Option<Vec<(u64, u64)>> {
+    let numbers: Vec<u64> = value.split_whitespace().map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    if !numbers.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(numbers.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect())
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self::new(".".to_string(), "dump.rdb".to_string())
+    }
+}
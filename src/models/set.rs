@@ -0,0 +1,6 @@
+// For SINTER, SUNION, SDIFF to pick how the input sets are combined
+pub enum SetOp {
+    Inter,
+    Union,
+    Diff
+}
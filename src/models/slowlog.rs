@@ -0,0 +1,73 @@
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Redis' defaults: log commands taking 10ms or more, and keep the last 128
+pub const DEFAULT_SLOWLOG_LOG_SLOWER_THAN: i64 = 10_000;
+pub const DEFAULT_SLOWLOG_MAX_LEN: usize = 128;
+
+// Long commands are cut down before they're kept, as Redis does
+const SLOWLOG_MAX_ARGS: usize = 32;
+const SLOWLOG_MAX_ARG_LEN: usize = 128;
+
+/// One command that took at least slowlog-log-slower-than to run.
+pub struct SlowlogEntry {
+    pub id: u64,
+    pub timestamp: u64, // Unix time in seconds when it was logged
+    pub duration: Duration,
+    pub args: Vec<String>,
+    pub client_addr: String,
+    pub client_name: String,
+}
+
+/// The slowest recent commands, newest first.
+#[derive(Default)]
+pub struct SlowlogBuffer {
+    pub entries: VecDeque<SlowlogEntry>,
+    // Ids keep counting up across SLOWLOG RESET
+    next_id: u64,
+}
+
+impl SlowlogBuffer {
+    /// Logs a command, dropping the oldest entries beyond `max_len`.
+    pub fn push(
+        &mut self,
+        args: &[String],
+        duration: Duration,
+        client_addr: String,
+        client_name: String,
+        max_len: usize
+    ) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+        self.entries.push_front(SlowlogEntry {
+            id: self.next_id,
+            timestamp,
+            duration,
+            args: trim_args(args),
+            client_addr,
+            client_name,
+        });
+        self.next_id += 1;
+        self.entries.truncate(max_len);
+    }
+}
+
+// At most SLOWLOG_MAX_ARGS arguments of at most SLOWLOG_MAX_ARG_LEN bytes,
+// each noting how much was left out
+fn trim_args(args: &[String]) -> Vec<String> {
+    let mut trimmed: Vec<String> = args.iter()
+        .take(if args.len() > SLOWLOG_MAX_ARGS { SLOWLOG_MAX_ARGS - 1 } else { SLOWLOG_MAX_ARGS })
+        .map(|arg| {
+            // Cut on a character boundary at or before the limit
+            let cut = (0..=SLOWLOG_MAX_ARG_LEN.min(arg.len())).rev().find(|&i| arg.is_char_boundary(i)).unwrap_or(0);
+            if cut < arg.len() {
+                format!("{}... ({} more bytes)", &arg[..cut], arg.len() - cut)
+            } else {
+                arg.clone()
+            }
+        })
+        .collect();
+    if args.len() > SLOWLOG_MAX_ARGS {
+        trimmed.push(format!("... ({} more arguments)", args.len() - trimmed.len()));
+    }
+    trimmed
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use super::acl::Acl;
+use super::client::ClientPause;
+use super::pubsub::Subscribers;
+use super::slowlog::SlowlogBuffer;
+use crate::utils::aof::AppendOnlyFile;
+use super::server::{ReplicationGate, ReplicationInfo, ServerConfig, ServerInfo};
+use super::store::{new_databases, DEFAULT_DATABASES};
+use super::types::{Clients, Databases, PubSub, Slowlog, WaitingRoom};
+
+/// The clients blocked in one database, kept apart by what they wait for so a
+/// write to one type never wakes or feeds a waiter expecting another.
+#[derive(Default)]
+pub struct WaitingRooms {
+    pub lists: WaitingRoom, // BLPOP
+    pub zsets: WaitingRoom, // BZPOPMIN, BZPOPMAX, BZMPOP
+    pub streams: WaitingRoom, // XREAD BLOCK
+}
+
+/// Everything shared between connections. Each connection holds a clone;
+/// the fields are all reference counted, so clones see the same data.
+#[derive(Clone)]
+pub struct ServerState {
+    pub databases: Databases,
+    pub waiting_rooms: Arc<Vec<WaitingRooms>>, // One per database, so blocked keys don't cross SELECT
+    pub pubsub: PubSub,
+    pub clients: Clients,
+    pub client_pause: Arc<ClientPause>,
+    pub slowlog: Slowlog,
+    pub server_info: Arc<Mutex<ServerInfo>>,
+    pub acl: Arc<Acl>,
+    pub replication: Arc<ReplicationGate>,
+    pub server_config: Arc<Mutex<ServerConfig>>,
+    pub bgsave_in_progress: Arc<AtomicBool>, // At most one BGSAVE writes at a time
+    pub aof: Option<Arc<AppendOnlyFile>>, // Set when running with --appendonly yes
+}
+
+impl ServerState {
+    pub fn new(databases: Databases, server_info: ServerInfo, server_config: ServerConfig) -> Self {
+        Self {
+            waiting_rooms: Arc::new((0..databases.len()).map(|_| WaitingRooms::default()).collect()),
+            databases,
+            pubsub: Arc::new(Mutex::new(Subscribers::default())),
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            client_pause: Arc::new(ClientPause::default()),
+            slowlog: Arc::new(Mutex::new(SlowlogBuffer::default())),
+            server_info: Arc::new(Mutex::new(server_info)),
+            acl: Arc::new(Acl::new()),
+            replication: Arc::new(ReplicationGate::default()),
+            server_config: Arc::new(Mutex::new(server_config)),
+            bgsave_in_progress: Arc::new(AtomicBool::new(false)),
+            aof: None,
+        }
+    }
+}
+
+// A master with default settings and empty databases
+impl Default for ServerState {
+    fn default() -> Self {
+        Self::new(
+            new_databases(DEFAULT_DATABASES),
+            ServerInfo::new(ReplicationInfo::new("master".to_string())),
+            ServerConfig::default(),
+        )
+    }
+}
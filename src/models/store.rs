@@ -0,0 +1,380 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use crate::constants::EVICTION_SAMPLES;
+
+use super::data::RedisValue;
+use super::types::Databases;
+
+pub const DEFAULT_SHARD_COUNT: usize = 16;
+// Same as Redis' default `databases` setting
+pub const DEFAULT_DATABASES: usize = 16;
+
+type Shard = HashMap<String, RedisValue>;
+
+// The keyspace, split into independently locked shards so commands on
+// unrelated keys don't queue up behind one another. Each shard is a RwLock,
+// so read-only commands on the same shard don't queue up either
+pub struct Store {
+    shards: Vec<RwLock<Shard>>,
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARD_COUNT)
+    }
+
+    pub fn with_shards(count: usize) -> Self {
+        assert!(count > 0, "a store needs at least one shard");
+        Self {
+            shards: (0..count).map(|_| RwLock::new(HashMap::new())).collect(),
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Index of the shard that owns `key`.
+    pub fn shard_of(&self, key: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.shards.len() as u64) as usize
+    }
+
+    /// Locks the shard owning `key`; everything a single-key command needs.
+    /// If `key` has expired it's dropped first, so the caller only ever finds
+    /// live data there whatever type it holds.
+    pub fn shard(&self, key: &str) -> RwLockWriteGuard<'_, Shard> {
+        let mut shard = self.shards[self.shard_of(key)].write().unwrap();
+        drop_if_expired(&mut shard, key, Instant::now());
+        shard
+    }
+
+    /// Shared lock on the shard owning `key`, for commands that only read.
+    /// Any number of these can be held at once.
+    pub fn shard_read(&self, key: &str) -> ShardReadGuard<'_> {
+        self.shard_read_at(self.shard_of(key))
+    }
+
+    /// Shared lock on shard `index`, for commands that walk the keyspace one
+    /// shard at a time instead of locking all of it.
+    pub fn shard_read_at(&self, index: usize) -> ShardReadGuard<'_> {
+        ShardReadGuard {
+            store: self,
+            shard: Some(self.shards[index].read().unwrap()),
+            expired: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Runs `read` on the value at `key` under a shared lock, passing `None`
+    /// if it's missing or expired. An expired value found on the way is then
+    /// removed under the write lock, so readers still clean up lazily.
+    pub fn read_live<T>(&self, key: &str, read: impl FnOnce(Option<&RedisValue>) -> T) -> T {
+        read(self.shard_read(key).get(key))
+    }
+
+    /// Locks every shard touched by `keys` so a multi-key command sees (and
+    /// writes) them atomically. Shards are always taken in index order, so two
+    /// overlapping callers can't deadlock. As with `shard`, any of `keys` that
+    /// has expired is dropped before the guard is handed back.
+    pub fn lock_keys<S: AsRef<str>>(&self, keys: &[S]) -> StoreGuard<'_> {
+        let mut guard = self.lock_shards(self.shards_of(keys), |shard| LockedShard::Write(shard.write().unwrap()));
+        let now = Instant::now();
+        for key in keys {
+            drop_if_expired(guard.shard_mut(key.as_ref()), key.as_ref(), now);
+        }
+        guard
+    }
+
+    /// Shared locks on every shard touched by `keys`, for multi-key commands
+    /// that only read, taken in the same order as `lock_keys`. Expired values
+    /// read as missing, and are removed once the guard is dropped.
+    pub fn read_keys<S: AsRef<str>>(&self, keys: &[S]) -> StoreGuard<'_> {
+        self.lock_shards(self.shards_of(keys), |shard| LockedShard::Read(shard.read().unwrap()))
+    }
+
+    /// Locks the whole keyspace, for commands that walk every key.
+    pub fn lock_all(&self) -> StoreGuard<'_> {
+        self.lock_shards((0..self.shards.len()).collect(), |shard| LockedShard::Write(shard.write().unwrap()))
+    }
+
+    // Indexes of the shards owning `keys`, ascending and without repeats
+    fn shards_of<S: AsRef<str>>(&self, keys: &[S]) -> Vec<usize> {
+        let mut wanted: Vec<usize> = keys.iter().map(|key| self.shard_of(key.as_ref())).collect();
+        wanted.sort_unstable();
+        wanted.dedup();
+        wanted
+    }
+
+    fn lock_shards<'a>(
+        &'a self,
+        indexes: Vec<usize>,
+        lock: impl Fn(&'a RwLock<Shard>) -> LockedShard<'a>
+    ) -> StoreGuard<'a> {
+        let mut guards: Vec<Option<LockedShard<'a>>> = (0..self.shards.len()).map(|_| None).collect();
+        for index in indexes {
+            guards[index] = Some(lock(&self.shards[index]));
+        }
+        StoreGuard { store: self, guards, expired: RefCell::new(Vec::new()) }
+    }
+
+    pub fn insert(&self, key: String, value: RedisValue) -> Option<RedisValue> {
+        self.shard(&key).insert(key, value)
+    }
+
+    pub fn remove(&self, key: &str) -> Option<RedisValue> {
+        self.shard(key).remove(key)
+    }
+
+    /// Whether anything is stored at `key`, even if it has expired and is
+    /// only waiting to be cleaned up.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.shards[self.shard_of(key)].read().unwrap().contains_key(key)
+    }
+
+    /// Drops every key that expired before `now`, one shard at a time so no
+    /// lock is held for longer than a single shard's scan. Returns how many
+    /// keys were removed.
+    pub fn remove_expired(&self, now: Instant) -> usize {
+        self.shards.iter()
+            .map(|shard| {
+                let mut shard = shard.write().unwrap();
+                let before = shard.len();
+                shard.retain(|_, value| !value.is_expired(now));
+                before - shard.len()
+            })
+            .sum()
+    }
+
+    /// Of the first `EVICTION_SAMPLES` keys in each shard, the one that has gone
+    /// longest without being read or written, and for how long.
+    pub fn least_recently_used(&self) -> Option<(String, Duration)> {
+        self.shards.iter()
+            .filter_map(|shard| {
+                let shard = shard.read().unwrap();
+                shard.iter()
+                    .take(EVICTION_SAMPLES)
+                    .map(|(key, value)| (key, value.idle_time()))
+                    .max_by_key(|(_, idle)| *idle)
+                    .map(|(key, idle)| (key.clone(), idle))
+            })
+            .max_by_key(|(_, idle)| *idle)
+    }
+
+    /// How many keys have a deadline that hasn't passed by `now`.
+    pub fn expiring_count(&self, now: Instant) -> usize {
+        self.shards.iter()
+            .map(|shard| {
+                shard.read().unwrap()
+                    .values()
+                    .filter(|value| value.expires_at.is_some() && !value.is_expired(now))
+                    .count()
+            })
+            .sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Lazy expiry: a key past its deadline is removed the first time it's locked
+fn drop_if_expired(shard: &mut Shard, key: &str, now: Instant) {
+    if shard.get(key).is_some_and(|value| value.is_expired(now)) {
+        shard.remove(key);
+    }
+}
+
+/// `count` empty, independent databases for SELECT to choose between.
+pub fn new_databases(count: usize) -> Databases {
+    Arc::new((0..count).map(|_| Arc::new(Store::new())).collect())
+}
+
+// One shard as held by a StoreGuard: shared for `read_keys`, exclusive otherwise
+enum LockedShard<'a> {
+    Read(RwLockReadGuard<'a, Shard>),
+    Write(RwLockWriteGuard<'a, Shard>),
+}
+
+impl LockedShard<'_> {
+    fn write(&mut self) -> &mut Shard {
+        match self {
+            LockedShard::Write(shard) => shard,
+            LockedShard::Read(_) => panic!("shard was only locked for reading"),
+        }
+    }
+}
+
+impl Deref for LockedShard<'_> {
+    type Target = Shard;
+
+    fn deref(&self) -> &Shard {
+        match self {
+            LockedShard::Read(shard) => shard,
+            LockedShard::Write(shard) => shard,
+        }
+    }
+}
+
+// A set of locked shards that reads like one map. Touching a key whose shard
+// wasn't locked, or writing through a guard from `read_keys`, is a bug in the
+// caller, so it panics rather than deadlocking.
+pub struct StoreGuard<'a> {
+    store: &'a Store,
+    guards: Vec<Option<LockedShard<'a>>>,
+    expired: RefCell<Vec<String>>, // Seen by `get`, removed once the locks are released
+}
+
+impl StoreGuard<'_> {
+    fn shard(&self, key: &str) -> &Shard {
+        self.guards[self.store.shard_of(key)]
+            .as_ref()
+            .expect("key's shard was not locked")
+    }
+
+    fn shard_mut(&mut self, key: &str) -> &mut Shard {
+        let index = self.store.shard_of(key);
+        self.guards[index]
+            .as_mut()
+            .expect("key's shard was not locked")
+            .write()
+    }
+
+    /// The live value at `key`; an expired one reads as missing.
+    pub fn get(&self, key: &str) -> Option<&RedisValue> {
+        live(self.shard(key).get(key), key, &self.expired)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut RedisValue> {
+        self.shard_mut(key).get_mut(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn entry(&mut self, key: String) -> Entry<'_, String, RedisValue> {
+        self.shard_mut(&key).entry(key)
+    }
+
+    pub fn insert(&mut self, key: String, value: RedisValue) -> Option<RedisValue> {
+        self.shard_mut(&key).insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<RedisValue> {
+        self.shard_mut(key).remove(key)
+    }
+
+    /// Every key/value pair in the locked shards.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &RedisValue)> {
+        self.guards.iter().flatten().flat_map(|shard| shard.iter())
+    }
+
+    /// Empties every locked shard.
+    pub fn clear(&mut self) {
+        for shard in self.guards.iter_mut().flatten() {
+            shard.write().clear();
+        }
+    }
+
+    /// Empties every locked shard, handing back what was in them so the
+    /// caller can decide where to pay for freeing it.
+    pub fn take_all(&mut self) -> Vec<HashMap<String, RedisValue>> {
+        self.guards.iter_mut().flatten().map(|shard| std::mem::take(shard.write())).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.guards.iter().flatten().map(|shard| shard.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for StoreGuard<'_> {
+    fn drop(&mut self) {
+        self.guards.clear();
+        remove_expired_keys(self.store, self.expired.get_mut());
+    }
+}
+
+// A shared lock on one shard, from `Store::shard_read`. Only lookups are
+// offered, and like `StoreGuard::get` they skip expired values, which are
+// removed under the write lock once this is dropped.
+pub struct ShardReadGuard<'a> {
+    store: &'a Store,
+    shard: Option<RwLockReadGuard<'a, Shard>>, // Only None while dropping
+    expired: RefCell<Vec<String>>,
+}
+
+impl ShardReadGuard<'_> {
+    /// The live value at `key`; an expired one reads as missing.
+    pub fn get(&self, key: &str) -> Option<&RedisValue> {
+        let shard = self.shard.as_ref().expect("shard read guard already released");
+        live(shard.get(key), key, &self.expired)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Every key in the shard, in the map's own order. An expired key is
+    /// still listed, so positions in that order stay put, but its value reads
+    /// as missing.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, Option<&RedisValue>)> {
+        let shard = self.shard.as_ref().expect("shard read guard already released");
+        shard.iter().map(|(key, value)| (key, live(Some(value), key, &self.expired)))
+    }
+
+    /// How many keys the shard holds, expired ones included.
+    pub fn len(&self) -> usize {
+        self.shard.as_ref().map_or(0, |shard| shard.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for ShardReadGuard<'_> {
+    fn drop(&mut self) {
+        self.shard = None;
+        remove_expired_keys(self.store, self.expired.get_mut());
+    }
+}
+
+// Hides `value` if it has expired, noting `key` so the guard can remove it later
+fn live<'a>(value: Option<&'a RedisValue>, key: &str, expired: &RefCell<Vec<String>>) -> Option<&'a RedisValue> {
+    match value {
+        Some(value) if value.is_expired(Instant::now()) => {
+            expired.borrow_mut().push(key.to_string());
+            None
+        },
+        value => value,
+    }
+}
+
+// Lazy expiry for readers: write-locking each key drops it, unless someone
+// replaced it after the reader's lock was released
+fn remove_expired_keys(store: &Store, keys: &mut Vec<String>) {
+    for key in keys.drain(..) {
+        drop(store.shard(&key));
+    }
+}
@@ -1,6 +1,32 @@
 use std::collections::HashMap;
 
+#[derive(Clone)]
 pub struct StreamEntry {
     pub id: String,
     pub fields: HashMap<String, String>,
 }
+
+#[derive(Clone)]
+pub struct Stream {
+    pub entries: Vec<StreamEntry>,
+    pub last_id: String, // Highest ID ever used, kept even once that entry is gone
+    pub entries_added: u64,
+    pub max_deleted_entry_id: String,
+}
+
+impl Stream {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            last_id: "0-0".to_string(),
+            entries_added: 0,
+            max_deleted_entry_id: "0-0".to_string(),
+        }
+    }
+}
+
+impl Default for Stream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
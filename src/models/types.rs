@@ -1 +1,30 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+use super::client::ClientInfo;
+use super::pubsub::Subscribers;
+use super::slowlog::SlowlogBuffer;
+use super::store::Store;
+
 pub type RespResult = Result<Vec<u8>, String>;
+
+pub type KvStore = Arc<Store>;
+
+// Every logical database, indexed by the number clients SELECT
+pub type Databases = Arc<Vec<KvStore>>;
+
+// Delivers already-encoded pub/sub messages to one connection
+pub type PushSender = mpsc::Sender<Vec<u8>>;
+
+// Pub/sub subscribers, kept apart from the waiting room
+pub type PubSub = Arc<Mutex<Subscribers>>;
+
+// Clients blocked on each key for one kind of value in one database, woken in arrival order
+pub type WaitingRoom = Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>;
+
+// Every open connection by CLIENT ID, for CLIENT LIST and CLIENT KILL
+pub type Clients = Arc<Mutex<HashMap<u64, ClientInfo>>>;
+
+// Commands that ran slower than slowlog-log-slower-than, for SLOWLOG
+pub type Slowlog = Arc<Mutex<SlowlogBuffer>>;
@@ -0,0 +1,132 @@
+/// Flags accepted by ZADD before the score/member pairs.
+#[derive(Default)]
+pub struct ZAddOptions {
+    pub nx: bool, // Only add new members
+    pub xx: bool, // Only update existing members
+    pub gt: bool, // Only update when the new score is greater
+    pub lt: bool, // Only update when the new score is lower
+    pub ch: bool, // Count changed members in the reply, not just added ones
+}
+
+/// Which end of a sorted set ZPOPMIN/ZPOPMAX/ZMPOP take from.
+pub enum ZPopSide {
+    Min,
+    Max,
+}
+
+/// How ZRANGE interprets its start/stop arguments.
+#[derive(Default, PartialEq)]
+pub enum ZRangeBy {
+    #[default]
+    Rank,
+    Score,
+    Lex,
+}
+
+/// Options shared by ZRANGE and its legacy ZREVRANGE/ZRANGEBYSCORE/... forms.
+#[derive(Default)]
+pub struct ZRangeOptions {
+    pub by: ZRangeBy,
+    pub rev: bool,
+    pub limit: Option<(i64, i64)>, // (offset, count), a negative count means "all"
+    pub with_scores: bool,
+}
+
+/// How ZUNION/ZINTER combine the scores a member has in several inputs.
+#[derive(Default)]
+pub enum ZAggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+}
+
+impl ZAggregate {
+    pub fn combine(&self, a: f64, b: f64) -> f64 {
+        match self {
+            // inf + -inf is NaN; Redis settles on 0
+            Self::Sum => {
+                let sum = a + b;
+                if sum.is_nan() { 0.0 } else { sum }
+            },
+            Self::Min => a.min(b),
+            Self::Max => a.max(b),
+        }
+    }
+}
+
+/// Options for ZUNION, ZINTER and ZDIFF and their STORE forms.
+#[derive(Default)]
+pub struct ZSetOpOptions {
+    pub weights: Option<Vec<f64>>, // One multiplier per input key
+    pub aggregate: ZAggregate,
+    pub with_scores: bool,
+}
+
+/// A score range endpoint: "1.5", "(1.5" (exclusive), "-inf" or "+inf".
+pub struct ScoreBound {
+    pub value: f64,
+    pub exclusive: bool,
+}
+
+impl ScoreBound {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let (exclusive, number) = match raw.strip_prefix('(') {
+            Some(rest) => (true, rest),
+            None => (false, raw),
+        };
+        let value: f64 = number.parse().ok()?;
+        if value.is_nan() {
+            return None;
+        }
+        Some(Self { value, exclusive })
+    }
+
+    pub fn allows_above(&self, score: f64) -> bool {
+        if self.exclusive { score > self.value } else { score >= self.value }
+    }
+
+    pub fn allows_below(&self, score: f64) -> bool {
+        if self.exclusive { score < self.value } else { score <= self.value }
+    }
+}
+
+/// A lexicographical range endpoint: "-", "+", "[member" (inclusive) or "(member" (exclusive).
+pub enum LexBound {
+    Min,
+    Max,
+    Inclusive(String),
+    Exclusive(String),
+}
+
+impl LexBound {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "-" => Some(Self::Min),
+            "+" => Some(Self::Max),
+            _ => match raw.split_at_checked(1)? {
+                ("[", member) => Some(Self::Inclusive(member.to_string())),
+                ("(", member) => Some(Self::Exclusive(member.to_string())),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn allows_above(&self, member: &str) -> bool {
+        match self {
+            Self::Min => true,
+            Self::Max => false,
+            Self::Inclusive(bound) => member >= bound.as_str(),
+            Self::Exclusive(bound) => member > bound.as_str(),
+        }
+    }
+
+    pub fn allows_below(&self, member: &str) -> bool {
+        match self {
+            Self::Min => false,
+            Self::Max => true,
+            Self::Inclusive(bound) => member <= bound.as_str(),
+            Self::Exclusive(bound) => member < bound.as_str(),
+        }
+    }
+}
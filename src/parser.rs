@@ -1,41 +1,124 @@
-use std::sync::{Arc, Mutex};
-use std::collections::{VecDeque, HashMap};
-use tokio::sync::mpsc;
-
-use crate::models::{ServerInfo, RedisValue};
+use crate::models::{ClientState, ReplyMode, ServerState};
 use crate::commands::*;
-use crate::utils::decoder::decode_resp;
+use crate::utils::decoder::{decode_resp, split_frame};
+use crate::utils::encoder::encode_error_string;
 use crate::executor::*;
+use crate::constants::{COMMAND_ARITY, WRITE_COMMANDS};
+
+/// Runs every whole command at the front of `buffer`, in order, and returns
+/// their replies concatenated along with how many bytes were consumed. A
+/// command that's only partly arrived is left for the caller to complete.
+pub async fn parse_pipeline(
+    buffer: &[u8],
+    state: &ServerState,
+    client: &mut ClientState
+) -> (Vec<u8>, usize) {
+    let mut replies = Vec::new();
+    let mut rest = buffer;
+    while let Some((frame, remainder)) = split_frame(rest) {
+        let reply_mode = client.reply_mode;
+        let reply = parse_resp(frame, frame.len(), state, client).await;
+        // CLIENT REPLY OFF drops every reply until CLIENT REPLY ON, whose OK
+        // is sent; SKIP drops just the reply to the command after it
+        if reply_mode == ReplyMode::On || client.reply_mode == ReplyMode::On {
+            replies.extend(reply);
+        }
+        if reply_mode == ReplyMode::Skip && client.reply_mode == ReplyMode::Skip {
+            client.reply_mode = ReplyMode::On;
+        }
+        if let Some(status) = &client.status {
+            status.lock().unwrap().refresh(client);
+        }
+        rest = remainder;
+        // Nothing after a QUIT runs
+        if client.close_after_reply {
+            break;
+        }
+    }
+    (replies, buffer.len() - rest.len())
+}
 
 pub async fn parse_resp(
-    buffer: &mut [u8],
+    buffer: &[u8],
     bytes_read: usize,
-    kv_store: &Arc<Mutex<HashMap<String, RedisValue>>>,
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>,
-    command_queue: &mut Option<VecDeque<Vec<String>>>,
-    server_info: &Arc<Mutex<ServerInfo>>
+    state: &ServerState,
+    client: &mut ClientState
 ) -> Vec<u8> {
 
-    let data = String::from_utf8_lossy(&buffer[..bytes_read]);
-    let parts = decode_resp(&data);
-    println!("DEBUG: Received parts: {:?}", parts);
+    // Framing is done on the raw bytes. Handlers that store values (the
+    // string commands) get those bytes as sent; the rest read each argument
+    // as a String, where a non-UTF-8 byte becomes a replacement character
+    let args = decode_resp(&buffer[..bytes_read]);
+    let parts = lossy_parts(&args);
+    log::debug!("Received parts: {:?}", parts);
 
     if parts.is_empty() {
         return vec![];
     }
     let command = parts[0].to_uppercase();
+    if let Some(status) = &client.status {
+        status.lock().unwrap().record_command(&command);
+    }
+
+    // Until it logs in, a connection to a server with a password can only log in or leave
+    if !client.authenticated
+        && !matches!(command.as_str(), "AUTH" | "HELLO" | "QUIT" | "RESET")
+        && state.acl.login_required()
+    {
+        return encode_error_string("NOAUTH Authentication required.");
+    }
+
+    // A subscribed connection only takes commands that manage its subscriptions
+    if client.subscription_count() > 0
+        && !matches!(command.as_str(), "SUBSCRIBE" | "UNSUBSCRIBE" | "PSUBSCRIBE" | "PUNSUBSCRIBE" | "PING" | "QUIT" | "RESET")
+    {
+        return encode_error_string(&format!(
+            "ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context",
+            parts[0].to_lowercase()
+        ));
+    }
 
-    // If multi is active, push all commands onto queue and return unless command is exec or discard
-    if let Some(queue) = command_queue {
+    // If multi is active, push all commands onto queue and return unless the command
+    // controls the transaction itself (MULTI and WATCH only report that they can't nest,
+    // RESET and QUIT abandon it)
+    if let Some(queue) = &mut client.command_queue {
         match command.as_str() {
-            "EXEC" | "DISCARD" => {},
+            "EXEC" | "DISCARD" | "MULTI" | "WATCH" | "RESET" | "QUIT" => {},
             _ => {
-                let queue_push_result = handle_push_command_queue(&parts, queue);
+                // Rejected commands aren't queued and doom the whole transaction
+                if let Err(e) = validate_command(&command, &parts) {
+                    client.transaction_dirty = true;
+                    return encode_error_string(&e);
+                }
+                let queue_push_result = handle_push_command_queue(&args, queue);
                 return match_result(queue_push_result);
             }
         }
     }
-    execute_commands(command, &parts, &kv_store, &waiting_room, command_queue, &server_info).await
+    // CLIENT PAUSE holds back everything, or just writes, until it's over.
+    // EXEC counts as a write if anything it's about to run is one
+    let is_write = WRITE_COMMANDS.contains(&command.as_str())
+        || (command == "EXEC" && client.command_queue.as_ref().is_some_and(|queue| {
+            queue.iter().any(|queued| WRITE_COMMANDS.contains(&String::from_utf8_lossy(&queued[0]).to_uppercase().as_str()))
+        }));
+    state.client_pause.wait(is_write).await;
+    execute_commands(command, &parts, &args, state, client).await
 }
 
+/// Each argument as text, for the commands that don't take raw bytes.
+pub fn lossy_parts(args: &[Vec<u8>]) -> Vec<String> {
+    args.iter().map(|arg| String::from_utf8_lossy(arg).into_owned()).collect()
+}
 
+// Checks the command exists and has a valid number of arguments
+fn validate_command(command: &str, parts: &[String]) -> Result<(), String> {
+    let Some((_, arity)) = COMMAND_ARITY.iter().find(|(name, _)| *name == command) else {
+        let args: String = parts[1..].iter().map(|arg| format!("'{}' ", arg)).collect();
+        return Err(format!("ERR unknown command '{}', with args beginning with: {}", parts[0], args));
+    };
+    let argc = parts.len() as i64;
+    if (*arity >= 0 && argc != *arity) || (*arity < 0 && argc < -arity) {
+        return Err(format!("ERR wrong number of arguments for '{}' command", parts[0].to_lowercase()));
+    }
+    Ok(())
+}
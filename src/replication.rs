@@ -0,0 +1,185 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::error::TrySendError;
+
+use crate::models::{ReplicationGate, ServerInfo};
+use crate::utils::encoder::{encode_array, encode_bytes_array};
+
+/// Sends `commands`, just run against database `db_index`, to every attached
+/// replica and advances `master_repl_offset` by the bytes sent. A replica
+/// that has disconnected, or fallen so far behind that its channel is full,
+/// is dropped: it could never catch up from here and has to resync.
+pub fn propagate(
+    server_info: &Arc<Mutex<ServerInfo>>,
+    replication: &ReplicationGate,
+    db_index: usize,
+    commands: &[Vec<Vec<u8>>]
+) {
+    if commands.is_empty() || !replication.may_have_replicas() {
+        return;
+    }
+    let mut info = server_info.lock().unwrap();
+    if info.replicas.senders.is_empty() {
+        replication.set_attached(false);
+        return;
+    }
+    let mut stream = Vec::new();
+    if info.replicas.db_index != Some(db_index) {
+        stream.extend(encode_array(&["SELECT".to_string(), db_index.to_string()]));
+        info.replicas.db_index = Some(db_index);
+    }
+    for command in commands {
+        stream.extend(encode_bytes_array(command));
+    }
+
+    info.replicas.senders.retain(|sender| match sender.try_send(stream.clone()) {
+        Ok(()) => true,
+        Err(TrySendError::Full(_)) => {
+            log::warn!("Dropping a replica that fell too far behind");
+            false
+        },
+        Err(TrySendError::Closed(_)) => false,
+    });
+    info.replication_info.connected_slaves = info.replicas.senders.len() as u64;
+    info.replication_info.record_written(stream.len() as u64);
+    replication.set_attached(!info.replicas.senders.is_empty());
+}
+
+/// Where `--replicaof` points, accepted as one `"host port"` argument or as two.
+pub fn parse_replicaof(args: &[String]) -> Option<(String, u16)> {
+    let first = args.first()?;
+    let mut words = first.split_whitespace();
+    let host = words.next()?.to_string();
+    let port = match words.next() {
+        Some(port) => port,
+        None => args.get(1)?,
+    };
+    Some((host, port.parse().ok()?))
+}
+
+/// What the master answered PSYNC with: the replication stream this replica
+/// now follows and the offset it starts from.
+#[derive(Debug, PartialEq)]
+pub struct FullResync {
+    pub replid: String,
+    pub offset: u64,
+}
+
+/// Runs the replica side of the handshake on a fresh connection to the
+/// master: PING, REPLCONF listening-port, REPLCONF capa psync2, then PSYNC ? -1.
+/// Any reply other than the expected one stops the handshake with an error.
+///
+/// The stream comes back buffered, since whatever the master sent after
+/// +FULLRESYNC (the snapshot, then the write stream) may already be in the buffer.
+pub async fn handshake(
+    stream: TcpStream,
+    listening_port: u16
+) -> io::Result<(FullResync, BufReader<TcpStream>)> {
+    let mut stream = BufReader::new(stream);
+
+    send(&mut stream, &["PING"]).await?;
+    expect(&mut stream, "PONG").await?;
+    send(&mut stream, &["REPLCONF", "listening-port", &listening_port.to_string()]).await?;
+    expect(&mut stream, "OK").await?;
+    send(&mut stream, &["REPLCONF", "capa", "psync2"]).await?;
+    expect(&mut stream, "OK").await?;
+    send(&mut stream, &["PSYNC", "?", "-1"]).await?;
+
+    let reply = read_simple_string(&mut stream).await?;
+    let resync = parse_fullresync(&reply)
+        .ok_or_else(|| protocol_error(format!("expected +FULLRESYNC, master replied '{}'", reply)))?;
+    Ok((resync, stream))
+}
+
+/// Connects to the master at `host`:`port`, completes the handshake and
+/// records the master's replication ID and offset as this server's own.
+/// Returns the open link to the master.
+pub async fn connect_to_master(
+    host: &str,
+    port: u16,
+    listening_port: u16,
+    server_info: &Arc<Mutex<ServerInfo>>
+) -> io::Result<BufReader<TcpStream>> {
+    let stream = TcpStream::connect((host, port)).await?;
+    let (resync, stream) = handshake(stream, listening_port).await?;
+    log::info!("Replicating {}:{} from offset {} of {}", host, port, resync.offset, resync.replid);
+
+    let mut info = server_info.lock().unwrap();
+    info.replication_info.master_replid = resync.replid;
+    info.replication_info.master_repl_offset = resync.offset;
+    Ok(stream)
+}
+
+/// Follows the master for as long as the link stays up. Nothing is applied
+/// yet; the link is only held open (and drained) so the master keeps this
+/// replica registered.
+pub async fn run_replica(
+    host: String,
+    port: u16,
+    listening_port: u16,
+    server_info: Arc<Mutex<ServerInfo>>
+) {
+    let mut stream = match connect_to_master(&host, port, listening_port, &server_info).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Replication handshake with {}:{} failed: {}", host, port, e);
+            return;
+        },
+    };
+    let mut buffer = [0; 4096];
+    loop {
+        match stream.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(read) => log::debug!("Ignoring {} bytes from master", read),
+            Err(e) => {
+                eprintln!("Lost connection to master {}:{}: {}", host, port, e);
+                break;
+            },
+        }
+    }
+}
+
+// "FULLRESYNC <replid> <offset>"
+fn parse_fullresync(reply: &str) -> Option<FullResync> {
+    let mut words = reply.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("FULLRESYNC") {
+        return None;
+    }
+    let replid = words.next()?.to_string();
+    let offset = words.next()?.parse().ok()?;
+    Some(FullResync { replid, offset })
+}
+
+async fn send(stream: &mut BufReader<TcpStream>, args: &[&str]) -> io::Result<()> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    stream.get_mut().write_all(&encode_array(&args)).await
+}
+
+async fn expect(stream: &mut BufReader<TcpStream>, expected: &str) -> io::Result<()> {
+    let reply = read_simple_string(stream).await?;
+    if !reply.eq_ignore_ascii_case(expected) {
+        return Err(protocol_error(format!("expected +{}, master replied '{}'", expected, reply)));
+    }
+    Ok(())
+}
+
+// One reply line, which must be a simple string; an error reply is passed on as an error
+async fn read_simple_string(stream: &mut BufReader<TcpStream>) -> io::Result<String> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "master closed the connection"));
+    }
+    let line = line.trim_end_matches(['\r', '\n']);
+    match line.as_bytes().first() {
+        Some(b'+') => Ok(line[1..].to_string()),
+        Some(b'-') => Err(protocol_error(format!("master replied with an error: {}", &line[1..]))),
+        _ => Err(protocol_error(format!("unexpected reply from master: '{}'", line))),
+    }
+}
+
+fn protocol_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
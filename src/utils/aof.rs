@@ -0,0 +1,169 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::models::{ClientState, ServerState};
+use crate::parser::parse_pipeline;
+use crate::utils::encoder::{encode_array, encode_bytes_array};
+
+/// The append-only file: every write command, as RESP, in the order it ran.
+/// Replaying it through the parser rebuilds the keyspace.
+pub struct AppendOnlyFile {
+    writer: Mutex<AofWriter>,
+}
+
+struct AofWriter {
+    file: BufWriter<File>,
+    // Database the last record ran against, so a SELECT is only written on a change
+    db_index: Option<usize>,
+}
+
+impl AppendOnlyFile {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: Mutex::new(AofWriter { file: BufWriter::new(file), db_index: None }),
+        })
+    }
+
+    /// Appends `commands`, run against database `db_index`, and hands them to
+    /// the OS before returning so they outlive a crash of this process.
+    pub fn append(&self, db_index: usize, commands: &[Vec<Vec<u8>>]) -> io::Result<()> {
+        if commands.is_empty() {
+            return Ok(());
+        }
+        let mut writer = self.writer.lock().unwrap();
+        if writer.db_index != Some(db_index) {
+            writer.file.write_all(&encode_array(&["SELECT".to_string(), db_index.to_string()]))?;
+            writer.db_index = Some(db_index);
+        }
+        for command in commands {
+            writer.file.write_all(&encode_bytes_array(command))?;
+        }
+        writer.file.flush()
+    }
+}
+
+/// What to log for a write command that replied `reply`: nothing if it
+/// failed, otherwise commands that redo it exactly. Relative deadlines
+/// become absolute (or a DEL if already past), generated stream IDs become
+/// the ID that was picked, and random or blocking pops become the plain
+/// removal they turned out to be, so a replay later lands on the same keyspace.
+pub fn propagated(args: &[Vec<u8>], reply: &[u8]) -> Vec<Vec<Vec<u8>>> {
+    // A handler's Err reaches the client as an empty reply
+    if reply.is_empty() || reply.starts_with(b"-") {
+        return Vec::new();
+    }
+    // Arguments are passed on as the bytes that came in; only the ones that
+    // steer the rewrite are read as text
+    let text = |index: usize| String::from_utf8_lossy(&args[index]);
+    let word = |word: &str| word.as_bytes().to_vec();
+    let popped = bulk_strings(reply);
+    match text(0).to_uppercase().as_str() {
+        "SET" if args.len() >= 5 => {
+            let Some(deadline) = deadline_ms(&text(3), &text(4)) else {
+                return Vec::new();
+            };
+            vec![
+                vec![word("SET"), args[1].clone(), args[2].clone()],
+                vec![word("PEXPIREAT"), args[1].clone(), word(&deadline.to_string())],
+            ]
+        },
+        // A deadline that has already passed deletes the key instead
+        "EXPIRE" | "PEXPIRE" if text(2).parse::<i64>().is_ok_and(|amount| amount <= 0) => match reply {
+            b":1\r\n" => vec![vec![word("DEL"), args[1].clone()]],
+            _ => Vec::new(),
+        },
+        "EXPIRE" | "PEXPIRE" => {
+            let unit = if text(0).eq_ignore_ascii_case("EXPIRE") { "EX" } else { "PX" };
+            let Some(deadline) = deadline_ms(unit, &text(2)) else {
+                return Vec::new();
+            };
+            let mut rewritten = vec![word("PEXPIREAT"), args[1].clone(), word(&deadline.to_string())];
+            rewritten.extend_from_slice(&args[3..]);
+            vec![rewritten]
+        },
+        // XADD * and ms-* are logged with the ID they generated
+        "XADD" => {
+            let mut rewritten = args.to_vec();
+            rewritten[2] = popped[0].clone();
+            vec![rewritten]
+        },
+        "SPOP" if popped.is_empty() => Vec::new(),
+        "SPOP" => {
+            let mut rewritten = vec![word("SREM"), args[1].clone()];
+            rewritten.extend(popped);
+            vec![rewritten]
+        },
+        // A timed out blocking pop replies with a null and changes nothing
+        "BLPOP" | "BZPOPMIN" | "BZPOPMAX" | "BZMPOP" if popped.is_empty() => Vec::new(),
+        "BLPOP" => vec![vec![word("LPOP"), popped[0].clone()]],
+        "BZPOPMIN" => vec![vec![word("ZPOPMIN"), popped[0].clone()]],
+        "BZPOPMAX" => vec![vec![word("ZPOPMAX"), popped[0].clone()]],
+        "BZMPOP" => {
+            // BZMPOP timeout numkeys key... MIN|MAX [COUNT n] -> ZMPOP 1 key MIN|MAX [COUNT n]
+            let numkeys: usize = text(2).parse().unwrap_or(0);
+            let mut rewritten = vec![word("ZMPOP"), word("1"), popped[0].clone()];
+            rewritten.extend_from_slice(args.get(3 + numkeys..).unwrap_or_default());
+            vec![rewritten]
+        },
+        _ => vec![args.to_vec()],
+    }
+}
+
+// `amount` seconds (EX) or milliseconds (PX) from now, as unix milliseconds
+fn deadline_ms(unit: &str, amount: &str) -> Option<u64> {
+    let amount: u64 = amount.parse().ok()?;
+    let ttl = match unit.to_uppercase().as_str() {
+        "EX" => Duration::from_secs(amount),
+        "PX" => Duration::from_millis(amount),
+        _ => return None,
+    };
+    let deadline = SystemTime::now().checked_add(ttl)?;
+    Some(deadline.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+// Every bulk string in a reply, however deeply nested
+fn bulk_strings(reply: &[u8]) -> Vec<Vec<u8>> {
+    let mut strings = Vec::new();
+    let mut pos = 0;
+    while let Some(newline) = reply[pos..].iter().position(|&b| b == b'\n') {
+        let line = &reply[pos..pos + newline];
+        pos += newline + 1;
+        let Some(len) = line.strip_prefix(b"$")
+            .and_then(|len| std::str::from_utf8(len.strip_suffix(b"\r").unwrap_or(len)).ok())
+            .and_then(|len| len.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        let end = (pos + len).min(reply.len());
+        strings.push(reply[pos..end].to_vec());
+        pos = (end + 2).min(reply.len());
+    }
+    strings
+}
+
+/// Re-runs every command in the file at `path` against `state`, returning how
+/// many bytes were replayed. `state` must not have an AOF attached yet, or the
+/// replay would be appended to the file it's reading. A command cut off by a
+/// crash mid-write is dropped from the file.
+pub async fn replay(path: &Path, state: &ServerState) -> io::Result<usize> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut client = ClientState::new();
+    // The file only holds what already ran, so it never has to log in
+    client.authenticated = true;
+    let (_, replayed) = parse_pipeline(&bytes, state, &mut client).await;
+    if replayed < bytes.len() {
+        // Cut off, so what's appended next doesn't run on from the fragment
+        log::warn!("Dropping {} bytes of incomplete command at the end of {}", bytes.len() - replayed, path.display());
+        OpenOptions::new().write(true).open(path)?.set_len(replayed as u64)?;
+    }
+    Ok(replayed)
+}
@@ -1,19 +1,103 @@
-use std::sync::{Arc, Mutex};
-use std::collections::{HashMap, VecDeque};
 use tokio::sync::mpsc;
 
-pub fn init_waiting_room(
+use crate::models::WaitingRoom;
+use crate::utils::encoder::encode_error_string;
+
+/// A client's place in the queues for `keys`. Dropping it takes the client
+/// back out, however the wait ended, so the room never fills up with
+/// senders nobody is listening on.
+pub struct Waiter<'a> {
+    pub rx: mpsc::Receiver<String>,
+    tx: mpsc::Sender<String>,
+    keys: Vec<String>,
+    waiting_room: &'a WaitingRoom,
+}
+
+impl Waiter<'_> {
+    /// Leaves every queue now rather than on drop, e.g. to stop a push from
+    /// handing over anything else before a last `try_recv`.
+    pub fn leave(&self) {
+        let mut room = self.waiting_room.lock().unwrap();
+        for key in &self.keys {
+            if let Some(queue) = room.get_mut(key) {
+                queue.retain(|sender| !sender.same_channel(&self.tx));
+                if queue.is_empty() {
+                    room.remove(key);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        self.leave();
+    }
+}
+
+pub fn init_waiting_room<'a>(
     keys: &[String],
-    waiting_room: &Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>>
-) -> (mpsc::Sender<String>, mpsc::Receiver<String>) {
+    waiting_room: &'a WaitingRoom
+) -> Waiter<'a> {
     let (tx, rx) = mpsc::channel(1);
     {
         let mut room = waiting_room.lock().unwrap();
         for key in keys {
             room.entry(key.to_string()).or_default().push_back(tx.clone());
-            println!("DEBUG: Waiter added to room. Current queue size for {}: {}",
+            log::debug!("Waiter added to room. Current queue size for {}: {}",
                     key, room.get(key).unwrap().len());
         }
     }
-    (tx, rx)
+    Waiter { rx, tx, keys: keys.to_vec(), waiting_room }
+}
+
+// Wakes every client blocked on `key`. Used where waiters re-check the key
+// themselves (XREAD, BZPOPMIN, ...), so a spurious wake-up is harmless.
+pub fn wake_all_waiters(
+    key: &str,
+    message: &str,
+    waiting_room: &WaitingRoom
+) {
+    let mut room = waiting_room.lock().unwrap();
+    if let Some(queue) = room.remove(key) {
+        for tx in queue {
+            // A failed send just means that waiter already gave up
+            let _ = tx.try_send(message.to_string());
+        }
+    }
+}
+
+// Drops `value` on a background task so freeing a big value doesn't hold up
+// the caller (UNLINK, FLUSHDB ASYNC). Without a runtime, e.g. when called
+// from plain code, it's just dropped here.
+pub fn free_in_background<T: Send + 'static>(value: T) {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn(async move { drop(value) });
+        },
+        Err(_) => drop(value),
+    }
+}
+
+// Blocking timeouts (BLPOP, BZPOPMIN, ...) are in seconds and may be fractional.
+// As in Redis they must fit in a signed 64-bit count of milliseconds, which also
+// keeps the deadline from overflowing. Errors come back as the reply to send
+pub fn parse_timeout(raw: &str) -> Result<f64, Vec<u8>> {
+    match raw.parse::<f64>() {
+        Ok(timeout) if timeout < 0.0 => Err(encode_error_string("ERR timeout is negative")),
+        Ok(timeout) if timeout.is_finite() && timeout * 1000.0 > i64::MAX as f64 => {
+            Err(encode_error_string("ERR timeout is out of range"))
+        },
+        Ok(timeout) if timeout.is_finite() => Ok(timeout),
+        _ => Err(encode_error_string("ERR timeout is not a float or out of range")),
+    }
+}
+
+// XREAD BLOCK takes whole milliseconds
+pub fn parse_block_ms(raw: &str) -> Result<u64, Vec<u8>> {
+    match raw.parse::<i64>() {
+        Ok(ms) if ms < 0 => Err(encode_error_string("ERR timeout is negative")),
+        Ok(ms) => Ok(ms as u64),
+        Err(_) => Err(encode_error_string("ERR timeout is not an integer or out of range")),
+    }
 }
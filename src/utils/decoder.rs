@@ -3,27 +3,178 @@
 /// Takes a raw RESP string like:
 /// `"*2\r\n$4\r\nECHO\r\n$3\r\nhey\r\n"`
 ///
-/// Returns only the meaningful parts as owned Strings:
-/// `["ECHO", "hey"]`
+/// Returns only the meaningful parts as raw bytes:
+/// `[b"ECHO", b"hey"]`
 ///
 /// This allows command handlers to access arguments directly:
 /// - parts[0] = command name (e.g., "SET", "XADD")
 /// - parts[1] = first argument (e.g., key)
 /// - parts[2] = second argument, etc.
-pub fn decode_resp(data: &str) -> Vec<String> {
+///
+/// Bulk strings are read by their declared `$<len>`, not up to the next line
+/// break, so values containing `\r\n` or bytes that aren't UTF-8 come
+/// through whole.
+///
+/// Anything that doesn't start with a RESP type byte is an inline command, the
+/// way `telnet`/`nc` users type them: `SET k "hello world"\r\n`.
+pub fn decode_resp(bytes: &[u8]) -> Vec<Vec<u8>> {
+    if !matches!(bytes.first(), Some(b'*' | b'$' | b'+')) {
+        return decode_inline(bytes);
+    }
     let mut parts = Vec::new();
-    let mut lines = data.lines();
+    let mut pos = 0;
 
-    while let Some(line) = lines.next() {
-        if line.starts_with('$') {
-            // The NEXT line is the actual string data
-            if let Some(actual_data) = lines.next() {
-                parts.push(actual_data.to_string());
+    while let Some((line, next)) = read_line(bytes, pos) {
+        pos = next;
+        if let Some(len) = line.strip_prefix(b"$") {
+            // A null bulk string ($-1) or a bad length carries no value
+            let Some(len) = std::str::from_utf8(len).ok().and_then(|len| len.parse::<usize>().ok()) else {
+                continue;
+            };
+            let end = (pos + len).min(bytes.len());
+            parts.push(bytes[pos..end].to_vec());
+            pos = end;
+            if bytes[pos..].starts_with(b"\r\n") {
+                pos += 2;
             }
-        } else if line.starts_with('+') {
+        } else if let Some(simple) = line.strip_prefix(b"+") {
             // Simple String (e.g. +PING)
-            parts.push(line[1..].to_string());
+            parts.push(simple.to_vec());
         }
     }
     parts
 }
+
+/// How many bytes at the start of `bytes` make up one whole command, or `None`
+/// if it hasn't all arrived yet and the caller should read more.
+///
+/// Only the framing is checked: `*<n>` and every `$<len>` are followed far
+/// enough to know where the command ends. A header that isn't a number can
+/// never be completed by more input, so it counts as a (malformed) frame of
+/// its own and is left to `decode_resp` to reject.
+pub fn frame_len(bytes: &[u8]) -> Option<usize> {
+    if !matches!(bytes.first()?, b'*' | b'$' | b'+') {
+        return complete_line(bytes, 0).map(|(_, end)| end);
+    }
+    let mut pos = 0;
+    let elements = match bytes[0] {
+        b'*' => {
+            let (header, next) = complete_line(bytes, 0)?;
+            pos = next;
+            match std::str::from_utf8(&header[1..]).ok().and_then(|n| n.parse::<i64>().ok()) {
+                Some(n) => n.max(0),
+                None => return Some(pos),
+            }
+        },
+        _ => 1,
+    };
+    for _ in 0..elements {
+        let (line, next) = complete_line(bytes, pos)?;
+        pos = next;
+        let Some(len) = line.strip_prefix(b"$") else {
+            continue;
+        };
+        if let Some(len) = std::str::from_utf8(len).ok().and_then(|len| len.parse::<usize>().ok()) {
+            pos = pos.checked_add(len)?.checked_add(2)?;
+            if pos > bytes.len() {
+                return None;
+            }
+        }
+    }
+    Some(pos)
+}
+
+/// Splits the first whole command off `bytes`, returning `(frame, remainder)`,
+/// or `None` if it hasn't all arrived yet. Pipelining clients send several
+/// commands in one write, and this takes them one at a time.
+pub fn split_frame(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    frame_len(bytes).map(|len| bytes.split_at(len))
+}
+
+// Like `read_line`, but only once the line's `\n` has arrived
+fn complete_line(bytes: &[u8], pos: usize) -> Option<(&[u8], usize)> {
+    let newline = bytes.get(pos..)?.iter().position(|&b| b == b'\n')?;
+    let line = &bytes[pos..pos + newline];
+    Some((line.strip_suffix(b"\r").unwrap_or(line), pos + newline + 1))
+}
+
+// The line starting at `pos` without its terminator, and where the next one
+// starts. A bare `\n` also ends a line, as it did with `str::lines`.
+fn read_line(bytes: &[u8], pos: usize) -> Option<(&[u8], usize)> {
+    if pos >= bytes.len() {
+        return None;
+    }
+    let rest = &bytes[pos..];
+    match rest.iter().position(|&b| b == b'\n') {
+        Some(newline) => {
+            let line = &rest[..newline];
+            Some((line.strip_suffix(b"\r").unwrap_or(line), pos + newline + 1))
+        },
+        None => Some((rest, bytes.len())),
+    }
+}
+
+// Splits the first line on whitespace, honouring quotes like redis-cli does.
+// Unbalanced quotes give no arguments at all rather than a guess.
+fn decode_inline(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let Some((line, _)) = read_line(bytes, 0) else {
+        return Vec::new();
+    };
+    let mut parts = Vec::new();
+    let mut chars = line.iter().copied().peekable();
+
+    loop {
+        while chars.next_if(|b| b.is_ascii_whitespace()).is_some() {}
+        let Some(first) = chars.next() else {
+            return parts;
+        };
+        let mut arg = Vec::new();
+        match first {
+            b'"' => loop {
+                match chars.next() {
+                    Some(b'"') => break,
+                    Some(b'\\') => match chars.next() {
+                        Some(b'n') => arg.push(b'\n'),
+                        Some(b'r') => arg.push(b'\r'),
+                        Some(b't') => arg.push(b'\t'),
+                        Some(b'x') => {
+                            let hex = [chars.next(), chars.next()];
+                            let byte = match hex {
+                                [Some(high), Some(low)] => std::str::from_utf8(&[high, low]).ok()
+                                    .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                                _ => None,
+                            };
+                            match byte {
+                                Some(byte) => arg.push(byte),
+                                None => return Vec::new(),
+                            }
+                        },
+                        Some(other) => arg.push(other),
+                        None => return Vec::new(),
+                    },
+                    Some(other) => arg.push(other),
+                    None => return Vec::new(),
+                }
+            },
+            b'\'' => loop {
+                match chars.next() {
+                    Some(b'\'') => break,
+                    Some(b'\\') if chars.peek() == Some(&b'\'') => arg.push(chars.next().unwrap()),
+                    Some(other) => arg.push(other),
+                    None => return Vec::new(),
+                }
+            },
+            _ => {
+                arg.push(first);
+                while let Some(b) = chars.next_if(|b| !b.is_ascii_whitespace()) {
+                    arg.push(b);
+                }
+            },
+        }
+        // A closing quote has to end the argument
+        if matches!(first, b'"' | b'\'') && chars.peek().is_some_and(|b| !b.is_ascii_whitespace()) {
+            return Vec::new();
+        }
+        parts.push(arg);
+    }
+}
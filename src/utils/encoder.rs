@@ -8,6 +8,14 @@ pub fn encode_bulk_string(s: &str) -> Vec<u8> {
     format!("${}\r\n{}\r\n", s.len(), s).into_bytes()
 }
 
+// For values that needn't be UTF-8: string values, or a GETRANGE cutting a character in half
+pub fn encode_bulk_bytes(b: &[u8]) -> Vec<u8> {
+    let mut bytes = format!("${}\r\n", b.len()).into_bytes();
+    bytes.extend_from_slice(b);
+    bytes.extend_from_slice(b"\r\n");
+    bytes
+}
+
 pub fn encode_null_string() -> Vec<u8> {
     "$-1\r\n".as_bytes().to_vec()
 }
@@ -23,6 +31,15 @@ pub fn encode_array(arr: &[String]) -> Vec<u8> {
     bytes
 }
 
+// A command as the client sent it, arguments and all, e.g. for the AOF
+pub fn encode_bytes_array(arr: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = format!("*{}\r\n", arr.len()).into_bytes();
+    for b in arr {
+        bytes.extend(encode_bulk_bytes(b));
+    }
+    bytes
+}
+
 pub fn encode_raw_array(parts: Vec<Vec<u8>>) -> Vec<u8> {
     let mut response = format!("*{}\r\n", parts.len()).into_bytes();
     for part in parts {
@@ -31,6 +48,54 @@ pub fn encode_raw_array(parts: Vec<Vec<u8>>) -> Vec<u8> {
     response
 }
 
+// RESP3 map: `%N` followed by N already-encoded key/value pairs
+pub fn encode_raw_map(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> Vec<u8> {
+    let mut response = format!("%{}\r\n", pairs.len()).into_bytes();
+    for (key, value) in pairs {
+        response.extend(key);
+        response.extend(value);
+    }
+    response
+}
+
+// RESP3 map of bulk strings, e.g. HGETALL's field/value pairs
+pub fn encode_map(pairs: &[(String, String)]) -> Vec<u8> {
+    encode_raw_map(pairs.iter().map(|(key, value)| (encode_bulk_string(key), encode_bulk_string(value))).collect())
+}
+
+// RESP3 boolean: `#t` or `#f`
+pub fn encode_bool(b: bool) -> Vec<u8> {
+    format!("#{}\r\n", if b { "t" } else { "f" }).into_bytes()
+}
+
+// RESP3 double, spelling the special values the way the spec does
+pub fn encode_double(d: f64) -> Vec<u8> {
+    let text = if d.is_nan() { "nan".to_string() } else { format_double(d) };
+    format!(",{}\r\n", text).into_bytes()
+}
+
+// Laid out like C's %.17g as Redis does, using the shortest digits that read back
+// as the same number: "1", "1.5", "1e+300", "1e-05", and "inf"/"-inf"
+pub fn format_double(d: f64) -> String {
+    if !d.is_finite() {
+        return d.to_string();
+    }
+    let scientific = format!("{:e}", d);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    if (-4..17).contains(&exponent) {
+        d.to_string()
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+    }
+}
+
+// RESP3's single null, standing in for both the null bulk string and null array
+pub fn encode_null() -> Vec<u8> {
+    "_\r\n".as_bytes().to_vec()
+}
+
 pub fn encode_stream_entry(entry: &StreamEntry) -> Vec<u8> {
     let mut fields_resp = Vec::new();
     for (k, v) in &entry.fields {
@@ -38,10 +103,7 @@ pub fn encode_stream_entry(entry: &StreamEntry) -> Vec<u8> {
         fields_resp.push(encode_bulk_string(v));
     }
     let encoded_fields = encode_raw_array(fields_resp);
-    let mut entry_resp = Vec::new();
-    entry_resp.push(encode_bulk_string(&entry.id));
-    entry_resp.push(encoded_fields);
-    encode_raw_array(entry_resp)
+    encode_raw_array(vec![encode_bulk_string(&entry.id), encoded_fields])
 }
 
 pub fn encode_null_array() -> Vec<u8> {
@@ -0,0 +1,26 @@
+use crate::models::Databases;
+
+/// Makes room for a write under a `maxmemory-keys` cap (0 meaning no cap) by
+/// evicting the least recently used keys, across every database, until one
+/// more key fits. Returns false if the cap is reached and nothing is left to
+/// evict.
+///
+/// Like Redis' approximated LRU, each pass only compares a few sampled keys
+/// per shard (see `EVICTION_SAMPLES`), so a write never scans the keyspace.
+pub fn evict_for_write(databases: &Databases, max_keys: u64) -> bool {
+    if max_keys == 0 {
+        return true;
+    }
+    while databases.iter().map(|kv_store| kv_store.len() as u64).sum::<u64>() >= max_keys {
+        let oldest = databases.iter()
+            .enumerate()
+            .filter_map(|(db, kv_store)| kv_store.least_recently_used().map(|(key, idle)| (db, key, idle)))
+            .max_by_key(|(_, _, idle)| *idle);
+        let Some((db, key, _)) = oldest else {
+            return false;
+        };
+        log::debug!("Evicting {} from db {} to stay under maxmemory-keys {}", key, db, max_keys);
+        databases[db].remove(&key);
+    }
+    true
+}
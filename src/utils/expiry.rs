@@ -0,0 +1,47 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use crate::models::{Databases, ServerConfig};
+
+// Redis runs its background jobs 10 times a second by default
+pub const DEFAULT_HZ: u64 = 10;
+
+/// Turns an `--hz` value into the delay between expiry sweeps.
+pub fn expiry_interval(hz: u64) -> Duration {
+    Duration::from_millis(1000 / hz.clamp(1, 500))
+}
+
+/// Spawns the active expiry task: lazy expiry only catches keys that get read,
+/// so this periodically sweeps every database for anything past its deadline.
+pub fn spawn_active_expiry(databases: Databases, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            sweep(&databases);
+        }
+    })
+}
+
+/// Like `spawn_active_expiry`, but the delay before each sweep follows the
+/// `hz` setting at the time, so CONFIG SET hz takes effect on the next one.
+/// While `active_expire` is off, sweeps are skipped.
+pub fn spawn_configured_expiry(databases: Databases, server_config: Arc<Mutex<ServerConfig>>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let hz = server_config.lock().unwrap().hz;
+            tokio::time::sleep(expiry_interval(hz as u64)).await;
+            if server_config.lock().unwrap().active_expire {
+                sweep(&databases);
+            }
+        }
+    })
+}
+
+fn sweep(databases: &Databases) {
+    let now = Instant::now();
+    for kv_store in databases.iter() {
+        kv_store.remove_expired(now);
+    }
+}
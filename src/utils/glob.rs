@@ -0,0 +1,94 @@
+/// Redis-style glob matching used by the *SCAN family and KEYS.
+///
+/// Supports `*` (any run of characters), `?` (exactly one character),
+/// `[abc]` / `[a-z]` / `[^abc]` character classes and `\` to escape the
+/// next character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text)
+}
+
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    let mut p = 0;
+    let mut t = 0;
+    // Where to resume if the current attempt fails after the most recent `*`
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+                continue;
+            },
+            Some('?') => Some(p + 1),
+            Some('[') => match match_class(&pattern[p..], text[t]) {
+                Some((true, consumed)) => Some(p + consumed),
+                _ => None,
+            },
+            Some('\\') if p + 1 < pattern.len() => {
+                (pattern[p + 1] == text[t]).then_some(p + 2)
+            },
+            Some(c) => (*c == text[t]).then_some(p + 1),
+            None => None,
+        };
+
+        match step {
+            Some(next) => {
+                p = next;
+                t += 1;
+            },
+            None => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+// Matches `c` against the class starting at `pattern[0] == '['`.
+// Returns whether it matched and how many pattern chars the class spans,
+// or None when the class is unterminated (treated as a mismatch).
+fn match_class(pattern: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negate = pattern.get(i) == Some(&'^');
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    loop {
+        match pattern.get(i)? {
+            ']' => break,
+            '\\' => {
+                i += 1;
+                if *pattern.get(i)? == c {
+                    matched = true;
+                }
+            },
+            start if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|e| *e != ']') => {
+                let end = pattern[i + 2];
+                let (low, high) = if *start <= end { (*start, end) } else { (end, *start) };
+                if (low..=high).contains(&c) {
+                    matched = true;
+                }
+                i += 2;
+            },
+            other => {
+                if *other == c {
+                    matched = true;
+                }
+            },
+        }
+        i += 1;
+    }
+    Some((matched != negate, i + 1))
+}
@@ -0,0 +1,100 @@
+/// Where a command takes its keys.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeySpec {
+    /// No keys, or it only walks the keyspace like KEYS and SCAN.
+    None,
+    /// Every `step`th argument from `first` to `last`. A negative `last`
+    /// counts back from the end, -1 being the final argument.
+    Range { first: usize, last: i64, step: usize },
+    /// A numkeys argument at `numkeys_index` followed by that many keys,
+    /// after a destination key at index 1 if `destination` is set.
+    NumKeys { numkeys_index: usize, destination: bool },
+    /// Keys after STREAMS, the first half of what follows it.
+    Streams,
+}
+
+impl KeySpec {
+    /// Redis' fixed (first, last, step) description. Commands whose keys
+    /// move with their arguments report (0, 0, 0), or just their fixed destination.
+    pub fn legacy_range(&self) -> (i64, i64, i64) {
+        match *self {
+            KeySpec::Range { first, last, step } => (first as i64, last, step as i64),
+            KeySpec::NumKeys { destination: true, .. } => (1, 1, 1),
+            _ => (0, 0, 0),
+        }
+    }
+
+    /// Whether where the keys are depends on the arguments.
+    pub fn is_movable(&self) -> bool {
+        matches!(self, KeySpec::NumKeys { .. } | KeySpec::Streams)
+    }
+}
+
+/// How `command` (given in upper case) takes its keys.
+pub fn key_spec(command: &str) -> KeySpec {
+    match command {
+        "DEL" | "UNLINK" | "EXISTS" | "TOUCH" | "WATCH"
+        | "SINTER" | "SUNION" | "SDIFF" | "SINTERSTORE" | "SUNIONSTORE" | "SDIFFSTORE" => {
+            KeySpec::Range { first: 1, last: -1, step: 1 }
+        },
+        "RENAME" | "RENAMENX" | "COPY" | "SMOVE" => KeySpec::Range { first: 1, last: 2, step: 1 },
+        // The last argument is the timeout
+        "BLPOP" | "BZPOPMIN" | "BZPOPMAX" => KeySpec::Range { first: 1, last: -2, step: 1 },
+        // numkeys key [key ...] ...
+        "SINTERCARD" | "ZINTERCARD" | "ZUNION" | "ZINTER" | "ZDIFF" | "ZMPOP" => {
+            KeySpec::NumKeys { numkeys_index: 1, destination: false }
+        },
+        // timeout numkeys key [key ...] ...
+        "BZMPOP" => KeySpec::NumKeys { numkeys_index: 2, destination: false },
+        // destination numkeys key [key ...] ...
+        "ZUNIONSTORE" | "ZINTERSTORE" | "ZDIFFSTORE" => KeySpec::NumKeys { numkeys_index: 2, destination: true },
+        "XREAD" => KeySpec::Streams,
+        // A subcommand, then the key
+        "OBJECT" | "XINFO" => KeySpec::Range { first: 2, last: 2, step: 1 },
+        "PING" | "ECHO" | "QUIT" | "LOLWUT" | "KEYS" | "RANDOMKEY" | "SCAN" | "DBSIZE" | "FLUSHDB" | "FLUSHALL" | "SAVE" | "BGSAVE"
+        | "SELECT" | "INFO" | "MULTI" | "EXEC" | "DISCARD" | "UNWATCH" | "CONFIG" | "COMMAND" | "HELLO" | "RESET" | "AUTH"
+        | "ACL" | "CLIENT" | "DEBUG" | "SLOWLOG" | "REPLCONF" | "PSYNC" | "WAIT" | "SUBSCRIBE" | "UNSUBSCRIBE" | "PSUBSCRIBE" | "PUNSUBSCRIBE"
+        | "PUBLISH" | "PUBSUB" => KeySpec::None,
+        // Everything else works on the one key after the command name
+        _ => KeySpec::Range { first: 1, last: 1, step: 1 },
+    }
+}
+
+/// The arguments of `command` (given in upper case) that name keys, for
+/// permission checks and COMMAND GETKEYS.
+pub fn command_keys<'a>(command: &str, parts: &'a [String]) -> Vec<&'a str> {
+    match key_spec(command) {
+        KeySpec::None => Vec::new(),
+        KeySpec::Range { first, last, step } => {
+            let last = if last < 0 { parts.len() as i64 + last } else { last };
+            if last < first as i64 {
+                return Vec::new();
+            }
+            let last = (last as usize).min(parts.len().saturating_sub(1));
+            parts.get(first..=last).unwrap_or_default().iter().step_by(step).map(String::as_str).collect()
+        },
+        KeySpec::NumKeys { numkeys_index, destination } => {
+            let mut keys: Vec<&str> = if destination { parts.get(1).map(String::as_str).into_iter().collect() } else { Vec::new() };
+            keys.extend(counted_keys(parts, numkeys_index));
+            keys
+        },
+        // ... STREAMS key [key ...] id [id ...]
+        KeySpec::Streams => match parts.iter().position(|part| part.eq_ignore_ascii_case("STREAMS")) {
+            Some(streams) => {
+                let rest = &parts[streams + 1..];
+                rest[..rest.len() / 2].iter().map(String::as_str).collect()
+            },
+            None => Vec::new(),
+        },
+    }
+}
+
+// The keys counted by the numkeys argument at `numkeys_index`
+fn counted_keys(parts: &[String], numkeys_index: usize) -> Vec<&str> {
+    let Some(numkeys) = parts.get(numkeys_index).and_then(|raw| raw.parse::<usize>().ok()) else {
+        return Vec::new();
+    };
+    let first = numkeys_index + 1;
+    let last = first.saturating_add(numkeys).min(parts.len());
+    parts.get(first..last).unwrap_or_default().iter().map(String::as_str).collect()
+}
@@ -1,7 +1,21 @@
 pub mod encoder;
 pub mod decoder;
 pub mod async_helpers;
+pub mod random;
+pub mod glob;
+pub mod scan;
+pub mod expiry;
+pub mod eviction;
+pub mod rdb;
+pub mod aof;
+pub mod keys;
 
 pub use encoder::*;
 pub use decoder::*;
 pub use async_helpers::*;
+pub use random::*;
+pub use glob::*;
+pub use scan::*;
+pub use expiry::*;
+pub use eviction::*;
+pub use keys::*;
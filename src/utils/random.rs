@@ -0,0 +1,21 @@
+use rand::seq::SliceRandom;
+
+/// The largest count, either way, the *RANDMEMBER/HRANDFIELD family accepts.
+/// Redis draws the line here so a huge negative count can't ask for ~2^63 picks.
+pub const MAX_SAMPLE_COUNT: i64 = i64::MAX / 2;
+
+/// Picks elements the way the Redis *RANDMEMBER/HRANDFIELD family does:
+/// a positive `count` returns up to `count` distinct elements, a negative
+/// `count` returns exactly `|count|` elements that may repeat.
+pub fn random_sample<T: Clone>(items: &[T], count: i64) -> Vec<T> {
+    let mut rng = rand::thread_rng();
+    if count >= 0 {
+        items.choose_multiple(&mut rng, count as usize).cloned().collect()
+    } else if items.is_empty() {
+        Vec::new()
+    } else {
+        (0..count.unsigned_abs())
+            .filter_map(|_| items.choose(&mut rng).cloned())
+            .collect()
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::models::{Databases, RedisData, RedisValue, ServerConfig, Stream, StreamEntry};
+
+// Not Redis' RDB encoding, just the same idea: every database's keys, values
+// and deadlines in one file. Layout, with lengths and counts as little-endian
+// u32 and numbers as little-endian u64:
+//
+//   "RCDB" version
+//   for each non-empty database:  DB_SELECTOR index
+//     for each key:               [EXPIRY unix-ms] type key value
+//   EOF
+const MAGIC: &[u8] = b"RCDB";
+const VERSION: u8 = 1;
+
+const DB_SELECTOR: u8 = 0xFE;
+const EXPIRY: u8 = 0xFC;
+const EOF: u8 = 0xFF;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+const TYPE_STREAM: u8 = 5;
+
+/// A point-in-time copy of one key, with its deadline as wall-clock
+/// milliseconds since the Unix epoch so it still means something after a restart.
+pub struct SnapshotEntry {
+    pub key: String,
+    pub data: RedisData,
+    pub expires_at_ms: Option<u64>,
+}
+
+/// Every database's live keys, indexed like `Databases`.
+pub type Snapshot = Vec<Vec<SnapshotEntry>>;
+
+/// Where SAVE writes and startup loads from: `dir`/`dbfilename`.
+pub fn rdb_path(config: &ServerConfig) -> PathBuf {
+    Path::new(&config.dir).join(&config.dbfilename)
+}
+
+/// Copies every live key. All databases are locked together for the copy, so
+/// the snapshot is consistent across them.
+pub fn snapshot(databases: &Databases) -> Snapshot {
+    let now = Instant::now();
+    let wall_now = unix_ms(SystemTime::now());
+    let guards: Vec<_> = databases.iter().map(|kv_store| kv_store.lock_all()).collect();
+    guards.iter()
+        .map(|map| {
+            map.iter()
+                .filter(|(_, value)| !value.is_expired(now))
+                .map(|(key, value)| SnapshotEntry {
+                    key: key.clone(),
+                    data: value.data.clone(),
+                    expires_at_ms: value.expires_at
+                        .map(|deadline| wall_now + deadline.saturating_duration_since(now).as_millis() as u64),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Writes `snapshot` to `path`. It goes to a temporary file first and is then
+/// renamed over the old one, so a crash mid-write never leaves a torn file.
+pub fn write_snapshot(snapshot: &Snapshot, path: &Path) -> io::Result<()> {
+    let temp = path.with_extension(format!("tmp-{}", std::process::id()));
+    std::fs::write(&temp, encode(snapshot))?;
+    std::fs::rename(&temp, path)
+}
+
+/// Fills `databases` from the file at `path`, returning how many keys were
+/// loaded. A missing file just means there's nothing to load; keys whose
+/// deadline passed while the server was down are skipped.
+pub fn load(databases: &Databases, path: &Path) -> io::Result<usize> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let snapshot = decode(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let now = Instant::now();
+    let wall_now = unix_ms(SystemTime::now());
+    let mut loaded = 0;
+    for (index, entries) in snapshot.into_iter().enumerate() {
+        let Some(kv_store) = databases.get(index) else {
+            log::warn!("Skipping database {} from {}, only {} are configured", index, path.display(), databases.len());
+            continue;
+        };
+        for entry in entries {
+            let expires_at = match entry.expires_at_ms {
+                Some(deadline) if deadline <= wall_now => continue,
+                Some(deadline) => Some(now + Duration::from_millis(deadline - wall_now)),
+                None => None,
+            };
+            kv_store.insert(entry.key, RedisValue::new(entry.data, expires_at));
+            loaded += 1;
+        }
+    }
+    Ok(loaded)
+}
+
+fn unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_millis() as u64)
+}
+
+pub fn encode(snapshot: &Snapshot) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(VERSION);
+    for (index, entries) in snapshot.iter().enumerate() {
+        if entries.is_empty() {
+            continue;
+        }
+        out.push(DB_SELECTOR);
+        put_u32(&mut out, index as u32);
+        for entry in entries {
+            if let Some(deadline) = entry.expires_at_ms {
+                out.push(EXPIRY);
+                out.extend_from_slice(&deadline.to_le_bytes());
+            }
+            encode_value(&mut out, &entry.key, &entry.data);
+        }
+    }
+    out.push(EOF);
+    out
+}
+
+fn encode_value(out: &mut Vec<u8>, key: &str, data: &RedisData) {
+    match data {
+        RedisData::String(s) => {
+            out.push(TYPE_STRING);
+            put_str(out, key);
+            put_bytes(out, s);
+        },
+        RedisData::List(items) => {
+            out.push(TYPE_LIST);
+            put_str(out, key);
+            put_u32(out, items.len() as u32);
+            items.iter().for_each(|item| put_str(out, item));
+        },
+        RedisData::Set(members) => {
+            out.push(TYPE_SET);
+            put_str(out, key);
+            put_u32(out, members.len() as u32);
+            members.iter().for_each(|member| put_str(out, member));
+        },
+        RedisData::ZSet(members) => {
+            out.push(TYPE_ZSET);
+            put_str(out, key);
+            put_u32(out, members.len() as u32);
+            for (score, member) in members {
+                out.extend_from_slice(&score.to_bits().to_le_bytes());
+                put_str(out, member);
+            }
+        },
+        RedisData::Hash(fields) => {
+            out.push(TYPE_HASH);
+            put_str(out, key);
+            put_u32(out, fields.len() as u32);
+            for (field, value) in fields {
+                put_str(out, field);
+                put_str(out, value);
+            }
+        },
+        RedisData::Stream(stream) => {
+            out.push(TYPE_STREAM);
+            put_str(out, key);
+            put_str(out, &stream.last_id);
+            out.extend_from_slice(&stream.entries_added.to_le_bytes());
+            put_str(out, &stream.max_deleted_entry_id);
+            put_u32(out, stream.entries.len() as u32);
+            for entry in &stream.entries {
+                put_str(out, &entry.id);
+                put_u32(out, entry.fields.len() as u32);
+                for (field, value) in &entry.fields {
+                    put_str(out, field);
+                    put_str(out, value);
+                }
+            }
+        },
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, n: u32) {
+    out.extend_from_slice(&n.to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    put_bytes(out, s.as_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_u32(out, bytes.len() as u32);
+    out.extend_from_slice(bytes);
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Snapshot, String> {
+    let mut reader = Reader { bytes, pos: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err("not a snapshot file".to_string());
+    }
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(format!("unsupported snapshot version {}", version));
+    }
+
+    let mut snapshot: Snapshot = Vec::new();
+    let mut db = None;
+    let mut expires_at_ms = None;
+    loop {
+        match reader.u8()? {
+            EOF => return Ok(snapshot),
+            DB_SELECTOR => {
+                let index = reader.u32()? as usize;
+                if snapshot.len() <= index {
+                    snapshot.resize_with(index + 1, Vec::new);
+                }
+                db = Some(index);
+            },
+            EXPIRY => expires_at_ms = Some(reader.u64()?),
+            kind => {
+                let index = db.ok_or("key before any database")?;
+                let key = reader.string()?;
+                let data = decode_value(&mut reader, kind)?;
+                snapshot[index].push(SnapshotEntry { key, data, expires_at_ms: expires_at_ms.take() });
+            },
+        }
+    }
+}
+
+fn decode_value(reader: &mut Reader, kind: u8) -> Result<RedisData, String> {
+    Ok(match kind {
+        TYPE_STRING => RedisData::String(reader.bytes()?.to_vec()),
+        TYPE_LIST => RedisData::List(reader.repeat(|r| r.string())?),
+        TYPE_SET => RedisData::Set(reader.repeat(|r| r.string())?.into_iter().collect::<HashSet<_>>()),
+        TYPE_ZSET => RedisData::ZSet(reader.repeat(|r| Ok((f64::from_bits(r.u64()?), r.string()?)))?),
+        TYPE_HASH => RedisData::Hash(reader.repeat(|r| Ok((r.string()?, r.string()?)))?.into_iter().collect()),
+        TYPE_STREAM => {
+            let last_id = reader.string()?;
+            let entries_added = reader.u64()?;
+            let max_deleted_entry_id = reader.string()?;
+            let entries = reader.repeat(|r| {
+                let id = r.string()?;
+                let fields: HashMap<String, String> = r.repeat(|r| Ok((r.string()?, r.string()?)))?.into_iter().collect();
+                Ok(StreamEntry { id, fields })
+            })?;
+            RedisData::Stream(Stream { entries, last_id, entries_added, max_deleted_entry_id })
+        },
+        other => return Err(format!("unknown value type {}", other)),
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len()).ok_or("snapshot is truncated")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    // A u32 length followed by that many bytes
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| "snapshot string is not UTF-8".to_string())
+    }
+
+    // A u32 count followed by that many items
+    fn repeat<T>(&mut self, mut item: impl FnMut(&mut Self) -> Result<T, String>) -> Result<Vec<T>, String> {
+        let count = self.u32()?;
+        (0..count).map(|_| item(self)).collect()
+    }
+}
@@ -0,0 +1,36 @@
+use crate::utils::encoder::encode_error_string;
+use crate::utils::glob::glob_match;
+
+/// Parsed `cursor [MATCH pattern] [COUNT count]` arguments shared by the *SCAN commands.
+pub struct ScanArgs {
+    pub cursor: u64,
+    pub pattern: Option<String>,
+    pub count: usize,
+}
+
+impl ScanArgs {
+    pub fn matches(&self, candidate: &str) -> bool {
+        self.pattern.as_deref().is_none_or(|p| glob_match(p, candidate))
+    }
+}
+
+/// Parses the cursor and options, handing back the error reply on bad input.
+pub fn parse_scan_args(args: &[String]) -> Result<ScanArgs, Vec<u8>> {
+    let Some(cursor) = args.first().and_then(|raw| raw.parse().ok()) else {
+        return Err(encode_error_string("ERR invalid cursor"));
+    };
+    let mut scan = ScanArgs { cursor, pattern: None, count: 10 };
+
+    for option in args[1..].chunks(2) {
+        match (option[0].to_uppercase().as_str(), option.get(1)) {
+            ("MATCH", Some(value)) => scan.pattern = Some(value.clone()),
+            ("COUNT", Some(value)) => match value.parse::<i64>() {
+                Ok(n) if n >= 1 => scan.count = n as usize,
+                Ok(_) => return Err(encode_error_string("ERR syntax error")),
+                Err(_) => return Err(encode_error_string("ERR value is not an integer or out of range")),
+            },
+            _ => return Err(encode_error_string("ERR syntax error")),
+        }
+    }
+    Ok(scan)
+}
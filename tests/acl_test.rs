@@ -0,0 +1,189 @@
+use redis_cache::commands::{process_acl, process_auth};
+use redis_cache::models::{Acl, AclRule, ClientState, ServerState};
+use redis_cache::parser::parse_pipeline;
+use redis_cache::utils::keys::command_keys;
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn make_resp(parts: &[&str]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", parts.len());
+    for part in parts {
+        result.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+    }
+    result.into_bytes()
+}
+
+async fn run(commands: &[&[&str]], state: &ServerState, client: &mut ClientState) -> Vec<u8> {
+    let buffer: Vec<u8> = commands.iter().flat_map(|command| make_resp(command)).collect();
+    parse_pipeline(&buffer, state, client).await.0
+}
+
+// A client logged in as `username`, after the default user sets that user up with `rules`
+async fn logged_in_as(username: &str, rules: &[&str], state: &ServerState) -> ClientState {
+    let mut admin = ClientState::new();
+    let mut setuser = vec!["ACL", "SETUSER", username];
+    setuser.extend_from_slice(rules);
+    assert_eq!(run(&[&setuser], state, &mut admin).await, b"+OK\r\n");
+
+    let mut client = ClientState::new();
+    assert_eq!(run(&[&["AUTH", username, "pw"]], state, &mut client).await, b"+OK\r\n");
+    client
+}
+
+// ==================== SETUSER / LIST / WHOAMI Tests ====================
+
+#[test]
+fn test_new_connection_is_default_user() {
+    let result = process_acl(&parts(&["ACL", "WHOAMI"]), &ClientState::new(), &Acl::new()).unwrap();
+    assert_eq!(result, b"$7\r\ndefault\r\n");
+}
+
+#[test]
+fn test_acl_list_shows_rules() {
+    let acl = Acl::new();
+    let client = ClientState::new();
+    let setuser = parts(&["ACL", "SETUSER", "alice", "on", ">pw", "~cache:*", "+get", "+set"]);
+    assert_eq!(process_acl(&setuser, &client, &acl).unwrap(), b"+OK\r\n");
+
+    let result = process_acl(&parts(&["ACL", "LIST"]), &client, &acl).unwrap();
+    let result = String::from_utf8(result).unwrap();
+    assert!(result.contains("user alice on ~cache:* -@all +get +set\r\n"));
+    assert!(result.contains("user default on nopass ~* +@all\r\n"));
+    // Passwords are never listed
+    assert!(!result.contains("pw"));
+}
+
+#[test]
+fn test_setuser_bad_rule_changes_nothing() {
+    let acl = Acl::new();
+    let client = ClientState::new();
+    let result = process_acl(&parts(&["ACL", "SETUSER", "bob", "on", "+@read"]), &client, &acl).unwrap();
+    assert_eq!(result, b"-ERR Error in ACL SETUSER modifier '+@read': Syntax error\r\n");
+    assert!(!acl.users().contains_key("bob"));
+
+    let result = process_acl(&parts(&["ACL", "FROB"]), &client, &acl).unwrap();
+    assert_eq!(result, b"-ERR unknown subcommand 'FROB'. Try ACL HELP.\r\n");
+}
+
+#[test]
+fn test_only_the_open_default_user_skips_checks() {
+    let acl = Acl::new();
+    let client = ClientState::new();
+    assert!(acl.is_unrestricted());
+
+    process_acl(&parts(&["ACL", "SETUSER", "default", "-flushall"]), &client, &acl).unwrap();
+    assert!(!acl.is_unrestricted());
+    process_acl(&parts(&["ACL", "SETUSER", "default", "+flushall"]), &client, &acl).unwrap();
+    assert!(acl.is_unrestricted());
+
+    // Anyone else could be logged in, and has to be checked
+    process_acl(&parts(&["ACL", "SETUSER", "alice", "on", "+@all", "allkeys"]), &client, &acl).unwrap();
+    assert!(!acl.is_unrestricted());
+}
+
+#[test]
+fn test_rules_accumulate() {
+    let mut user = AclRule::new();
+    for rule in ["on", "+@all", "-flushall", "~a*"] {
+        user.apply(rule).unwrap();
+    }
+    assert!(user.can_run("GET"));
+    assert!(!user.can_run("FLUSHALL"));
+    assert!(user.can_access("abc"));
+    assert!(!user.can_access("xyz"));
+
+    user.apply("+flushall").unwrap();
+    user.apply("resetkeys").unwrap();
+    assert!(user.can_run("FLUSHALL"));
+    assert!(!user.can_access("abc"));
+}
+
+// ==================== Enable / Disable Tests ====================
+
+#[test]
+fn test_disabled_user_cannot_log_in() {
+    let acl = Acl::new();
+    let mut client = ClientState::new();
+    process_acl(&parts(&["ACL", "SETUSER", "carol", ">pw", "+@all", "allkeys"]), &client, &acl).unwrap();
+
+    // New users start disabled
+    let result = process_auth(&parts(&["AUTH", "carol", "pw"]), &mut client, &acl).unwrap();
+    assert_eq!(result, b"-WRONGPASS invalid username-password pair or user is disabled.\r\n");
+
+    process_acl(&parts(&["ACL", "SETUSER", "carol", "enabled"]), &client, &acl).unwrap();
+    assert_eq!(process_auth(&parts(&["AUTH", "carol", "pw"]), &mut client, &acl).unwrap(), b"+OK\r\n");
+    assert_eq!(process_acl(&parts(&["ACL", "WHOAMI"]), &client, &acl).unwrap(), b"$5\r\ncarol\r\n");
+}
+
+#[tokio::test]
+async fn test_disabling_a_logged_in_user_revokes_it() {
+    let state = ServerState::default();
+    let mut client = logged_in_as("dave", &["on", ">pw", "+@all", "allkeys"], &state).await;
+    assert_eq!(run(&[&["SET", "k", "v"]], &state, &mut client).await, b"+OK\r\n");
+
+    let mut admin = ClientState::new();
+    run(&[&["ACL", "SETUSER", "dave", "off"]], &state, &mut admin).await;
+    assert_eq!(
+        run(&[&["GET", "k"]], &state, &mut client).await,
+        b"-NOPERM User dave has no permissions to run the 'get' command\r\n"
+    );
+}
+
+// ==================== Command Permission Tests ====================
+
+#[tokio::test]
+async fn test_only_allowed_commands_run() {
+    let state = ServerState::default();
+    let mut client = logged_in_as("reader", &["on", ">pw", "allkeys", "+get"], &state).await;
+
+    let replies = run(&[&["SET", "k", "v"], &["GET", "k"]], &state, &mut client).await;
+    assert_eq!(replies, b"-NOPERM User reader has no permissions to run the 'set' command\r\n$-1\r\n");
+    assert!(!state.databases[0].contains_key("k"));
+}
+
+#[tokio::test]
+async fn test_queued_commands_are_checked_at_exec() {
+    let state = ServerState::default();
+    let mut client = logged_in_as("writer", &["on", ">pw", "allkeys", "+multi", "+exec", "+set"], &state).await;
+
+    let replies = run(&[&["MULTI"], &["SET", "k", "v"], &["DEL", "k"], &["EXEC"]], &state, &mut client).await;
+    let replies = String::from_utf8(replies).unwrap();
+    assert!(replies.ends_with("*2\r\n+OK\r\n-NOPERM User writer has no permissions to run the 'del' command\r\n"));
+    assert!(state.databases[0].contains_key("k"));
+}
+
+// ==================== Key Pattern Tests ====================
+
+#[tokio::test]
+async fn test_key_patterns_limit_keys() {
+    let state = ServerState::default();
+    let mut client = logged_in_as("cache", &["on", ">pw", "~cache:*", "+@all"], &state).await;
+
+    let replies = run(&[&["SET", "cache:1", "v"], &["SET", "secret", "v"]], &state, &mut client).await;
+    assert_eq!(replies, b"+OK\r\n-NOPERM No permissions to access a key\r\n");
+    // Every key of a multi-key command has to match
+    let replies = run(&[&["DEL", "cache:1", "secret"], &["EXISTS", "cache:1"]], &state, &mut client).await;
+    assert_eq!(replies, b"-NOPERM No permissions to access a key\r\n:1\r\n");
+    // Commands without keys aren't affected
+    assert_eq!(run(&[&["PING"]], &state, &mut client).await, b"+PONG\r\n");
+}
+
+#[test]
+fn test_command_keys() {
+    let keys = |args: &[&str]| {
+        let parts = parts(args);
+        command_keys(&args[0].to_uppercase(), &parts).into_iter().map(String::from).collect::<Vec<_>>()
+    };
+    assert_eq!(keys(&["GET", "a"]), vec!["a"]);
+    assert_eq!(keys(&["MSET"]), Vec::<String>::new());
+    assert_eq!(keys(&["DEL", "a", "b"]), vec!["a", "b"]);
+    assert_eq!(keys(&["RENAME", "a", "b"]), vec!["a", "b"]);
+    assert_eq!(keys(&["BLPOP", "a", "b", "0"]), vec!["a", "b"]);
+    assert_eq!(keys(&["ZUNIONSTORE", "dest", "2", "a", "b", "WEIGHTS", "1", "2"]), vec!["dest", "a", "b"]);
+    assert_eq!(keys(&["BZMPOP", "0", "1", "a", "MIN"]), vec!["a"]);
+    assert_eq!(keys(&["XREAD", "COUNT", "1", "STREAMS", "a", "b", "0", "0"]), vec!["a", "b"]);
+    assert_eq!(keys(&["OBJECT", "ENCODING", "a"]), vec!["a"]);
+    assert_eq!(keys(&["KEYS", "*"]), Vec::<String>::new());
+}
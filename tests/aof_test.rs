@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use redis_cache::models::{ClientState, RedisData, ServerState};
+use redis_cache::parser::parse_pipeline;
+use redis_cache::utils::aof::{propagated, replay, AppendOnlyFile};
+
+fn args(parts: &[&str]) -> Vec<Vec<u8>> {
+    parts.iter().map(|s| s.as_bytes().to_vec()).collect()
+}
+
+fn make_resp(parts: &[&str]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", parts.len());
+    for part in parts {
+        result.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+    }
+    result.into_bytes()
+}
+
+// A fresh file per test, so tests running in parallel don't share one
+fn temp_aof(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("redis-cache-aof-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("appendonly.aof");
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn state_logging_to(path: &Path) -> ServerState {
+    ServerState {
+        aof: Some(Arc::new(AppendOnlyFile::open(path).unwrap())),
+        ..ServerState::default()
+    }
+}
+
+async fn run(commands: &[&[&str]], state: &ServerState, client: &mut ClientState) -> Vec<u8> {
+    let buffer: Vec<u8> = commands.iter().flat_map(|command| make_resp(command)).collect();
+    parse_pipeline(&buffer, state, client).await.0
+}
+
+fn string_at(state: &ServerState, db: usize, key: &str) -> Option<String> {
+    match &state.databases[db].shard(key).get(key)?.data {
+        RedisData::String(s) => Some(String::from_utf8(s.clone()).unwrap()),
+        _ => panic!("Expected string data"),
+    }
+}
+
+// ==================== Logging Tests ====================
+
+#[tokio::test]
+async fn test_only_successful_writes_are_logged() {
+    let path = temp_aof("writes_only");
+    let state = state_logging_to(&path);
+    let mut client = ClientState::new();
+
+    run(&[
+        &["SET", "a", "1"],
+        &["GET", "a"],
+        &["LRANGE", "list", "0", "-1"],
+        &["INCR", "a"],
+        &["RPUSH", "a", "x"], // WRONGTYPE, changes nothing
+    ], &state, &mut client).await;
+
+    let logged = std::fs::read(&path).unwrap();
+    let mut expected = make_resp(&["SELECT", "0"]);
+    expected.extend(make_resp(&["SET", "a", "1"]));
+    expected.extend(make_resp(&["INCR", "a"]));
+    assert_eq!(logged, expected);
+}
+
+#[tokio::test]
+async fn test_database_changes_are_logged_once() {
+    let path = temp_aof("select");
+    let state = state_logging_to(&path);
+    let mut client = ClientState::new();
+
+    run(&[
+        &["SET", "a", "1"],
+        &["SELECT", "2"],
+        &["SET", "b", "1"],
+        &["SET", "c", "1"],
+    ], &state, &mut client).await;
+
+    let logged = String::from_utf8(std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(logged.matches("SELECT").count(), 2);
+}
+
+// ==================== Replay Tests ====================
+
+#[tokio::test]
+async fn test_restart_recovers_state() {
+    let path = temp_aof("restart");
+    {
+        let state = state_logging_to(&path);
+        let mut client = ClientState::new();
+        run(&[
+            &["SET", "counter", "10"],
+            &["INCR", "counter"],
+            &["RPUSH", "list", "a", "b", "c"],
+            &["LPOP", "list"],
+            &["SELECT", "1"],
+            &["SET", "elsewhere", "v"],
+            &["SET", "gone", "v"],
+            &["DEL", "gone"],
+            &["MULTI"],
+            &["SET", "in_tx", "v"],
+            &["EXEC"],
+        ], &state, &mut client).await;
+    }
+
+    let restarted = ServerState::default();
+    replay(&path, &restarted).await.unwrap();
+    assert_eq!(string_at(&restarted, 0, "counter").as_deref(), Some("11"));
+    assert_eq!(string_at(&restarted, 1, "elsewhere").as_deref(), Some("v"));
+    assert_eq!(string_at(&restarted, 1, "in_tx").as_deref(), Some("v"));
+    assert!(!restarted.databases[1].contains_key("gone"));
+    match &restarted.databases[0].shard("list").get("list").unwrap().data {
+        RedisData::List(items) => assert_eq!(items, &["b", "c"]),
+        _ => panic!("Expected list data"),
+    }
+}
+
+#[tokio::test]
+async fn test_restart_keeps_generated_ids_and_past_deadlines() {
+    let path = temp_aof("generated");
+    let mut generated = Vec::new();
+    {
+        let state = state_logging_to(&path);
+        let mut client = ClientState::new();
+        for fields in [["a", "1"], ["b", "2"]] {
+            let reply = run(&[&["XADD", "stream", "*", fields[0], fields[1]]], &state, &mut client).await;
+            // $<len>\r\n<id>\r\n
+            generated.push(String::from_utf8(reply).unwrap().split("\r\n").nth(1).unwrap().to_string());
+        }
+        run(&[
+            &["SET", "doomed", "v"],
+            &["EXPIRE", "doomed", "-1"],
+            &["SET", "also_doomed", "v"],
+            &["PEXPIRE", "also_doomed", "0"],
+        ], &state, &mut client).await;
+    }
+
+    let restarted = ServerState::default();
+    replay(&path, &restarted).await.unwrap();
+    match &restarted.databases[0].shard("stream").get("stream").unwrap().data {
+        RedisData::Stream(stream) => {
+            let ids: Vec<String> = stream.entries.iter().map(|entry| entry.id.clone()).collect();
+            assert_eq!(ids, generated);
+        },
+        _ => panic!("Expected stream data"),
+    }
+    assert!(!restarted.databases[0].contains_key("doomed"));
+    assert!(!restarted.databases[0].contains_key("also_doomed"));
+}
+
+#[tokio::test]
+async fn test_replay_without_a_file() {
+    let path = temp_aof("missing");
+    let state = ServerState::default();
+    assert_eq!(replay(&path, &state).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_replay_drops_a_torn_last_command() {
+    let path = temp_aof("torn");
+    let mut bytes = make_resp(&["SET", "a", "1"]);
+    let whole = bytes.len();
+    bytes.extend(b"*3\r\n$3\r\nSET\r\n$1\r\nb");
+    std::fs::write(&path, &bytes).unwrap();
+
+    let state = ServerState::default();
+    assert_eq!(replay(&path, &state).await.unwrap(), whole);
+    assert!(state.databases[0].contains_key("a"));
+    assert_eq!(std::fs::read(&path).unwrap().len(), whole);
+}
+
+// ==================== Propagation Tests ====================
+
+#[test]
+fn test_propagated_makes_deadlines_absolute() {
+    let logged = propagated(&args(&["SET", "k", "v", "EX", "100"]), b"+OK\r\n");
+    assert_eq!(logged.len(), 2);
+    assert_eq!(logged[0], args(&["SET", "k", "v"]));
+    assert_eq!(logged[1][0..2], args(&["PEXPIREAT", "k"]));
+    assert!(String::from_utf8_lossy(&logged[1][2]).parse::<u64>().is_ok());
+
+    let logged = propagated(&args(&["PEXPIRE", "k", "500", "NX"]), b":1\r\n");
+    assert_eq!(logged[0][0], b"PEXPIREAT");
+    assert_eq!(logged[0][3], b"NX");
+}
+
+#[test]
+fn test_propagated_past_deadlines_become_deletes() {
+    assert_eq!(propagated(&args(&["EXPIRE", "k", "-5"]), b":1\r\n"), vec![args(&["DEL", "k"])]);
+    assert_eq!(propagated(&args(&["PEXPIRE", "k", "0"]), b":1\r\n"), vec![args(&["DEL", "k"])]);
+    // A missing key, or an option that stopped it, changed nothing
+    assert!(propagated(&args(&["EXPIRE", "k", "-5", "XX"]), b":0\r\n").is_empty());
+}
+
+#[test]
+fn test_propagated_xadd_uses_the_generated_id() {
+    let logged = propagated(&args(&["XADD", "s", "*", "f", "v"]), b"$15\r\n1700000000000-0\r\n");
+    assert_eq!(logged, vec![args(&["XADD", "s", "1700000000000-0", "f", "v"])]);
+    let logged = propagated(&args(&["XADD", "s", "5-*", "f", "v"]), b"$3\r\n5-1\r\n");
+    assert_eq!(logged, vec![args(&["XADD", "s", "5-1", "f", "v"])]);
+}
+
+#[test]
+fn test_propagated_pops_become_removals() {
+    let spop = propagated(&args(&["SPOP", "s", "2"]), b"*2\r\n$1\r\na\r\n$1\r\nb\r\n");
+    assert_eq!(spop, vec![args(&["SREM", "s", "a", "b"])]);
+
+    let blpop = propagated(&args(&["BLPOP", "l1", "l2", "0"]), b"*2\r\n$2\r\nl2\r\n$1\r\nx\r\n");
+    assert_eq!(blpop, vec![args(&["LPOP", "l2"])]);
+
+    let bzmpop = propagated(
+        &args(&["BZMPOP", "0", "2", "z1", "z2", "MAX", "COUNT", "2"]),
+        b"*2\r\n$2\r\nz2\r\n*1\r\n*2\r\n$1\r\nm\r\n$1\r\n1\r\n",
+    );
+    assert_eq!(bzmpop, vec![args(&["ZMPOP", "1", "z2", "MAX", "COUNT", "2"])]);
+}
+
+#[test]
+fn test_propagated_skips_failures_and_no_ops() {
+    assert!(propagated(&args(&["INCR", "k"]), b"-ERR value is not an integer or out of range\r\n").is_empty());
+    assert!(propagated(&args(&["RPUSH", "k", "v"]), b"").is_empty());
+    assert!(propagated(&args(&["BLPOP", "l", "1"]), b"*-1\r\n").is_empty());
+    assert!(propagated(&args(&["SPOP", "s"]), b"$-1\r\n").is_empty());
+}
@@ -0,0 +1,298 @@
+use std::sync::{Arc, Mutex};
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use redis_cache::commands::{process_client, process_hello};
+use redis_cache::models::{Acl, ClientInfo, ClientPause, ClientState, Clients, ReplicationInfo, ServerInfo, ServerState};
+use redis_cache::parser::parse_pipeline;
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn make_resp(parts: &[&str]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", parts.len());
+    for part in parts {
+        result.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+    }
+    result.into_bytes()
+}
+
+async fn run(commands: &[&[&str]], state: &ServerState, client: &mut ClientState) -> Vec<u8> {
+    let buffer: Vec<u8> = commands.iter().flat_map(|command| make_resp(command)).collect();
+    parse_pipeline(&buffer, state, client).await.0
+}
+
+fn new_clients() -> Clients {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+// A connection registered the way handle_client registers it
+fn connect(state: &ServerState, id: u64, addr: &str) -> ClientState {
+    let info = ClientInfo::new(id, addr.to_string());
+    let mut client = ClientState::new();
+    client.id = id;
+    client.status = Some(Arc::clone(&info.status));
+    state.clients.lock().unwrap().insert(id, info);
+    client
+}
+
+fn bulk_string(reply: &[u8]) -> String {
+    let reply = String::from_utf8(reply.to_vec()).unwrap();
+    let (_, body) = reply.split_once("\r\n").unwrap();
+    body.strip_suffix("\r\n").unwrap().to_string()
+}
+
+// ==================== CLIENT SETNAME / GETNAME Tests ====================
+
+#[test]
+fn test_client_getname_unset() {
+    let mut client = ClientState::new();
+    assert_eq!(process_client(&parts(&["CLIENT", "GETNAME"]), &mut client, &new_clients(), &ClientPause::default()).unwrap(), b"$-1\r\n");
+}
+
+#[test]
+fn test_client_setname_then_getname() {
+    let mut client = ClientState::new();
+    assert_eq!(process_client(&parts(&["CLIENT", "SETNAME", "worker-1"]), &mut client, &new_clients(), &ClientPause::default()).unwrap(), b"+OK\r\n");
+    assert_eq!(process_client(&parts(&["client", "getname"]), &mut client, &new_clients(), &ClientPause::default()).unwrap(), b"$8\r\nworker-1\r\n");
+
+    // An empty name clears it
+    assert_eq!(process_client(&parts(&["CLIENT", "SETNAME", ""]), &mut client, &new_clients(), &ClientPause::default()).unwrap(), b"+OK\r\n");
+    assert_eq!(client.name, None);
+}
+
+#[test]
+fn test_client_setname_rejects_spaces() {
+    let mut client = ClientState::new();
+    process_client(&parts(&["CLIENT", "SETNAME", "ok"]), &mut client, &new_clients(), &ClientPause::default()).unwrap();
+    let result = process_client(&parts(&["CLIENT", "SETNAME", "has space"]), &mut client, &new_clients(), &ClientPause::default()).unwrap();
+    assert_eq!(result, b"-ERR Client names cannot contain spaces, newlines or special characters.\r\n");
+    assert_eq!(client.name.as_deref(), Some("ok"));
+}
+
+#[test]
+fn test_hello_setname_is_visible_to_getname() {
+    let mut client = ClientState::new();
+    let server_info = Arc::new(Mutex::new(ServerInfo::new(ReplicationInfo::new("master".to_string()))));
+    process_hello(&parts(&["HELLO", "2", "SETNAME", "app"]), &mut client, &Acl::new(), &server_info).unwrap();
+    assert_eq!(process_client(&parts(&["CLIENT", "GETNAME"]), &mut client, &new_clients(), &ClientPause::default()).unwrap(), b"$3\r\napp\r\n");
+}
+
+// ==================== CLIENT ID Tests ====================
+
+#[test]
+fn test_client_id() {
+    let mut client = ClientState::new();
+    client.id = 42;
+    assert_eq!(process_client(&parts(&["CLIENT", "ID"]), &mut client, &new_clients(), &ClientPause::default()).unwrap(), b":42\r\n");
+}
+
+#[test]
+fn test_client_bad_arguments() {
+    let mut client = ClientState::new();
+    let result = process_client(&parts(&["CLIENT", "ID", "extra"]), &mut client, &new_clients(), &ClientPause::default()).unwrap();
+    assert_eq!(result, b"-ERR wrong number of arguments for 'client|id' command\r\n");
+    let result = process_client(&parts(&["CLIENT", "SETNAME"]), &mut client, &new_clients(), &ClientPause::default()).unwrap();
+    assert_eq!(result, b"-ERR wrong number of arguments for 'client|setname' command\r\n");
+    let result = process_client(&parts(&["CLIENT", "FROB"]), &mut client, &new_clients(), &ClientPause::default()).unwrap();
+    assert_eq!(result, b"-ERR unknown subcommand 'FROB'. Try CLIENT HELP.\r\n");
+}
+
+// ==================== CLIENT LIST Tests ====================
+
+#[tokio::test]
+async fn test_client_list_shows_every_connection() {
+    let state = ServerState::default();
+    let mut first = connect(&state, 1, "127.0.0.1:5001");
+    let mut second = connect(&state, 2, "127.0.0.1:5002");
+    run(&[&["CLIENT", "SETNAME", "worker"], &["SELECT", "3"]], &state, &mut first).await;
+    run(&[&["MULTI"], &["PING"], &["PING"]], &state, &mut second).await;
+
+    let mut third = connect(&state, 3, "127.0.0.1:5003");
+    let list = bulk_string(&run(&[&["CLIENT", "LIST"]], &state, &mut third).await);
+    let lines: Vec<&str> = list.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "id=1 addr=127.0.0.1:5001 name=worker age=0 idle=0 flags=N db=3 sub=0 psub=0 multi=-1 cmd=select user=default");
+    assert!(lines[1].starts_with("id=2 addr=127.0.0.1:5002 name= "));
+    assert!(lines[1].contains(" flags=x ") && lines[1].contains(" multi=2 "));
+    assert!(lines[2].contains(" cmd=client "));
+}
+
+#[tokio::test]
+async fn test_client_list_by_id() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    connect(&state, 2, "127.0.0.1:5002");
+    connect(&state, 3, "127.0.0.1:5003");
+
+    let list = bulk_string(&run(&[&["CLIENT", "LIST", "ID", "3", "2", "9"]], &state, &mut client).await);
+    let ids: Vec<&str> = list.lines().map(|line| line.split(' ').next().unwrap()).collect();
+    assert_eq!(ids, vec!["id=2", "id=3"]);
+
+    let result = run(&[&["CLIENT", "LIST", "ID", "x"]], &state, &mut client).await;
+    assert_eq!(result, b"-ERR Invalid client ID\r\n");
+}
+
+// ==================== CLIENT KILL Tests ====================
+
+#[tokio::test]
+async fn test_client_kill_by_addr() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    connect(&state, 2, "127.0.0.1:5002");
+    let kill = Arc::clone(&state.clients.lock().unwrap()[&2].kill);
+
+    assert_eq!(run(&[&["CLIENT", "KILL", "127.0.0.1:5002"]], &state, &mut client).await, b"+OK\r\n");
+    assert!(!state.clients.lock().unwrap().contains_key(&2));
+    // The connection's task is told to close
+    tokio::time::timeout(Duration::from_secs(1), kill.notified()).await.unwrap();
+
+    let result = run(&[&["CLIENT", "KILL", "127.0.0.1:5002"]], &state, &mut client).await;
+    assert_eq!(result, b"-ERR No such client\r\n");
+}
+
+#[tokio::test]
+async fn test_client_kill_filters() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    connect(&state, 2, "127.0.0.1:5002");
+    connect(&state, 3, "127.0.0.1:5003");
+
+    assert_eq!(run(&[&["CLIENT", "KILL", "ID", "2"]], &state, &mut client).await, b":1\r\n");
+    // The caller is skipped unless SKIPME no
+    assert_eq!(run(&[&["CLIENT", "KILL", "USER", "default"]], &state, &mut client).await, b":1\r\n");
+    assert_eq!(state.clients.lock().unwrap().len(), 1);
+    let result = run(&[&["CLIENT", "KILL", "USER", "default", "SKIPME", "no"]], &state, &mut client).await;
+    assert_eq!(result, b":1\r\n");
+    assert!(state.clients.lock().unwrap().is_empty());
+
+    let result = run(&[&["CLIENT", "KILL", "ID", "0"]], &state, &mut client).await;
+    assert_eq!(result, b"-ERR client-id should be greater than 0\r\n");
+    let result = run(&[&["CLIENT", "KILL", "NAME", "x"]], &state, &mut client).await;
+    assert_eq!(result, b"-ERR syntax error\r\n");
+}
+
+// ==================== CLIENT NO-EVICT Tests ====================
+
+#[tokio::test]
+async fn test_client_no_evict_flag() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    assert_eq!(run(&[&["CLIENT", "NO-EVICT", "on"]], &state, &mut client).await, b"+OK\r\n");
+    assert!(client.no_evict);
+
+    let list = bulk_string(&run(&[&["CLIENT", "LIST"]], &state, &mut client).await);
+    assert!(list.contains(" flags=e "));
+
+    assert_eq!(run(&[&["CLIENT", "NO-EVICT", "maybe"]], &state, &mut client).await, b"-ERR syntax error\r\n");
+    assert_eq!(run(&[&["CLIENT", "NO-EVICT", "OFF"]], &state, &mut client).await, b"+OK\r\n");
+    assert!(!client.no_evict);
+}
+
+// ==================== CLIENT REPLY Tests ====================
+
+#[tokio::test]
+async fn test_client_reply_off_until_on() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    let replies = run(&[
+        &["CLIENT", "REPLY", "OFF"],
+        &["SET", "k", "v"],
+        &["GET", "k"],
+        &["CLIENT", "REPLY", "ON"],
+        &["GET", "k"],
+    ], &state, &mut client).await;
+    assert_eq!(replies, b"+OK\r\n$1\r\nv\r\n");
+    // Commands still ran while their replies were dropped
+    assert!(state.databases[0].contains_key("k"));
+}
+
+#[tokio::test]
+async fn test_client_reply_skip_drops_one_reply() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    let replies = run(&[
+        &["CLIENT", "REPLY", "SKIP"],
+        &["SET", "k", "v"],
+        &["GET", "k"],
+    ], &state, &mut client).await;
+    assert_eq!(replies, b"$1\r\nv\r\n");
+
+    let result = run(&[&["CLIENT", "REPLY", "MAYBE"]], &state, &mut client).await;
+    assert_eq!(result, b"-ERR syntax error\r\n");
+}
+
+// ==================== CLIENT NO-TOUCH / CACHING Tests ====================
+
+#[tokio::test]
+async fn test_client_no_touch_leaves_idle_time() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    let idle = |state: &ServerState| state.databases[0].read_live("k", |value| value.unwrap().idle_time());
+    run(&[&["SET", "k", "v"], &["CLIENT", "NO-TOUCH", "on"]], &state, &mut client).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert_eq!(run(&[&["GET", "k"], &["GETRANGE", "k", "0", "-1"]], &state, &mut client).await, b"$1\r\nv\r\n$1\r\nv\r\n");
+    assert!(idle(&state) >= Duration::from_millis(50));
+
+    run(&[&["CLIENT", "NO-TOUCH", "off"], &["GET", "k"]], &state, &mut client).await;
+    assert!(idle(&state) < Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn test_client_caching_flag() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    assert_eq!(run(&[&["CLIENT", "CACHING", "yes"]], &state, &mut client).await, b"+OK\r\n");
+    assert!(client.caching);
+    assert_eq!(run(&[&["CLIENT", "CACHING", "on"]], &state, &mut client).await, b"-ERR syntax error\r\n");
+    assert_eq!(run(&[&["CLIENT", "CACHING", "NO"]], &state, &mut client).await, b"+OK\r\n");
+    assert!(!client.caching);
+}
+
+// ==================== CLIENT PAUSE / UNPAUSE Tests ====================
+
+#[tokio::test]
+async fn test_client_pause_write_holds_only_writes() {
+    let state = ServerState::default();
+    let mut admin = connect(&state, 1, "127.0.0.1:5001");
+    let mut writer = connect(&state, 2, "127.0.0.1:5002");
+    assert_eq!(run(&[&["CLIENT", "PAUSE", "200", "WRITE"]], &state, &mut admin).await, b"+OK\r\n");
+
+    let started = Instant::now();
+    assert_eq!(run(&[&["GET", "k"]], &state, &mut writer).await, b"$-1\r\n");
+    assert!(started.elapsed() < Duration::from_millis(100));
+    assert_eq!(run(&[&["SET", "k", "v"]], &state, &mut writer).await, b"+OK\r\n");
+    assert!(started.elapsed() >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn test_client_unpause_releases_waiting_commands() {
+    let state = ServerState::default();
+    let mut admin = connect(&state, 1, "127.0.0.1:5001");
+    run(&[&["CLIENT", "PAUSE", "10000", "WRITE"]], &state, &mut admin).await;
+
+    let writer_state = state.clone();
+    let writer = tokio::spawn(async move {
+        let mut writer = connect(&writer_state, 2, "127.0.0.1:5002");
+        run(&[&["SET", "k", "v"]], &writer_state, &mut writer).await
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!state.databases[0].contains_key("k"));
+
+    assert_eq!(run(&[&["CLIENT", "UNPAUSE"]], &state, &mut admin).await, b"+OK\r\n");
+    let reply = tokio::time::timeout(Duration::from_secs(1), writer).await.unwrap().unwrap();
+    assert_eq!(reply, b"+OK\r\n");
+}
+
+#[tokio::test]
+async fn test_client_pause_bad_arguments() {
+    let state = ServerState::default();
+    let mut client = connect(&state, 1, "127.0.0.1:5001");
+    let result = run(&[&["CLIENT", "PAUSE", "-1"]], &state, &mut client).await;
+    assert_eq!(result, b"-ERR timeout is not an integer or out of range\r\n");
+    let result = run(&[&["CLIENT", "PAUSE", "10", "READ"]], &state, &mut client).await;
+    assert_eq!(result, b"-ERR syntax error\r\n");
+}
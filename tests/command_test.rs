@@ -0,0 +1,116 @@
+use redis_cache::commands::{process_command, COMMAND_TABLE};
+use redis_cache::constants::{COMMAND_ARITY, COMMAND_DOCS};
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn command(args: &[&str]) -> String {
+    String::from_utf8(process_command(&parts(args)).unwrap()).unwrap()
+}
+
+// ==================== COMMAND COUNT / LIST Tests ====================
+
+#[test]
+fn test_command_count_covers_every_command() {
+    let count: usize = command(&["COMMAND", "COUNT"])[1..].trim_end().parse().unwrap();
+    assert!(count > 10);
+    assert_eq!(count, COMMAND_ARITY.len());
+}
+
+#[test]
+fn test_command_list() {
+    let list = command(&["command", "list"]);
+    assert!(list.starts_with(&format!("*{}\r\n", COMMAND_ARITY.len())));
+    assert!(list.contains("$3\r\nget\r\n"));
+    assert!(list.contains("$6\r\nzunion\r\n"));
+}
+
+#[test]
+fn test_every_command_is_documented() {
+    let documented: Vec<&str> = COMMAND_DOCS.iter().map(|(name, _, _)| *name).collect();
+    let supported: Vec<&str> = COMMAND_ARITY.iter().map(|(name, _)| *name).collect();
+    assert_eq!(documented, supported);
+    assert!(COMMAND_TABLE.iter().all(|meta| !meta.summary.is_empty()));
+}
+
+// ==================== COMMAND INFO Tests ====================
+
+#[test]
+fn test_command_info_single_key_command() {
+    assert_eq!(
+        command(&["COMMAND", "INFO", "get"]),
+        "*1\r\n*6\r\n$3\r\nget\r\n:2\r\n*1\r\n+readonly\r\n:1\r\n:1\r\n:1\r\n"
+    );
+    assert_eq!(
+        command(&["COMMAND", "INFO", "SET"]),
+        "*1\r\n*6\r\n$3\r\nset\r\n:-3\r\n*2\r\n+write\r\n+denyoom\r\n:1\r\n:1\r\n:1\r\n"
+    );
+}
+
+#[test]
+fn test_command_info_key_ranges() {
+    assert!(command(&["COMMAND", "INFO", "del"]).ends_with(":1\r\n:-1\r\n:1\r\n"));
+    assert!(command(&["COMMAND", "INFO", "blpop"]).contains("+blocking\r\n:1\r\n:-2\r\n:1\r\n"));
+    assert!(command(&["COMMAND", "INFO", "zunion"]).contains("+movablekeys\r\n:0\r\n:0\r\n:0\r\n"));
+    assert!(command(&["COMMAND", "INFO", "ping"]).ends_with("*0\r\n:0\r\n:0\r\n:0\r\n"));
+}
+
+#[test]
+fn test_command_info_unknown_and_several() {
+    let info = command(&["COMMAND", "INFO", "get", "nosuch", "llen"]);
+    assert!(info.starts_with("*3\r\n*6\r\n$3\r\nget\r\n"));
+    assert!(info.contains("*-1\r\n*6\r\n$4\r\nllen\r\n"));
+}
+
+#[test]
+fn test_command_without_subcommand_lists_every_command() {
+    assert_eq!(command(&["COMMAND"]), command(&["COMMAND", "INFO"]));
+    assert!(command(&["COMMAND"]).starts_with(&format!("*{}\r\n*6\r\n", COMMAND_ARITY.len())));
+}
+
+// ==================== COMMAND DOCS Tests ====================
+
+#[test]
+fn test_command_docs_named() {
+    assert_eq!(
+        command(&["COMMAND", "DOCS", "get"]),
+        "*2\r\n$3\r\nget\r\n*4\r\n$7\r\nsummary\r\n$34\r\nReturns the string value of a key.\r\n$5\r\ngroup\r\n$6\r\nstring\r\n"
+    );
+    // Unknown names are left out
+    assert_eq!(command(&["COMMAND", "DOCS", "nosuch"]), "*0\r\n");
+}
+
+#[test]
+fn test_command_docs_all() {
+    assert!(command(&["COMMAND", "DOCS"]).starts_with(&format!("*{}\r\n", COMMAND_ARITY.len() * 2)));
+}
+
+// ==================== COMMAND GETKEYS Tests ====================
+
+#[test]
+fn test_command_getkeys() {
+    assert_eq!(command(&["COMMAND", "GETKEYS", "SET", "k", "v"]), "*1\r\n$1\r\nk\r\n");
+    assert_eq!(
+        command(&["COMMAND", "GETKEYS", "ZUNIONSTORE", "dest", "2", "a", "b"]),
+        "*3\r\n$4\r\ndest\r\n$1\r\na\r\n$1\r\nb\r\n"
+    );
+    assert_eq!(
+        command(&["COMMAND", "GETKEYS", "XREAD", "STREAMS", "s1", "s2", "0", "0"]),
+        "*2\r\n$2\r\ns1\r\n$2\r\ns2\r\n"
+    );
+}
+
+#[test]
+fn test_command_getkeys_errors() {
+    assert_eq!(command(&["COMMAND", "GETKEYS", "NOSUCH", "k"]), "-ERR Invalid command specified\r\n");
+    assert_eq!(command(&["COMMAND", "GETKEYS"]), "-ERR Invalid command specified\r\n");
+    assert_eq!(command(&["COMMAND", "GETKEYS", "GET"]), "-ERR Invalid number of arguments specified for command\r\n");
+    assert_eq!(command(&["COMMAND", "GETKEYS", "PING"]), "-ERR The command has no key arguments\r\n");
+}
+
+#[test]
+fn test_command_unknown_subcommand() {
+    assert!(command(&["COMMAND", "BOGUS"]).starts_with("-ERR unknown subcommand 'BOGUS'"));
+    assert!(command(&["COMMAND", "COUNT", "x"]).starts_with("-ERR wrong number of arguments for 'command|count'"));
+}
@@ -0,0 +1,177 @@
+use std::sync::{Arc, Mutex};
+
+use redis_cache::models::{Acl, ServerConfig};
+use redis_cache::commands::process_config;
+
+fn new_server_config() -> Arc<Mutex<ServerConfig>> {
+    Arc::new(Mutex::new(ServerConfig::new("/tmp/redis-files".to_string(), "dump.rdb".to_string())))
+}
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+// ==================== CONFIG GET Tests ====================
+
+#[test]
+fn test_config_get_dir() {
+    let config = new_server_config();
+    let result = process_config(&parts(&["CONFIG", "GET", "dir"]), &config, &Acl::new()).unwrap();
+    assert_eq!(result, b"*2\r\n$3\r\ndir\r\n$16\r\n/tmp/redis-files\r\n");
+}
+
+#[test]
+fn test_config_get_dbfilename_case_insensitive() {
+    let config = new_server_config();
+    let result = process_config(&parts(&["config", "get", "DBFILENAME"]), &config, &Acl::new()).unwrap();
+    assert_eq!(result, b"*2\r\n$10\r\ndbfilename\r\n$8\r\ndump.rdb\r\n");
+}
+
+#[test]
+fn test_config_get_pattern_and_multiple_params() {
+    let config = new_server_config();
+    let all = process_config(&parts(&["CONFIG", "GET", "*"]), &config, &Acl::new()).unwrap();
+    assert!(all.starts_with(b"*22\r\n"));
+    let both = process_config(&parts(&["CONFIG", "GET", "dir", "dbfilename"]), &config, &Acl::new()).unwrap();
+    assert!(both.starts_with(b"*4\r\n"));
+}
+
+#[test]
+fn test_config_get_unknown_param() {
+    let config = new_server_config();
+    let result = process_config(&parts(&["CONFIG", "GET", "nosuchparam"]), &config, &Acl::new()).unwrap();
+    assert_eq!(result, b"*0\r\n");
+}
+
+#[test]
+fn test_config_get_missing_argument() {
+    let config = new_server_config();
+    let result = process_config(&parts(&["CONFIG", "GET"]), &config, &Acl::new()).unwrap();
+    assert!(result.starts_with(b"-ERR wrong number of arguments"));
+}
+
+// ==================== CONFIG SET Tests ====================
+
+#[test]
+fn test_config_set_then_get() {
+    let config = new_server_config();
+    let result = process_config(&parts(&["CONFIG", "SET", "dbfilename", "other.rdb"]), &config, &Acl::new()).unwrap();
+    assert_eq!(result, b"+OK\r\n");
+
+    let get = process_config(&parts(&["CONFIG", "GET", "dbfilename"]), &config, &Acl::new()).unwrap();
+    assert_eq!(get, b"*2\r\n$10\r\ndbfilename\r\n$9\r\nother.rdb\r\n");
+}
+
+#[test]
+fn test_config_set_unknown_param_changes_nothing() {
+    let config = new_server_config();
+    let p = parts(&["CONFIG", "SET", "dir", "/elsewhere", "bogus", "1"]);
+    let result = process_config(&p, &config, &Acl::new()).unwrap();
+    assert!(result.starts_with(b"-ERR Unknown option or number of arguments for CONFIG SET - 'bogus'"));
+    assert_eq!(config.lock().unwrap().dir, "/tmp/redis-files");
+}
+
+#[test]
+fn test_config_set_odd_arguments() {
+    let config = new_server_config();
+    let result = process_config(&parts(&["CONFIG", "SET", "dir"]), &config, &Acl::new()).unwrap();
+    assert!(result.starts_with(b"-ERR wrong number of arguments"));
+}
+
+#[test]
+fn test_config_unknown_subcommand() {
+    let config = new_server_config();
+    let result = process_config(&parts(&["CONFIG", "FROB"]), &config, &Acl::new()).unwrap();
+    assert!(result.starts_with(b"-ERR unknown subcommand 'FROB'"));
+}
+
+#[test]
+fn test_config_set_maxmemory_keys() {
+    let config = new_server_config();
+    let result = process_config(&parts(&["CONFIG", "SET", "maxmemory-keys", "100"]), &config, &Acl::new()).unwrap();
+    assert_eq!(result, b"+OK\r\n");
+    assert_eq!(config.lock().unwrap().maxmemory_keys, 100);
+
+    let get = process_config(&parts(&["CONFIG", "GET", "maxmemory-keys"]), &config, &Acl::new()).unwrap();
+    assert_eq!(get, b"*2\r\n$14\r\nmaxmemory-keys\r\n$3\r\n100\r\n");
+}
+
+#[test]
+fn test_config_set_invalid_value_changes_nothing() {
+    let config = new_server_config();
+    let p = parts(&["CONFIG", "SET", "dir", "/elsewhere", "maxmemory-keys", "lots"]);
+    let result = process_config(&p, &config, &Acl::new()).unwrap();
+    assert!(result.starts_with(b"-ERR CONFIG SET failed (possibly related to argument 'maxmemory-keys')"));
+    assert_eq!(config.lock().unwrap().dir, "/tmp/redis-files");
+}
+
+#[test]
+fn test_config_get_defaults() {
+    let config = new_server_config();
+    let get = |name: &str| process_config(&parts(&["CONFIG", "GET", name]), &config, &Acl::new()).unwrap();
+    assert_eq!(get("maxmemory"), b"*2\r\n$9\r\nmaxmemory\r\n$1\r\n0\r\n");
+    assert_eq!(get("hz"), b"*2\r\n$2\r\nhz\r\n$2\r\n10\r\n");
+    assert_eq!(get("requirepass"), b"*2\r\n$11\r\nrequirepass\r\n$0\r\n\r\n");
+    assert_eq!(get("save"), b"*2\r\n$4\r\nsave\r\n$23\r\n3600 1 300 100 60 10000\r\n");
+    assert_eq!(get("loglevel"), b"*2\r\n$8\r\nloglevel\r\n$6\r\nnotice\r\n");
+    assert_eq!(get("databases"), b"*2\r\n$9\r\ndatabases\r\n$2\r\n16\r\n");
+}
+
+#[test]
+fn test_config_set_new_params() {
+    let config = new_server_config();
+    let p = parts(&["CONFIG", "SET", "maxmemory", "2mb", "hz", "1000", "save", "900 1", "loglevel", "WARNING"]);
+    assert_eq!(process_config(&p, &config, &Acl::new()).unwrap(), b"+OK\r\n");
+
+    let config = config.lock().unwrap();
+    assert_eq!(config.maxmemory, 2 * 1024 * 1024);
+    // Clamped like Redis does
+    assert_eq!(config.hz, 500);
+    assert_eq!(config.save_intervals, vec![(900, 1)]);
+    assert_eq!(config.loglevel, "warning");
+}
+
+#[test]
+fn test_config_set_rejects_bad_values() {
+    let config = new_server_config();
+    let set = |name: &str, value: &str| process_config(&parts(&["CONFIG", "SET", name, value]), &config, &Acl::new()).unwrap();
+    assert!(set("maxmemory", "lots").starts_with(b"-ERR CONFIG SET failed (possibly related to argument 'maxmemory')"));
+    assert!(set("save", "900").starts_with(b"-ERR CONFIG SET failed (possibly related to argument 'save')"));
+    assert!(set("loglevel", "loud").starts_with(b"-ERR CONFIG SET failed (possibly related to argument 'loglevel')"));
+    assert!(set("databases", "4").ends_with(b"can't set immutable config\r\n"));
+    assert_eq!(config.lock().unwrap().databases, 16);
+    // An empty save turns saving off
+    assert_eq!(set("save", ""), b"+OK\r\n");
+    assert!(config.lock().unwrap().save_intervals.is_empty());
+}
+
+#[test]
+fn test_config_set_requirepass_changes_auth() {
+    let config = new_server_config();
+    let acl = Acl::new();
+    let set = |value: &str| process_config(&parts(&["CONFIG", "SET", "requirepass", value]), &config, &acl).unwrap();
+
+    assert_eq!(set("secret"), b"+OK\r\n");
+    assert!(acl.login_required());
+    assert!(acl.check_password("default", "secret"));
+    assert!(!acl.check_password("default", "other"));
+
+    assert_eq!(set(""), b"+OK\r\n");
+    assert_eq!(config.lock().unwrap().requirepass, None);
+    assert!(!acl.login_required());
+}
+
+// ==================== CONFIG RESETSTAT / REWRITE Tests ====================
+
+#[test]
+fn test_config_resetstat_and_rewrite() {
+    let config = new_server_config();
+    let acl = Acl::new();
+    assert_eq!(process_config(&parts(&["CONFIG", "RESETSTAT"]), &config, &acl).unwrap(), b"+OK\r\n");
+    assert_eq!(
+        process_config(&parts(&["CONFIG", "REWRITE"]), &config, &acl).unwrap(),
+        b"-ERR The server is running without a config file\r\n"
+    );
+    assert!(process_config(&parts(&["CONFIG", "RESETSTAT", "x"]), &config, &acl).unwrap()
+        .starts_with(b"-ERR wrong number of arguments"));
+}
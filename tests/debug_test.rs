@@ -0,0 +1,195 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use redis_cache::commands::{process_debug, process_slowlog};
+use redis_cache::models::{new_databases, ClientState, RedisData, RedisValue, ServerConfig, ServerState};
+use redis_cache::parser::parse_pipeline;
+use redis_cache::utils::expiry::spawn_configured_expiry;
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn make_resp(parts: &[&str]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", parts.len());
+    for part in parts {
+        result.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+    }
+    result.into_bytes()
+}
+
+async fn run(commands: &[&[&str]], state: &ServerState, client: &mut ClientState) -> Vec<u8> {
+    let buffer: Vec<u8> = commands.iter().flat_map(|command| make_resp(command)).collect();
+    parse_pipeline(&buffer, state, client).await.0
+}
+
+fn new_server_config() -> Arc<Mutex<ServerConfig>> {
+    Arc::new(Mutex::new(ServerConfig::default()))
+}
+
+// ==================== SLEEP Tests ====================
+
+#[tokio::test]
+async fn test_debug_sleep_waits() {
+    let server_config = new_server_config();
+    let started = Instant::now();
+    let result = process_debug(&parts(&["DEBUG", "SLEEP", "0.2"]), &server_config).await.unwrap();
+    assert_eq!(result, b"+OK\r\n");
+    assert!(started.elapsed() >= Duration::from_millis(200));
+
+    assert_eq!(process_debug(&parts(&["DEBUG", "sleep", "0"]), &server_config).await.unwrap(), b"+OK\r\n");
+}
+
+#[tokio::test]
+async fn test_debug_sleep_rejects_bad_durations() {
+    let server_config = new_server_config();
+    for bad in ["abc", "-1", "inf"] {
+        let result = process_debug(&parts(&["DEBUG", "SLEEP", bad]), &server_config).await.unwrap();
+        assert_eq!(result, b"-ERR value is not a valid float\r\n");
+    }
+}
+
+// ==================== SET-ACTIVE-EXPIRE Tests ====================
+
+#[tokio::test]
+async fn test_set_active_expire_pauses_the_sweep() {
+    let databases = new_databases(16);
+    let server_config = new_server_config();
+    server_config.lock().unwrap().hz = 100;
+    let result = process_debug(&parts(&["DEBUG", "SET-ACTIVE-EXPIRE", "0"]), &server_config).await.unwrap();
+    assert_eq!(result, b"+OK\r\n");
+    let task = spawn_configured_expiry(Arc::clone(&databases), Arc::clone(&server_config));
+
+    let expires_at = Some(Instant::now() + Duration::from_millis(10));
+    databases[0].insert("k".to_string(), RedisValue::new(RedisData::String("v".into()), expires_at));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(databases[0].contains_key("k"));
+
+    process_debug(&parts(&["DEBUG", "SET-ACTIVE-EXPIRE", "1"]), &server_config).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    task.abort();
+    assert!(!databases[0].contains_key("k"));
+}
+
+#[tokio::test]
+async fn test_set_active_expire_needs_zero_or_one() {
+    let server_config = new_server_config();
+    let result = process_debug(&parts(&["DEBUG", "SET-ACTIVE-EXPIRE", "2"]), &server_config).await.unwrap();
+    assert_eq!(result, b"-ERR value is out of range, must be 0 or 1\r\n");
+    assert!(server_config.lock().unwrap().active_expire);
+}
+
+// ==================== Other Subcommand Tests ====================
+
+#[tokio::test]
+async fn test_debug_no_ops_and_unknown_subcommands() {
+    let server_config = new_server_config();
+    assert_eq!(process_debug(&parts(&["DEBUG", "JMAP"]), &server_config).await.unwrap(), b"+OK\r\n");
+
+    let result = process_debug(&parts(&["DEBUG", "SEGFAULT"]), &server_config).await.unwrap();
+    assert_eq!(result, b"-ERR unknown subcommand or wrong number of arguments for 'SEGFAULT'. Try DEBUG HELP.\r\n");
+    let result = process_debug(&parts(&["DEBUG", "SLEEP"]), &server_config).await.unwrap();
+    assert_eq!(result, b"-ERR unknown subcommand or wrong number of arguments for 'SLEEP'. Try DEBUG HELP.\r\n");
+}
+
+// ==================== SLOWLOG Tests ====================
+
+#[tokio::test(start_paused = true)]
+async fn test_slowlog_records_slow_commands() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    client.name = Some("worker".to_string());
+    // 10ms is the default threshold; the paused clock skips the wait itself
+    run(&[&["DEBUG", "SLEEP", "0.02"], &["SET", "k", "v"]], &state, &mut client).await;
+
+    assert_eq!(run(&[&["SLOWLOG", "LEN"]], &state, &mut client).await, b":1\r\n");
+    let reply = String::from_utf8(run(&[&["SLOWLOG", "GET"]], &state, &mut client).await).unwrap();
+    assert!(reply.starts_with("*1\r\n*6\r\n:0\r\n:"));
+    assert!(reply.contains(":20000\r\n*3\r\n$5\r\nDEBUG\r\n$5\r\nSLEEP\r\n$4\r\n0.02\r\n"));
+    assert!(reply.ends_with("$6\r\nworker\r\n"));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_slowlog_times_with_the_tokio_clock() {
+    let state = ServerState::default();
+    let handle = tokio::spawn({
+        let state = state.clone();
+        async move { run(&[&["DEBUG", "SLEEP", "5"]], &state, &mut ClientState::new()).await }
+    });
+    // Let the command start, then jump past its sleep
+    tokio::task::yield_now().await;
+    tokio::time::advance(Duration::from_secs(5)).await;
+    assert_eq!(handle.await.unwrap(), b"+OK\r\n");
+
+    let entries = state.slowlog.lock().unwrap();
+    assert_eq!(entries.entries.len(), 1);
+    assert_eq!(entries.entries[0].duration, Duration::from_secs(5));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_slowlog_threshold_and_max_len_come_from_config() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    run(&[&["CONFIG", "SET", "slowlog-log-slower-than", "0"], &["CONFIG", "SET", "slowlog-max-len", "2"]], &state, &mut client).await;
+    run(&[&["SET", "a", "1"], &["SET", "b", "2"], &["GET", "a"]], &state, &mut client).await;
+
+    // Newest first, the oldest dropped, ids still counting up
+    let entries = state.slowlog.lock().unwrap().entries.iter().map(|entry| (entry.id, entry.args.join(" "))).collect::<Vec<_>>();
+    assert_eq!(entries, vec![(4, "GET a".to_string()), (3, "SET b 2".to_string())]);
+
+    // A negative threshold turns logging off
+    run(&[&["CONFIG", "SET", "slowlog-log-slower-than", "-1"], &["SLOWLOG", "RESET"]], &state, &mut client).await;
+    run(&[&["DEBUG", "SLEEP", "1"]], &state, &mut client).await;
+    assert_eq!(run(&[&["SLOWLOG", "LEN"]], &state, &mut client).await, b":0\r\n");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_slowlog_skips_exec_and_auth() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    run(&[&["CONFIG", "SET", "slowlog-log-slower-than", "0"], &["SLOWLOG", "RESET"]], &state, &mut client).await;
+    run(&[&["AUTH", "secret"], &["MULTI"], &["SET", "k", "v"], &["EXEC"]], &state, &mut client).await;
+
+    let logged = state.slowlog.lock().unwrap().entries.iter().map(|entry| entry.args[0].clone()).collect::<Vec<_>>();
+    assert_eq!(logged, vec!["SET", "MULTI", "SLOWLOG"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_slowlog_trims_long_commands() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    run(&[&["CONFIG", "SET", "slowlog-log-slower-than", "0"], &["SLOWLOG", "RESET"]], &state, &mut client).await;
+    let long_value = "x".repeat(200);
+    let fields: Vec<String> = (0..40).map(|i| i.to_string()).collect();
+    let mut rpush = vec!["RPUSH", "list", long_value.as_str()];
+    rpush.extend(fields.iter().map(String::as_str));
+    run(&[&rpush], &state, &mut client).await;
+
+    let slowlog = state.slowlog.lock().unwrap();
+    let args = &slowlog.entries[0].args;
+    assert_eq!(args.len(), 32);
+    assert_eq!(args[2], format!("{}... (72 more bytes)", "x".repeat(128)));
+    assert_eq!(args[31], "... (12 more arguments)");
+}
+
+#[test]
+fn test_slowlog_get_len_and_reset() {
+    let state = ServerState::default();
+    for i in 0..12 {
+        let command = vec![format!("CMD{}", i)];
+        state.slowlog.lock().unwrap().push(&command, Duration::from_millis(20), String::new(), String::new(), 128);
+    }
+    let get = |args: &[&str]| String::from_utf8(process_slowlog(&parts(args), &state.slowlog).unwrap()).unwrap();
+
+    assert_eq!(get(&["SLOWLOG", "LEN"]), ":12\r\n");
+    assert!(get(&["SLOWLOG", "GET"]).starts_with("*10\r\n*6\r\n:11\r\n"));
+    assert!(get(&["SLOWLOG", "get", "2"]).starts_with("*2\r\n"));
+    assert!(get(&["SLOWLOG", "GET", "-1"]).starts_with("*12\r\n"));
+    assert_eq!(get(&["SLOWLOG", "GET", "-2"]), "-ERR count should be greater than or equal to -1\r\n");
+    assert_eq!(get(&["SLOWLOG", "GET", "many"]), "-ERR value is not an integer or out of range\r\n");
+
+    assert_eq!(get(&["SLOWLOG", "RESET"]), "+OK\r\n");
+    assert_eq!(get(&["SLOWLOG", "GET"]), "*0\r\n");
+    assert_eq!(get(&["SLOWLOG", "LEN", "x"]), "-ERR wrong number of arguments for 'slowlog|len' command\r\n");
+    assert_eq!(get(&["SLOWLOG", "FROB"]), "-ERR unknown subcommand 'FROB'. Try SLOWLOG HELP.\r\n");
+}
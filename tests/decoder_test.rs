@@ -1,40 +1,44 @@
-use redis_cache::utils::decoder::decode_resp;
+use redis_cache::utils::decoder::{decode_resp, frame_len, split_frame};
+
+fn args(parts: &[&str]) -> Vec<Vec<u8>> {
+    parts.iter().map(|part| part.as_bytes().to_vec()).collect()
+}
 
 // ==================== Basic RESP Decoding ====================
 
 #[test]
 fn test_decode_resp_ping() {
     let raw = "*1\r\n$4\r\nPING\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["PING"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["PING"]));
 }
 
 #[test]
 fn test_decode_resp_echo() {
     let raw = "*2\r\n$4\r\nECHO\r\n$5\r\nhello\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["ECHO", "hello"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["ECHO", "hello"]));
 }
 
 #[test]
 fn test_decode_resp_set() {
     let raw = "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["SET", "key", "value"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["SET", "key", "value"]));
 }
 
 #[test]
 fn test_decode_resp_set_with_expiry() {
     let raw = "*5\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n$2\r\nEX\r\n$2\r\n10\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["SET", "key", "value", "EX", "10"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["SET", "key", "value", "EX", "10"]));
 }
 
 #[test]
 fn test_decode_resp_get() {
     let raw = "*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["GET", "key"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["GET", "key"]));
 }
 
 // ==================== List Commands Decoding ====================
@@ -42,57 +46,57 @@ fn test_decode_resp_get() {
 #[test]
 fn test_decode_resp_rpush_single() {
     let raw = "*3\r\n$5\r\nRPUSH\r\n$6\r\nmylist\r\n$5\r\nvalue\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["RPUSH", "mylist", "value"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["RPUSH", "mylist", "value"]));
 }
 
 #[test]
 fn test_decode_resp_rpush_multiple() {
     let raw = "*4\r\n$5\r\nRPUSH\r\n$6\r\nmylist\r\n$2\r\nv1\r\n$2\r\nv2\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["RPUSH", "mylist", "v1", "v2"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["RPUSH", "mylist", "v1", "v2"]));
 }
 
 #[test]
 fn test_decode_resp_lpush() {
     let raw = "*3\r\n$5\r\nLPUSH\r\n$6\r\nmylist\r\n$5\r\nvalue\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["LPUSH", "mylist", "value"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["LPUSH", "mylist", "value"]));
 }
 
 #[test]
 fn test_decode_resp_lrange() {
     let raw = "*4\r\n$6\r\nLRANGE\r\n$6\r\nmylist\r\n$1\r\n0\r\n$2\r\n-1\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["LRANGE", "mylist", "0", "-1"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["LRANGE", "mylist", "0", "-1"]));
 }
 
 #[test]
 fn test_decode_resp_lpop() {
     let raw = "*2\r\n$4\r\nLPOP\r\n$6\r\nmylist\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["LPOP", "mylist"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["LPOP", "mylist"]));
 }
 
 #[test]
 fn test_decode_resp_lpop_with_count() {
     let raw = "*3\r\n$4\r\nLPOP\r\n$6\r\nmylist\r\n$1\r\n3\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["LPOP", "mylist", "3"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["LPOP", "mylist", "3"]));
 }
 
 #[test]
 fn test_decode_resp_blpop() {
     let raw = "*3\r\n$5\r\nBLPOP\r\n$6\r\nmylist\r\n$1\r\n0\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["BLPOP", "mylist", "0"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["BLPOP", "mylist", "0"]));
 }
 
 #[test]
 fn test_decode_resp_blpop_with_timeout() {
     let raw = "*3\r\n$5\r\nBLPOP\r\n$6\r\nmylist\r\n$3\r\n0.1\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["BLPOP", "mylist", "0.1"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["BLPOP", "mylist", "0.1"]));
 }
 
 // ==================== Stream Commands Decoding ====================
@@ -100,64 +104,64 @@ fn test_decode_resp_blpop_with_timeout() {
 #[test]
 fn test_decode_resp_xadd() {
     let raw = "*6\r\n$4\r\nXADD\r\n$10\r\nstream_key\r\n$3\r\n0-1\r\n$11\r\ntemperature\r\n$2\r\n96\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XADD", "stream_key", "0-1", "temperature", "96"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XADD", "stream_key", "0-1", "temperature", "96"]));
 }
 
 #[test]
 fn test_decode_resp_xadd_with_star() {
     let raw = "*5\r\n$4\r\nXADD\r\n$8\r\nmystream\r\n$1\r\n*\r\n$3\r\nfoo\r\n$3\r\nbar\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XADD", "mystream", "*", "foo", "bar"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XADD", "mystream", "*", "foo", "bar"]));
 }
 
 #[test]
 fn test_decode_resp_xadd_partial_wildcard() {
     let raw = "*5\r\n$4\r\nXADD\r\n$8\r\nmystream\r\n$3\r\n0-*\r\n$3\r\nfoo\r\n$3\r\nbar\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XADD", "mystream", "0-*", "foo", "bar"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XADD", "mystream", "0-*", "foo", "bar"]));
 }
 
 #[test]
 fn test_decode_resp_xrange() {
     let raw = "*4\r\n$6\r\nXRANGE\r\n$8\r\nmystream\r\n$1\r\n-\r\n$1\r\n+\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XRANGE", "mystream", "-", "+"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XRANGE", "mystream", "-", "+"]));
 }
 
 #[test]
 fn test_decode_resp_xrange_specific() {
     let raw = "*4\r\n$6\r\nXRANGE\r\n$8\r\nmystream\r\n$3\r\n0-1\r\n$3\r\n0-3\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XRANGE", "mystream", "0-1", "0-3"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XRANGE", "mystream", "0-1", "0-3"]));
 }
 
 #[test]
 fn test_decode_resp_xread_simple() {
     let raw = "*4\r\n$5\r\nXREAD\r\n$7\r\nstreams\r\n$8\r\nmystream\r\n$3\r\n0-0\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XREAD", "streams", "mystream", "0-0"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XREAD", "streams", "mystream", "0-0"]));
 }
 
 #[test]
 fn test_decode_resp_xread_with_block() {
     let raw = "*6\r\n$5\r\nXREAD\r\n$5\r\nblock\r\n$4\r\n1000\r\n$7\r\nstreams\r\n$8\r\nmystream\r\n$3\r\n0-0\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XREAD", "block", "1000", "streams", "mystream", "0-0"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XREAD", "block", "1000", "streams", "mystream", "0-0"]));
 }
 
 #[test]
 fn test_decode_resp_xread_with_dollar() {
     let raw = "*6\r\n$5\r\nXREAD\r\n$5\r\nblock\r\n$1\r\n0\r\n$7\r\nstreams\r\n$4\r\npear\r\n$1\r\n$\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XREAD", "block", "0", "streams", "pear", "$"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XREAD", "block", "0", "streams", "pear", "$"]));
 }
 
 #[test]
 fn test_decode_resp_xread_multiple_streams() {
     let raw = "*6\r\n$5\r\nXREAD\r\n$7\r\nstreams\r\n$5\r\napple\r\n$9\r\nblueberry\r\n$3\r\n0-0\r\n$3\r\n0-1\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["XREAD", "streams", "apple", "blueberry", "0-0", "0-1"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["XREAD", "streams", "apple", "blueberry", "0-0", "0-1"]));
 }
 
 // ==================== Other Commands Decoding ====================
@@ -165,15 +169,15 @@ fn test_decode_resp_xread_multiple_streams() {
 #[test]
 fn test_decode_resp_type() {
     let raw = "*2\r\n$4\r\nTYPE\r\n$5\r\nmykey\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["TYPE", "mykey"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["TYPE", "mykey"]));
 }
 
 #[test]
 fn test_decode_resp_llen() {
     let raw = "*2\r\n$4\r\nLLEN\r\n$6\r\nmylist\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["LLEN", "mylist"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["LLEN", "mylist"]));
 }
 
 // ==================== Edge Cases ====================
@@ -181,42 +185,190 @@ fn test_decode_resp_llen() {
 #[test]
 fn test_decode_resp_empty_value() {
     let raw = "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$0\r\n\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["SET", "key", ""]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["SET", "key", ""]));
 }
 
 #[test]
 fn test_decode_resp_value_with_spaces() {
     let raw = "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$11\r\nhello world\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["SET", "key", "hello world"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["SET", "key", "hello world"]));
 }
 
 #[test]
 fn test_decode_resp_numeric_values() {
     let raw = "*3\r\n$3\r\nSET\r\n$7\r\ncounter\r\n$5\r\n12345\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["SET", "counter", "12345"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["SET", "counter", "12345"]));
 }
 
 #[test]
 fn test_decode_resp_long_command() {
     let raw = "*7\r\n$5\r\nRPUSH\r\n$6\r\nmylist\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n$1\r\nd\r\n$1\r\ne\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["RPUSH", "mylist", "a", "b", "c", "d", "e"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["RPUSH", "mylist", "a", "b", "c", "d", "e"]));
 }
 
 #[test]
 fn test_decode_resp_simple_string() {
     // Simple string format (starts with +)
     let raw = "+PING\r\n";
-    let result = decode_resp(raw);
-    assert_eq!(result, vec!["PING"]);
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["PING"]));
 }
 
 #[test]
 fn test_decode_resp_case_preserved() {
     let raw = "*2\r\n$4\r\necho\r\n$5\r\nHELLO\r\n";
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["echo", "HELLO"]));
+}
+
+#[test]
+fn test_decode_resp_value_containing_crlf() {
+    let raw = "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$6\r\nab\r\ncd\r\n";
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["SET", "key", "ab\r\ncd"]));
+}
+
+#[test]
+fn test_decode_resp_value_that_looks_like_a_header() {
+    // The value itself starts with '$', which must not be read as another bulk string
+    let raw = "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$7\r\n$5\r\nabc\r\n";
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["SET", "key", "$5\r\nabc"]));
+}
+
+#[test]
+fn test_decode_resp_multibyte_value() {
+    let raw = "*2\r\n$4\r\nECHO\r\n$6\r\nh\u{e9}llo\r\n";
+    let result = decode_resp(raw.as_bytes());
+    assert_eq!(result, args(&["ECHO", "h\u{e9}llo"]));
+}
+
+#[test]
+fn test_decode_resp_non_utf8_value() {
+    let raw = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$3\r\n\xff\x00\xfe\r\n";
+    let result = decode_resp(raw);
+    assert_eq!(result, vec![b"SET".to_vec(), b"key".to_vec(), vec![0xff, 0x00, 0xfe]]);
+}
+
+#[test]
+fn test_decode_resp_non_utf8_does_not_shift_later_arguments() {
+    let raw = b"*4\r\n$4\r\nMSET\r\n$1\r\na\r\n$2\r\n\xc3\x28\r\n$1\r\nb\r\n";
     let result = decode_resp(raw);
-    assert_eq!(result, vec!["echo", "HELLO"]);
+    assert_eq!(result[3], b"b");
+}
+
+// ==================== Inline Commands ====================
+
+#[test]
+fn test_decode_inline_ping() {
+    let result = decode_resp(b"PING\r\n");
+    assert_eq!(result, args(&["PING"]));
+}
+
+#[test]
+fn test_decode_inline_echo() {
+    let result = decode_resp(b"ECHO hello\r\n");
+    assert_eq!(result, args(&["ECHO", "hello"]));
+}
+
+#[test]
+fn test_decode_inline_get() {
+    let result = decode_resp(b"GET key\r\n");
+    assert_eq!(result, args(&["GET", "key"]));
+}
+
+#[test]
+fn test_decode_inline_mixed_with_resp_pipeline() {
+    let raw = b"SET key value\r\n*2\r\n$3\r\nGET\r\n$3\r\nkey\r\nPING\r\n";
+    let mut commands = Vec::new();
+    let mut rest: &[u8] = raw;
+    while let Some((frame, remainder)) = split_frame(rest) {
+        commands.push(decode_resp(frame));
+        rest = remainder;
+    }
+    assert!(rest.is_empty());
+    assert_eq!(commands, vec![args(&["SET", "key", "value"]), args(&["GET", "key"]), args(&["PING"])]);
+}
+
+#[test]
+fn test_decode_inline_extra_whitespace_and_bare_newline() {
+    let result = decode_resp(b"  SET   key\tvalue  \n");
+    assert_eq!(result, args(&["SET", "key", "value"]));
+}
+
+#[test]
+fn test_decode_inline_quoted_arguments() {
+    let result = decode_resp(b"SET key \"hello world\" 'it\\'s'\r\n");
+    assert_eq!(result, args(&["SET", "key", "hello world", "it's"]));
+
+    let escaped = decode_resp(b"ECHO \"a\\nb\\x41\\\"\"\r\n");
+    assert_eq!(escaped, args(&["ECHO", "a\nbA\""]));
+}
+
+#[test]
+fn test_decode_inline_rejects_unbalanced_quotes() {
+    assert!(decode_resp(b"ECHO \"hello\r\n").is_empty());
+    assert!(decode_resp(b"ECHO \"hello\"world\r\n").is_empty());
+}
+
+#[test]
+fn test_decode_inline_empty_line() {
+    assert!(decode_resp(b"\r\n").is_empty());
+    assert!(decode_resp(b"").is_empty());
+}
+
+// ==================== Frame Length ====================
+
+#[test]
+fn test_frame_len_complete_command() {
+    let raw = b"*2\r\n$4\r\nECHO\r\n$5\r\nhello\r\n";
+    assert_eq!(frame_len(raw), Some(raw.len()));
+    assert_eq!(frame_len(b"PING\r\n"), Some(6));
+}
+
+#[test]
+fn test_frame_len_incomplete_command() {
+    let raw = b"*2\r\n$4\r\nECHO\r\n$5\r\nhello\r\n";
+    for cut in 0..raw.len() {
+        assert_eq!(frame_len(&raw[..cut]), None, "complete after {} bytes", cut);
+    }
+    assert_eq!(frame_len(b"PING"), None);
+}
+
+#[test]
+fn test_frame_len_stops_at_end_of_first_command() {
+    let raw = b"*1\r\n$4\r\nPING\r\n*1\r\n$4\r\nPI";
+    assert_eq!(frame_len(raw), Some(14));
+}
+
+#[test]
+fn test_frame_len_value_across_many_reads() {
+    let value = "x".repeat(10 * 1024);
+    let raw = format!("*3\r\n$3\r\nSET\r\n$3\r\nbig\r\n${}\r\n{}\r\n", value.len(), value).into_bytes();
+
+    // Arrives 4 KB at a time, the way the server reads it
+    let mut received = Vec::new();
+    for chunk in raw.chunks(4096) {
+        assert_eq!(frame_len(&received), None);
+        received.extend_from_slice(chunk);
+    }
+    assert_eq!(frame_len(&received), Some(raw.len()));
+    assert_eq!(decode_resp(&received), args(&["SET", "big", &value]));
+}
+
+#[test]
+fn test_frame_len_value_containing_crlf() {
+    // The \r\n inside the value must not look like the end of the command
+    let raw = b"*2\r\n$4\r\nECHO\r\n$4\r\na\r\nb\r\n";
+    assert_eq!(frame_len(&raw[..16]), None);
+    assert_eq!(frame_len(raw), Some(raw.len()));
+}
+
+#[test]
+fn test_frame_len_malformed_header_is_its_own_frame() {
+    assert_eq!(frame_len(b"*abc\r\n$4\r\nPING\r\n"), Some(6));
 }
@@ -171,6 +171,68 @@ fn test_encode_null_array() {
     assert_eq!(result, b"*-1\r\n");
 }
 
+// ==================== Error Encoding ====================
+
+#[test]
+fn test_encode_error_string() {
+    assert_eq!(encode_error_string("ERR syntax error"), b"-ERR syntax error\r\n");
+    assert_eq!(
+        encode_error_string("WRONGTYPE Operation against a key holding the wrong kind of value"),
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+    );
+}
+
+#[test]
+fn test_encode_bulk_bytes() {
+    assert_eq!(encode_bulk_bytes(b"h\xc3"), b"$2\r\nh\xc3\r\n");
+    assert_eq!(encode_bulk_bytes(b""), b"$0\r\n\r\n");
+}
+
+// ==================== Map Encoding ====================
+
+#[test]
+fn test_encode_raw_map() {
+    let pairs = vec![
+        (encode_bulk_string("proto"), encode_integer(3)),
+        (encode_bulk_string("mode"), encode_bulk_string("standalone")),
+    ];
+    let result = encode_raw_map(pairs);
+    assert_eq!(result, b"%2\r\n$5\r\nproto\r\n:3\r\n$4\r\nmode\r\n$10\r\nstandalone\r\n");
+}
+
+#[test]
+fn test_encode_empty_map() {
+    assert_eq!(encode_raw_map(vec![]), b"%0\r\n");
+}
+
+#[test]
+fn test_encode_map_of_strings() {
+    let pairs = vec![("field".to_string(), "value".to_string())];
+    assert_eq!(encode_map(&pairs), b"%1\r\n$5\r\nfield\r\n$5\r\nvalue\r\n");
+}
+
+// ==================== Other RESP3 Types ====================
+
+#[test]
+fn test_encode_bool() {
+    assert_eq!(encode_bool(true), b"#t\r\n");
+    assert_eq!(encode_bool(false), b"#f\r\n");
+}
+
+#[test]
+fn test_encode_double() {
+    assert_eq!(encode_double(1.5), b",1.5\r\n");
+    assert_eq!(encode_double(-3.0), b",-3\r\n");
+    assert_eq!(encode_double(f64::INFINITY), b",inf\r\n");
+    assert_eq!(encode_double(f64::NEG_INFINITY), b",-inf\r\n");
+    assert_eq!(encode_double(f64::NAN), b",nan\r\n");
+}
+
+#[test]
+fn test_encode_null() {
+    assert_eq!(encode_null(), b"_\r\n");
+}
+
 // ==================== Integration Tests ====================
 
 #[test]
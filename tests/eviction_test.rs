@@ -0,0 +1,126 @@
+use std::thread::sleep;
+use std::time::Duration;
+
+use redis_cache::commands::{process_get, process_hget, process_hset, process_set};
+use redis_cache::models::{new_databases, ClientState, ServerState};
+use redis_cache::parser::parse_resp;
+use redis_cache::utils::eviction::evict_for_write;
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn make_resp(parts: &[&str]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", parts.len());
+    for part in parts {
+        result.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+    }
+    result.into_bytes()
+}
+
+// Access times are kept in milliseconds, so keys written back to back need a
+// gap to have a well-defined order
+fn pause() {
+    sleep(Duration::from_millis(5));
+}
+
+// ==================== evict_for_write Tests ====================
+
+#[test]
+fn test_evict_for_write_without_cap_keeps_everything() {
+    let databases = new_databases(1);
+    for key in ["a", "b", "c"] {
+        process_set(&parts(&["SET", key, "v"]), &databases[0]).unwrap();
+    }
+    assert!(evict_for_write(&databases, 0));
+    assert_eq!(databases[0].len(), 3);
+}
+
+#[test]
+fn test_evict_for_write_drops_least_recently_used() {
+    let databases = new_databases(1);
+    let kv_store = &databases[0];
+    for key in ["a", "b", "c"] {
+        process_set(&parts(&["SET", key, "v"]), kv_store).unwrap();
+        pause();
+    }
+    // Reading "a" makes "b" the oldest
+    process_get(&parts(&["GET", "a"]), kv_store).unwrap();
+
+    assert!(evict_for_write(&databases, 3));
+    assert_eq!(kv_store.len(), 2);
+    assert!(kv_store.contains_key("a"));
+    assert!(!kv_store.contains_key("b"));
+    assert!(kv_store.contains_key("c"));
+}
+
+#[test]
+fn test_evict_for_write_counts_hash_reads_as_access() {
+    let databases = new_databases(1);
+    let kv_store = &databases[0];
+    process_hset(&parts(&["HSET", "h", "field", "v"]), kv_store).unwrap();
+    pause();
+    process_set(&parts(&["SET", "a", "v"]), kv_store).unwrap();
+    pause();
+    // Reading the hash makes "a" the oldest
+    process_hget(&parts(&["HGET", "h", "field"]), kv_store).unwrap();
+
+    assert!(evict_for_write(&databases, 2));
+    assert!(kv_store.contains_key("h"));
+    assert!(!kv_store.contains_key("a"));
+}
+
+#[test]
+fn test_evict_for_write_looks_across_databases() {
+    let databases = new_databases(2);
+    process_set(&parts(&["SET", "old", "v"]), &databases[1]).unwrap();
+    pause();
+    process_set(&parts(&["SET", "new", "v"]), &databases[0]).unwrap();
+
+    assert!(evict_for_write(&databases, 2));
+    assert!(databases[0].contains_key("new"));
+    assert!(databases[1].is_empty());
+}
+
+#[test]
+fn test_evict_for_write_under_cap_is_a_no_op() {
+    let databases = new_databases(1);
+    process_set(&parts(&["SET", "a", "v"]), &databases[0]).unwrap();
+    assert!(evict_for_write(&databases, 2));
+    assert!(databases[0].contains_key("a"));
+}
+
+// ==================== Write Command Tests ====================
+
+#[tokio::test]
+async fn test_writes_stay_under_maxmemory_keys() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    state.server_config.lock().unwrap().maxmemory_keys = 2;
+
+    for (i, key) in ["first", "second", "third"].iter().enumerate() {
+        let buffer = make_resp(&["SET", key, &i.to_string()]);
+        let len = buffer.len();
+        assert_eq!(parse_resp(&buffer, len, &state, &mut client).await, b"+OK\r\n");
+        pause();
+    }
+
+    let kv_store = &state.databases[0];
+    assert_eq!(kv_store.len(), 2);
+    assert!(!kv_store.contains_key("first"));
+    assert!(kv_store.contains_key("second"));
+    assert!(kv_store.contains_key("third"));
+}
+
+#[tokio::test]
+async fn test_reads_are_not_limited_by_maxmemory_keys() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    process_set(&parts(&["SET", "a", "v"]), &state.databases[0]).unwrap();
+    state.server_config.lock().unwrap().maxmemory_keys = 1;
+
+    let buffer = make_resp(&["GET", "a"]);
+    let len = buffer.len();
+    assert_eq!(parse_resp(&buffer, len, &state, &mut client).await, b"$1\r\nv\r\n");
+    assert!(state.databases[0].contains_key("a"));
+}
@@ -0,0 +1,111 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use redis_cache::commands::{process_dbsize, process_set};
+use redis_cache::models::{new_databases, KvStore, RedisData, RedisValue, ServerConfig, Store};
+use redis_cache::utils::expiry::{expiry_interval, spawn_active_expiry, spawn_configured_expiry};
+
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
+}
+
+fn insert_string(kv_store: &KvStore, key: &str, expires_at: Option<Instant>) {
+    kv_store.insert(
+        key.to_string(),
+        RedisValue::new(RedisData::String("value".into()), expires_at),
+    );
+}
+
+// ==================== Sweep Tests ====================
+
+#[test]
+fn test_remove_expired_only_drops_past_deadlines() {
+    let kv_store = new_kv_store();
+    let now = Instant::now();
+    insert_string(&kv_store, "expired1", Some(now - Duration::from_millis(10)));
+    insert_string(&kv_store, "expired2", Some(now - Duration::from_secs(5)));
+    insert_string(&kv_store, "future", Some(now + Duration::from_secs(60)));
+    insert_string(&kv_store, "forever", None);
+
+    assert_eq!(kv_store.remove_expired(now), 2);
+    assert!(!kv_store.contains_key("expired1"));
+    assert!(!kv_store.contains_key("expired2"));
+    assert!(kv_store.contains_key("future"));
+    assert!(kv_store.contains_key("forever"));
+}
+
+#[test]
+fn test_remove_expired_on_empty_store() {
+    let kv_store = new_kv_store();
+    assert_eq!(kv_store.remove_expired(Instant::now()), 0);
+}
+
+#[test]
+fn test_expiry_interval_from_hz() {
+    assert_eq!(expiry_interval(10), Duration::from_millis(100));
+    assert_eq!(expiry_interval(100), Duration::from_millis(10));
+    // Out of range values are clamped rather than dividing by zero
+    assert_eq!(expiry_interval(0), Duration::from_millis(1000));
+    assert_eq!(expiry_interval(10_000), Duration::from_millis(2));
+}
+
+// ==================== Active Expiry Task Tests ====================
+
+#[tokio::test]
+async fn test_active_expiry_evicts_unread_keys() {
+    let databases = new_databases(2);
+    insert_string(&databases[0], "short", Some(Instant::now() + Duration::from_millis(30)));
+    insert_string(&databases[0], "forever", None);
+    insert_string(&databases[1], "other_db", Some(Instant::now() + Duration::from_millis(30)));
+
+    let task = spawn_active_expiry(Arc::clone(&databases), Duration::from_millis(10));
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    task.abort();
+
+    // Never read, but still gone, in every database
+    assert!(!databases[0].contains_key("short"));
+    assert!(databases[0].contains_key("forever"));
+    assert!(!databases[1].contains_key("other_db"));
+}
+
+#[tokio::test]
+async fn test_active_expiry_clears_many_short_lived_keys() {
+    let databases = new_databases(1);
+    let kv_store = &databases[0];
+    for i in 0..1000 {
+        let key = format!("key:{}", i);
+        process_set(&["SET", &key, "v", "PX", "50"].map(String::from), kv_store).unwrap();
+    }
+
+    let task = spawn_active_expiry(Arc::clone(&databases), Duration::from_millis(100));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    task.abort();
+
+    let dbsize = process_dbsize(&["DBSIZE".to_string()], kv_store).unwrap();
+    assert_eq!(dbsize, b":0\r\n");
+    // DBSIZE skips expired keys on its own; the store being empty shows the sweep ran
+    assert!(kv_store.is_empty());
+}
+
+#[tokio::test]
+async fn test_configured_expiry_follows_hz() {
+    let databases = new_databases(1);
+    let server_config = Arc::new(Mutex::new(ServerConfig::default()));
+    server_config.lock().unwrap().hz = 1;
+    let task = spawn_configured_expiry(Arc::clone(&databases), Arc::clone(&server_config));
+
+    // The first sweep waits out the 1s interval, so the key outlives its deadline for now
+    insert_string(&databases[0], "short", Some(Instant::now() + Duration::from_millis(10)));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(databases[0].contains_key("short"));
+
+    // Picked up once the current sleep ends
+    server_config.lock().unwrap().hz = 100;
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    insert_string(&databases[0], "shorter", Some(Instant::now() + Duration::from_millis(10)));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    task.abort();
+
+    assert!(!databases[0].contains_key("short"));
+    assert!(!databases[0].contains_key("shorter"));
+}
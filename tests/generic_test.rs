@@ -1,23 +1,45 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use std::time::Instant;
+use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use redis_cache::models::{RedisData, RedisValue};
-use redis_cache::commands::{process_ping, process_echo, process_type};
+use redis_cache::models::{
+    new_databases, Acl, ClientState, RedisData, RedisValue, ServerState, Stream, KvStore, Store
+};
+use redis_cache::commands::{
+    process_ping, process_echo, process_type, process_keys, process_del, process_exists, process_scan,
+    process_expire, process_pexpire, process_expireat, process_pexpireat, process_ttl, process_pttl,
+    process_persist, process_rename, process_renamenx, process_copy, process_object,
+    process_unlink, process_touch, process_randomkey, process_get, process_auth, process_lolwut
+};
+use redis_cache::parser::parse_pipeline;
 
-fn new_kv_store() -> Arc<Mutex<HashMap<String, RedisValue>>> {
-    Arc::new(Mutex::new(HashMap::new()))
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
 }
 
 fn parts(args: &[&str]) -> Vec<String> {
     args.iter().map(|s| s.to_string()).collect()
 }
 
+fn acl_with_password(requirepass: Option<&str>) -> Acl {
+    let acl = Acl::new();
+    acl.set_requirepass(requirepass.map(String::from));
+    acl
+}
+
+fn make_resp(parts: &[&str]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", parts.len());
+    for part in parts {
+        result.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+    }
+    result.into_bytes()
+}
+
 // ==================== PING Tests ====================
 
 #[test]
 fn test_ping_returns_pong() {
-    let result = process_ping();
+    let result = process_ping(&parts(&["PING"]), &ClientState::new());
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), b"+PONG\r\n");
 }
@@ -25,12 +47,19 @@ fn test_ping_returns_pong() {
 #[test]
 fn test_ping_multiple_calls() {
     for _ in 0..100 {
-        let result = process_ping();
+        let result = process_ping(&parts(&["PING"]), &ClientState::new());
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), b"+PONG\r\n");
     }
 }
 
+#[test]
+fn test_ping_with_message() {
+    let result = process_ping(&parts(&["PING", "hello"]), &ClientState::new()).unwrap();
+    assert_eq!(result, b"$5\r\nhello\r\n");
+    assert!(process_ping(&parts(&["PING", "a", "b"]), &ClientState::new()).is_err());
+}
+
 // ==================== ECHO Tests ====================
 
 #[test]
@@ -89,16 +118,67 @@ fn test_echo_only_uses_first_argument() {
     assert_eq!(result.unwrap(), b"$5\r\nfirst\r\n");
 }
 
+// ==================== QUIT Tests ====================
+
+#[tokio::test]
+async fn test_quit_replies_then_stops_the_pipeline() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let buffer: Vec<u8> = [make_resp(&["PING"]), make_resp(&["QUIT"]), make_resp(&["SET", "k", "v"])].concat();
+
+    let (replies, _) = parse_pipeline(&buffer, &state, &mut client).await;
+    assert_eq!(replies, b"+PONG\r\n+OK\r\n");
+    assert!(client.close_after_reply);
+    assert!(!state.databases[0].contains_key("k"));
+}
+
+#[tokio::test]
+async fn test_quit_inside_multi_is_not_queued() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let buffer: Vec<u8> = [make_resp(&["MULTI"]), make_resp(&["QUIT"])].concat();
+
+    let (replies, _) = parse_pipeline(&buffer, &state, &mut client).await;
+    assert_eq!(replies, b"+OK\r\n+OK\r\n");
+    assert!(client.close_after_reply);
+}
+
+// ==================== LOLWUT Tests ====================
+
+#[test]
+fn test_lolwut_draws_schotter() {
+    let result = String::from_utf8(process_lolwut(&parts(&["LOLWUT"])).unwrap()).unwrap();
+    let (_, art) = result.split_once("\r\n").unwrap();
+    let lines: Vec<&str> = art.strip_suffix("\r\n").unwrap().lines().collect();
+    // 12 rows of squares, a blank line, then the caption
+    assert_eq!(lines.len(), 14);
+    // The top row is always in order
+    assert_eq!(lines[0], " [] ".repeat(8).trim_end());
+    assert!(lines[..12].iter().all(|line| line.len() <= 32));
+    assert_eq!(lines[13], "Georg Nees - schotter, plotter on paper, 1968. Redis ver. 7.2.0");
+}
+
+#[test]
+fn test_lolwut_other_versions() {
+    let result = process_lolwut(&parts(&["LOLWUT", "version", "6"])).unwrap();
+    assert_eq!(result, b"$17\r\nRedis ver. 7.2.0\n\r\n");
+
+    let result = process_lolwut(&parts(&["LOLWUT", "VERSION", "five"])).unwrap();
+    assert_eq!(result, b"-ERR value is not an integer or out of range\r\n");
+    let result = process_lolwut(&parts(&["LOLWUT", "5"])).unwrap();
+    assert_eq!(result, b"-ERR syntax error\r\n");
+}
+
 // ==================== TYPE Tests ====================
 
 #[test]
 fn test_type_string() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mykey".to_string(),
-            RedisValue::new(RedisData::String("value".to_string()), None),
+            RedisValue::new(RedisData::String("value".into()), None),
         );
     }
 
@@ -112,7 +192,7 @@ fn test_type_string() {
 fn test_type_list() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(RedisData::List(vec!["item".to_string()]), None),
@@ -129,10 +209,10 @@ fn test_type_list() {
 fn test_type_stream() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mystream".to_string(),
-            RedisValue::new(RedisData::Stream(vec![]), None),
+            RedisValue::new(RedisData::Stream(Stream::new()), None),
         );
     }
 
@@ -142,6 +222,46 @@ fn test_type_stream() {
     assert_eq!(result.unwrap(), b"+stream\r\n");
 }
 
+#[test]
+fn test_type_set() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        let set: HashSet<String> = ["member".to_string()].into_iter().collect();
+        map.insert(
+            "myset".to_string(),
+            RedisValue::new(RedisData::Set(set), None),
+        );
+    }
+
+    let p = parts(&["TYPE", "myset"]);
+    let result = process_type(&p, &kv_store);
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), b"+set\r\n");
+}
+
+#[test]
+fn test_type_zset() {
+    let kv_store = new_kv_store();
+    kv_store.insert(
+        "myzset".to_string(),
+        RedisValue::new(RedisData::ZSet(vec![(1.0, "member".to_string())]), None),
+    );
+
+    let result = process_type(&parts(&["TYPE", "myzset"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+zset\r\n");
+}
+
+#[test]
+fn test_type_hash() {
+    let kv_store = new_kv_store();
+    let fields: HashMap<String, String> = [("field".to_string(), "value".to_string())].into_iter().collect();
+    kv_store.insert("myhash".to_string(), RedisValue::new(RedisData::Hash(fields), None));
+
+    let result = process_type(&parts(&["TYPE", "myhash"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+hash\r\n");
+}
+
 #[test]
 fn test_type_nonexistent_key() {
     let kv_store = new_kv_store();
@@ -155,11 +275,11 @@ fn test_type_nonexistent_key() {
 fn test_type_expired_key() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         let expired_time = Instant::now() - std::time::Duration::from_secs(10);
         map.insert(
             "expired".to_string(),
-            RedisValue::new(RedisData::String("value".to_string()), Some(expired_time)),
+            RedisValue::new(RedisData::String("value".into()), Some(expired_time)),
         );
     }
 
@@ -169,7 +289,7 @@ fn test_type_expired_key() {
     assert_eq!(result.unwrap(), b"+none\r\n");
 
     // Verify key was removed
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     assert!(map.get("expired").is_none());
 }
 
@@ -181,6 +301,853 @@ fn test_type_missing_key_argument() {
     assert!(result.is_err());
 }
 
+// ==================== KEYS Tests ====================
+
+fn insert_string(kv_store: &KvStore, key: &str, expires_at: Option<Instant>) {
+    kv_store.insert(
+        key.to_string(),
+        RedisValue::new(RedisData::String("value".into()), expires_at),
+    );
+}
+
+// Decodes a flat array of bulk strings, sorted since KEYS has no defined order
+fn sorted_keys(bytes: &[u8]) -> Vec<String> {
+    let mut keys: Vec<String> = String::from_utf8_lossy(bytes)
+        .split("\r\n")
+        .skip(1)
+        .filter(|line| !line.starts_with('$') && !line.is_empty())
+        .map(|line| line.to_string())
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn test_keys_star_returns_everything() {
+    let kv_store = new_kv_store();
+    for key in ["one", "two", "three"] {
+        insert_string(&kv_store, key, None);
+    }
+
+    let result = process_keys(&parts(&["KEYS", "*"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"*3\r\n"));
+    assert_eq!(sorted_keys(&result), vec!["one", "three", "two"]);
+}
+
+#[test]
+fn test_keys_glob_patterns() {
+    let kv_store = new_kv_store();
+    for key in ["hello", "hallo", "hxllo", "hllo", "heeeello", "user:1", "user:22"] {
+        insert_string(&kv_store, key, None);
+    }
+
+    let question = process_keys(&parts(&["KEYS", "h?llo"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&question), vec!["hallo", "hello", "hxllo"]);
+    let star = process_keys(&parts(&["KEYS", "h*llo"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&star), vec!["hallo", "heeeello", "hello", "hllo", "hxllo"]);
+    let class = process_keys(&parts(&["KEYS", "h[ae]llo"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&class), vec!["hallo", "hello"]);
+    let negated = process_keys(&parts(&["KEYS", "h[^e]llo"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&negated), vec!["hallo", "hxllo"]);
+    let prefix = process_keys(&parts(&["KEYS", "user:?"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&prefix), vec!["user:1"]);
+}
+
+#[test]
+fn test_keys_skips_expired_keys() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "live", None);
+    insert_string(&kv_store, "dead", Some(Instant::now() - std::time::Duration::from_secs(1)));
+
+    let result = process_keys(&parts(&["KEYS", "*"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&result), vec!["live"]);
+    // Expired keys seen during the walk are deleted
+    assert!(!kv_store.contains_key("dead"));
+}
+
+#[test]
+fn test_keys_literal_and_escaped_patterns() {
+    let kv_store = new_kv_store();
+    for key in ["exact", "exactly", "star*", "starry", "what?", "whats"] {
+        insert_string(&kv_store, key, None);
+    }
+
+    let literal = process_keys(&parts(&["KEYS", "exact"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&literal), vec!["exact"]);
+    let escaped_star = process_keys(&parts(&["KEYS", "star\\*"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&escaped_star), vec!["star*"]);
+    let escaped_question = process_keys(&parts(&["KEYS", "what\\?"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&escaped_question), vec!["what?"]);
+    let range = process_keys(&parts(&["KEYS", "[a-f]xact*"]), &kv_store).unwrap();
+    assert_eq!(sorted_keys(&range), vec!["exact", "exactly"]);
+}
+
+#[test]
+fn test_keys_no_match_and_bad_arity() {
+    let kv_store = new_kv_store();
+    let result = process_keys(&parts(&["KEYS", "*"]), &kv_store).unwrap();
+    assert_eq!(result, b"*0\r\n");
+    assert!(process_keys(&parts(&["KEYS"]), &kv_store).is_err());
+}
+
+// ==================== DEL Tests ====================
+
+#[test]
+fn test_del_existing_keys() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    insert_string(&kv_store, "b", None);
+
+    let result = process_del(&parts(&["DEL", "a", "b"]), &kv_store).unwrap();
+    assert_eq!(result, b":2\r\n");
+    assert!(kv_store.is_empty());
+}
+
+#[test]
+fn test_del_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_del(&parts(&["DEL", "nokey"]), &kv_store).unwrap();
+    assert_eq!(result, b":0\r\n");
+}
+
+#[test]
+fn test_del_expired_key_counts_as_absent() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "dead", Some(Instant::now() - std::time::Duration::from_secs(1)));
+
+    let result = process_del(&parts(&["DEL", "dead"]), &kv_store).unwrap();
+    assert_eq!(result, b":0\r\n");
+    // The stale entry is still cleaned up
+    assert!(!kv_store.contains_key("dead"));
+}
+
+#[test]
+fn test_del_mixed_types() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        let set: HashSet<String> = ["member".to_string()].into_iter().collect();
+        map.insert("str".to_string(), RedisValue::new(RedisData::String("v".into()), None));
+        map.insert("list".to_string(), RedisValue::new(RedisData::List(vec!["item".to_string()]), None));
+        map.insert("stream".to_string(), RedisValue::new(RedisData::Stream(Stream::new()), None));
+        map.insert("set".to_string(), RedisValue::new(RedisData::Set(set), None));
+    }
+
+    let p = parts(&["DEL", "str", "list", "nokey", "stream", "set"]);
+    let result = process_del(&p, &kv_store).unwrap();
+    assert_eq!(result, b":4\r\n");
+    assert!(kv_store.is_empty());
+}
+
+#[test]
+fn test_del_repeated_key_counts_once() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    let result = process_del(&parts(&["DEL", "a", "a"]), &kv_store).unwrap();
+    assert_eq!(result, b":1\r\n");
+}
+
+#[test]
+fn test_del_missing_key_argument() {
+    let kv_store = new_kv_store();
+    assert!(process_del(&parts(&["DEL"]), &kv_store).is_err());
+}
+
+// ==================== EXISTS Tests ====================
+
+#[test]
+fn test_exists_single_key() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    assert_eq!(process_exists(&parts(&["EXISTS", "a"]), &kv_store).unwrap(), b":1\r\n");
+    assert_eq!(process_exists(&parts(&["EXISTS", "nokey"]), &kv_store).unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_exists_expired_key() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "dead", Some(Instant::now() - std::time::Duration::from_secs(1)));
+
+    let result = process_exists(&parts(&["EXISTS", "dead"]), &kv_store).unwrap();
+    assert_eq!(result, b":0\r\n");
+    assert!(!kv_store.contains_key("dead"));
+}
+
+#[test]
+fn test_exists_counts_duplicates() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    let result = process_exists(&parts(&["EXISTS", "a", "a", "a"]), &kv_store).unwrap();
+    assert_eq!(result, b":3\r\n");
+}
+
+#[test]
+fn test_exists_mixed() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    insert_string(&kv_store, "b", None);
+    insert_string(&kv_store, "dead", Some(Instant::now() - std::time::Duration::from_secs(1)));
+
+    let p = parts(&["EXISTS", "a", "nokey", "b", "dead", "a"]);
+    assert_eq!(process_exists(&p, &kv_store).unwrap(), b":3\r\n");
+    assert!(process_exists(&parts(&["EXISTS"]), &kv_store).is_err());
+}
+
+// ==================== TOUCH Tests ====================
+
+#[test]
+fn test_touch_counts_existing_keys() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    insert_string(&kv_store, "b", None);
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+
+    let result = process_touch(&parts(&["TOUCH", "a", "b", "a", "nokey", "dead"]), &kv_store).unwrap();
+    assert_eq!(result, b":3\r\n");
+    assert!(kv_store.contains_key("a"));
+    assert!(!kv_store.contains_key("dead"));
+}
+
+// ==================== UNLINK Tests ====================
+
+#[test]
+fn test_unlink_counts_removed_keys() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    insert_string(&kv_store, "b", None);
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+
+    let result = process_unlink(&parts(&["UNLINK", "a", "b", "a", "nokey", "dead"]), &kv_store).unwrap();
+    assert_eq!(result, b":2\r\n");
+    assert!(kv_store.is_empty());
+}
+
+#[tokio::test]
+async fn test_unlink_inside_runtime_removes_immediately() {
+    let kv_store = new_kv_store();
+    kv_store.insert(
+        "big".to_string(),
+        RedisValue::new(RedisData::List((0..10_000).map(|i| i.to_string()).collect()), None),
+    );
+
+    let result = process_unlink(&parts(&["UNLINK", "big"]), &kv_store).unwrap();
+    assert_eq!(result, b":1\r\n");
+    // Gone before the background drop has had a chance to run
+    assert!(!kv_store.contains_key("big"));
+}
+
+// ==================== RANDOMKEY Tests ====================
+
+#[test]
+fn test_randomkey_empty_database() {
+    let kv_store = new_kv_store();
+    let result = process_randomkey(&parts(&["RANDOMKEY"]), &kv_store).unwrap();
+    assert_eq!(result, b"$-1\r\n");
+}
+
+#[test]
+fn test_randomkey_returns_live_key() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    insert_string(&kv_store, "b", None);
+
+    let mut seen = HashSet::new();
+    for _ in 0..100 {
+        seen.insert(process_randomkey(&parts(&["RANDOMKEY"]), &kv_store).unwrap());
+    }
+    let expected: HashSet<Vec<u8>> = [b"$1\r\na\r\n".to_vec(), b"$1\r\nb\r\n".to_vec()].into();
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn test_randomkey_skips_expired_keys() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+
+    let result = process_randomkey(&parts(&["RANDOMKEY"]), &kv_store).unwrap();
+    assert_eq!(result, b"$-1\r\n");
+    assert!(kv_store.is_empty());
+
+    insert_string(&kv_store, "live", None);
+    let result = process_randomkey(&parts(&["RANDOMKEY"]), &kv_store).unwrap();
+    assert_eq!(result, b"$4\r\nlive\r\n");
+}
+
+// ==================== SCAN Tests ====================
+
+// Splits a SCAN reply into its next cursor and the returned keys
+fn scan_page(bytes: &[u8]) -> (String, Vec<String>) {
+    let lines: Vec<String> = String::from_utf8_lossy(bytes)
+        .split("\r\n")
+        .filter(|line| !line.is_empty() && !line.starts_with('$') && !line.starts_with('*'))
+        .map(|line| line.to_string())
+        .collect();
+    (lines[0].clone(), lines[1..].to_vec())
+}
+
+// Follows the cursor from 0 until SCAN says it's done, collecting every page
+fn scan_all(kv_store: &KvStore, options: &[&str]) -> Vec<Vec<String>> {
+    let mut pages = Vec::new();
+    let mut cursor = "0".to_string();
+    loop {
+        let mut args = vec!["SCAN", cursor.as_str()];
+        args.extend_from_slice(options);
+        let (next, keys) = scan_page(&process_scan(&parts(&args), kv_store).unwrap());
+        pages.push(keys);
+        if next == "0" {
+            return pages;
+        }
+        cursor = next;
+    }
+}
+
+#[test]
+fn test_scan_pages_through_every_key() {
+    let kv_store = new_kv_store();
+    for i in 0..25 {
+        insert_string(&kv_store, &format!("key:{:02}", i), None);
+    }
+
+    let pages = scan_all(&kv_store, &[]);
+    assert!(pages.len() >= 3);
+    assert!(pages.iter().all(|page| page.len() <= 10));
+    let mut keys: Vec<String> = pages.concat();
+    keys.sort();
+    let expected: Vec<String> = (0..25).map(|i| format!("key:{:02}", i)).collect();
+    assert_eq!(keys, expected);
+}
+
+#[test]
+fn test_scan_count_controls_page_size() {
+    let kv_store = new_kv_store();
+    for key in ["a", "b", "c"] {
+        insert_string(&kv_store, key, None);
+    }
+
+    let pages = scan_all(&kv_store, &["COUNT", "2"]);
+    assert!(pages.len() >= 2);
+    assert!(pages.iter().all(|page| page.len() <= 2));
+    let mut keys = pages.concat();
+    keys.sort();
+    assert_eq!(keys, vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_scan_only_walks_one_shard_at_a_time() {
+    let kv_store = new_kv_store();
+    for i in 0..100 {
+        insert_string(&kv_store, &format!("key:{}", i), None);
+    }
+
+    // A writer holding one shard doesn't stop SCAN from paging through the others
+    let held = kv_store.shard_of("key:0");
+    let _writer = kv_store.shard("key:0");
+    let other = (held + 1) % kv_store.shard_count();
+    let (next, keys) = scan_page(&process_scan(&parts(&["SCAN", &other.to_string(), "COUNT", "1"]), &kv_store).unwrap());
+    assert_eq!(keys.len(), 1);
+    assert_eq!(kv_store.shard_of(&keys[0]), other);
+    assert_ne!(next, "0");
+}
+
+#[test]
+fn test_scan_match_filters_keys() {
+    let kv_store = new_kv_store();
+    for key in ["user:1", "user:2", "session:1"] {
+        insert_string(&kv_store, key, None);
+    }
+
+    let result = process_scan(&parts(&["SCAN", "0", "MATCH", "user:*", "COUNT", "100"]), &kv_store).unwrap();
+    let (cursor, mut keys) = scan_page(&result);
+    keys.sort();
+    assert_eq!(cursor, "0");
+    assert_eq!(keys, vec!["user:1", "user:2"]);
+}
+
+#[test]
+fn test_scan_type_filter() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        let set: HashSet<String> = ["member".to_string()].into_iter().collect();
+        map.insert("str".to_string(), RedisValue::new(RedisData::String("v".into()), None));
+        map.insert("list".to_string(), RedisValue::new(RedisData::List(vec!["item".to_string()]), None));
+        map.insert("set".to_string(), RedisValue::new(RedisData::Set(set), None));
+    }
+
+    let (_, lists) = scan_page(&process_scan(&parts(&["SCAN", "0", "TYPE", "list"]), &kv_store).unwrap());
+    assert_eq!(lists, vec!["list"]);
+    let (_, sets) = scan_page(&process_scan(&parts(&["SCAN", "0", "type", "SET", "MATCH", "s*"]), &kv_store).unwrap());
+    assert_eq!(sets, vec!["set"]);
+    let (_, none) = scan_page(&process_scan(&parts(&["SCAN", "0", "TYPE", "zset"]), &kv_store).unwrap());
+    assert!(none.is_empty());
+}
+
+#[test]
+fn test_scan_skips_expired_keys() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "live", None);
+    insert_string(&kv_store, "dead", Some(Instant::now() - std::time::Duration::from_secs(1)));
+
+    let (_, keys) = scan_page(&process_scan(&parts(&["SCAN", "0"]), &kv_store).unwrap());
+    assert_eq!(keys, vec!["live"]);
+}
+
+#[test]
+fn test_scan_empty_store_and_past_the_end() {
+    let kv_store = new_kv_store();
+    let empty = process_scan(&parts(&["SCAN", "0"]), &kv_store).unwrap();
+    assert_eq!(empty, b"*2\r\n$1\r\n0\r\n*0\r\n");
+    let past_end = process_scan(&parts(&["SCAN", "999"]), &kv_store).unwrap();
+    assert_eq!(past_end, b"*2\r\n$1\r\n0\r\n*0\r\n");
+}
+
+#[test]
+fn test_scan_invalid_arguments() {
+    let kv_store = new_kv_store();
+    let cursor = process_scan(&parts(&["SCAN", "abc"]), &kv_store).unwrap();
+    assert!(cursor.starts_with(b"-ERR invalid cursor"));
+    let count = process_scan(&parts(&["SCAN", "0", "COUNT", "0"]), &kv_store).unwrap();
+    assert!(count.starts_with(b"-ERR syntax error"));
+    let dangling = process_scan(&parts(&["SCAN", "0", "TYPE"]), &kv_store).unwrap();
+    assert!(dangling.starts_with(b"-ERR syntax error"));
+}
+
+// ==================== EXPIRE Tests ====================
+
+// Milliseconds until `key` expires, None when it has no expiry
+fn remaining_ms(kv_store: &KvStore, key: &str) -> Option<u128> {
+    let map = kv_store.shard(key);
+    map.get(key)
+        .and_then(|value| value.expires_at)
+        .map(|expiry| expiry.saturating_duration_since(Instant::now()).as_millis())
+}
+
+fn unix_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64
+}
+
+#[test]
+fn test_expire_sets_ttl() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+
+    let result = process_expire(&parts(&["EXPIRE", "key", "100"]), &kv_store).unwrap();
+    assert_eq!(result, b":1\r\n");
+    let remaining = remaining_ms(&kv_store, "key").unwrap();
+    assert!(remaining > 99_000 && remaining <= 100_000);
+}
+
+#[test]
+fn test_pexpire_sets_ttl() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+
+    let result = process_pexpire(&parts(&["PEXPIRE", "key", "1500"]), &kv_store).unwrap();
+    assert_eq!(result, b":1\r\n");
+    let remaining = remaining_ms(&kv_store, "key").unwrap();
+    assert!(remaining > 1000 && remaining <= 1500);
+}
+
+#[test]
+fn test_expireat_and_pexpireat() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "secs", None);
+    insert_string(&kv_store, "millis", None);
+
+    let at_secs = (unix_ms() / 1000 + 100).to_string();
+    assert_eq!(process_expireat(&parts(&["EXPIREAT", "secs", &at_secs]), &kv_store).unwrap(), b":1\r\n");
+    let remaining = remaining_ms(&kv_store, "secs").unwrap();
+    assert!(remaining > 98_000 && remaining <= 100_000);
+
+    let at_millis = (unix_ms() + 5000).to_string();
+    assert_eq!(process_pexpireat(&parts(&["PEXPIREAT", "millis", &at_millis]), &kv_store).unwrap(), b":1\r\n");
+    let remaining = remaining_ms(&kv_store, "millis").unwrap();
+    assert!(remaining > 4000 && remaining <= 5000);
+}
+
+#[test]
+fn test_expire_missing_key() {
+    let kv_store = new_kv_store();
+    let result = process_expire(&parts(&["EXPIRE", "nokey", "10"]), &kv_store).unwrap();
+    assert_eq!(result, b":0\r\n");
+
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+    let result = process_expire(&parts(&["EXPIRE", "dead", "10"]), &kv_store).unwrap();
+    assert_eq!(result, b":0\r\n");
+}
+
+#[test]
+fn test_expire_in_the_past_deletes_key() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    insert_string(&kv_store, "b", None);
+
+    assert_eq!(process_expire(&parts(&["EXPIRE", "a", "-1"]), &kv_store).unwrap(), b":1\r\n");
+    assert!(!kv_store.contains_key("a"));
+    assert_eq!(process_expireat(&parts(&["EXPIREAT", "b", "1"]), &kv_store).unwrap(), b":1\r\n");
+    assert!(!kv_store.contains_key("b"));
+}
+
+#[test]
+fn test_expire_nx_and_xx() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+
+    // XX needs an existing expiry, NX needs there to be none
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "100", "XX"]), &kv_store).unwrap(), b":0\r\n");
+    assert!(remaining_ms(&kv_store, "key").is_none());
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "100", "NX"]), &kv_store).unwrap(), b":1\r\n");
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "200", "NX"]), &kv_store).unwrap(), b":0\r\n");
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "200", "xx"]), &kv_store).unwrap(), b":1\r\n");
+    assert!(remaining_ms(&kv_store, "key").unwrap() > 150_000);
+}
+
+#[test]
+fn test_expire_gt_and_lt() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+
+    // No expiry counts as infinite: GT never applies, LT always does
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "100", "GT"]), &kv_store).unwrap(), b":0\r\n");
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "100", "LT"]), &kv_store).unwrap(), b":1\r\n");
+
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "50", "GT"]), &kv_store).unwrap(), b":0\r\n");
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "200", "GT"]), &kv_store).unwrap(), b":1\r\n");
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "300", "LT"]), &kv_store).unwrap(), b":0\r\n");
+    assert_eq!(process_expire(&parts(&["EXPIRE", "key", "10", "LT"]), &kv_store).unwrap(), b":1\r\n");
+    assert!(remaining_ms(&kv_store, "key").unwrap() <= 10_000);
+}
+
+#[test]
+fn test_expire_invalid_arguments() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+
+    let not_int = process_expire(&parts(&["EXPIRE", "key", "soon"]), &kv_store).unwrap();
+    assert!(not_int.starts_with(b"-ERR value is not an integer or out of range"));
+    let overflow = process_expire(&parts(&["EXPIRE", "key", "9223372036854775807"]), &kv_store).unwrap();
+    assert!(overflow.starts_with(b"-ERR invalid expire time in 'expire' command"));
+    let unknown = process_expire(&parts(&["EXPIRE", "key", "10", "ZZ"]), &kv_store).unwrap();
+    assert!(unknown.starts_with(b"-ERR Unsupported option ZZ"));
+    let nx_gt = process_expire(&parts(&["EXPIRE", "key", "10", "NX", "GT"]), &kv_store).unwrap();
+    assert!(nx_gt.starts_with(b"-ERR NX and XX, GT or LT options at the same time are not compatible"));
+    let gt_lt = process_expire(&parts(&["EXPIRE", "key", "10", "GT", "LT"]), &kv_store).unwrap();
+    assert!(gt_lt.starts_with(b"-ERR GT and LT options at the same time are not compatible"));
+    assert!(process_expire(&parts(&["EXPIRE", "key"]), &kv_store).is_err());
+    // Nothing above changed the key
+    assert!(remaining_ms(&kv_store, "key").is_none());
+}
+
+// ==================== TTL / PTTL / PERSIST Tests ====================
+
+#[test]
+fn test_ttl_and_pttl_with_expiry() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", Some(Instant::now() + Duration::from_secs(100)));
+
+    assert_eq!(process_ttl(&parts(&["TTL", "key"]), &kv_store).unwrap(), b":100\r\n");
+    let pttl = String::from_utf8(process_pttl(&parts(&["PTTL", "key"]), &kv_store).unwrap()).unwrap();
+    let millis: i64 = pttl[1..].trim().parse().unwrap();
+    assert!(millis > 99_000 && millis <= 100_000);
+}
+
+#[test]
+fn test_ttl_without_expiry() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+    assert_eq!(process_ttl(&parts(&["TTL", "key"]), &kv_store).unwrap(), b":-1\r\n");
+    assert_eq!(process_pttl(&parts(&["PTTL", "key"]), &kv_store).unwrap(), b":-1\r\n");
+}
+
+#[test]
+fn test_ttl_missing_and_expired_keys() {
+    let kv_store = new_kv_store();
+    assert_eq!(process_ttl(&parts(&["TTL", "nokey"]), &kv_store).unwrap(), b":-2\r\n");
+
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+    assert_eq!(process_pttl(&parts(&["PTTL", "dead"]), &kv_store).unwrap(), b":-2\r\n");
+    assert!(!kv_store.contains_key("dead"));
+    assert!(process_ttl(&parts(&["TTL"]), &kv_store).is_err());
+}
+
+#[test]
+fn test_ttl_after_expire_command() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+    process_pexpire(&parts(&["PEXPIRE", "key", "2500"]), &kv_store).unwrap();
+    // Rounded to the nearest second, like Redis
+    assert_eq!(process_ttl(&parts(&["TTL", "key"]), &kv_store).unwrap(), b":2\r\n");
+}
+
+#[test]
+fn test_persist_removes_expiry() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", Some(Instant::now() + Duration::from_secs(100)));
+
+    assert_eq!(process_persist(&parts(&["PERSIST", "key"]), &kv_store).unwrap(), b":1\r\n");
+    assert_eq!(process_ttl(&parts(&["TTL", "key"]), &kv_store).unwrap(), b":-1\r\n");
+    // Already persistent
+    assert_eq!(process_persist(&parts(&["PERSIST", "key"]), &kv_store).unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_persist_missing_and_expired_keys() {
+    let kv_store = new_kv_store();
+    assert_eq!(process_persist(&parts(&["PERSIST", "nokey"]), &kv_store).unwrap(), b":0\r\n");
+
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+    assert_eq!(process_persist(&parts(&["PERSIST", "dead"]), &kv_store).unwrap(), b":0\r\n");
+    assert!(!kv_store.contains_key("dead"));
+}
+
+// ==================== RENAME / RENAMENX Tests ====================
+
+#[test]
+fn test_rename_moves_value() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "old", None);
+
+    let result = process_rename(&parts(&["RENAME", "old", "new"]), &kv_store).unwrap();
+    assert_eq!(result, b"+OK\r\n");
+    assert!(!kv_store.contains_key("old"));
+    assert_eq!(process_type(&parts(&["TYPE", "new"]), &kv_store).unwrap(), b"+string\r\n");
+}
+
+#[test]
+fn test_rename_overwrites_destination() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "src", None);
+    kv_store.insert("dst".to_string(), RedisValue::new(RedisData::List(vec!["item".to_string()]), None));
+
+    process_rename(&parts(&["RENAME", "src", "dst"]), &kv_store).unwrap();
+    assert_eq!(process_type(&parts(&["TYPE", "dst"]), &kv_store).unwrap(), b"+string\r\n");
+    assert_eq!(kv_store.len(), 1);
+}
+
+#[test]
+fn test_rename_keeps_ttl() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "src", Some(Instant::now() + Duration::from_secs(100)));
+    insert_string(&kv_store, "other", None);
+
+    process_rename(&parts(&["RENAME", "src", "dst"]), &kv_store).unwrap();
+    assert_eq!(process_ttl(&parts(&["TTL", "dst"]), &kv_store).unwrap(), b":100\r\n");
+    process_renamenx(&parts(&["RENAMENX", "dst", "fresh"]), &kv_store).unwrap();
+    assert_eq!(process_ttl(&parts(&["TTL", "fresh"]), &kv_store).unwrap(), b":100\r\n");
+}
+
+#[test]
+fn test_rename_missing_source() {
+    let kv_store = new_kv_store();
+    let result = process_rename(&parts(&["RENAME", "nokey", "dst"]), &kv_store).unwrap();
+    assert_eq!(result, b"-ERR no such key\r\n");
+
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+    let result = process_renamenx(&parts(&["RENAMENX", "dead", "dst"]), &kv_store).unwrap();
+    assert_eq!(result, b"-ERR no such key\r\n");
+    assert!(kv_store.is_empty());
+}
+
+#[test]
+fn test_rename_onto_itself() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "same", None);
+    assert_eq!(process_rename(&parts(&["RENAME", "same", "same"]), &kv_store).unwrap(), b"+OK\r\n");
+    assert!(kv_store.contains_key("same"));
+    // RENAMENX sees the destination already exists
+    assert_eq!(process_renamenx(&parts(&["RENAMENX", "same", "same"]), &kv_store).unwrap(), b":0\r\n");
+
+    let missing = process_rename(&parts(&["RENAME", "nokey", "nokey"]), &kv_store).unwrap();
+    assert_eq!(missing, b"-ERR no such key\r\n");
+}
+
+#[test]
+fn test_renamenx() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "a", None);
+    insert_string(&kv_store, "b", None);
+
+    assert_eq!(process_renamenx(&parts(&["RENAMENX", "a", "b"]), &kv_store).unwrap(), b":0\r\n");
+    assert!(kv_store.contains_key("a"));
+    assert_eq!(process_renamenx(&parts(&["RENAMENX", "a", "c"]), &kv_store).unwrap(), b":1\r\n");
+    assert!(!kv_store.contains_key("a"));
+    assert!(kv_store.contains_key("c"));
+
+    // An expired destination doesn't block the rename
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+    assert_eq!(process_renamenx(&parts(&["RENAMENX", "c", "dead"]), &kv_store).unwrap(), b":1\r\n");
+}
+
+// ==================== COPY Tests ====================
+
+#[test]
+fn test_copy_deep_clones_value() {
+    let databases = new_databases(2);
+    let kv_store = &databases[0];
+    kv_store.insert("src".to_string(), RedisValue::new(RedisData::List(vec!["a".to_string()]), None));
+
+    let result = process_copy(&parts(&["COPY", "src", "dst"]), 0, &databases).unwrap();
+    assert_eq!(result, b":1\r\n");
+
+    // Changing the copy leaves the source alone
+    let mut map = kv_store.lock_all();
+    if let RedisData::List(list) = &mut map.get_mut("dst").unwrap().data {
+        list.push("b".to_string());
+    }
+    match &map.get("src").unwrap().data {
+        RedisData::List(list) => assert_eq!(list, &vec!["a".to_string()]),
+        _ => panic!("source changed type"),
+    }
+}
+
+#[test]
+fn test_copy_keeps_ttl() {
+    let databases = new_databases(1);
+    insert_string(&databases[0], "src", Some(Instant::now() + Duration::from_secs(100)));
+
+    process_copy(&parts(&["COPY", "src", "dst"]), 0, &databases).unwrap();
+    assert_eq!(process_ttl(&parts(&["TTL", "dst"]), &databases[0]).unwrap(), b":100\r\n");
+}
+
+#[test]
+fn test_copy_existing_destination_needs_replace() {
+    let databases = new_databases(1);
+    let kv_store = &databases[0];
+    insert_string(kv_store, "src", None);
+    kv_store.insert("dst".to_string(), RedisValue::new(RedisData::List(vec!["x".to_string()]), None));
+
+    assert_eq!(process_copy(&parts(&["COPY", "src", "dst"]), 0, &databases).unwrap(), b":0\r\n");
+    assert_eq!(process_type(&parts(&["TYPE", "dst"]), kv_store).unwrap(), b"+list\r\n");
+
+    assert_eq!(process_copy(&parts(&["COPY", "src", "dst", "REPLACE"]), 0, &databases).unwrap(), b":1\r\n");
+    assert_eq!(process_type(&parts(&["TYPE", "dst"]), kv_store).unwrap(), b"+string\r\n");
+}
+
+#[test]
+fn test_copy_missing_source() {
+    let databases = new_databases(1);
+    assert_eq!(process_copy(&parts(&["COPY", "nokey", "dst"]), 0, &databases).unwrap(), b":0\r\n");
+
+    insert_string(&databases[0], "dead", Some(Instant::now() - Duration::from_secs(1)));
+    assert_eq!(process_copy(&parts(&["COPY", "dead", "dst"]), 0, &databases).unwrap(), b":0\r\n");
+    assert!(!databases[0].contains_key("dst"));
+}
+
+#[test]
+fn test_copy_to_another_database() {
+    let databases = new_databases(4);
+    insert_string(&databases[0], "key", None);
+
+    let result = process_copy(&parts(&["COPY", "key", "key", "DB", "3"]), 0, &databases).unwrap();
+    assert_eq!(result, b":1\r\n");
+    assert!(databases[0].contains_key("key"));
+    assert!(databases[3].contains_key("key"));
+}
+
+#[test]
+fn test_copy_invalid_arguments() {
+    let databases = new_databases(2);
+    insert_string(&databases[0], "key", None);
+
+    let same = process_copy(&parts(&["COPY", "key", "key"]), 0, &databases).unwrap();
+    assert_eq!(same, b"-ERR source and destination objects are the same\r\n");
+    let range = process_copy(&parts(&["COPY", "key", "dst", "DB", "2"]), 0, &databases).unwrap();
+    assert_eq!(range, b"-ERR DB index is out of range\r\n");
+    let not_int = process_copy(&parts(&["COPY", "key", "dst", "DB", "x"]), 0, &databases).unwrap();
+    assert!(not_int.starts_with(b"-ERR value is not an integer"));
+    let unknown = process_copy(&parts(&["COPY", "key", "dst", "NOW"]), 0, &databases).unwrap();
+    assert_eq!(unknown, b"-ERR syntax error\r\n");
+    let dangling = process_copy(&parts(&["COPY", "key", "dst", "DB"]), 0, &databases).unwrap();
+    assert_eq!(dangling, b"-ERR syntax error\r\n");
+}
+
+// ==================== OBJECT Tests ====================
+
+fn object_encoding(kv_store: &KvStore, key: &str) -> Vec<u8> {
+    process_object(&parts(&["OBJECT", "ENCODING", key]), kv_store).unwrap()
+}
+
+#[test]
+fn test_object_encoding_strings() {
+    let kv_store = new_kv_store();
+    let long = "x".repeat(45);
+    for (key, value) in [("int", "12345"), ("neg", "-7"), ("padded", "012"), ("short", "hello"), ("long", long.as_str())] {
+        kv_store.insert(key.to_string(), RedisValue::new(RedisData::String(value.into()), None));
+    }
+
+    assert_eq!(object_encoding(&kv_store, "int"), b"$3\r\nint\r\n");
+    assert_eq!(object_encoding(&kv_store, "neg"), b"$3\r\nint\r\n");
+    assert_eq!(object_encoding(&kv_store, "padded"), b"$6\r\nembstr\r\n");
+    assert_eq!(object_encoding(&kv_store, "short"), b"$6\r\nembstr\r\n");
+    assert_eq!(object_encoding(&kv_store, "long"), b"$3\r\nraw\r\n");
+}
+
+#[test]
+fn test_object_encoding_other_types() {
+    let kv_store = new_kv_store();
+    kv_store.insert("list".to_string(), RedisValue::new(RedisData::List(vec!["a".to_string()]), None));
+    kv_store.insert("stream".to_string(), RedisValue::new(RedisData::Stream(Stream::new()), None));
+
+    assert_eq!(object_encoding(&kv_store, "list"), b"$9\r\nquicklist\r\n");
+    assert_eq!(object_encoding(&kv_store, "stream"), b"$6\r\nstream\r\n");
+}
+
+#[test]
+fn test_object_missing_or_expired_key() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+
+    assert_eq!(object_encoding(&kv_store, "nokey"), b"$-1\r\n");
+    assert_eq!(object_encoding(&kv_store, "dead"), b"$-1\r\n");
+    assert!(!kv_store.contains_key("dead"));
+}
+
+#[test]
+fn test_object_refcount_and_idletime() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+
+    assert_eq!(process_object(&parts(&["OBJECT", "REFCOUNT", "key"]), &kv_store).unwrap(), b":1\r\n");
+    assert_eq!(process_object(&parts(&["object", "idletime", "key"]), &kv_store).unwrap(), b":0\r\n");
+    let freq = process_object(&parts(&["OBJECT", "FREQ", "key"]), &kv_store).unwrap();
+    assert!(freq.starts_with(b"-ERR An LFU maxmemory policy is not selected"));
+}
+
+#[tokio::test]
+async fn test_idle_time_resets_on_access() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "read", None);
+    insert_string(&kv_store, "touched", None);
+    insert_string(&kv_store, "idle", None);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    process_get(&parts(&["GET", "read"]), &kv_store).unwrap();
+    process_touch(&parts(&["TOUCH", "touched"]), &kv_store).unwrap();
+    // Neither EXISTS nor OBJECT itself counts as an access
+    process_exists(&parts(&["EXISTS", "idle"]), &kv_store).unwrap();
+    process_object(&parts(&["OBJECT", "IDLETIME", "idle"]), &kv_store).unwrap();
+
+    let map = kv_store.lock_all();
+    assert!(map.get("read").unwrap().idle_time() < Duration::from_millis(50));
+    assert!(map.get("touched").unwrap().idle_time() < Duration::from_millis(50));
+    assert!(map.get("idle").unwrap().idle_time() >= Duration::from_millis(50));
+}
+
+#[test]
+fn test_object_help_and_errors() {
+    let kv_store = new_kv_store();
+
+    let help = process_object(&parts(&["OBJECT", "HELP"]), &kv_store).unwrap();
+    assert!(help.starts_with(b"*15\r\n"));
+
+    let missing = process_object(&parts(&["OBJECT"]), &kv_store).unwrap();
+    assert_eq!(missing, b"-ERR wrong number of arguments for 'object' command\r\n");
+    let no_key = process_object(&parts(&["OBJECT", "ENCODING"]), &kv_store).unwrap();
+    assert_eq!(no_key, b"-ERR wrong number of arguments for 'object|encoding' command\r\n");
+    let unknown = process_object(&parts(&["OBJECT", "SIZE", "key"]), &kv_store).unwrap();
+    assert_eq!(unknown, b"-ERR unknown subcommand 'SIZE'. Try OBJECT HELP.\r\n");
+}
+
 // ==================== Concurrent Tests ====================
 
 #[tokio::test]
@@ -191,7 +1158,7 @@ async fn test_concurrent_ping() {
     for _ in 0..num_clients {
         let handle = tokio::spawn(async move {
             for _ in 0..100 {
-                let result = process_ping();
+                let result = process_ping(&parts(&["PING"]), &ClientState::new());
                 assert!(result.is_ok());
                 assert_eq!(result.unwrap(), b"+PONG\r\n");
             }
@@ -210,11 +1177,11 @@ async fn test_concurrent_type_checks() {
 
     // Pre-populate with different types
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         for i in 0..10 {
             map.insert(
                 format!("string_{}", i),
-                RedisValue::new(RedisData::String("value".to_string()), None),
+                RedisValue::new(RedisData::String("value".into()), None),
             );
             map.insert(
                 format!("list_{}", i),
@@ -222,7 +1189,7 @@ async fn test_concurrent_type_checks() {
             );
             map.insert(
                 format!("stream_{}", i),
-                RedisValue::new(RedisData::Stream(vec![]), None),
+                RedisValue::new(RedisData::Stream(Stream::new()), None),
             );
         }
     }
@@ -257,3 +1224,127 @@ async fn test_concurrent_type_checks() {
         handle.await.unwrap();
     }
 }
+
+#[tokio::test]
+async fn test_concurrent_del_removes_each_key_once() {
+    let kv_store = new_kv_store();
+    let num_keys = 100;
+    for i in 0..num_keys {
+        insert_string(&kv_store, &format!("key_{}", i), None);
+    }
+
+    // Every client races to delete every key; each key must be counted by exactly one of them
+    let mut handles = vec![];
+    for _ in 0..10 {
+        let store = Arc::clone(&kv_store);
+        handles.push(tokio::spawn(async move {
+            let mut deleted = 0;
+            for i in 0..num_keys {
+                let result = process_del(&parts(&["DEL", &format!("key_{}", i)]), &store).unwrap();
+                deleted += String::from_utf8(result).unwrap()[1..].trim().parse::<usize>().unwrap();
+            }
+            deleted
+        }));
+    }
+
+    let mut total = 0;
+    for handle in handles {
+        total += handle.await.unwrap();
+    }
+    assert_eq!(total, num_keys);
+    assert!(kv_store.is_empty());
+}
+
+#[test]
+fn test_concurrent_renames_never_lose_the_key() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "left", None);
+
+    // Two threads bounce the key back and forth; if the move weren't atomic a
+    // reader could see both names, or neither
+    let handles: Vec<_> = [("left", "right"), ("right", "left")]
+        .into_iter()
+        .map(|(from, to)| {
+            let store = Arc::clone(&kv_store);
+            std::thread::spawn(move || {
+                for _ in 0..1_000 {
+                    process_rename(&parts(&["RENAME", from, to]), &store).unwrap();
+                    let present = process_exists(&parts(&["EXISTS", "left", "right"]), &store).unwrap();
+                    assert_eq!(present, b":1\r\n");
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(kv_store.len(), 1);
+}
+
+// ==================== AUTH Tests ====================
+
+#[test]
+fn test_auth_with_wrong_password() {
+    let acl = acl_with_password(Some("secret"));
+    let mut client = ClientState::new();
+
+    let result = process_auth(&parts(&["AUTH", "guess"]), &mut client, &acl).unwrap();
+    assert_eq!(result, b"-WRONGPASS invalid username-password pair or user is disabled.\r\n");
+    assert!(!client.authenticated);
+
+    let result = process_auth(&parts(&["AUTH", "alice", "secret"]), &mut client, &acl).unwrap();
+    assert_eq!(result, b"-WRONGPASS invalid username-password pair or user is disabled.\r\n");
+    assert!(!client.authenticated);
+}
+
+#[test]
+fn test_auth_with_right_password() {
+    let acl = acl_with_password(Some("secret"));
+
+    let mut client = ClientState::new();
+    assert_eq!(process_auth(&parts(&["AUTH", "secret"]), &mut client, &acl).unwrap(), b"+OK\r\n");
+    assert!(client.authenticated);
+
+    let mut client = ClientState::new();
+    assert_eq!(process_auth(&parts(&["AUTH", "default", "secret"]), &mut client, &acl).unwrap(), b"+OK\r\n");
+    assert!(client.authenticated);
+}
+
+#[test]
+fn test_auth_without_a_password_configured() {
+    let acl = acl_with_password(None);
+    let mut client = ClientState::new();
+
+    let result = process_auth(&parts(&["AUTH", "anything"]), &mut client, &acl).unwrap();
+    assert!(result.starts_with(b"-ERR AUTH <password> called without any password configured"));
+    // The default user takes any password when none is required
+    assert_eq!(process_auth(&parts(&["AUTH", "default", "anything"]), &mut client, &acl).unwrap(), b"+OK\r\n");
+    assert!(process_auth(&parts(&["AUTH", "a", "b", "c"]), &mut client, &acl).is_err());
+}
+
+#[tokio::test]
+async fn test_commands_need_auth_when_password_is_set() {
+    let state = ServerState::default();
+    state.acl.set_requirepass(Some("secret".to_string()));
+    let mut client = ClientState::new();
+
+    let buffer: Vec<u8> = [&["SET", "k", "v"][..], &["AUTH", "wrong"], &["GET", "k"]]
+        .iter()
+        .flat_map(|command| make_resp(command))
+        .collect();
+    let (replies, _) = parse_pipeline(&buffer, &state, &mut client).await;
+    assert_eq!(
+        replies,
+        b"-NOAUTH Authentication required.\r\n\
+        -WRONGPASS invalid username-password pair or user is disabled.\r\n\
+        -NOAUTH Authentication required.\r\n"
+    );
+    assert!(!state.databases[0].contains_key("k"));
+
+    let buffer: Vec<u8> = [&["AUTH", "secret"][..], &["SET", "k", "v"], &["GET", "k"]]
+        .iter()
+        .flat_map(|command| make_resp(command))
+        .collect();
+    let (replies, _) = parse_pipeline(&buffer, &state, &mut client).await;
+    assert_eq!(replies, b"+OK\r\n+OK\r\n$1\r\nv\r\n");
+}
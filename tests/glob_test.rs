@@ -0,0 +1,79 @@
+use redis_cache::utils::glob::glob_match;
+
+// ==================== Literal Matching ====================
+
+#[test]
+fn test_glob_literal() {
+    assert!(glob_match("hello", "hello"));
+    assert!(!glob_match("hello", "hell"));
+    assert!(!glob_match("hello", "hello!"));
+}
+
+#[test]
+fn test_glob_empty() {
+    assert!(glob_match("", ""));
+    assert!(!glob_match("", "a"));
+    assert!(glob_match("*", ""));
+}
+
+// ==================== Wildcards ====================
+
+#[test]
+fn test_glob_star() {
+    assert!(glob_match("*", "anything"));
+    assert!(glob_match("h*o", "hello"));
+    assert!(glob_match("h*o", "ho"));
+    assert!(glob_match("*llo", "hello"));
+    assert!(!glob_match("h*x", "hello"));
+}
+
+#[test]
+fn test_glob_star_backtracks() {
+    assert!(glob_match("*a*b", "xaxxab"));
+    assert!(glob_match("a*b*c", "abxbxc"));
+    assert!(!glob_match("a*b*c", "abxbx"));
+}
+
+#[test]
+fn test_glob_question_mark() {
+    assert!(glob_match("h?llo", "hello"));
+    assert!(glob_match("h?llo", "hallo"));
+    assert!(!glob_match("h?llo", "hllo"));
+}
+
+// ==================== Character Classes ====================
+
+#[test]
+fn test_glob_class() {
+    assert!(glob_match("h[ae]llo", "hello"));
+    assert!(glob_match("h[ae]llo", "hallo"));
+    assert!(!glob_match("h[ae]llo", "hillo"));
+}
+
+#[test]
+fn test_glob_class_range() {
+    assert!(glob_match("key[0-9]", "key5"));
+    assert!(!glob_match("key[0-9]", "keyx"));
+    assert!(glob_match("key[9-0]", "key5"));
+}
+
+#[test]
+fn test_glob_class_negated() {
+    assert!(glob_match("h[^e]llo", "hallo"));
+    assert!(!glob_match("h[^e]llo", "hello"));
+}
+
+#[test]
+fn test_glob_unterminated_class_never_matches() {
+    assert!(!glob_match("h[ello", "hello"));
+}
+
+// ==================== Escaping ====================
+
+#[test]
+fn test_glob_escape() {
+    assert!(glob_match("h\\*llo", "h*llo"));
+    assert!(!glob_match("h\\*llo", "hello"));
+    assert!(glob_match("what\\?", "what?"));
+    assert!(glob_match("[\\]]", "]"));
+}
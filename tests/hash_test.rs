@@ -0,0 +1,641 @@
+use std::sync::Arc;
+
+use redis_cache::models::{RedisData, RedisValue, KvStore, Store};
+use redis_cache::commands::{
+    process_hset, process_hget, process_hdel, process_hexists, process_hlen,
+    process_hkeys, process_hvals, process_hgetall, process_hmset, process_hmget,
+    process_hincrby, process_hincrbyfloat, process_hsetnx, process_hrandfield, process_hscan, process_type
+};
+
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
+}
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+const WRONGTYPE: &[u8] = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+
+fn insert_string(kv_store: &KvStore, key: &str) {
+    let mut map = kv_store.lock_all();
+    map.insert(
+        key.to_string(),
+        RedisValue::new(RedisData::String("value".into()), None),
+    );
+}
+
+// Hash replies are unordered, so decode the bulk strings of a flat array and sort them
+fn sorted_elements(bytes: &[u8]) -> Vec<String> {
+    let response = String::from_utf8_lossy(bytes);
+    let mut elements: Vec<String> = response
+        .split("\r\n")
+        .skip(1)
+        .filter(|line| !line.starts_with('$') && !line.is_empty())
+        .map(|line| line.to_string())
+        .collect();
+    elements.sort();
+    elements
+}
+
+// ==================== HSET Tests ====================
+
+#[test]
+fn test_hset_new_hash() {
+    let kv_store = new_kv_store();
+    let p = parts(&["HSET", "myhash", "field1", "value1"]);
+    let result = process_hset(&p, &kv_store);
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_hset_multiple_fields() {
+    let kv_store = new_kv_store();
+    let p = parts(&["HSET", "myhash", "f1", "v1", "f2", "v2", "f3", "v3"]);
+    let result = process_hset(&p, &kv_store);
+    assert_eq!(result.unwrap(), b":3\r\n");
+}
+
+#[test]
+fn test_hset_overwrite_counts_only_new_fields() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1"]), &kv_store).unwrap();
+
+    let result = process_hset(&parts(&["HSET", "myhash", "f1", "updated", "f2", "v2"]), &kv_store);
+    assert_eq!(result.unwrap(), b":1\r\n");
+
+    let value = process_hget(&parts(&["HGET", "myhash", "f1"]), &kv_store).unwrap();
+    assert_eq!(value, b"$7\r\nupdated\r\n");
+}
+
+#[test]
+fn test_hset_odd_arguments() {
+    let kv_store = new_kv_store();
+    let result = process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2"]), &kv_store);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_hset_incomplete_command() {
+    let kv_store = new_kv_store();
+    let result = process_hset(&parts(&["HSET", "myhash", "f1"]), &kv_store);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_hset_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    let result = process_hset(&parts(&["HSET", "strkey", "f1", "v1"]), &kv_store).unwrap();
+    assert_eq!(result, WRONGTYPE);
+}
+
+// ==================== HGET Tests ====================
+
+#[test]
+fn test_hget_existing_field() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "field", "hello"]), &kv_store).unwrap();
+
+    let result = process_hget(&parts(&["HGET", "myhash", "field"]), &kv_store);
+    assert_eq!(result.unwrap(), b"$5\r\nhello\r\n");
+}
+
+#[test]
+fn test_hget_missing_field() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "field", "hello"]), &kv_store).unwrap();
+
+    let result = process_hget(&parts(&["HGET", "myhash", "other"]), &kv_store);
+    assert_eq!(result.unwrap(), b"$-1\r\n");
+}
+
+#[test]
+fn test_hget_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_hget(&parts(&["HGET", "nokey", "field"]), &kv_store);
+    assert_eq!(result.unwrap(), b"$-1\r\n");
+}
+
+#[test]
+fn test_hget_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    let result = process_hget(&parts(&["HGET", "strkey", "field"]), &kv_store).unwrap();
+    assert_eq!(result, WRONGTYPE);
+}
+
+#[test]
+fn test_hget_missing_field_argument() {
+    let kv_store = new_kv_store();
+    let result = process_hget(&parts(&["HGET", "myhash"]), &kv_store);
+    assert!(result.is_err());
+}
+
+// ==================== HDEL Tests ====================
+
+#[test]
+fn test_hdel_existing_fields() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2", "f3", "v3"]), &kv_store).unwrap();
+
+    let result = process_hdel(&parts(&["HDEL", "myhash", "f1", "f2", "missing"]), &kv_store);
+    assert_eq!(result.unwrap(), b":2\r\n");
+
+    let remaining = process_hlen(&parts(&["HLEN", "myhash"]), &kv_store).unwrap();
+    assert_eq!(remaining, b":1\r\n");
+}
+
+#[test]
+fn test_hdel_last_field_removes_key() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1"]), &kv_store).unwrap();
+
+    process_hdel(&parts(&["HDEL", "myhash", "f1"]), &kv_store).unwrap();
+
+    let map = kv_store.lock_all();
+    assert!(map.get("myhash").is_none());
+}
+
+#[test]
+fn test_hdel_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_hdel(&parts(&["HDEL", "nokey", "f1"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_hdel_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hdel(&parts(&["HDEL", "strkey", "f1"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+#[test]
+fn test_hdel_missing_fields_argument() {
+    let kv_store = new_kv_store();
+    assert!(process_hdel(&parts(&["HDEL", "myhash"]), &kv_store).is_err());
+}
+
+// ==================== HEXISTS Tests ====================
+
+#[test]
+fn test_hexists() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1"]), &kv_store).unwrap();
+
+    let present = process_hexists(&parts(&["HEXISTS", "myhash", "f1"]), &kv_store);
+    assert_eq!(present.unwrap(), b":1\r\n");
+    let absent = process_hexists(&parts(&["HEXISTS", "myhash", "f2"]), &kv_store);
+    assert_eq!(absent.unwrap(), b":0\r\n");
+    let no_key = process_hexists(&parts(&["HEXISTS", "nokey", "f1"]), &kv_store);
+    assert_eq!(no_key.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_hexists_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hexists(&parts(&["HEXISTS", "strkey", "f1"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== HLEN Tests ====================
+
+#[test]
+fn test_hlen() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store).unwrap();
+
+    let result = process_hlen(&parts(&["HLEN", "myhash"]), &kv_store);
+    assert_eq!(result.unwrap(), b":2\r\n");
+}
+
+#[test]
+fn test_hlen_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_hlen(&parts(&["HLEN", "nokey"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_hlen_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hlen(&parts(&["HLEN", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== HKEYS / HVALS / HGETALL Tests ====================
+
+#[test]
+fn test_hkeys() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store).unwrap();
+
+    let result = process_hkeys(&parts(&["HKEYS", "myhash"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"*2\r\n"));
+    assert_eq!(sorted_elements(&result), vec!["f1", "f2"]);
+}
+
+#[test]
+fn test_hvals() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store).unwrap();
+
+    let result = process_hvals(&parts(&["HVALS", "myhash"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"*2\r\n"));
+    assert_eq!(sorted_elements(&result), vec!["v1", "v2"]);
+}
+
+#[test]
+fn test_hgetall_interleaves_fields_and_values() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store).unwrap();
+
+    let result = process_hgetall(&parts(&["HGETALL", "myhash"]), &kv_store, 2).unwrap();
+    assert!(result.starts_with(b"*4\r\n"));
+    let response = String::from_utf8_lossy(&result);
+    assert!(response.contains("$2\r\nf1\r\n$2\r\nv1\r\n"));
+    assert!(response.contains("$2\r\nf2\r\n$2\r\nv2\r\n"));
+}
+
+#[test]
+fn test_hgetall_resp3_is_a_map() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1"]), &kv_store).unwrap();
+
+    let result = process_hgetall(&parts(&["HGETALL", "myhash"]), &kv_store, 3).unwrap();
+    assert_eq!(result, b"%1\r\n$2\r\nf1\r\n$2\r\nv1\r\n");
+    assert_eq!(process_hgetall(&parts(&["HGETALL", "nokey"]), &kv_store, 3).unwrap(), b"%0\r\n");
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hgetall(&parts(&["HGETALL", "strkey"]), &kv_store, 3).unwrap(), WRONGTYPE);
+}
+
+#[test]
+fn test_hash_readers_nonexistent_key() {
+    let kv_store = new_kv_store();
+    assert_eq!(process_hkeys(&parts(&["HKEYS", "nokey"]), &kv_store).unwrap(), b"*0\r\n");
+    assert_eq!(process_hvals(&parts(&["HVALS", "nokey"]), &kv_store).unwrap(), b"*0\r\n");
+    assert_eq!(process_hgetall(&parts(&["HGETALL", "nokey"]), &kv_store, 2).unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_hash_readers_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hkeys(&parts(&["HKEYS", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+    assert_eq!(process_hvals(&parts(&["HVALS", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+    assert_eq!(process_hgetall(&parts(&["HGETALL", "strkey"]), &kv_store, 2).unwrap(), WRONGTYPE);
+}
+
+// ==================== HMSET / HMGET Tests ====================
+
+#[test]
+fn test_hmset_returns_ok() {
+    let kv_store = new_kv_store();
+    let result = process_hmset(&parts(&["HMSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+OK\r\n");
+
+    let len = process_hlen(&parts(&["HLEN", "myhash"]), &kv_store).unwrap();
+    assert_eq!(len, b":2\r\n");
+}
+
+#[test]
+fn test_hmset_odd_arguments() {
+    let kv_store = new_kv_store();
+    assert!(process_hmset(&parts(&["HMSET", "myhash", "f1"]), &kv_store).is_err());
+}
+
+#[test]
+fn test_hmget_with_missing_fields() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f3", "v3"]), &kv_store).unwrap();
+
+    let result = process_hmget(&parts(&["HMGET", "myhash", "f1", "f2", "f3"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*3\r\n$2\r\nv1\r\n$-1\r\n$2\r\nv3\r\n");
+}
+
+#[test]
+fn test_hmget_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_hmget(&parts(&["HMGET", "nokey", "f1", "f2"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*2\r\n$-1\r\n$-1\r\n");
+}
+
+#[test]
+fn test_hmget_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hmget(&parts(&["HMGET", "strkey", "f1"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== HINCRBY Tests ====================
+
+#[test]
+fn test_hincrby_creates_field() {
+    let kv_store = new_kv_store();
+    let result = process_hincrby(&parts(&["HINCRBY", "myhash", "counter", "5"]), &kv_store);
+    assert_eq!(result.unwrap(), b":5\r\n");
+
+    let result = process_hincrby(&parts(&["HINCRBY", "myhash", "counter", "-8"]), &kv_store);
+    assert_eq!(result.unwrap(), b":-3\r\n");
+
+    let stored = process_hget(&parts(&["HGET", "myhash", "counter"]), &kv_store).unwrap();
+    assert_eq!(stored, b"$2\r\n-3\r\n");
+}
+
+#[test]
+fn test_hincrby_non_integer_field() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "name", "bob"]), &kv_store).unwrap();
+
+    let result = process_hincrby(&parts(&["HINCRBY", "myhash", "name", "1"]), &kv_store);
+    assert_eq!(result.unwrap(), b"-ERR hash value is not an integer\r\n");
+}
+
+#[test]
+fn test_hincrby_invalid_increment() {
+    let kv_store = new_kv_store();
+    let result = process_hincrby(&parts(&["HINCRBY", "myhash", "counter", "1.5"]), &kv_store);
+    assert_eq!(result.unwrap(), b"-ERR value is not an integer or out of range\r\n");
+}
+
+#[test]
+fn test_hincrby_overflow() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "big", &i64::MAX.to_string()]), &kv_store).unwrap();
+
+    let result = process_hincrby(&parts(&["HINCRBY", "myhash", "big", "1"]), &kv_store);
+    assert_eq!(result.unwrap(), b"-ERR increment or decrement would overflow\r\n");
+}
+
+#[test]
+fn test_hincrby_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hincrby(&parts(&["HINCRBY", "strkey", "f", "1"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== HINCRBYFLOAT Tests ====================
+
+#[test]
+fn test_hincrbyfloat() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "price", "10.5"]), &kv_store).unwrap();
+
+    let result = process_hincrbyfloat(&parts(&["HINCRBYFLOAT", "myhash", "price", "0.25"]), &kv_store);
+    assert_eq!(result.unwrap(), b"$5\r\n10.75\r\n");
+}
+
+#[test]
+fn test_hincrbyfloat_creates_field() {
+    let kv_store = new_kv_store();
+    let result = process_hincrbyfloat(&parts(&["HINCRBYFLOAT", "myhash", "f", "3"]), &kv_store);
+    assert_eq!(result.unwrap(), b"$1\r\n3\r\n");
+}
+
+#[test]
+fn test_hincrbyfloat_large_value_uses_exponent() {
+    let kv_store = new_kv_store();
+    let result = process_hincrbyfloat(&parts(&["HINCRBYFLOAT", "myhash", "f", "1e300"]), &kv_store);
+    assert_eq!(result.unwrap(), b"$6\r\n1e+300\r\n");
+    // The stored value is the same short form
+    let result = process_hget(&parts(&["HGET", "myhash", "f"]), &kv_store);
+    assert_eq!(result.unwrap(), b"$6\r\n1e+300\r\n");
+}
+
+#[test]
+fn test_hincrbyfloat_invalid_values() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "name", "bob"]), &kv_store).unwrap();
+
+    let result = process_hincrbyfloat(&parts(&["HINCRBYFLOAT", "myhash", "name", "1"]), &kv_store);
+    assert_eq!(result.unwrap(), b"-ERR hash value is not a float\r\n");
+
+    let result = process_hincrbyfloat(&parts(&["HINCRBYFLOAT", "myhash", "f", "abc"]), &kv_store);
+    assert_eq!(result.unwrap(), b"-ERR value is not a valid float\r\n");
+}
+
+// ==================== HSETNX Tests ====================
+
+#[test]
+fn test_hsetnx_only_sets_absent_field() {
+    let kv_store = new_kv_store();
+
+    let result = process_hsetnx(&parts(&["HSETNX", "myhash", "f1", "first"]), &kv_store);
+    assert_eq!(result.unwrap(), b":1\r\n");
+
+    let result = process_hsetnx(&parts(&["HSETNX", "myhash", "f1", "second"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+
+    let stored = process_hget(&parts(&["HGET", "myhash", "f1"]), &kv_store).unwrap();
+    assert_eq!(stored, b"$5\r\nfirst\r\n");
+}
+
+#[test]
+fn test_hsetnx_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hsetnx(&parts(&["HSETNX", "strkey", "f", "v"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== HRANDFIELD Tests ====================
+
+// Returns the bulk strings of a flat array in reply order
+fn array_elements(bytes: &[u8]) -> Vec<String> {
+    let response = String::from_utf8_lossy(bytes);
+    response
+        .split("\r\n")
+        .skip(1)
+        .filter(|line| !line.starts_with('$') && !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn test_hrandfield_without_count() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store).unwrap();
+
+    let result = process_hrandfield(&parts(&["HRANDFIELD", "myhash"]), &kv_store).unwrap();
+    assert!(result == b"$2\r\nf1\r\n" || result == b"$2\r\nf2\r\n");
+}
+
+#[test]
+fn test_hrandfield_positive_count_is_distinct() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2", "f3", "v3"]), &kv_store).unwrap();
+
+    let result = process_hrandfield(&parts(&["HRANDFIELD", "myhash", "2"]), &kv_store).unwrap();
+    let mut fields = array_elements(&result);
+    assert_eq!(fields.len(), 2);
+    fields.dedup();
+    assert_eq!(fields.len(), 2);
+}
+
+#[test]
+fn test_hrandfield_count_larger_than_hash() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store).unwrap();
+
+    let result = process_hrandfield(&parts(&["HRANDFIELD", "myhash", "10"]), &kv_store).unwrap();
+    assert_eq!(sorted_elements(&result), vec!["f1", "f2"]);
+}
+
+#[test]
+fn test_hrandfield_negative_count_allows_repeats() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "only", "v"]), &kv_store).unwrap();
+
+    let result = process_hrandfield(&parts(&["HRANDFIELD", "myhash", "-5"]), &kv_store).unwrap();
+    assert_eq!(array_elements(&result), vec!["only"; 5]);
+}
+
+#[test]
+fn test_hrandfield_with_values() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store).unwrap();
+
+    let result = process_hrandfield(&parts(&["HRANDFIELD", "myhash", "2", "WITHVALUES"]), &kv_store).unwrap();
+    let elements = array_elements(&result);
+    assert_eq!(elements.len(), 4);
+    for pair in elements.chunks(2) {
+        assert_eq!(pair[0].replace('f', "v"), pair[1]);
+    }
+}
+
+#[test]
+fn test_hrandfield_zero_count() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1"]), &kv_store).unwrap();
+
+    let result = process_hrandfield(&parts(&["HRANDFIELD", "myhash", "0"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_hrandfield_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let single = process_hrandfield(&parts(&["HRANDFIELD", "nokey"]), &kv_store);
+    assert_eq!(single.unwrap(), b"$-1\r\n");
+    let counted = process_hrandfield(&parts(&["HRANDFIELD", "nokey", "3"]), &kv_store);
+    assert_eq!(counted.unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_hrandfield_invalid_arguments() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1"]), &kv_store).unwrap();
+
+    let bad_count = process_hrandfield(&parts(&["HRANDFIELD", "myhash", "x"]), &kv_store).unwrap();
+    assert!(bad_count.starts_with(b"-ERR"));
+    let bad_option = process_hrandfield(&parts(&["HRANDFIELD", "myhash", "1", "WITHKEYS"]), &kv_store).unwrap();
+    assert!(bad_option.starts_with(b"-ERR syntax error"));
+}
+
+#[test]
+fn test_hrandfield_rejects_huge_counts() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1"]), &kv_store).unwrap();
+    for count in ["-9223372036854775808", "9223372036854775807"] {
+        let result = process_hrandfield(&parts(&["HRANDFIELD", "myhash", count]), &kv_store).unwrap();
+        assert_eq!(result, b"-ERR value is out of range\r\n");
+    }
+}
+
+#[test]
+fn test_hrandfield_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hrandfield(&parts(&["HRANDFIELD", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== HSCAN Tests ====================
+
+// Splits an HSCAN reply into its cursor and the sorted field/value lines
+fn scan_reply(bytes: &[u8]) -> (String, Vec<String>) {
+    let lines = array_elements(bytes);
+    let cursor = lines[0].clone();
+    let mut rest: Vec<String> = lines[1..].iter().filter(|l| !l.starts_with('*')).cloned().collect();
+    rest.sort();
+    (cursor, rest)
+}
+
+#[test]
+fn test_hscan_returns_all_fields() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2"]), &kv_store).unwrap();
+
+    let result = process_hscan(&parts(&["HSCAN", "myhash", "0"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"*2\r\n$1\r\n0\r\n*4\r\n"));
+    let (cursor, elements) = scan_reply(&result);
+    assert_eq!(cursor, "0");
+    assert_eq!(elements, vec!["f1", "f2", "v1", "v2"]);
+}
+
+#[test]
+fn test_hscan_match_pattern() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "name", "a", "nick", "b", "age", "c"]), &kv_store).unwrap();
+
+    let result = process_hscan(&parts(&["HSCAN", "myhash", "0", "MATCH", "n*"]), &kv_store).unwrap();
+    let (_, elements) = scan_reply(&result);
+    assert_eq!(elements, vec!["a", "b", "name", "nick"]);
+}
+
+#[test]
+fn test_hscan_match_and_count() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1", "f2", "v2", "g1", "v3"]), &kv_store).unwrap();
+
+    let p = parts(&["HSCAN", "myhash", "0", "MATCH", "f[12]", "COUNT", "1"]);
+    let (cursor, elements) = scan_reply(&process_hscan(&p, &kv_store).unwrap());
+    assert_eq!(cursor, "0");
+    assert_eq!(elements, vec!["f1", "f2", "v1", "v2"]);
+}
+
+#[test]
+fn test_hscan_nonzero_cursor_is_finished() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f1", "v1"]), &kv_store).unwrap();
+
+    let result = process_hscan(&parts(&["HSCAN", "myhash", "7"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*2\r\n$1\r\n0\r\n*0\r\n");
+}
+
+#[test]
+fn test_hscan_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_hscan(&parts(&["HSCAN", "nokey", "0"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*2\r\n$1\r\n0\r\n*0\r\n");
+}
+
+#[test]
+fn test_hscan_invalid_arguments() {
+    let kv_store = new_kv_store();
+    let bad_cursor = process_hscan(&parts(&["HSCAN", "myhash", "abc"]), &kv_store).unwrap();
+    assert!(bad_cursor.starts_with(b"-ERR invalid cursor"));
+    let bad_count = process_hscan(&parts(&["HSCAN", "myhash", "0", "COUNT", "0"]), &kv_store).unwrap();
+    assert!(bad_count.starts_with(b"-ERR syntax error"));
+    let dangling = process_hscan(&parts(&["HSCAN", "myhash", "0", "MATCH"]), &kv_store).unwrap();
+    assert!(dangling.starts_with(b"-ERR syntax error"));
+    assert!(process_hscan(&parts(&["HSCAN", "myhash"]), &kv_store).is_err());
+}
+
+#[test]
+fn test_hscan_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_hscan(&parts(&["HSCAN", "strkey", "0"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== TYPE Tests ====================
+
+#[test]
+fn test_type_hash() {
+    let kv_store = new_kv_store();
+    process_hset(&parts(&["HSET", "myhash", "f", "v"]), &kv_store).unwrap();
+
+    let result = process_type(&parts(&["TYPE", "myhash"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+hash\r\n");
+}
@@ -0,0 +1,83 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use redis_cache::commands::process_info;
+use redis_cache::models::{new_databases, Databases, RedisData, RedisValue, ReplicationInfo, ServerInfo};
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn new_server_info() -> Arc<Mutex<ServerInfo>> {
+    let mut info = ServerInfo::new(ReplicationInfo::new("master".to_string()));
+    info.tcp_port = 6380;
+    Arc::new(Mutex::new(info))
+}
+
+fn info(args: &[&str], databases: &Databases) -> String {
+    let reply = process_info(&parts(args), &new_server_info(), databases).unwrap();
+    String::from_utf8(reply).unwrap()
+}
+
+fn insert_string(databases: &Databases, db: usize, key: &str, expires_at: Option<Instant>) {
+    databases[db].insert(key.to_string(), RedisValue::new(RedisData::String("v".into()), expires_at));
+}
+
+// ==================== Section Selection Tests ====================
+
+#[test]
+fn test_info_without_args_has_every_section() {
+    let output = info(&["INFO"], &new_databases(1));
+    let server = output.find("# Server\r\n").unwrap();
+    let replication = output.find("# Replication\r\n").unwrap();
+    let keyspace = output.find("# Keyspace\r\n").unwrap();
+    assert!(server < replication && replication < keyspace);
+}
+
+#[test]
+fn test_info_single_section() {
+    let output = info(&["INFO", "server"], &new_databases(1));
+    assert!(output.contains("# Server\r\n"));
+    assert!(!output.contains("# Replication"));
+    assert!(!output.contains("# Keyspace"));
+
+    let output = info(&["INFO", "REPLICATION"], &new_databases(1));
+    assert!(output.contains("role:master\r\n"));
+    assert!(!output.contains("# Server"));
+}
+
+#[test]
+fn test_info_several_sections_listed_once() {
+    let output = info(&["INFO", "keyspace", "server", "keyspace"], &new_databases(1));
+    assert_eq!(output.matches("# Keyspace").count(), 1);
+    assert!(output.find("# Keyspace").unwrap() < output.find("# Server").unwrap());
+    assert!(!output.contains("# Replication"));
+}
+
+#[test]
+fn test_info_unknown_section_is_empty() {
+    assert_eq!(info(&["INFO", "bogus"], &new_databases(1)), "$0\r\n\r\n");
+}
+
+// ==================== Section Content Tests ====================
+
+#[test]
+fn test_info_server_fields() {
+    let output = info(&["INFO", "server"], &new_databases(1));
+    assert!(output.contains("redis_version:7.2.0\r\n"));
+    assert!(output.contains(&format!("os:{} {}\r\n", std::env::consts::OS, std::env::consts::ARCH)));
+    assert!(output.contains("tcp_port:6380\r\n"));
+    assert!(output.contains("uptime_in_seconds:0\r\n"));
+    assert!(output.contains("uptime_in_days:0\r\n"));
+}
+
+#[test]
+fn test_info_keyspace_counts_keys_per_database() {
+    let databases = new_databases(3);
+    insert_string(&databases, 0, "a", None);
+    insert_string(&databases, 0, "b", Some(Instant::now() + Duration::from_secs(60)));
+    insert_string(&databases, 2, "c", None);
+
+    let output = info(&["INFO", "keyspace"], &databases);
+    assert_eq!(output.trim_start_matches(|c| c != '#'), "# Keyspace\r\ndb0:keys=2,expires=1\r\ndb2:keys=1,expires=0\r\n\r\n");
+}
@@ -1,15 +1,14 @@
 use std::sync::{Arc, Mutex};
-use std::collections::{HashMap, VecDeque};
-use tokio::sync::mpsc;
+use std::collections::HashMap;
 
-use redis_cache::models::{ListDir, RedisData, RedisValue};
+use redis_cache::models::{ListDir, RedisData, RedisValue, KvStore, Store, WaitingRoom};
 use redis_cache::commands::{process_push, process_lrange, process_llen, process_pop, process_blpop};
 
-fn new_kv_store() -> Arc<Mutex<HashMap<String, RedisValue>>> {
-    Arc::new(Mutex::new(HashMap::new()))
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
 }
 
-fn new_waiting_room() -> Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>> {
+fn new_waiting_room() -> WaitingRoom {
     Arc::new(Mutex::new(HashMap::new()))
 }
 
@@ -28,7 +27,7 @@ fn test_rpush_new_list() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), b":1\r\n");
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("mylist").unwrap();
     match &stored.data {
         RedisData::List(list) => {
@@ -49,7 +48,7 @@ fn test_rpush_existing_list() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), b":2\r\n");
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("mylist").unwrap();
     match &stored.data {
         RedisData::List(list) => {
@@ -68,7 +67,7 @@ fn test_rpush_multiple_values() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), b":3\r\n");
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("mylist").unwrap();
     match &stored.data {
         RedisData::List(list) => {
@@ -85,10 +84,10 @@ fn test_rpush_wrong_type() {
 
     // Create a string key first
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mykey".to_string(),
-            RedisValue::new(RedisData::String("value".to_string()), None),
+            RedisValue::new(RedisData::String("value".into()), None),
         );
     }
 
@@ -127,7 +126,7 @@ fn test_lpush_prepends() {
     process_push(&parts(&["LPUSH", "mylist", "value1"]), &kv_store, &waiting_room, ListDir::L).unwrap();
     process_push(&parts(&["LPUSH", "mylist", "value2"]), &kv_store, &waiting_room, ListDir::L).unwrap();
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("mylist").unwrap();
     match &stored.data {
         RedisData::List(list) => {
@@ -145,7 +144,7 @@ fn test_lpush_multiple_values() {
     let p = parts(&["LPUSH", "mylist", "a", "b", "c"]);
     process_push(&p, &kv_store, &waiting_room, ListDir::L).unwrap();
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("mylist").unwrap();
     match &stored.data {
         RedisData::List(list) => {
@@ -161,7 +160,7 @@ fn test_lpush_multiple_values() {
 fn test_lrange_full_list() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -182,7 +181,7 @@ fn test_lrange_full_list() {
 fn test_lrange_partial() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -203,7 +202,7 @@ fn test_lrange_partial() {
 fn test_lrange_negative_indices() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -233,7 +232,7 @@ fn test_lrange_nonexistent_key() {
 fn test_lrange_out_of_bounds() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(RedisData::List(vec!["a".to_string()]), None),
@@ -250,7 +249,7 @@ fn test_lrange_out_of_bounds() {
 fn test_lrange_start_greater_than_end() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(RedisData::List(vec!["a".to_string(), "b".to_string(), "c".to_string()]), None),
@@ -267,7 +266,7 @@ fn test_lrange_start_greater_than_end() {
 fn test_lrange_single_element() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(RedisData::List(vec!["only".to_string()]), None),
@@ -284,10 +283,10 @@ fn test_lrange_single_element() {
 fn test_lrange_wrong_type() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "strkey".to_string(),
-            RedisValue::new(RedisData::String("value".to_string()), None),
+            RedisValue::new(RedisData::String("value".into()), None),
         );
     }
 
@@ -303,7 +302,7 @@ fn test_lrange_wrong_type() {
 fn test_llen_existing_list() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -332,7 +331,7 @@ fn test_llen_nonexistent_key() {
 fn test_llen_empty_list() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "emptylist".to_string(),
             RedisValue::new(RedisData::List(vec![]), None),
@@ -349,10 +348,10 @@ fn test_llen_empty_list() {
 fn test_llen_wrong_type() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "strkey".to_string(),
-            RedisValue::new(RedisData::String("value".to_string()), None),
+            RedisValue::new(RedisData::String("value".into()), None),
         );
     }
 
@@ -368,7 +367,7 @@ fn test_llen_wrong_type() {
 fn test_lpop_single() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -383,7 +382,7 @@ fn test_lpop_single() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), b"$1\r\na\r\n");
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("mylist").unwrap();
     match &stored.data {
         RedisData::List(list) => {
@@ -397,7 +396,7 @@ fn test_lpop_single() {
 fn test_lpop_with_count() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -427,7 +426,7 @@ fn test_lpop_nonexistent_key() {
 fn test_lpop_empty_list() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(RedisData::List(vec![]), None),
@@ -444,7 +443,7 @@ fn test_lpop_empty_list() {
 fn test_lpop_removes_empty_list() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(RedisData::List(vec!["only".to_string()]), None),
@@ -454,7 +453,7 @@ fn test_lpop_removes_empty_list() {
     let p = parts(&["LPOP", "mylist"]);
     process_pop(&p, &kv_store, ListDir::L).unwrap();
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     assert!(map.get("mylist").is_none());
 }
 
@@ -462,7 +461,7 @@ fn test_lpop_removes_empty_list() {
 fn test_lpop_count_exceeds_list_size() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(RedisData::List(vec!["a".to_string(), "b".to_string()]), None),
@@ -477,7 +476,7 @@ fn test_lpop_count_exceeds_list_size() {
     assert_eq!(result.unwrap(), expected.to_vec());
 
     // List should be removed
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     assert!(map.get("mylist").is_none());
 }
 
@@ -487,7 +486,7 @@ fn test_lpop_count_exceeds_list_size() {
 fn test_rpop_single() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -502,7 +501,7 @@ fn test_rpop_single() {
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), b"$1\r\nc\r\n");
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("mylist").unwrap();
     match &stored.data {
         RedisData::List(list) => {
@@ -516,7 +515,7 @@ fn test_rpop_single() {
 fn test_rpop_with_count() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -541,7 +540,7 @@ async fn test_blpop_existing_list() {
     let kv_store = new_kv_store();
     let waiting_room = new_waiting_room();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(
@@ -600,7 +599,7 @@ async fn test_blpop_zero_timeout_with_existing_data() {
     let kv_store = new_kv_store();
     let waiting_room = new_waiting_room();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mylist".to_string(),
             RedisValue::new(RedisData::List(vec!["immediate".to_string()]), None),
@@ -614,6 +613,24 @@ async fn test_blpop_zero_timeout_with_existing_data() {
     assert_eq!(result.unwrap(), expected.to_vec());
 }
 
+#[tokio::test]
+async fn test_blpop_rejects_bad_timeouts() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let result = process_blpop(&parts(&["BLPOP", "mylist", "-1"]), &kv_store, &waiting_room).await.unwrap();
+    assert_eq!(result, b"-ERR timeout is negative\r\n");
+    for bad in ["soon", "inf", "NaN"] {
+        let result = process_blpop(&parts(&["BLPOP", "mylist", bad]), &kv_store, &waiting_room).await.unwrap();
+        assert_eq!(result, b"-ERR timeout is not a float or out of range\r\n");
+    }
+    // Too large to turn into a deadline
+    let result = process_blpop(&parts(&["BLPOP", "mylist", "1e300"]), &kv_store, &waiting_room).await.unwrap();
+    assert_eq!(result, b"-ERR timeout is out of range\r\n");
+    // Nothing was left waiting on the key
+    assert!(waiting_room.lock().unwrap().get("mylist").is_none_or(|queue| queue.is_empty()));
+}
+
 #[tokio::test]
 async fn test_blpop_indefinite_timeout_wakeup() {
     // Test with 0 timeout (indefinite) - should block until data arrives
@@ -715,7 +732,7 @@ async fn test_concurrent_list_push() {
         handle.await.unwrap();
     }
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let list = map.get("sharedlist").unwrap();
     match &list.data {
         RedisData::List(items) => {
@@ -732,7 +749,7 @@ async fn test_concurrent_lpop() {
     let num_poppers = 10;
 
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         let items: Vec<String> = (0..num_items).map(|i| format!("item{}", i)).collect();
         map.insert("poplist".to_string(), RedisValue::new(RedisData::List(items), None));
     }
@@ -768,7 +785,7 @@ async fn test_concurrent_lpop() {
     let collected = popped_items.lock().unwrap();
     assert_eq!(collected.len(), num_items, "All items should be popped exactly once");
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     assert!(map.get("poplist").is_none(), "List should be removed when empty");
 }
 
@@ -802,7 +819,7 @@ async fn test_concurrent_rpush_lpush() {
     rpush_handle.await.unwrap();
     lpush_handle.await.unwrap();
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let list = map.get("duallist").unwrap();
     match &list.data {
         RedisData::List(items) => {
@@ -825,7 +842,7 @@ async fn test_blpop_multiple_keys_first_available() {
 
     // Populate the first list (since implementation only checks first key)
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "list1".to_string(),
             RedisValue::new(RedisData::List(vec!["from_list1".to_string()]), None),
@@ -1,18 +1,6 @@
-use std::sync::{Arc, Mutex};
-use std::collections::{HashMap, VecDeque};
-use tokio::sync::mpsc;
-
-use redis_cache::models::RedisValue;
+use redis_cache::models::{ClientState, ServerState};
 use redis_cache::parser::parse_resp;
 
-fn new_kv_store() -> Arc<Mutex<HashMap<String, RedisValue>>> {
-    Arc::new(Mutex::new(HashMap::new()))
-}
-
-fn new_waiting_room() -> Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>> {
-    Arc::new(Mutex::new(HashMap::new()))
-}
-
 // Helper to create raw RESP format from parts
 fn make_resp(parts: &[&str]) -> Vec<u8> {
     let mut result = format!("*{}\r\n", parts.len());
@@ -26,51 +14,72 @@ fn make_resp(parts: &[&str]) -> Vec<u8> {
 
 #[tokio::test]
 async fn test_parser_ping() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["PING"]);
+    let buffer = make_resp(&["PING"]);
     let bytes_read = buffer.len();
 
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"+PONG\r\n");
 }
 
 #[tokio::test]
 async fn test_parser_ping_lowercase() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["ping"]);
+    let buffer = make_resp(&["ping"]);
     let bytes_read = buffer.len();
 
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"+PONG\r\n");
 }
 
+#[tokio::test]
+async fn test_parser_inline_commands() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    let buffer = b"PING\r\n".to_vec();
+    let bytes_read = buffer.len();
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
+    assert_eq!(result, b"+PONG\r\n");
+
+    let buffer = b"set greeting \"hello there\"\r\n".to_vec();
+    let bytes_read = buffer.len();
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
+    assert_eq!(result, b"+OK\r\n");
+
+    let buffer = b"GET greeting\r\n".to_vec();
+    let bytes_read = buffer.len();
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
+    assert_eq!(result, b"$11\r\nhello there\r\n");
+}
+
 // ==================== ECHO Tests ====================
 
 #[tokio::test]
 async fn test_parser_echo() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["ECHO", "hello"]);
+    let buffer = make_resp(&["ECHO", "hello"]);
     let bytes_read = buffer.len();
 
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"$5\r\nhello\r\n");
 }
 
 #[tokio::test]
 async fn test_parser_echo_strawberry() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["ECHO", "strawberry"]);
+    let buffer = make_resp(&["ECHO", "strawberry"]);
     let bytes_read = buffer.len();
 
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"$10\r\nstrawberry\r\n");
 }
 
@@ -78,86 +87,132 @@ async fn test_parser_echo_strawberry() {
 
 #[tokio::test]
 async fn test_parser_set_get() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // SET
-    let mut buffer = make_resp(&["SET", "orange", "mango"]);
+    let buffer = make_resp(&["SET", "orange", "mango"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"+OK\r\n");
 
     // GET
-    let mut buffer = make_resp(&["GET", "orange"]);
+    let buffer = make_resp(&["GET", "orange"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"$5\r\nmango\r\n");
 }
 
 #[tokio::test]
 async fn test_parser_set_with_expiry() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["SET", "banana", "pineapple", "PX", "100"]);
+    let buffer = make_resp(&["SET", "banana", "pineapple", "PX", "100"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"+OK\r\n");
 
     // GET immediately - should succeed
-    let mut buffer = make_resp(&["GET", "banana"]);
+    let buffer = make_resp(&["GET", "banana"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"$9\r\npineapple\r\n");
 
     // Wait for expiry
     tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
 
     // GET after expiry
-    let mut buffer = make_resp(&["GET", "banana"]);
+    let buffer = make_resp(&["GET", "banana"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"$-1\r\n");
 }
 
 #[tokio::test]
 async fn test_parser_get_nonexistent() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["GET", "nokey"]);
+    let buffer = make_resp(&["GET", "nokey"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"$-1\r\n");
 }
 
+#[tokio::test]
+async fn test_parser_set_non_utf8_value_keeps_arguments_aligned() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    // A lone 0xff before the expiry options mustn't shift where PX and 100 are read from
+    let buffer = b"*5\r\n$3\r\nSET\r\n$3\r\nkey\r\n$1\r\n\xff\r\n$2\r\nPX\r\n$3\r\n100\r\n".to_vec();
+    let bytes_read = buffer.len();
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
+    assert_eq!(result, b"+OK\r\n");
+
+    let map = state.databases[0].lock_all();
+    assert!(map.get("key").unwrap().expires_at.is_some());
+}
+
+#[tokio::test]
+async fn test_parser_set_get_non_utf8_value() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    let buffer = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$4\r\n\xff\x00\xfe\xc3\r\n".to_vec();
+    let result = parse_resp(&buffer, buffer.len(), &state, &mut client).await;
+    assert_eq!(result, b"+OK\r\n");
+
+    let buffer = make_resp(&["GET", "key"]);
+    let result = parse_resp(&buffer, buffer.len(), &state, &mut client).await;
+    assert_eq!(result, b"$4\r\n\xff\x00\xfe\xc3\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_set_get_10kb_value() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let value = "v".repeat(10 * 1024);
+
+    let buffer = make_resp(&["SET", "big", &value]);
+    let bytes_read = buffer.len();
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
+    assert_eq!(result, b"+OK\r\n");
+
+    let buffer = make_resp(&["GET", "big"]);
+    let bytes_read = buffer.len();
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
+    assert_eq!(result, format!("${}\r\n{}\r\n", value.len(), value).into_bytes());
+}
+
 // ==================== TYPE Tests ====================
 
 #[tokio::test]
 async fn test_parser_type_string() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // SET creates a string
-    let mut buffer = make_resp(&["SET", "banana", "blueberry"]);
+    let buffer = make_resp(&["SET", "banana", "blueberry"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // TYPE
-    let mut buffer = make_resp(&["TYPE", "banana"]);
+    let buffer = make_resp(&["TYPE", "banana"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"+string\r\n");
 }
 
 #[tokio::test]
 async fn test_parser_type_none() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["TYPE", "missing_key"]);
+    let buffer = make_resp(&["TYPE", "missing_key"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"+none\r\n");
 }
 
@@ -165,90 +220,90 @@ async fn test_parser_type_none() {
 
 #[tokio::test]
 async fn test_parser_rpush_lrange() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // RPUSH
-    let mut buffer = make_resp(&["RPUSH", "pear", "mango"]);
+    let buffer = make_resp(&["RPUSH", "pear", "mango"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b":1\r\n");
 
     // RPUSH more
-    let mut buffer = make_resp(&["RPUSH", "pear", "banana", "grape"]);
+    let buffer = make_resp(&["RPUSH", "pear", "banana", "grape"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b":3\r\n");
 
     // LRANGE
-    let mut buffer = make_resp(&["LRANGE", "pear", "0", "-1"]);
+    let buffer = make_resp(&["LRANGE", "pear", "0", "-1"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     // Should contain all 3 items
     assert!(result.starts_with(b"*3\r\n"));
 }
 
 #[tokio::test]
 async fn test_parser_lpush() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // LPUSH
-    let mut buffer = make_resp(&["LPUSH", "grape", "raspberry"]);
+    let buffer = make_resp(&["LPUSH", "grape", "raspberry"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b":1\r\n");
 
     // LPUSH more (prepends)
-    let mut buffer = make_resp(&["LPUSH", "grape", "blueberry", "grape"]);
+    let buffer = make_resp(&["LPUSH", "grape", "blueberry", "grape"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b":3\r\n");
 }
 
 #[tokio::test]
 async fn test_parser_llen() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // Create list
-    let mut buffer = make_resp(&["RPUSH", "orange", "a", "b", "c", "d"]);
+    let buffer = make_resp(&["RPUSH", "orange", "a", "b", "c", "d"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // LLEN
-    let mut buffer = make_resp(&["LLEN", "orange"]);
+    let buffer = make_resp(&["LLEN", "orange"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b":4\r\n");
 
     // LLEN nonexistent
-    let mut buffer = make_resp(&["LLEN", "missing_key"]);
+    let buffer = make_resp(&["LLEN", "missing_key"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b":0\r\n");
 }
 
 #[tokio::test]
 async fn test_parser_lpop() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // Create list
-    let mut buffer = make_resp(&["RPUSH", "mango", "pear", "grape", "pineapple"]);
+    let buffer = make_resp(&["RPUSH", "mango", "pear", "grape", "pineapple"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // LPOP single
-    let mut buffer = make_resp(&["LPOP", "mango"]);
+    let buffer = make_resp(&["LPOP", "mango"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"$4\r\npear\r\n");
 
     // LPOP with count
-    let mut buffer = make_resp(&["LPOP", "mango", "2"]);
+    let buffer = make_resp(&["LPOP", "mango", "2"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert!(result.starts_with(b"*2\r\n"));
 }
 
@@ -256,30 +311,30 @@ async fn test_parser_lpop() {
 
 #[tokio::test]
 async fn test_parser_blpop_immediate() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // Create list with data
-    let mut buffer = make_resp(&["RPUSH", "mylist", "value"]);
+    let buffer = make_resp(&["RPUSH", "mylist", "value"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // BLPOP should return immediately
-    let mut buffer = make_resp(&["BLPOP", "mylist", "0"]);
+    let buffer = make_resp(&["BLPOP", "mylist", "0"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert!(result.starts_with(b"*2\r\n"));
 }
 
 #[tokio::test]
 async fn test_parser_blpop_timeout() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // BLPOP on empty list with timeout
-    let mut buffer = make_resp(&["BLPOP", "nolist", "0.1"]);
+    let buffer = make_resp(&["BLPOP", "nolist", "0.1"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"*-1\r\n");
 }
 
@@ -287,12 +342,12 @@ async fn test_parser_blpop_timeout() {
 
 #[tokio::test]
 async fn test_parser_xadd_explicit_id() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["XADD", "strawberry", "0-1", "foo", "bar"]);
+    let buffer = make_resp(&["XADD", "strawberry", "0-1", "foo", "bar"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     let response = String::from_utf8_lossy(&result);
     assert!(response.contains("0-1"));
@@ -300,30 +355,30 @@ async fn test_parser_xadd_explicit_id() {
 
 #[tokio::test]
 async fn test_parser_xadd_type_check() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // XADD creates stream
-    let mut buffer = make_resp(&["XADD", "strawberry", "0-1", "foo", "bar"]);
+    let buffer = make_resp(&["XADD", "strawberry", "0-1", "foo", "bar"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // TYPE should be stream
-    let mut buffer = make_resp(&["TYPE", "strawberry"]);
+    let buffer = make_resp(&["TYPE", "strawberry"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
     assert_eq!(result, b"+stream\r\n");
 }
 
 #[tokio::test]
 async fn test_parser_xadd_partial_wildcard() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // 0-* should auto-generate sequence
-    let mut buffer = make_resp(&["XADD", "raspberry", "0-*", "blueberry", "pear"]);
+    let buffer = make_resp(&["XADD", "raspberry", "0-*", "blueberry", "pear"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     let response = String::from_utf8_lossy(&result);
     assert!(response.contains("0-1"));
@@ -331,26 +386,26 @@ async fn test_parser_xadd_partial_wildcard() {
 
 #[tokio::test]
 async fn test_parser_xadd_validation() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // Add first entry
-    let mut buffer = make_resp(&["XADD", "banana", "1-1", "pear", "pineapple"]);
+    let buffer = make_resp(&["XADD", "banana", "1-1", "pear", "pineapple"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // Try to add with same ID - should error
-    let mut buffer = make_resp(&["XADD", "banana", "1-1", "apple", "orange"]);
+    let buffer = make_resp(&["XADD", "banana", "1-1", "apple", "orange"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     let response = String::from_utf8_lossy(&result);
     assert!(response.contains("ERR"));
 
     // Try 0-0 - should error
-    let mut buffer = make_resp(&["XADD", "newstream", "0-0", "a", "b"]);
+    let buffer = make_resp(&["XADD", "newstream", "0-0", "a", "b"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     let response = String::from_utf8_lossy(&result);
     assert!(response.contains("ERR") && response.contains("0-0"));
@@ -358,22 +413,22 @@ async fn test_parser_xadd_validation() {
 
 #[tokio::test]
 async fn test_parser_xrange() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // Add entries
-    let mut buffer = make_resp(&["XADD", "orange", "0-1", "blueberry", "mango"]);
+    let buffer = make_resp(&["XADD", "orange", "0-1", "blueberry", "mango"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
-    let mut buffer = make_resp(&["XADD", "orange", "0-2", "strawberry", "orange"]);
+    let buffer = make_resp(&["XADD", "orange", "0-2", "strawberry", "orange"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // XRANGE full
-    let mut buffer = make_resp(&["XRANGE", "orange", "-", "+"]);
+    let buffer = make_resp(&["XRANGE", "orange", "-", "+"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // Should have 2 entries
     let response = String::from_utf8_lossy(&result);
@@ -383,18 +438,18 @@ async fn test_parser_xrange() {
 
 #[tokio::test]
 async fn test_parser_xread() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // Add entry
-    let mut buffer = make_resp(&["XADD", "orange", "0-1", "temperature", "36"]);
+    let buffer = make_resp(&["XADD", "orange", "0-1", "temperature", "36"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // XREAD
-    let mut buffer = make_resp(&["XREAD", "streams", "orange", "0-0"]);
+    let buffer = make_resp(&["XREAD", "streams", "orange", "0-0"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     let response = String::from_utf8_lossy(&result);
     assert!(response.contains("orange"));
@@ -403,22 +458,22 @@ async fn test_parser_xread() {
 
 #[tokio::test]
 async fn test_parser_xread_multiple_streams() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
     // Add to two streams
-    let mut buffer = make_resp(&["XADD", "apple", "0-1", "temperature", "0"]);
+    let buffer = make_resp(&["XADD", "apple", "0-1", "temperature", "0"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
-    let mut buffer = make_resp(&["XADD", "blueberry", "0-2", "humidity", "1"]);
+    let buffer = make_resp(&["XADD", "blueberry", "0-2", "humidity", "1"]);
     let bytes_read = buffer.len();
-    parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // XREAD both streams
-    let mut buffer = make_resp(&["XREAD", "streams", "apple", "blueberry", "0-0", "0-1"]);
+    let buffer = make_resp(&["XREAD", "streams", "apple", "blueberry", "0-0", "0-1"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     let response = String::from_utf8_lossy(&result);
     assert!(response.contains("apple"));
@@ -429,28 +484,27 @@ async fn test_parser_xread_multiple_streams() {
 
 #[tokio::test]
 async fn test_parser_concurrent_clients() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
     let num_clients = 5;
 
     let mut handles = vec![];
 
     for client_id in 0..num_clients {
-        let store = Arc::clone(&kv_store);
-        let room = Arc::clone(&waiting_room);
+        let state = state.clone();
         let handle = tokio::spawn(async move {
+            let mut client = ClientState::new();
             // Each client does PING
-            let mut buffer = make_resp(&["PING"]);
+            let buffer = make_resp(&["PING"]);
             let bytes_read = buffer.len();
-            let result = parse_resp(&mut buffer, bytes_read, &store, &room).await;
+            let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
             assert_eq!(result, b"+PONG\r\n", "Client {} PING failed", client_id);
 
             // Each client SETs a unique key
             let key = format!("key{}", client_id);
             let value = format!("value{}", client_id);
-            let mut buffer = make_resp(&["SET", &key, &value]);
+            let buffer = make_resp(&["SET", &key, &value]);
             let bytes_read = buffer.len();
-            let result = parse_resp(&mut buffer, bytes_read, &store, &room).await;
+            let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
             assert_eq!(result, b"+OK\r\n", "Client {} SET failed", client_id);
         });
         handles.push(handle);
@@ -461,20 +515,240 @@ async fn test_parser_concurrent_clients() {
     }
 
     // Verify all keys exist
-    let map = kv_store.lock().unwrap();
+    let map = state.databases[0].lock_all();
     assert_eq!(map.len(), num_clients);
 }
 
+// ==================== WATCH Tests ====================
+
+// Sends one command through the parser on behalf of `client`
+async fn send(
+    args: &[&str],
+    state: &ServerState,
+    client: &mut ClientState
+) -> Vec<u8> {
+    let buffer = make_resp(args);
+    let bytes_read = buffer.len();
+    parse_resp(&buffer, bytes_read, state, client).await
+}
+
+#[tokio::test]
+async fn test_parser_watch_unmodified_key_runs_exec() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["SET", "counter", "1"], &state, &mut client).await;
+    let result = send(&["WATCH", "counter"], &state, &mut client).await;
+    assert_eq!(result, b"+OK\r\n");
+
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["INCR", "counter"], &state, &mut client).await;
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"*1\r\n:2\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_watch_aborts_exec_when_key_modified() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let mut other = ClientState::new();
+
+    send(&["SET", "counter", "1"], &state, &mut client).await;
+    send(&["WATCH", "counter"], &state, &mut client).await;
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["INCR", "counter"], &state, &mut client).await;
+
+    // Another connection writes the watched key before EXEC
+    send(&["INCR", "counter"], &state, &mut other).await;
+
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"*-1\r\n");
+    let value = send(&["GET", "counter"], &state, &mut client).await;
+    assert_eq!(value, b"$1\r\n2\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_watch_detects_created_and_deleted_keys() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let mut other = ClientState::new();
+
+    // Key missing at WATCH time, then created
+    send(&["WATCH", "fresh"], &state, &mut client).await;
+    send(&["RPUSH", "fresh", "a"], &state, &mut other).await;
+    send(&["MULTI"], &state, &mut client).await;
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"*-1\r\n");
+
+    // Key present at WATCH time, then removed
+    send(&["WATCH", "fresh"], &state, &mut client).await;
+    send(&["LPOP", "fresh"], &state, &mut other).await;
+    send(&["MULTI"], &state, &mut client).await;
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"*-1\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_exec_clears_watched_keys() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let mut other = ClientState::new();
+
+    send(&["WATCH", "key"], &state, &mut client).await;
+    send(&["SET", "key", "1"], &state, &mut other).await;
+    send(&["MULTI"], &state, &mut client).await;
+    assert_eq!(send(&["EXEC"], &state, &mut client).await, b"*-1\r\n");
+
+    // The aborted EXEC dropped the watch, so the next transaction runs
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["GET", "key"], &state, &mut client).await;
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"*1\r\n$1\r\n1\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_unwatch_and_discard_forget_keys() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let mut other = ClientState::new();
+
+    send(&["WATCH", "a"], &state, &mut client).await;
+    let result = send(&["UNWATCH"], &state, &mut client).await;
+    assert_eq!(result, b"+OK\r\n");
+    send(&["SET", "a", "1"], &state, &mut other).await;
+    send(&["MULTI"], &state, &mut client).await;
+    assert_eq!(send(&["EXEC"], &state, &mut client).await, b"*0\r\n");
+
+    send(&["WATCH", "a"], &state, &mut client).await;
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["DISCARD"], &state, &mut client).await;
+    send(&["SET", "a", "2"], &state, &mut other).await;
+    send(&["MULTI"], &state, &mut client).await;
+    assert_eq!(send(&["EXEC"], &state, &mut client).await, b"*0\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_watch_inside_multi_is_rejected() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["MULTI"], &state, &mut client).await;
+    let result = send(&["WATCH", "a"], &state, &mut client).await;
+    assert_eq!(result, b"-ERR WATCH inside MULTI is not allowed\r\n");
+    let nested = send(&["MULTI"], &state, &mut client).await;
+    assert_eq!(nested, b"-ERR MULTI calls can not be nested\r\n");
+
+    // Neither attempt was queued
+    assert_eq!(send(&["EXEC"], &state, &mut client).await, b"*0\r\n");
+}
+
+// ==================== MULTI Validation Tests ====================
+
+#[tokio::test]
+async fn test_parser_unknown_command_in_multi_aborts_exec() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["MULTI"], &state, &mut client).await;
+    let queued = send(&["SET", "a", "1"], &state, &mut client).await;
+    assert_eq!(queued, b"+QUEUED\r\n");
+    let rejected = send(&["NOPE", "x"], &state, &mut client).await;
+    assert_eq!(rejected, b"-ERR unknown command 'NOPE', with args beginning with: 'x' \r\n");
+
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"-EXECABORT Transaction discarded because of previous errors.\r\n");
+
+    // Nothing from the aborted transaction ran
+    let value = send(&["GET", "a"], &state, &mut client).await;
+    assert_eq!(value, b"$-1\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_wrong_arity_in_multi_aborts_exec() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["MULTI"], &state, &mut client).await;
+    let exact = send(&["GET", "a", "b"], &state, &mut client).await;
+    assert_eq!(exact, b"-ERR wrong number of arguments for 'get' command\r\n");
+    let minimum = send(&["set", "a"], &state, &mut client).await;
+    assert_eq!(minimum, b"-ERR wrong number of arguments for 'set' command\r\n");
+
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert!(result.starts_with(b"-EXECABORT"));
+}
+
+#[tokio::test]
+async fn test_parser_dirty_flag_resets_for_next_transaction() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["NOPE"], &state, &mut client).await;
+    let discarded = send(&["DISCARD"], &state, &mut client).await;
+    assert_eq!(discarded, b"+OK\r\n");
+
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["PING"], &state, &mut client).await;
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"*1\r\n+PONG\r\n");
+}
+
+// ==================== Blocking Commands in EXEC Tests ====================
+
+#[tokio::test]
+async fn test_parser_blpop_in_exec_does_not_block() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["BLPOP", "missing", "0"], &state, &mut client).await;
+    let exec = send(&["EXEC"], &state, &mut client);
+    let result = tokio::time::timeout(tokio::time::Duration::from_secs(1), exec)
+        .await
+        .expect("EXEC blocked on BLPOP");
+    assert_eq!(result, b"*1\r\n*-1\r\n");
+
+    // Blocking works again once the transaction is over
+    assert!(!client.in_exec);
+}
+
+#[tokio::test]
+async fn test_parser_blpop_in_exec_pops_available_element() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["RPUSH", "list", "a"], &state, &mut client).await;
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["BLPOP", "list", "0"], &state, &mut client).await;
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"*1\r\n*2\r\n$4\r\nlist\r\n$1\r\na\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_xread_block_in_exec_does_not_block() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["XREAD", "BLOCK", "0", "STREAMS", "stream", "$"], &state, &mut client).await;
+    let exec = send(&["EXEC"], &state, &mut client);
+    let result = tokio::time::timeout(tokio::time::Duration::from_secs(1), exec)
+        .await
+        .expect("EXEC blocked on XREAD");
+    assert_eq!(result, b"*1\r\n*-1\r\n");
+}
+
 // ==================== Unknown Command Test ====================
 
 #[tokio::test]
 async fn test_parser_unknown_command() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = make_resp(&["UNKNOWNCMD", "arg"]);
+    let buffer = make_resp(&["UNKNOWNCMD", "arg"]);
     let bytes_read = buffer.len();
-    let result = parse_resp(&mut buffer, bytes_read, &kv_store, &waiting_room).await;
+    let result = parse_resp(&buffer, bytes_read, &state, &mut client).await;
 
     // Should return empty (error case)
     assert!(result.is_empty());
@@ -484,10 +758,126 @@ async fn test_parser_unknown_command() {
 
 #[tokio::test]
 async fn test_parser_empty_input() {
-    let kv_store = new_kv_store();
-    let waiting_room = new_waiting_room();
+    let state = ServerState::default();
+    let mut client = ClientState::new();
 
-    let mut buffer = vec![];
-    let result = parse_resp(&mut buffer, 0, &kv_store, &waiting_room).await;
+    let buffer = vec![];
+    let result = parse_resp(&buffer, 0, &state, &mut client).await;
     assert!(result.is_empty());
 }
+
+#[tokio::test]
+async fn test_parser_bzpop_family_in_exec_does_not_block() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["BZPOPMIN", "missing", "0"], &state, &mut client).await;
+    send(&["BZPOPMAX", "missing", "0"], &state, &mut client).await;
+    send(&["BZMPOP", "0", "1", "missing", "MIN"], &state, &mut client).await;
+    let exec = send(&["EXEC"], &state, &mut client);
+    let result = tokio::time::timeout(tokio::time::Duration::from_secs(1), exec)
+        .await
+        .expect("EXEC blocked on a BZPOP command");
+    assert_eq!(result, b"*3\r\n*-1\r\n*-1\r\n*-1\r\n");
+}
+
+// ==================== CONFIG Tests ====================
+
+#[tokio::test]
+async fn test_parser_config_get_set() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    let set = send(&["CONFIG", "SET", "dir", "/data"], &state, &mut client).await;
+    assert_eq!(set, b"+OK\r\n");
+    let get = send(&["CONFIG", "GET", "dir"], &state, &mut client).await;
+    assert_eq!(get, b"*2\r\n$3\r\ndir\r\n$5\r\n/data\r\n");
+}
+
+// ==================== SELECT Tests ====================
+
+#[tokio::test]
+async fn test_parser_select_isolates_databases() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let mut other = ClientState::new();
+
+    send(&["SET", "key", "db0"], &state, &mut client).await;
+    let result = send(&["SELECT", "3"], &state, &mut client).await;
+    assert_eq!(result, b"+OK\r\n");
+    assert_eq!(send(&["GET", "key"], &state, &mut client).await, b"$-1\r\n");
+    send(&["SET", "key", "db3"], &state, &mut client).await;
+
+    // Other connections stay on database 0
+    let value = send(&["GET", "key"], &state, &mut other).await;
+    assert_eq!(value, b"$3\r\ndb0\r\n");
+    assert_eq!(state.databases[3].len(), 1);
+}
+
+#[tokio::test]
+async fn test_parser_select_invalid_index_keeps_database() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    assert_eq!(send(&["SELECT", "1"], &state, &mut client).await, b"+OK\r\n");
+    for index in ["16", "-1", "abc"] {
+        let result = send(&["SELECT", index], &state, &mut client).await;
+        assert!(result.starts_with(b"-ERR"), "SELECT {} should fail", index);
+        assert_eq!(client.db_index, 1);
+    }
+    assert_eq!(send(&["SELECT", "0"], &state, &mut client).await, b"+OK\r\n");
+    assert_eq!(client.db_index, 0);
+}
+
+#[tokio::test]
+async fn test_parser_flushdb_only_clears_selected_database() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["SET", "key", "db0"], &state, &mut client).await;
+    send(&["SELECT", "1"], &state, &mut client).await;
+    send(&["SET", "key", "db1"], &state, &mut client).await;
+    assert_eq!(send(&["FLUSHDB"], &state, &mut client).await, b"+OK\r\n");
+
+    assert_eq!(send(&["DBSIZE"], &state, &mut client).await, b":0\r\n");
+    send(&["SELECT", "0"], &state, &mut client).await;
+    assert_eq!(send(&["GET", "key"], &state, &mut client).await, b"$3\r\ndb0\r\n");
+}
+
+#[tokio::test]
+async fn test_parser_select_inside_multi() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    send(&["MULTI"], &state, &mut client).await;
+    send(&["SELECT", "1"], &state, &mut client).await;
+    send(&["SET", "key", "v"], &state, &mut client).await;
+    let result = send(&["EXEC"], &state, &mut client).await;
+    assert_eq!(result, b"*2\r\n+OK\r\n+OK\r\n");
+
+    assert!(state.databases[1].contains_key("key"));
+    assert!(!state.databases[0].contains_key("key"));
+    assert_eq!(client.db_index, 1);
+}
+
+#[tokio::test]
+async fn test_parser_watch_checks_the_watched_database() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let mut other = ClientState::new();
+
+    send(&["WATCH", "key"], &state, &mut client).await;
+    send(&["SELECT", "2"], &state, &mut client).await;
+    // Writing the same name in database 2 doesn't touch the watched key in database 0
+    send(&["SET", "key", "1"], &state, &mut client).await;
+    send(&["MULTI"], &state, &mut client).await;
+    assert_eq!(send(&["EXEC"], &state, &mut client).await, b"*0\r\n");
+
+    send(&["WATCH", "key"], &state, &mut client).await;
+    send(&["SELECT", "0"], &state, &mut client).await;
+    send(&["SELECT", "2"], &state, &mut other).await;
+    send(&["SET", "key", "2"], &state, &mut other).await;
+    send(&["MULTI"], &state, &mut client).await;
+    assert_eq!(send(&["EXEC"], &state, &mut client).await, b"*-1\r\n");
+}
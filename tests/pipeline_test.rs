@@ -0,0 +1,107 @@
+use redis_cache::models::{ClientState, ServerState};
+use redis_cache::parser::parse_pipeline;
+use redis_cache::utils::decoder::split_frame;
+
+fn make_resp(parts: &[&str]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", parts.len());
+    for part in parts {
+        result.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+    }
+    result.into_bytes()
+}
+
+// ==================== Frame Splitting Tests ====================
+
+#[test]
+fn test_split_frame_takes_one_command() {
+    let mut raw = make_resp(&["PING"]);
+    raw.extend(make_resp(&["ECHO", "hi"]));
+
+    let (frame, rest) = split_frame(&raw).unwrap();
+    assert_eq!(frame, make_resp(&["PING"]));
+    let (frame, rest) = split_frame(rest).unwrap();
+    assert_eq!(frame, make_resp(&["ECHO", "hi"]));
+    assert!(rest.is_empty());
+    assert!(split_frame(rest).is_none());
+}
+
+#[test]
+fn test_split_frame_leaves_partial_command() {
+    let mut raw = make_resp(&["PING"]);
+    raw.extend(b"*2\r\n$4\r\nECHO\r\n$2\r\nh");
+
+    let (_, rest) = split_frame(&raw).unwrap();
+    assert!(split_frame(rest).is_none());
+}
+
+#[test]
+fn test_split_frame_inline_commands() {
+    let (frame, rest) = split_frame(b"PING\r\nECHO hi\r\n").unwrap();
+    assert_eq!(frame, b"PING\r\n");
+    assert_eq!(rest, b"ECHO hi\r\n");
+}
+
+// ==================== Pipeline Tests ====================
+
+#[tokio::test]
+async fn test_pipeline_of_ten_commands() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    let mut buffer = Vec::new();
+    for i in 0..5 {
+        buffer.extend(make_resp(&["SET", &format!("key{}", i), &i.to_string()]));
+        buffer.extend(make_resp(&["INCR", &format!("key{}", i)]));
+    }
+
+    let (replies, consumed) = parse_pipeline(&buffer, &state, &mut client).await;
+    assert_eq!(consumed, buffer.len());
+    let expected: Vec<u8> = (0..5)
+        .flat_map(|i| format!("+OK\r\n:{}\r\n", i + 1).into_bytes())
+        .collect();
+    assert_eq!(replies, expected);
+}
+
+#[tokio::test]
+async fn test_pipeline_stops_before_partial_command() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    let mut buffer = make_resp(&["SET", "a", "1"]);
+    let whole = buffer.len();
+    buffer.extend(b"*2\r\n$3\r\nGET\r\n$1");
+
+    let (replies, consumed) = parse_pipeline(&buffer, &state, &mut client).await;
+    assert_eq!(replies, b"+OK\r\n");
+    assert_eq!(consumed, whole);
+
+    // The rest of the GET arrives with the next read
+    let mut next = buffer[consumed..].to_vec();
+    next.extend(b"\r\na\r\n");
+    let (replies, consumed) = parse_pipeline(&next, &state, &mut client).await;
+    assert_eq!(replies, b"$1\r\n1\r\n");
+    assert_eq!(consumed, next.len());
+}
+
+#[tokio::test]
+async fn test_pipeline_through_multi_exec() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    let mut buffer = make_resp(&["MULTI"]);
+    buffer.extend(make_resp(&["SET", "a", "1"]));
+    buffer.extend(make_resp(&["INCR", "a"]));
+    buffer.extend(make_resp(&["EXEC"]));
+
+    let (replies, _) = parse_pipeline(&buffer, &state, &mut client).await;
+    assert_eq!(replies, b"+OK\r\n+QUEUED\r\n+QUEUED\r\n*2\r\n+OK\r\n:2\r\n");
+}
+
+#[tokio::test]
+async fn test_pipeline_empty_buffer() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let (replies, consumed) = parse_pipeline(b"", &state, &mut client).await;
+    assert!(replies.is_empty());
+    assert_eq!(consumed, 0);
+}
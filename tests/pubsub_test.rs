@@ -0,0 +1,395 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use redis_cache::models::{ClientState, PubSub, ServerState, Subscribers};
+use redis_cache::commands::{
+    process_psubscribe, process_publish, process_pubsub, process_punsubscribe, process_subscribe, process_unsubscribe
+};
+use redis_cache::parser::parse_resp;
+
+fn new_pubsub() -> PubSub {
+    Arc::new(Mutex::new(Subscribers::default()))
+}
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+// A client wired up the way handle_client does it, plus the receiving end of its push channel
+fn new_subscriber() -> (ClientState, mpsc::Receiver<Vec<u8>>) {
+    let (tx, rx) = mpsc::channel(16);
+    let mut client = ClientState::new();
+    client.push_sender = Some(tx);
+    (client, rx)
+}
+
+// ==================== SUBSCRIBE Tests ====================
+
+#[test]
+fn test_subscribe_confirms_each_channel() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+
+    let result = process_subscribe(&parts(&["SUBSCRIBE", "news", "sports"]), &mut client, &pubsub).unwrap();
+    assert_eq!(
+        result,
+        b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$9\r\nsubscribe\r\n$6\r\nsports\r\n:2\r\n"
+    );
+    assert_eq!(client.subscriptions.len(), 2);
+}
+
+#[test]
+fn test_subscribe_twice_registers_once() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut client, &pubsub).unwrap();
+    let again = process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut client, &pubsub).unwrap();
+    assert_eq!(again, b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n");
+    assert_eq!(pubsub.lock().unwrap().channels.get("news").unwrap().len(), 1);
+}
+
+#[test]
+fn test_subscribe_without_push_channel_fails() {
+    let pubsub = new_pubsub();
+    let mut client = ClientState::new();
+    assert!(process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut client, &pubsub).is_err());
+    assert!(client.subscriptions.is_empty());
+}
+
+// ==================== UNSUBSCRIBE Tests ====================
+
+#[test]
+fn test_unsubscribe_named_channel() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "a", "b"]), &mut client, &pubsub).unwrap();
+
+    let result = process_unsubscribe(&parts(&["UNSUBSCRIBE", "a"]), &mut client, &pubsub).unwrap();
+    assert_eq!(result, b"*3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:1\r\n");
+    assert!(!pubsub.lock().unwrap().channels.contains_key("a"));
+    assert!(client.subscriptions.contains("b"));
+}
+
+#[test]
+fn test_unsubscribe_all() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "a", "b"]), &mut client, &pubsub).unwrap();
+
+    let result = process_unsubscribe(&parts(&["UNSUBSCRIBE"]), &mut client, &pubsub).unwrap();
+    assert_eq!(
+        result,
+        b"*3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:1\r\n*3\r\n$11\r\nunsubscribe\r\n$1\r\nb\r\n:0\r\n"
+    );
+    assert!(client.subscriptions.is_empty());
+    assert!(pubsub.lock().unwrap().channels.is_empty());
+}
+
+#[test]
+fn test_unsubscribe_when_not_subscribed() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+    let result = process_unsubscribe(&parts(&["UNSUBSCRIBE"]), &mut client, &pubsub).unwrap();
+    assert_eq!(result, b"*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n");
+}
+
+#[test]
+fn test_unsubscribe_leaves_other_clients() {
+    let pubsub = new_pubsub();
+    let (mut first, _rx1) = new_subscriber();
+    let (mut second, _rx2) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut first, &pubsub).unwrap();
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut second, &pubsub).unwrap();
+
+    process_unsubscribe(&parts(&["UNSUBSCRIBE", "news"]), &mut first, &pubsub).unwrap();
+    let delivered = process_publish(&parts(&["PUBLISH", "news", "hi"]), &pubsub).unwrap();
+    assert_eq!(delivered, b":1\r\n");
+}
+
+// ==================== PUBLISH Tests ====================
+
+#[test]
+fn test_publish_delivers_message() {
+    let pubsub = new_pubsub();
+    let (mut client, mut rx) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut client, &pubsub).unwrap();
+
+    let result = process_publish(&parts(&["PUBLISH", "news", "hello"]), &pubsub).unwrap();
+    assert_eq!(result, b":1\r\n");
+    assert_eq!(rx.try_recv().unwrap(), b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n");
+}
+
+#[test]
+fn test_publish_without_subscribers() {
+    let pubsub = new_pubsub();
+    let result = process_publish(&parts(&["PUBLISH", "nobody", "hello"]), &pubsub).unwrap();
+    assert_eq!(result, b":0\r\n");
+}
+
+#[test]
+fn test_publish_skips_disconnected_subscribers() {
+    let pubsub = new_pubsub();
+    let (mut gone, rx) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut gone, &pubsub).unwrap();
+    drop(rx);
+
+    let result = process_publish(&parts(&["PUBLISH", "news", "hello"]), &pubsub).unwrap();
+    assert_eq!(result, b":0\r\n");
+    assert!(!pubsub.lock().unwrap().channels.contains_key("news"));
+}
+
+// ==================== PSUBSCRIBE Tests ====================
+
+#[test]
+fn test_psubscribe_confirms_each_pattern() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "plain"]), &mut client, &pubsub).unwrap();
+
+    let result = process_psubscribe(&parts(&["PSUBSCRIBE", "news.*"]), &mut client, &pubsub).unwrap();
+    // The count covers channels and patterns together
+    assert_eq!(result, b"*3\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n:2\r\n");
+    assert!(client.pattern_subscriptions.contains("news.*"));
+}
+
+#[test]
+fn test_publish_reaches_pattern_subscribers() {
+    let pubsub = new_pubsub();
+    let (mut client, mut rx) = new_subscriber();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "news.*"]), &mut client, &pubsub).unwrap();
+
+    let result = process_publish(&parts(&["PUBLISH", "news.tech", "rust"]), &pubsub).unwrap();
+    assert_eq!(result, b":1\r\n");
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        b"*4\r\n$8\r\npmessage\r\n$6\r\nnews.*\r\n$9\r\nnews.tech\r\n$4\r\nrust\r\n"
+    );
+
+    let missed = process_publish(&parts(&["PUBLISH", "sports.tennis", "ace"]), &pubsub).unwrap();
+    assert_eq!(missed, b":0\r\n");
+}
+
+#[test]
+fn test_publish_counts_exact_and_pattern_subscribers() {
+    let pubsub = new_pubsub();
+    let (mut exact, mut exact_rx) = new_subscriber();
+    let (mut pattern, mut pattern_rx) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "news.tech"]), &mut exact, &pubsub).unwrap();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "news.*", "*.tech"]), &mut pattern, &pubsub).unwrap();
+
+    // One exact delivery plus one per matching pattern
+    let result = process_publish(&parts(&["PUBLISH", "news.tech", "hi"]), &pubsub).unwrap();
+    assert_eq!(result, b":3\r\n");
+    assert!(exact_rx.try_recv().unwrap().starts_with(b"*3\r\n$7\r\nmessage"));
+    assert!(pattern_rx.try_recv().unwrap().starts_with(b"*4\r\n$8\r\npmessage"));
+    assert!(pattern_rx.try_recv().unwrap().starts_with(b"*4\r\n$8\r\npmessage"));
+}
+
+#[test]
+fn test_pattern_glob_syntax() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "news.?", "news.[ab]*", "news.\\*"]), &mut client, &pubsub).unwrap();
+
+    let cases = [("news.x", 1), ("news.xy", 0), ("news.apple", 1), ("news.a", 2), ("news.*", 2), ("news", 0)];
+    for (channel, expected) in cases {
+        let result = process_publish(&parts(&["PUBLISH", channel, "m"]), &pubsub).unwrap();
+        assert_eq!(result, format!(":{}\r\n", expected).into_bytes(), "PUBLISH {}", channel);
+    }
+}
+
+#[test]
+fn test_psubscribe_twice_delivers_once() {
+    let pubsub = new_pubsub();
+    let (mut client, mut rx) = new_subscriber();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "news.*"]), &mut client, &pubsub).unwrap();
+    let again = process_psubscribe(&parts(&["PSUBSCRIBE", "news.*"]), &mut client, &pubsub).unwrap();
+    assert_eq!(again, b"*3\r\n$10\r\npsubscribe\r\n$6\r\nnews.*\r\n:1\r\n");
+
+    let result = process_publish(&parts(&["PUBLISH", "news.tech", "hi"]), &pubsub).unwrap();
+    assert_eq!(result, b":1\r\n");
+    assert!(rx.try_recv().is_ok());
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn test_punsubscribe() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "a.*", "b.*"]), &mut client, &pubsub).unwrap();
+    process_subscribe(&parts(&["SUBSCRIBE", "chan"]), &mut client, &pubsub).unwrap();
+
+    let one = process_punsubscribe(&parts(&["PUNSUBSCRIBE", "a.*"]), &mut client, &pubsub).unwrap();
+    assert_eq!(one, b"*3\r\n$12\r\npunsubscribe\r\n$3\r\na.*\r\n:2\r\n");
+    let all = process_punsubscribe(&parts(&["PUNSUBSCRIBE"]), &mut client, &pubsub).unwrap();
+    assert_eq!(all, b"*3\r\n$12\r\npunsubscribe\r\n$3\r\nb.*\r\n:1\r\n");
+
+    // Channel subscriptions are untouched
+    assert!(client.subscriptions.contains("chan"));
+    assert!(pubsub.lock().unwrap().patterns.is_empty());
+    let published = process_publish(&parts(&["PUBLISH", "a.x", "hi"]), &pubsub).unwrap();
+    assert_eq!(published, b":0\r\n");
+}
+
+// ==================== PUBSUB Tests ====================
+
+#[test]
+fn test_pubsub_channels() {
+    let pubsub = new_pubsub();
+    let (mut client, _rx) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "news.tech", "news.art", "sports"]), &mut client, &pubsub).unwrap();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "weather.*"]), &mut client, &pubsub).unwrap();
+
+    let all = process_pubsub(&parts(&["PUBSUB", "CHANNELS"]), &pubsub).unwrap();
+    assert_eq!(all, b"*3\r\n$8\r\nnews.art\r\n$9\r\nnews.tech\r\n$6\r\nsports\r\n");
+    let matching = process_pubsub(&parts(&["PUBSUB", "channels", "news.*"]), &pubsub).unwrap();
+    assert_eq!(matching, b"*2\r\n$8\r\nnews.art\r\n$9\r\nnews.tech\r\n");
+}
+
+#[test]
+fn test_pubsub_numsub_and_numpat() {
+    let pubsub = new_pubsub();
+    let (mut first, _rx1) = new_subscriber();
+    let (mut second, _rx2) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut first, &pubsub).unwrap();
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut second, &pubsub).unwrap();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "a*", "b*"]), &mut first, &pubsub).unwrap();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "a*"]), &mut second, &pubsub).unwrap();
+
+    let numsub = process_pubsub(&parts(&["PUBSUB", "NUMSUB", "news", "nobody"]), &pubsub).unwrap();
+    assert_eq!(numsub, b"*4\r\n$4\r\nnews\r\n:2\r\n$6\r\nnobody\r\n:0\r\n");
+    assert_eq!(process_pubsub(&parts(&["PUBSUB", "NUMSUB"]), &pubsub).unwrap(), b"*0\r\n");
+    // Unique patterns, however many clients share them
+    assert_eq!(process_pubsub(&parts(&["PUBSUB", "NUMPAT"]), &pubsub).unwrap(), b":2\r\n");
+}
+
+#[test]
+fn test_pubsub_ignores_disconnected_subscribers() {
+    let pubsub = new_pubsub();
+    let (mut client, rx) = new_subscriber();
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut client, &pubsub).unwrap();
+    process_psubscribe(&parts(&["PSUBSCRIBE", "n*"]), &mut client, &pubsub).unwrap();
+    drop(rx);
+
+    assert_eq!(process_pubsub(&parts(&["PUBSUB", "CHANNELS"]), &pubsub).unwrap(), b"*0\r\n");
+    let numsub = process_pubsub(&parts(&["PUBSUB", "NUMSUB", "news"]), &pubsub).unwrap();
+    assert_eq!(numsub, b"*2\r\n$4\r\nnews\r\n:0\r\n");
+    assert_eq!(process_pubsub(&parts(&["PUBSUB", "NUMPAT"]), &pubsub).unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_pubsub_shard_subcommands_are_empty() {
+    let pubsub = new_pubsub();
+    assert_eq!(process_pubsub(&parts(&["PUBSUB", "SHARDCHANNELS"]), &pubsub).unwrap(), b"*0\r\n");
+    let numsub = process_pubsub(&parts(&["PUBSUB", "SHARDNUMSUB", "a"]), &pubsub).unwrap();
+    assert_eq!(numsub, b"*2\r\n$1\r\na\r\n:0\r\n");
+}
+
+#[test]
+fn test_pubsub_errors() {
+    let pubsub = new_pubsub();
+    let missing = process_pubsub(&parts(&["PUBSUB"]), &pubsub).unwrap();
+    assert_eq!(missing, b"-ERR wrong number of arguments for 'pubsub' command\r\n");
+    let extra = process_pubsub(&parts(&["PUBSUB", "NUMPAT", "x"]), &pubsub).unwrap();
+    assert_eq!(extra, b"-ERR wrong number of arguments for 'pubsub|numpat' command\r\n");
+    let unknown = process_pubsub(&parts(&["PUBSUB", "LIST"]), &pubsub).unwrap();
+    assert_eq!(unknown, b"-ERR unknown subcommand 'LIST'. Try PUBSUB HELP.\r\n");
+}
+
+// ==================== Producer / Consumer Tests ====================
+
+#[tokio::test]
+async fn test_publish_reaches_spawned_subscribers() {
+    let pubsub = new_pubsub();
+    let mut consumers = vec![];
+
+    for _ in 0..3 {
+        let (mut client, mut rx) = new_subscriber();
+        process_subscribe(&parts(&["SUBSCRIBE", "jobs"]), &mut client, &pubsub).unwrap();
+        consumers.push(tokio::spawn(async move {
+            let mut received = vec![];
+            for _ in 0..2 {
+                let message = tokio::time::timeout(Duration::from_secs(2), rx.recv()).await.unwrap().unwrap();
+                received.push(String::from_utf8(message).unwrap());
+            }
+            // Keeps the subscription alive until both messages arrived
+            drop(client);
+            received
+        }));
+    }
+
+    let producer_pubsub = Arc::clone(&pubsub);
+    let producer = tokio::spawn(async move {
+        let first = process_publish(&parts(&["PUBLISH", "jobs", "one"]), &producer_pubsub).unwrap();
+        let second = process_publish(&parts(&["PUBLISH", "jobs", "two"]), &producer_pubsub).unwrap();
+        (first, second)
+    });
+
+    let (first, second) = producer.await.unwrap();
+    assert_eq!(first, b":3\r\n");
+    assert_eq!(second, b":3\r\n");
+    for consumer in consumers {
+        let received = consumer.await.unwrap();
+        assert!(received[0].ends_with("$3\r\none\r\n"));
+        assert!(received[1].ends_with("$3\r\ntwo\r\n"));
+    }
+}
+
+// ==================== Subscribed Mode Tests ====================
+
+async fn send(args: &[&str], state: &ServerState, client: &mut ClientState) -> Vec<u8> {
+    let mut buffer = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        buffer.extend(format!("${}\r\n{}\r\n", arg.len(), arg).into_bytes());
+    }
+    let bytes_read = buffer.len();
+    parse_resp(&buffer, bytes_read, state, client).await
+}
+
+#[tokio::test]
+async fn test_subscribed_connection_rejects_other_commands() {
+    let state = ServerState::default();
+    let (mut client, _rx) = new_subscriber();
+
+    send(&["SUBSCRIBE", "news"], &state, &mut client).await;
+    let rejected = send(&["GET", "key"], &state, &mut client).await;
+    assert!(rejected.starts_with(b"-ERR Can't execute 'get': only (P|S)SUBSCRIBE"));
+    assert_eq!(send(&["PING"], &state, &mut client).await, b"*2\r\n$4\r\npong\r\n$0\r\n\r\n");
+
+    send(&["PSUBSCRIBE", "n*"], &state, &mut client).await;
+    send(&["UNSUBSCRIBE"], &state, &mut client).await;
+    // Still subscribed to a pattern
+    assert!(send(&["GET", "key"], &state, &mut client).await.starts_with(b"-ERR"));
+
+    // Leaving every channel and pattern returns the connection to normal
+    send(&["PUNSUBSCRIBE"], &state, &mut client).await;
+    assert_eq!(send(&["GET", "key"], &state, &mut client).await, b"$-1\r\n");
+}
+
+#[tokio::test]
+async fn test_subscribed_ping_replies() {
+    let state = ServerState::default();
+    let (mut client, _rx) = new_subscriber();
+
+    send(&["SUBSCRIBE", "news"], &state, &mut client).await;
+    let result = send(&["PING", "hi"], &state, &mut client).await;
+    assert_eq!(result, b"*2\r\n$4\r\npong\r\n$2\r\nhi\r\n");
+
+    // RESP3 clients can tell pushes from replies, so PING answers normally
+    client.protocol = 3;
+    assert_eq!(send(&["PING"], &state, &mut client).await, b"+PONG\r\n");
+}
+
+#[tokio::test]
+async fn test_publish_through_parser() {
+    let state = ServerState::default();
+    let (mut subscriber, mut rx) = new_subscriber();
+    let mut publisher = ClientState::new();
+
+    send(&["SUBSCRIBE", "news"], &state, &mut subscriber).await;
+    let result = send(&["PUBLISH", "news", "hi"], &state, &mut publisher).await;
+    assert_eq!(result, b":1\r\n");
+    assert_eq!(rx.recv().await.unwrap(), b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n");
+}
@@ -0,0 +1,176 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use redis_cache::commands::{
+    process_bgsave, process_hset, process_push, process_sadd, process_save, process_set, process_xadd, process_zadd
+};
+use redis_cache::models::{new_databases, ListDir, RedisData, RedisValue, ServerConfig, ServerState};
+use redis_cache::utils::rdb::{decode, encode, load, rdb_path, snapshot, write_snapshot};
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+// A fresh directory per test, so tests running in parallel don't share a file
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("redis-cache-rdb-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn state_saving_to(dir: &Path) -> ServerState {
+    let state = ServerState::default();
+    *state.server_config.lock().unwrap() = ServerConfig::new(dir.display().to_string(), "dump.rdb".to_string());
+    state
+}
+
+fn populate(state: &ServerState) {
+    let kv_store = &state.databases[0];
+    let waiting_rooms = &state.waiting_rooms[0];
+    process_set(&parts(&["SET", "string", "hello"]), kv_store).unwrap();
+    process_push(&parts(&["RPUSH", "list", "a", "b", "c"]), kv_store, &waiting_rooms.lists, ListDir::R).unwrap();
+    process_sadd(&parts(&["SADD", "set", "x", "y"]), kv_store).unwrap();
+    process_zadd(&parts(&["ZADD", "zset", "1.5", "one", "2", "two"]), kv_store, &waiting_rooms.zsets).unwrap();
+    process_hset(&parts(&["HSET", "hash", "field", "value"]), kv_store).unwrap();
+    process_xadd(&parts(&["XADD", "stream", "1-1", "temp", "20"]), kv_store, &waiting_rooms.streams).unwrap();
+    process_set(&parts(&["SET", "other_db", "v"]), &state.databases[3]).unwrap();
+}
+
+// ==================== Encoding Tests ====================
+
+#[test]
+fn test_round_trip_every_type() {
+    let state = ServerState::default();
+    populate(&state);
+
+    let restored = decode(&encode(&snapshot(&state.databases))).unwrap();
+    assert_eq!(restored.len(), 4);
+    assert_eq!(restored[0].len(), 6);
+    assert_eq!(restored[3].len(), 1);
+
+    for entry in &restored[0] {
+        match (entry.key.as_str(), &entry.data) {
+            ("string", RedisData::String(s)) => assert_eq!(s, b"hello"),
+            ("list", RedisData::List(items)) => assert_eq!(items, &["a", "b", "c"]),
+            ("set", RedisData::Set(members)) => {
+                assert_eq!(members.len(), 2);
+                assert!(members.contains("x") && members.contains("y"));
+            },
+            ("zset", RedisData::ZSet(members)) => {
+                assert_eq!(members, &[(1.5, "one".to_string()), (2.0, "two".to_string())]);
+            },
+            ("hash", RedisData::Hash(fields)) => assert_eq!(fields.get("field").unwrap(), "value"),
+            ("stream", RedisData::Stream(stream)) => {
+                assert_eq!(stream.last_id, "1-1");
+                assert_eq!(stream.entries_added, 1);
+                assert_eq!(stream.entries[0].fields.get("temp").unwrap(), "20");
+            },
+            (key, _) => panic!("unexpected key {} or wrong type", key),
+        }
+        assert!(entry.expires_at_ms.is_none());
+    }
+}
+
+#[test]
+fn test_decode_rejects_bad_files() {
+    assert!(decode(b"").is_err());
+    assert!(decode(b"NOPE\x01\xFF").is_err());
+    assert!(decode(b"RCDB\x09\xFF").is_err());
+
+    // Cut anywhere before the end marker, the file is refused rather than half loaded
+    let state = ServerState::default();
+    populate(&state);
+    let bytes = encode(&snapshot(&state.databases));
+    for cut in 0..bytes.len() {
+        assert!(decode(&bytes[..cut]).is_err(), "accepted after {} bytes", cut);
+    }
+}
+
+// ==================== Save / Load Tests ====================
+
+#[test]
+fn test_save_then_load_into_fresh_databases() {
+    let dir = temp_dir("save_load");
+    let state = state_saving_to(&dir);
+    populate(&state);
+
+    assert_eq!(process_save(&parts(&["SAVE"]), &state.databases, &state.server_config).unwrap(), b"+OK\r\n");
+
+    let restarted = new_databases(16);
+    let path = rdb_path(&state.server_config.lock().unwrap());
+    assert_eq!(load(&restarted, &path).unwrap(), 7);
+    assert!(restarted[0].contains_key("stream"));
+    assert!(restarted[3].contains_key("other_db"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_load_keeps_deadlines_and_drops_passed_ones() {
+    let dir = temp_dir("deadlines");
+    let path = dir.join("dump.rdb");
+    let databases = new_databases(1);
+    let kv_store = &databases[0];
+    let in_a_minute = Instant::now() + Duration::from_secs(60);
+    kv_store.insert("later".to_string(), RedisValue::new(RedisData::String("v".into()), Some(in_a_minute)));
+    kv_store.insert("soon".to_string(), RedisValue::new(
+        RedisData::String("v".into()),
+        Some(Instant::now() + Duration::from_millis(20)),
+    ));
+    write_snapshot(&snapshot(&databases), &path).unwrap();
+
+    std::thread::sleep(Duration::from_millis(50));
+    let restarted = new_databases(1);
+    assert_eq!(load(&restarted, &path).unwrap(), 1);
+    assert!(!restarted[0].contains_key("soon"));
+
+    let expires_at = restarted[0].shard("later").get("later").unwrap().expires_at.unwrap();
+    let drift = expires_at.max(in_a_minute) - expires_at.min(in_a_minute);
+    assert!(drift < Duration::from_secs(1));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_load_without_a_file_is_empty() {
+    let databases = new_databases(1);
+    let missing = temp_dir("missing").join("nothing-here.rdb");
+    assert_eq!(load(&databases, &missing).unwrap(), 0);
+    assert!(databases[0].is_empty());
+}
+
+#[test]
+fn test_save_to_missing_directory_fails() {
+    let state = ServerState::default();
+    *state.server_config.lock().unwrap() = ServerConfig::new("/nonexistent/dir".to_string(), "dump.rdb".to_string());
+    let result = process_save(&parts(&["SAVE"]), &state.databases, &state.server_config).unwrap();
+    assert!(result.starts_with(b"-ERR "));
+}
+
+#[test]
+fn test_bgsave_writes_in_background() {
+    let dir = temp_dir("bgsave");
+    let state = state_saving_to(&dir);
+    populate(&state);
+
+    let p = parts(&["BGSAVE"]);
+    let result = process_bgsave(&p, &state.databases, &state.server_config, &state.bgsave_in_progress).unwrap();
+    assert_eq!(result, b"+Background saving started\r\n");
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while state.bgsave_in_progress.load(Ordering::Acquire) {
+        assert!(Instant::now() < deadline, "BGSAVE never finished");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    let restarted = new_databases(16);
+    assert_eq!(load(&restarted, &dir.join("dump.rdb")).unwrap(), 7);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_bgsave_while_one_is_running() {
+    let state = ServerState::default();
+    state.bgsave_in_progress.store(true, Ordering::Release);
+    let p = parts(&["BGSAVE"]);
+    let result = process_bgsave(&p, &state.databases, &state.server_config, &state.bgsave_in_progress).unwrap();
+    assert_eq!(result, b"-ERR Background save already in progress\r\n");
+}
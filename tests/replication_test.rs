@@ -0,0 +1,320 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+use redis_cache::commands::process_replconf;
+use redis_cache::models::{ClientState, ReplicationInfo, ServerInfo, ServerState};
+use redis_cache::parser::parse_pipeline;
+use redis_cache::replication::{connect_to_master, handshake, parse_replicaof, FullResync};
+use redis_cache::utils::decoder::{decode_resp, split_frame};
+use redis_cache::utils::rdb;
+
+const REPLID: &str = "8371b4fb1155b71f4a04d3e1bc3e18c4a990aeeb";
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|s| s.to_string()).collect()
+}
+
+// Plays the master: answers each command the replica sends with the next of
+// `replies`, and hands back the commands it received
+async fn fake_master(listener: TcpListener, replies: Vec<&'static str>) -> Vec<Vec<String>> {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut received = Vec::new();
+    let mut buffer = Vec::new();
+    for reply in replies {
+        let frame = loop {
+            if let Some((frame, rest)) = split_frame(&buffer) {
+                let (frame, rest) = (frame.to_vec(), rest.to_vec());
+                buffer = rest;
+                break frame;
+            }
+            let mut chunk = [0; 512];
+            let read = stream.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "replica hung up mid-handshake");
+            buffer.extend_from_slice(&chunk[..read]);
+        };
+        received.push(decode_resp(&frame).iter().map(|part| String::from_utf8_lossy(part).into_owned()).collect());
+        stream.write_all(reply.as_bytes()).await.unwrap();
+    }
+    received
+}
+
+fn make_resp(parts: &[&str]) -> Vec<u8> {
+    let mut result = format!("*{}\r\n", parts.len());
+    for part in parts {
+        result.push_str(&format!("${}\r\n{}\r\n", part.len(), part));
+    }
+    result.into_bytes()
+}
+
+async fn run(commands: &[&[&str]], state: &ServerState, client: &mut ClientState) -> Vec<u8> {
+    let buffer: Vec<u8> = commands.iter().flat_map(|command| make_resp(command)).collect();
+    parse_pipeline(&buffer, state, client).await.0
+}
+
+// A client whose write stream lands in the returned receiver, as a connection's would
+fn replica_client() -> (ClientState, mpsc::Receiver<Vec<u8>>) {
+    let (tx, rx) = mpsc::channel(16);
+    let mut client = ClientState::new();
+    client.push_sender = Some(tx);
+    (client, rx)
+}
+
+fn master_offset(state: &ServerState) -> u64 {
+    state.server_info.lock().unwrap().replication_info.master_repl_offset
+}
+
+async fn listener() -> (TcpListener, u16) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    (listener, port)
+}
+
+// ==================== Handshake Tests ====================
+
+#[tokio::test]
+async fn test_handshake_sends_commands_in_order() {
+    let (listener, port) = listener().await;
+    let master = tokio::spawn(fake_master(listener, vec![
+        "+PONG\r\n",
+        "+OK\r\n",
+        "+OK\r\n",
+        "+FULLRESYNC 8371b4fb1155b71f4a04d3e1bc3e18c4a990aeeb 0\r\n",
+    ]));
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let (resync, _) = handshake(stream, 6380).await.unwrap();
+    assert_eq!(resync, FullResync { replid: REPLID.to_string(), offset: 0 });
+
+    let received = master.await.unwrap();
+    assert_eq!(received, vec![
+        args(&["PING"]),
+        args(&["REPLCONF", "listening-port", "6380"]),
+        args(&["REPLCONF", "capa", "psync2"]),
+        args(&["PSYNC", "?", "-1"]),
+    ]);
+}
+
+#[tokio::test]
+async fn test_handshake_stops_on_error_reply() {
+    let (listener, port) = listener().await;
+    let master = tokio::spawn(fake_master(listener, vec![
+        "+PONG\r\n",
+        "-NOAUTH Authentication required.\r\n",
+    ]));
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let error = handshake(stream, 6380).await.unwrap_err();
+    assert!(error.to_string().contains("NOAUTH"));
+    assert_eq!(master.await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_handshake_rejects_unexpected_psync_reply() {
+    let (listener, port) = listener().await;
+    let master = tokio::spawn(fake_master(listener, vec!["+PONG\r\n", "+OK\r\n", "+OK\r\n", "+CONTINUE\r\n"]));
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    assert!(handshake(stream, 6380).await.is_err());
+    master.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_connect_to_master_adopts_replid_and_offset() {
+    let (listener, port) = listener().await;
+    let master = tokio::spawn(fake_master(listener, vec![
+        "+PONG\r\n",
+        "+OK\r\n",
+        "+OK\r\n",
+        "+FULLRESYNC 0123456789abcdef0123456789abcdef01234567 42\r\n",
+    ]));
+    let server_info = Arc::new(Mutex::new(ServerInfo::new(ReplicationInfo::new("slave".to_string()))));
+
+    connect_to_master("127.0.0.1", port, 6380, &server_info).await.unwrap();
+    master.await.unwrap();
+    let info = server_info.lock().unwrap();
+    assert_eq!(info.replication_info.master_replid, "0123456789abcdef0123456789abcdef01234567");
+    assert_eq!(info.replication_info.master_repl_offset, 42);
+}
+
+// ==================== Master Side Tests ====================
+
+#[test]
+fn test_replconf_accepts_handshake_options() {
+    assert_eq!(process_replconf(&args(&["REPLCONF", "listening-port", "6380"])).unwrap(), b"+OK\r\n");
+    assert_eq!(process_replconf(&args(&["REPLCONF", "capa", "eof", "capa", "psync2"])).unwrap(), b"+OK\r\n");
+    assert_eq!(
+        process_replconf(&args(&["REPLCONF", "bogus", "1"])).unwrap(),
+        b"-ERR Unrecognized REPLCONF option: bogus\r\n"
+    );
+    assert_eq!(process_replconf(&args(&["REPLCONF", "capa"])).unwrap(), b"-ERR syntax error\r\n");
+}
+
+#[tokio::test]
+async fn test_psync_replies_with_fullresync_and_snapshot() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    run(&[&["SET", "foo", "bar"]], &state, &mut client).await;
+
+    let (mut replica, _rx) = replica_client();
+    let reply = run(&[&["PSYNC", "?", "-1"]], &state, &mut replica).await;
+
+    let (header, rest) = reply.split_at(reply.iter().position(|&b| b == b'\n').unwrap() + 1);
+    let replid = state.server_info.lock().unwrap().replication_info.master_replid.clone();
+    assert_eq!(header, format!("+FULLRESYNC {} 0\r\n", replid).as_bytes());
+
+    let (length, payload) = rest.split_at(rest.iter().position(|&b| b == b'\n').unwrap() + 1);
+    assert_eq!(length, format!("${}\r\n", payload.len()).as_bytes());
+    let snapshot = rdb::decode(payload).unwrap();
+    assert_eq!(snapshot[0].len(), 1);
+    assert_eq!(snapshot[0][0].key, "foo");
+}
+
+#[tokio::test]
+async fn test_writes_reach_attached_replica() {
+    let state = ServerState::default();
+    let (mut replica, mut rx) = replica_client();
+    run(&[&["PSYNC", "?", "-1"]], &state, &mut replica).await;
+
+    let mut client = ClientState::new();
+    run(&[&["SET", "foo", "bar"], &["GET", "foo"], &["INCR", "foo"], &["RPUSH", "list", "a"]], &state, &mut client).await;
+
+    let mut expected = make_resp(&["SELECT", "0"]);
+    expected.extend(make_resp(&["SET", "foo", "bar"]));
+    assert_eq!(rx.try_recv().unwrap(), expected);
+    // INCR failed on a non-integer, so only the RPUSH follows
+    assert_eq!(rx.try_recv().unwrap(), make_resp(&["RPUSH", "list", "a"]));
+    assert!(rx.try_recv().is_err());
+    assert_eq!(master_offset(&state), (expected.len() + make_resp(&["RPUSH", "list", "a"]).len()) as u64);
+}
+
+#[tokio::test]
+async fn test_propagation_follows_select_and_rewrites() {
+    let state = ServerState::default();
+    let (mut replica, mut rx) = replica_client();
+    run(&[&["PSYNC", "?", "-1"]], &state, &mut replica).await;
+
+    let mut client = ClientState::new();
+    run(&[&["SELECT", "3"], &["SADD", "set", "only"], &["SPOP", "set"]], &state, &mut client).await;
+
+    let mut expected = make_resp(&["SELECT", "3"]);
+    expected.extend(make_resp(&["SADD", "set", "only"]));
+    assert_eq!(rx.try_recv().unwrap(), expected);
+    // The member SPOP chose at random is sent, so the replica removes the same one
+    assert_eq!(rx.try_recv().unwrap(), make_resp(&["SREM", "set", "only"]));
+}
+
+#[tokio::test]
+async fn test_disconnected_replica_is_dropped() {
+    let state = ServerState::default();
+    let (mut replica, rx) = replica_client();
+    run(&[&["PSYNC", "?", "-1"]], &state, &mut replica).await;
+    drop(rx);
+
+    let mut client = ClientState::new();
+    run(&[&["SET", "foo", "bar"]], &state, &mut client).await;
+    assert!(state.server_info.lock().unwrap().replicas.senders.is_empty());
+}
+
+#[tokio::test]
+async fn test_psync_waits_for_writes_in_progress() {
+    let state = ServerState::default();
+    // Stands in for a write that has run but not yet propagated
+    let write = state.replication.write().await;
+
+    let (mut replica, _rx) = replica_client();
+    let psync_state = state.clone();
+    let psync = tokio::spawn(async move {
+        run(&[&["PSYNC", "?", "-1"]], &psync_state, &mut replica).await
+    });
+    tokio::task::yield_now().await;
+    assert!(!psync.is_finished());
+    assert!(state.server_info.lock().unwrap().replicas.senders.is_empty());
+
+    drop(write);
+    assert!(psync.await.unwrap().starts_with(b"+FULLRESYNC"));
+    assert_eq!(state.server_info.lock().unwrap().replicas.senders.len(), 1);
+}
+
+#[tokio::test]
+async fn test_no_replicas_leaves_offset_alone() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    run(&[&["SET", "foo", "bar"]], &state, &mut client).await;
+    assert_eq!(master_offset(&state), 0);
+}
+
+// ==================== INFO replication Tests ====================
+
+fn info_field(info: &[u8], field: &str) -> String {
+    let info = String::from_utf8_lossy(info);
+    let prefix = format!("{}:", field);
+    info.lines()
+        .find_map(|line| line.strip_prefix(&prefix))
+        .unwrap_or_else(|| panic!("INFO has no {} field", field))
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_info_before_any_replica() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let info = run(&[&["INFO", "replication"]], &state, &mut client).await;
+
+    assert_eq!(info_field(&info, "connected_slaves"), "0");
+    assert_eq!(info_field(&info, "second_repl_offset"), "-1");
+    assert_eq!(info_field(&info, "repl_backlog_active"), "0");
+    assert_eq!(info_field(&info, "repl_backlog_size"), "1048576");
+    assert_eq!(info_field(&info, "repl_backlog_histlen"), "0");
+}
+
+#[tokio::test]
+async fn test_info_tracks_replicas_and_backlog() {
+    let state = ServerState::default();
+    let (mut replica, rx) = replica_client();
+    run(&[&["PSYNC", "?", "-1"]], &state, &mut replica).await;
+
+    let mut client = ClientState::new();
+    run(&[&["SET", "foo", "bar"]], &state, &mut client).await;
+    let info = run(&[&["INFO", "replication"]], &state, &mut client).await;
+    let offset = master_offset(&state);
+    assert_eq!(info_field(&info, "connected_slaves"), "1");
+    assert_eq!(info_field(&info, "repl_backlog_active"), "1");
+    assert_eq!(info_field(&info, "repl_backlog_first_byte_offset"), "1");
+    assert_eq!(info_field(&info, "repl_backlog_histlen"), offset.to_string());
+
+    // Hanging up is noticed without waiting for the next write
+    drop(rx);
+    let info = run(&[&["INFO", "replication"]], &state, &mut client).await;
+    assert_eq!(info_field(&info, "connected_slaves"), "0");
+}
+
+#[test]
+fn test_backlog_keeps_only_the_latest_bytes() {
+    let mut info = ReplicationInfo::new("master".to_string());
+    info.repl_backlog_size = 100;
+    let (tx, _rx) = mpsc::channel(1);
+    let mut server_info = ServerInfo::new(info);
+    server_info.attach_replica(tx);
+
+    server_info.replication_info.record_written(60);
+    assert_eq!(server_info.replication_info.repl_backlog_histlen, 60);
+    server_info.replication_info.record_written(70);
+    let info = &server_info.replication_info;
+    assert_eq!(info.master_repl_offset, 130);
+    assert_eq!(info.repl_backlog_histlen, 100);
+    assert_eq!(info.repl_backlog_first_byte_offset, 31);
+}
+
+// ==================== --replicaof Parsing Tests ====================
+
+#[test]
+fn test_parse_replicaof_forms() {
+    assert_eq!(parse_replicaof(&args(&["localhost 6379"])), Some(("localhost".to_string(), 6379)));
+    assert_eq!(parse_replicaof(&args(&["localhost", "6379", "--port"])), Some(("localhost".to_string(), 6379)));
+    assert_eq!(parse_replicaof(&args(&["localhost"])), None);
+    assert_eq!(parse_replicaof(&args(&["localhost notaport"])), None);
+    assert_eq!(parse_replicaof(&[]), None);
+}
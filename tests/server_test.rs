@@ -0,0 +1,461 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use redis_cache::models::{
+    new_databases, Acl, ClientState, KvStore, RedisData, RedisValue, ReplicationInfo, ServerInfo, ServerState, Store
+};
+use redis_cache::commands::{
+    process_dbsize, process_hello, process_flushall, process_flushdb, process_reset, process_sadd, process_select,
+    process_set, process_subscribe, process_wait
+};
+use redis_cache::parser::parse_pipeline;
+
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
+}
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn new_server_info(role: &str) -> Arc<Mutex<ServerInfo>> {
+    Arc::new(Mutex::new(ServerInfo::new(ReplicationInfo::new(role.to_string()))))
+}
+
+fn insert_string(kv_store: &KvStore, key: &str, expires_at: Option<Instant>) {
+    kv_store.insert(
+        key.to_string(),
+        RedisValue::new(RedisData::String("value".into()), expires_at),
+    );
+}
+
+// ==================== DBSIZE Tests ====================
+
+#[test]
+fn test_dbsize_empty() {
+    let kv_store = new_kv_store();
+    let result = process_dbsize(&parts(&["DBSIZE"]), &kv_store).unwrap();
+    assert_eq!(result, b":0\r\n");
+}
+
+#[test]
+fn test_dbsize_counts_all_types() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "str", "v"]), &kv_store).unwrap();
+    process_sadd(&parts(&["SADD", "set", "a", "b"]), &kv_store).unwrap();
+    insert_string(&kv_store, "other", None);
+
+    let result = process_dbsize(&parts(&["DBSIZE"]), &kv_store).unwrap();
+    assert_eq!(result, b":3\r\n");
+}
+
+#[test]
+fn test_dbsize_skips_expired_keys() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "live", Some(Instant::now() + Duration::from_secs(60)));
+    insert_string(&kv_store, "dead", Some(Instant::now() - Duration::from_secs(1)));
+
+    let result = process_dbsize(&parts(&["DBSIZE"]), &kv_store).unwrap();
+    assert_eq!(result, b":1\r\n");
+}
+
+#[tokio::test]
+async fn test_dbsize_after_set_expires() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "forever", "v"]), &kv_store).unwrap();
+    process_set(&parts(&["SET", "short", "v", "PX", "50"]), &kv_store).unwrap();
+    assert_eq!(process_dbsize(&parts(&["DBSIZE"]), &kv_store).unwrap(), b":2\r\n");
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(process_dbsize(&parts(&["DBSIZE"]), &kv_store).unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_dbsize_rejects_arguments() {
+    let kv_store = new_kv_store();
+    assert!(process_dbsize(&parts(&["DBSIZE", "extra"]), &kv_store).is_err());
+}
+
+// ==================== FLUSHDB / FLUSHALL Tests ====================
+
+#[test]
+fn test_flushdb_clears_every_shard() {
+    let kv_store = new_kv_store();
+    for i in 0..50 {
+        insert_string(&kv_store, &format!("key:{}", i), None);
+    }
+
+    let result = process_flushdb(&parts(&["FLUSHDB"]), &kv_store).unwrap();
+    assert_eq!(result, b"+OK\r\n");
+    assert!(kv_store.is_empty());
+}
+
+#[test]
+fn test_flushdb_leaves_other_databases() {
+    let databases = new_databases(2);
+    insert_string(&databases[0], "a", None);
+    insert_string(&databases[1], "b", None);
+
+    process_flushdb(&parts(&["FLUSHDB"]), &databases[0]).unwrap();
+    assert!(databases[0].is_empty());
+    assert!(databases[1].contains_key("b"));
+}
+
+#[test]
+fn test_flushall_clears_every_database() {
+    let databases = new_databases(4);
+    for (db, kv_store) in databases.iter().enumerate() {
+        for i in 0..20 {
+            insert_string(kv_store, &format!("key:{}:{}", db, i), None);
+        }
+    }
+
+    let result = process_flushall(&parts(&["FLUSHALL"]), &databases).unwrap();
+    assert_eq!(result, b"+OK\r\n");
+    for kv_store in databases.iter() {
+        let size = process_dbsize(&parts(&["DBSIZE"]), kv_store).unwrap();
+        assert_eq!(size, b":0\r\n");
+    }
+}
+
+#[test]
+fn test_flush_on_empty_store() {
+    let kv_store = new_kv_store();
+    assert_eq!(process_flushdb(&parts(&["FLUSHDB"]), &kv_store).unwrap(), b"+OK\r\n");
+    assert_eq!(process_flushall(&parts(&["FLUSHALL"]), &new_databases(1)).unwrap(), b"+OK\r\n");
+}
+
+#[test]
+fn test_flush_rejects_unknown_option() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "key", None);
+    let result = process_flushdb(&parts(&["FLUSHDB", "NOW"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"-ERR syntax error"));
+    assert!(kv_store.contains_key("key"));
+}
+
+#[test]
+fn test_flush_sync_and_async_modes() {
+    let databases = new_databases(2);
+    for mode in ["SYNC", "async", "ASYNC"] {
+        insert_string(&databases[0], "a", None);
+        insert_string(&databases[1], "b", None);
+
+        assert_eq!(process_flushdb(&parts(&["FLUSHDB", mode]), &databases[0]).unwrap(), b"+OK\r\n");
+        assert!(databases[0].is_empty());
+        assert_eq!(process_flushall(&parts(&["FLUSHALL", mode]), &databases).unwrap(), b"+OK\r\n");
+        assert!(databases[1].is_empty());
+    }
+
+    let extra = process_flushall(&parts(&["FLUSHALL", "ASYNC", "SYNC"]), &databases).unwrap();
+    assert!(extra.starts_with(b"-ERR syntax error"));
+}
+
+#[tokio::test]
+async fn test_flushdb_async_with_concurrent_writers() {
+    let kv_store = new_kv_store();
+    let handles: Vec<_> = (0..8)
+        .map(|t| {
+            let store = Arc::clone(&kv_store);
+            tokio::spawn(async move {
+                for i in 0..100 {
+                    process_set(&parts(&["SET", &format!("key:{}:{}", t, i), "v"]), &store).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap();
+    }
+    assert_eq!(process_dbsize(&parts(&["DBSIZE"]), &kv_store).unwrap(), b":800\r\n");
+
+    // The keys are gone as soon as the reply is, even though freeing them isn't
+    process_flushdb(&parts(&["FLUSHDB", "ASYNC"]), &kv_store).unwrap();
+    assert_eq!(process_dbsize(&parts(&["DBSIZE"]), &kv_store).unwrap(), b":0\r\n");
+
+    // And the store is immediately usable again
+    process_set(&parts(&["SET", "after", "v"]), &kv_store).unwrap();
+    assert_eq!(process_dbsize(&parts(&["DBSIZE"]), &kv_store).unwrap(), b":1\r\n");
+}
+
+// ==================== HELLO Tests ====================
+
+#[test]
+fn test_hello_3_switches_to_resp3_map() {
+    let mut client = ClientState::new();
+    let result = process_hello(&parts(&["HELLO", "3"]), &mut client, &Acl::new(), &new_server_info("master")).unwrap();
+
+    assert_eq!(client.protocol, 3);
+    assert!(result.starts_with(b"%6\r\n$6\r\nserver\r\n$5\r\nredis\r\n"));
+    let text = String::from_utf8(result).unwrap();
+    assert!(text.contains("$5\r\nproto\r\n:3\r\n"));
+    assert!(text.contains("$4\r\nrole\r\n$6\r\nmaster\r\n"));
+    assert!(text.ends_with("$7\r\nmodules\r\n*0\r\n"));
+}
+
+#[test]
+fn test_hello_2_replies_with_flat_array() {
+    let mut client = ClientState::new();
+    client.protocol = 3;
+    let result = process_hello(&parts(&["HELLO", "2"]), &mut client, &Acl::new(), &new_server_info("slave")).unwrap();
+
+    assert_eq!(client.protocol, 2);
+    assert!(result.starts_with(b"*12\r\n$6\r\nserver\r\n"));
+    let text = String::from_utf8(result).unwrap();
+    assert!(text.contains("$5\r\nproto\r\n:2\r\n"));
+    assert!(text.contains("$4\r\nrole\r\n$5\r\nslave\r\n"));
+}
+
+#[test]
+fn test_hello_without_version_keeps_protocol() {
+    let mut client = ClientState::new();
+    let result = process_hello(&parts(&["HELLO"]), &mut client, &Acl::new(), &new_server_info("master")).unwrap();
+    assert_eq!(client.protocol, 2);
+    assert!(result.starts_with(b"*12\r\n"));
+}
+
+#[test]
+fn test_hello_rejects_unsupported_version() {
+    let mut client = ClientState::new();
+    let server_info = new_server_info("master");
+
+    let result = process_hello(&parts(&["HELLO", "4"]), &mut client, &Acl::new(), &server_info).unwrap();
+    assert!(result.starts_with(b"-NOPROTO"));
+    let not_a_number = process_hello(&parts(&["HELLO", "three"]), &mut client, &Acl::new(), &server_info).unwrap();
+    assert!(not_a_number.starts_with(b"-ERR Protocol version is not an integer"));
+    assert_eq!(client.protocol, 2);
+}
+
+#[test]
+fn test_hello_setname_and_auth() {
+    let mut client = ClientState::new();
+    let p = parts(&["HELLO", "3", "AUTH", "default", "anything", "SETNAME", "conn-1"]);
+    let result = process_hello(&p, &mut client, &Acl::new(), &new_server_info("master")).unwrap();
+    assert!(result.starts_with(b"%6\r\n"));
+    assert_eq!(client.protocol, 3);
+    assert_eq!(client.name.as_deref(), Some("conn-1"));
+}
+
+#[test]
+fn test_hello_bad_option_changes_nothing() {
+    let mut client = ClientState::new();
+    let server_info = new_server_info("master");
+
+    let unknown_user = process_hello(&parts(&["HELLO", "3", "AUTH", "alice", "pw"]), &mut client, &Acl::new(), &server_info).unwrap();
+    assert_eq!(unknown_user, b"-WRONGPASS invalid username-password pair or user is disabled.\r\n");
+
+    let bad_name = process_hello(&parts(&["HELLO", "3", "SETNAME", "has space"]), &mut client, &Acl::new(), &server_info).unwrap();
+    assert!(bad_name.starts_with(b"-ERR Client names cannot contain spaces"));
+
+    let missing_arg = process_hello(&parts(&["HELLO", "3", "AUTH", "default"]), &mut client, &Acl::new(), &server_info).unwrap();
+    assert_eq!(missing_arg, b"-ERR Syntax error in HELLO option 'AUTH'\r\n");
+
+    let unknown = process_hello(&parts(&["HELLO", "3", "FAST"]), &mut client, &Acl::new(), &server_info).unwrap();
+    assert_eq!(unknown, b"-ERR Syntax error in HELLO option 'FAST'\r\n");
+
+    assert_eq!(client.protocol, 2);
+    assert!(client.name.is_none());
+}
+
+// ==================== RESET Tests ====================
+
+#[test]
+fn test_reset_restores_connection_defaults() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let (push_tx, _push_rx) = mpsc::channel(16);
+    client.push_sender = Some(push_tx);
+    client.id = 7;
+    client.db_index = 3;
+    client.protocol = 3;
+    client.name = Some("worker".to_string());
+    client.no_evict = true;
+    client.command_queue = Some(Default::default());
+    client.transaction_dirty = true;
+    client.watched_keys.insert((0, "k".to_string()), None);
+    process_subscribe(&parts(&["SUBSCRIBE", "news"]), &mut client, &state.pubsub).unwrap();
+
+    let result = process_reset(&parts(&["RESET"]), &mut client, &state.pubsub, &state.acl).unwrap();
+    assert_eq!(result, b"+RESET\r\n");
+    assert!(client.command_queue.is_none());
+    assert!(!client.transaction_dirty);
+    assert!(client.watched_keys.is_empty());
+    assert_eq!(client.subscription_count(), 0);
+    assert!(state.pubsub.lock().unwrap().channels.is_empty());
+    assert_eq!((client.db_index, client.protocol), (0, 2));
+    assert!(client.name.is_none());
+    assert!(!client.no_evict);
+    assert_eq!(client.id, 7);
+}
+
+#[test]
+fn test_reset_logs_out_when_a_password_is_required() {
+    let state = ServerState::default();
+    state.acl.set_requirepass(Some("secret".to_string()));
+    let mut client = ClientState::new();
+    client.authenticated = true;
+    client.username = "alice".to_string();
+
+    process_reset(&parts(&["RESET"]), &mut client, &state.pubsub, &state.acl).unwrap();
+    assert!(!client.authenticated);
+    assert_eq!(client.username, "default");
+}
+
+#[tokio::test]
+async fn test_reset_is_not_queued_by_multi() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    client.authenticated = true;
+    let buffer = b"*1\r\n$5\r\nMULTI\r\n*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n*1\r\n$5\r\nRESET\r\n*1\r\n$4\r\nEXEC\r\n";
+    let (replies, _) = parse_pipeline(buffer, &state, &mut client).await;
+    assert_eq!(replies, b"+OK\r\n+QUEUED\r\n+RESET\r\n-ERR EXEC without MULTI\r\n");
+    assert!(!state.databases[0].contains_key("k"));
+}
+
+// ==================== SELECT Tests ====================
+
+#[test]
+fn test_select_switches_database() {
+    let databases = new_databases(16);
+    let mut client = ClientState::new();
+    assert_eq!(client.db_index, 0);
+
+    let result = process_select(&parts(&["SELECT", "15"]), &mut client, &databases).unwrap();
+    assert_eq!(result, b"+OK\r\n");
+    assert_eq!(client.db_index, 15);
+}
+
+#[test]
+fn test_select_out_of_range() {
+    let databases = new_databases(16);
+    let mut client = ClientState::new();
+
+    let too_big = process_select(&parts(&["SELECT", "16"]), &mut client, &databases).unwrap();
+    assert_eq!(too_big, b"-ERR DB index is out of range\r\n");
+    let negative = process_select(&parts(&["SELECT", "-1"]), &mut client, &databases).unwrap();
+    assert_eq!(negative, b"-ERR DB index is out of range\r\n");
+    let not_a_number = process_select(&parts(&["SELECT", "one"]), &mut client, &databases).unwrap();
+    assert!(not_a_number.starts_with(b"-ERR value is not an integer"));
+    assert_eq!(client.db_index, 0);
+}
+
+#[tokio::test]
+async fn test_blocked_keys_stay_in_their_database() {
+    let state = ServerState::default();
+    let blpop = tokio::spawn({
+        let state = state.clone();
+        async move {
+            let mut client = ClientState::new();
+            let buffer = b"*2\r\n$6\r\nSELECT\r\n$1\r\n1\r\n*3\r\n$5\r\nBLPOP\r\n$1\r\nk\r\n$3\r\n0.2\r\n";
+            parse_pipeline(buffer, &state, &mut client).await.0
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // The same key name in database 0 isn't what database 1 is waiting on
+    let mut client = ClientState::new();
+    let (replies, _) = parse_pipeline(b"*3\r\n$5\r\nRPUSH\r\n$1\r\nk\r\n$1\r\nv\r\n", &state, &mut client).await;
+    assert_eq!(replies, b":1\r\n");
+    assert_eq!(blpop.await.unwrap(), b"+OK\r\n*-1\r\n");
+    assert!(state.databases[0].contains_key("k"));
+}
+
+#[tokio::test]
+async fn test_blocked_clients_only_wake_for_their_type() {
+    let state = ServerState::default();
+    let blpop = tokio::spawn({
+        let state = state.clone();
+        async move {
+            let buffer = b"*3\r\n$5\r\nBLPOP\r\n$1\r\nl\r\n$3\r\n0.2\r\n";
+            parse_pipeline(buffer, &state, &mut ClientState::new()).await.0
+        }
+    });
+    let bzpopmin = tokio::spawn({
+        let state = state.clone();
+        async move {
+            let buffer = b"*3\r\n$8\r\nBZPOPMIN\r\n$1\r\nz\r\n$3\r\n0.2\r\n";
+            parse_pipeline(buffer, &state, &mut ClientState::new()).await.0
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // A sorted set showing up isn't something BLPOP can pop from
+    let mut client = ClientState::new();
+    let (replies, _) = parse_pipeline(b"*4\r\n$4\r\nZADD\r\n$1\r\nl\r\n$1\r\n1\r\n$1\r\nm\r\n", &state, &mut client).await;
+    assert_eq!(replies, b":1\r\n");
+    // And a pushed element goes to the list, not to the BZPOPMIN waiter
+    let (replies, _) = parse_pipeline(b"*3\r\n$5\r\nRPUSH\r\n$1\r\nz\r\n$1\r\nv\r\n", &state, &mut client).await;
+    assert_eq!(replies, b":1\r\n");
+
+    assert_eq!(blpop.await.unwrap(), b"*-1\r\n");
+    assert_eq!(bzpopmin.await.unwrap(), b"*-1\r\n");
+    let (replies, _) = parse_pipeline(b"*4\r\n$6\r\nLRANGE\r\n$1\r\nz\r\n$1\r\n0\r\n$2\r\n-1\r\n", &state, &mut client).await;
+    assert_eq!(replies, b"*1\r\n$1\r\nv\r\n");
+}
+
+// ==================== WAIT Tests ====================
+
+#[test]
+fn test_wait_without_replicas_returns_zero() {
+    let result = process_wait(&parts(&["WAIT", "1", "1000"]), &new_server_info("master")).unwrap();
+    assert_eq!(result, b":0\r\n");
+}
+
+#[tokio::test]
+async fn test_wait_zero_returns_immediately() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    let (replies, _) = parse_pipeline(b"*3\r\n$4\r\nWAIT\r\n$1\r\n0\r\n$1\r\n0\r\n", &state, &mut client).await;
+    assert_eq!(replies, b":0\r\n");
+}
+
+#[test]
+fn test_wait_reports_connected_replicas() {
+    let server_info = new_server_info("master");
+    let (first_tx, _first_rx) = mpsc::channel(16);
+    let (second_tx, second_rx) = mpsc::channel(16);
+    server_info.lock().unwrap().attach_replica(first_tx);
+    server_info.lock().unwrap().attach_replica(second_tx);
+
+    let result = process_wait(&parts(&["WAIT", "3", "0"]), &server_info).unwrap();
+    assert_eq!(result, b":2\r\n");
+    // A replica that has disconnected no longer counts
+    drop(second_rx);
+    let result = process_wait(&parts(&["WAIT", "3", "0"]), &server_info).unwrap();
+    assert_eq!(result, b":1\r\n");
+}
+
+#[test]
+fn test_wait_invalid_arguments() {
+    let server_info = new_server_info("master");
+    let replicas = process_wait(&parts(&["WAIT", "x", "0"]), &server_info).unwrap();
+    assert!(replicas.starts_with(b"-ERR value is not an integer"));
+    let negative = process_wait(&parts(&["WAIT", "1", "-5"]), &server_info).unwrap();
+    assert!(negative.starts_with(b"-ERR timeout is negative"));
+    let timeout = process_wait(&parts(&["WAIT", "1", "soon"]), &server_info).unwrap();
+    assert!(timeout.starts_with(b"-ERR timeout is not an integer"));
+    assert!(process_wait(&parts(&["WAIT", "1"]), &server_info).is_err());
+}
+
+#[test]
+fn test_hello_with_password_required() {
+    let server_info = new_server_info("master");
+    let acl = Acl::new();
+    acl.set_requirepass(Some("secret".to_string()));
+    let mut client = ClientState::new();
+
+    let result = process_hello(&parts(&["HELLO", "3"]), &mut client, &acl, &server_info).unwrap();
+    assert!(result.starts_with(b"-NOAUTH HELLO must be called with the client already authenticated"));
+    assert_eq!(client.protocol, 2);
+
+    let result = process_hello(&parts(&["HELLO", "3", "AUTH", "default", "wrong"]), &mut client, &acl, &server_info).unwrap();
+    assert_eq!(result, b"-WRONGPASS invalid username-password pair or user is disabled.\r\n");
+    assert!(!client.authenticated);
+
+    let result = process_hello(&parts(&["HELLO", "3", "AUTH", "default", "secret"]), &mut client, &acl, &server_info).unwrap();
+    assert!(result.starts_with(b"%"));
+    assert!(client.authenticated);
+    assert_eq!(client.protocol, 3);
+}
@@ -0,0 +1,770 @@
+use std::sync::Arc;
+use std::collections::HashSet;
+
+use redis_cache::models::{RedisData, RedisValue, KvStore, Store};
+use redis_cache::commands::{
+    process_sadd, process_srem, process_smembers, process_scard, process_sismember,
+    process_smismember, process_srandmember, process_spop, process_smove, process_sscan,
+    process_sinter, process_sunion, process_sdiff,
+    process_sinterstore, process_sunionstore, process_sdiffstore, process_sintercard, process_type
+};
+
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
+}
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+const WRONGTYPE: &[u8] = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+
+fn insert_set(kv_store: &KvStore, key: &str, members: &[&str]) {
+    let set: HashSet<String> = members.iter().map(|m| m.to_string()).collect();
+    let mut map = kv_store.lock_all();
+    map.insert(key.to_string(), RedisValue::new(RedisData::Set(set), None));
+}
+
+// Set replies are unordered, so decode the bulk strings of a flat array and sort them
+fn sorted_members(bytes: &[u8]) -> Vec<String> {
+    let response = String::from_utf8_lossy(bytes);
+    let mut members: Vec<String> = response
+        .split("\r\n")
+        .skip(1)
+        .filter(|line| !line.starts_with('$') && !line.is_empty())
+        .map(|line| line.to_string())
+        .collect();
+    members.sort();
+    members
+}
+
+fn insert_string(kv_store: &KvStore, key: &str) {
+    let mut map = kv_store.lock_all();
+    map.insert(
+        key.to_string(),
+        RedisValue::new(RedisData::String("value".into()), None),
+    );
+}
+
+// ==================== SADD Tests ====================
+
+#[test]
+fn test_sadd_new_members() {
+    let kv_store = new_kv_store();
+    let result = process_sadd(&parts(&["SADD", "myset", "a", "b", "c"]), &kv_store);
+    assert_eq!(result.unwrap(), b":3\r\n");
+}
+
+#[test]
+fn test_sadd_ignores_existing_and_repeated_members() {
+    let kv_store = new_kv_store();
+    process_sadd(&parts(&["SADD", "myset", "a"]), &kv_store).unwrap();
+
+    let result = process_sadd(&parts(&["SADD", "myset", "a", "b", "b"]), &kv_store);
+    assert_eq!(result.unwrap(), b":1\r\n");
+
+    let members = process_smembers(&parts(&["SMEMBERS", "myset"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&members), vec!["a", "b"]);
+}
+
+#[test]
+fn test_sadd_missing_members() {
+    let kv_store = new_kv_store();
+    assert!(process_sadd(&parts(&["SADD", "myset"]), &kv_store).is_err());
+}
+
+#[test]
+fn test_sadd_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_sadd(&parts(&["SADD", "strkey", "a"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SREM Tests ====================
+
+#[test]
+fn test_srem_existing_and_missing_members() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a", "b", "c"]);
+
+    let result = process_srem(&parts(&["SREM", "myset", "a", "x"]), &kv_store);
+    assert_eq!(result.unwrap(), b":1\r\n");
+
+    let members = process_smembers(&parts(&["SMEMBERS", "myset"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&members), vec!["b", "c"]);
+}
+
+#[test]
+fn test_srem_last_member_removes_key() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a"]);
+
+    process_srem(&parts(&["SREM", "myset", "a"]), &kv_store).unwrap();
+    assert!(kv_store.lock_all().get("myset").is_none());
+}
+
+#[test]
+fn test_srem_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_srem(&parts(&["SREM", "nokey", "a"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_srem_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_srem(&parts(&["SREM", "strkey", "a"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SMEMBERS Tests ====================
+
+#[test]
+fn test_smembers_returns_all_members() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["x", "y"]);
+
+    let result = process_smembers(&parts(&["SMEMBERS", "myset"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"*2\r\n"));
+    assert_eq!(sorted_members(&result), vec!["x", "y"]);
+}
+
+#[test]
+fn test_smembers_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_smembers(&parts(&["SMEMBERS", "nokey"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_smembers_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_smembers(&parts(&["SMEMBERS", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SCARD Tests ====================
+
+#[test]
+fn test_scard_counts_members() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a", "b", "c"]);
+    let result = process_scard(&parts(&["SCARD", "myset"]), &kv_store);
+    assert_eq!(result.unwrap(), b":3\r\n");
+}
+
+#[test]
+fn test_scard_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_scard(&parts(&["SCARD", "nokey"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_scard_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_scard(&parts(&["SCARD", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SISMEMBER Tests ====================
+
+#[test]
+fn test_sismember() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a"]);
+
+    let present = process_sismember(&parts(&["SISMEMBER", "myset", "a"]), &kv_store);
+    assert_eq!(present.unwrap(), b":1\r\n");
+    let absent = process_sismember(&parts(&["SISMEMBER", "myset", "b"]), &kv_store);
+    assert_eq!(absent.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_sismember_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_sismember(&parts(&["SISMEMBER", "nokey", "a"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_sismember_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_sismember(&parts(&["SISMEMBER", "strkey", "a"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SMISMEMBER Tests ====================
+
+#[test]
+fn test_smismember_flags_each_member() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a", "c"]);
+
+    let result = process_smismember(&parts(&["SMISMEMBER", "myset", "a", "b", "c"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*3\r\n:1\r\n:0\r\n:1\r\n");
+}
+
+#[test]
+fn test_smismember_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_smismember(&parts(&["SMISMEMBER", "nokey", "a", "b"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*2\r\n:0\r\n:0\r\n");
+}
+
+#[test]
+fn test_smismember_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_smismember(&parts(&["SMISMEMBER", "strkey", "a"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SRANDMEMBER Tests ====================
+
+#[test]
+fn test_srandmember_without_count() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["only"]);
+
+    let result = process_srandmember(&parts(&["SRANDMEMBER", "myset"]), &kv_store);
+    assert_eq!(result.unwrap(), b"$4\r\nonly\r\n");
+    // SRANDMEMBER never removes anything
+    let card = process_scard(&parts(&["SCARD", "myset"]), &kv_store);
+    assert_eq!(card.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_srandmember_positive_count_is_distinct() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a", "b", "c"]);
+
+    let result = process_srandmember(&parts(&["SRANDMEMBER", "myset", "2"]), &kv_store).unwrap();
+    let mut members = sorted_members(&result);
+    assert_eq!(members.len(), 2);
+    members.dedup();
+    assert_eq!(members.len(), 2);
+
+    let all = process_srandmember(&parts(&["SRANDMEMBER", "myset", "10"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&all), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_srandmember_negative_count_may_repeat() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a"]);
+
+    let result = process_srandmember(&parts(&["SRANDMEMBER", "myset", "-3"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*3\r\n$1\r\na\r\n$1\r\na\r\n$1\r\na\r\n");
+}
+
+#[test]
+fn test_srandmember_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let single = process_srandmember(&parts(&["SRANDMEMBER", "nokey"]), &kv_store);
+    assert_eq!(single.unwrap(), b"$-1\r\n");
+    let counted = process_srandmember(&parts(&["SRANDMEMBER", "nokey", "-2"]), &kv_store);
+    assert_eq!(counted.unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_srandmember_invalid_count() {
+    let kv_store = new_kv_store();
+    let result = process_srandmember(&parts(&["SRANDMEMBER", "myset", "x"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"-ERR"));
+}
+
+#[test]
+fn test_srandmember_rejects_huge_counts() {
+    let kv_store = new_kv_store();
+    process_sadd(&parts(&["SADD", "myset", "a"]), &kv_store).unwrap();
+    // Would otherwise try to pick ~2^63 members
+    for count in ["-9223372036854775808", "-4611686018427387904", "9223372036854775807"] {
+        let result = process_srandmember(&parts(&["SRANDMEMBER", "myset", count]), &kv_store).unwrap();
+        assert_eq!(result, b"-ERR value is out of range\r\n");
+    }
+}
+
+#[test]
+fn test_srandmember_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_srandmember(&parts(&["SRANDMEMBER", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SPOP Tests ====================
+
+#[test]
+fn test_spop_without_count_removes_member() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a", "b"]);
+
+    let result = process_spop(&parts(&["SPOP", "myset"]), &kv_store).unwrap();
+    let popped = if result == b"$1\r\na\r\n" { "a" } else { "b" };
+    assert!(result == b"$1\r\na\r\n" || result == b"$1\r\nb\r\n");
+
+    let still_there = process_sismember(&parts(&["SISMEMBER", "myset", popped]), &kv_store);
+    assert_eq!(still_there.unwrap(), b":0\r\n");
+    let card = process_scard(&parts(&["SCARD", "myset"]), &kv_store);
+    assert_eq!(card.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_spop_with_count() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a", "b", "c"]);
+
+    let result = process_spop(&parts(&["SPOP", "myset", "2"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&result).len(), 2);
+    let card = process_scard(&parts(&["SCARD", "myset"]), &kv_store);
+    assert_eq!(card.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_spop_count_exceeding_size_removes_key() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a", "b"]);
+
+    let result = process_spop(&parts(&["SPOP", "myset", "5"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&result), vec!["a", "b"]);
+    assert!(kv_store.lock_all().get("myset").is_none());
+}
+
+#[test]
+fn test_spop_negative_count() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a"]);
+
+    let result = process_spop(&parts(&["SPOP", "myset", "-1"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"-ERR"));
+    let card = process_scard(&parts(&["SCARD", "myset"]), &kv_store);
+    assert_eq!(card.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_spop_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let single = process_spop(&parts(&["SPOP", "nokey"]), &kv_store);
+    assert_eq!(single.unwrap(), b"$-1\r\n");
+    let counted = process_spop(&parts(&["SPOP", "nokey", "3"]), &kv_store);
+    assert_eq!(counted.unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_spop_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_spop(&parts(&["SPOP", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SMOVE Tests ====================
+
+#[test]
+fn test_smove_moves_member() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "src", &["a", "b"]);
+    insert_set(&kv_store, "dst", &["c"]);
+
+    let result = process_smove(&parts(&["SMOVE", "src", "dst", "a"]), &kv_store);
+    assert_eq!(result.unwrap(), b":1\r\n");
+
+    let src = process_smembers(&parts(&["SMEMBERS", "src"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&src), vec!["b"]);
+    let dst = process_smembers(&parts(&["SMEMBERS", "dst"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&dst), vec!["a", "c"]);
+}
+
+#[test]
+fn test_smove_creates_destination_and_removes_empty_source() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "src", &["a"]);
+
+    let result = process_smove(&parts(&["SMOVE", "src", "dst", "a"]), &kv_store);
+    assert_eq!(result.unwrap(), b":1\r\n");
+
+    let map = kv_store.lock_all();
+    assert!(map.get("src").is_none());
+    assert!(map.get("dst").is_some());
+}
+
+#[test]
+fn test_smove_missing_member_or_source() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "src", &["a"]);
+
+    let missing_member = process_smove(&parts(&["SMOVE", "src", "dst", "x"]), &kv_store);
+    assert_eq!(missing_member.unwrap(), b":0\r\n");
+    let missing_source = process_smove(&parts(&["SMOVE", "nokey", "dst", "a"]), &kv_store);
+    assert_eq!(missing_source.unwrap(), b":0\r\n");
+    assert!(kv_store.lock_all().get("dst").is_none());
+}
+
+#[test]
+fn test_smove_same_source_and_destination() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s", &["a"]);
+
+    let result = process_smove(&parts(&["SMOVE", "s", "s", "a"]), &kv_store);
+    assert_eq!(result.unwrap(), b":1\r\n");
+    let members = process_smembers(&parts(&["SMEMBERS", "s"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&members), vec!["a"]);
+}
+
+#[test]
+fn test_smove_wrong_type_destination_leaves_source() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "src", &["a"]);
+    insert_string(&kv_store, "strkey");
+
+    assert_eq!(process_smove(&parts(&["SMOVE", "src", "strkey", "a"]), &kv_store).unwrap(), WRONGTYPE);
+    let still_there = process_sismember(&parts(&["SISMEMBER", "src", "a"]), &kv_store);
+    assert_eq!(still_there.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_smove_wrong_type_source() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_smove(&parts(&["SMOVE", "strkey", "dst", "a"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SSCAN Tests ====================
+
+#[test]
+fn test_sscan_returns_all_members() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a", "b", "c"]);
+
+    let result = process_sscan(&parts(&["SSCAN", "myset", "0"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"*2\r\n$1\r\n0\r\n*3\r\n"));
+    assert_eq!(sorted_members(&result), vec!["*3", "0", "a", "b", "c"]);
+}
+
+#[test]
+fn test_sscan_match_pattern() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["user:1", "user:2", "admin:1"]);
+
+    let p = parts(&["SSCAN", "myset", "0", "MATCH", "user:*", "COUNT", "100"]);
+    let result = process_sscan(&p, &kv_store).unwrap();
+    assert_eq!(sorted_members(&result), vec!["*2", "0", "user:1", "user:2"]);
+}
+
+#[test]
+fn test_sscan_nonzero_cursor_and_missing_key() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "myset", &["a"]);
+
+    let finished = process_sscan(&parts(&["SSCAN", "myset", "5"]), &kv_store);
+    assert_eq!(finished.unwrap(), b"*2\r\n$1\r\n0\r\n*0\r\n");
+    let missing = process_sscan(&parts(&["SSCAN", "nokey", "0"]), &kv_store);
+    assert_eq!(missing.unwrap(), b"*2\r\n$1\r\n0\r\n*0\r\n");
+}
+
+#[test]
+fn test_sscan_invalid_arguments() {
+    let kv_store = new_kv_store();
+    let bad_cursor = process_sscan(&parts(&["SSCAN", "myset", "-1"]), &kv_store).unwrap();
+    assert!(bad_cursor.starts_with(b"-ERR invalid cursor"));
+    let bad_option = process_sscan(&parts(&["SSCAN", "myset", "0", "LIMIT", "1"]), &kv_store).unwrap();
+    assert!(bad_option.starts_with(b"-ERR syntax error"));
+}
+
+#[test]
+fn test_sscan_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_sscan(&parts(&["SSCAN", "strkey", "0"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SINTER Tests ====================
+
+#[test]
+fn test_sinter_two_sets() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b", "c"]);
+    insert_set(&kv_store, "s2", &["b", "c", "d"]);
+
+    let p = parts(&["SINTER", "s1", "s2"]);
+    let result = process_sinter(&p, &kv_store).unwrap();
+    assert!(result.starts_with(b"*2\r\n"));
+    assert_eq!(sorted_members(&result), vec!["b", "c"]);
+}
+
+#[test]
+fn test_sinter_single_key_returns_members() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b"]);
+
+    let p = parts(&["SINTER", "s1"]);
+    let result = process_sinter(&p, &kv_store).unwrap();
+    assert_eq!(sorted_members(&result), vec!["a", "b"]);
+}
+
+#[test]
+fn test_sinter_with_missing_key_is_empty() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b"]);
+
+    let p = parts(&["SINTER", "s1", "nokey"]);
+    let result = process_sinter(&p, &kv_store).unwrap();
+    assert_eq!(result, b"*0\r\n");
+}
+
+#[test]
+fn test_sinter_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a"]);
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+
+    let p = parts(&["SINTER", "s1", "strkey"]);
+    let result = process_sinter(&p, &kv_store).unwrap();
+    assert_eq!(result, WRONGTYPE);
+}
+
+#[test]
+fn test_sinter_missing_keys_argument() {
+    let kv_store = new_kv_store();
+    let p = parts(&["SINTER"]);
+    assert!(process_sinter(&p, &kv_store).is_err());
+}
+
+// ==================== SUNION Tests ====================
+
+#[test]
+fn test_sunion_two_sets() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b"]);
+    insert_set(&kv_store, "s2", &["b", "c"]);
+
+    let p = parts(&["SUNION", "s1", "s2"]);
+    let result = process_sunion(&p, &kv_store).unwrap();
+    assert!(result.starts_with(b"*3\r\n"));
+    assert_eq!(sorted_members(&result), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_sunion_missing_keys_are_empty() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s2", &["x"]);
+
+    let p = parts(&["SUNION", "nokey", "s2", "other"]);
+    let result = process_sunion(&p, &kv_store).unwrap();
+    assert_eq!(sorted_members(&result), vec!["x"]);
+}
+
+#[test]
+fn test_sunion_all_missing() {
+    let kv_store = new_kv_store();
+    let p = parts(&["SUNION", "a", "b"]);
+    let result = process_sunion(&p, &kv_store).unwrap();
+    assert_eq!(result, b"*0\r\n");
+}
+
+#[test]
+fn test_sunion_wrong_type() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "mylist".to_string(),
+            RedisValue::new(RedisData::List(vec!["item".to_string()]), None),
+        );
+    }
+
+    let p = parts(&["SUNION", "mylist"]);
+    assert_eq!(process_sunion(&p, &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== SDIFF Tests ====================
+
+#[test]
+fn test_sdiff_first_minus_rest() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b", "c", "d"]);
+    insert_set(&kv_store, "s2", &["b"]);
+    insert_set(&kv_store, "s3", &["d", "e"]);
+
+    let p = parts(&["SDIFF", "s1", "s2", "s3"]);
+    let result = process_sdiff(&p, &kv_store).unwrap();
+    assert_eq!(sorted_members(&result), vec!["a", "c"]);
+}
+
+#[test]
+fn test_sdiff_missing_first_key() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s2", &["a"]);
+
+    let p = parts(&["SDIFF", "nokey", "s2"]);
+    let result = process_sdiff(&p, &kv_store).unwrap();
+    assert_eq!(result, b"*0\r\n");
+}
+
+#[test]
+fn test_sdiff_missing_other_keys_ignored() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b"]);
+
+    let p = parts(&["SDIFF", "s1", "nokey"]);
+    let result = process_sdiff(&p, &kv_store).unwrap();
+    assert_eq!(sorted_members(&result), vec!["a", "b"]);
+}
+
+#[test]
+fn test_sdiff_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a"]);
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+
+    let p = parts(&["SDIFF", "s1", "strkey"]);
+    assert_eq!(process_sdiff(&p, &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== *STORE Tests ====================
+
+#[test]
+fn test_sinterstore_writes_destination() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b", "c"]);
+    insert_set(&kv_store, "s2", &["b", "c", "d"]);
+
+    let result = process_sinterstore(&parts(&["SINTERSTORE", "dest", "s1", "s2"]), &kv_store);
+    assert_eq!(result.unwrap(), b":2\r\n");
+
+    let members = process_smembers(&parts(&["SMEMBERS", "dest"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&members), vec!["b", "c"]);
+}
+
+#[test]
+fn test_sunionstore_overwrites_existing_destination() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a"]);
+    insert_set(&kv_store, "s2", &["b"]);
+    insert_string(&kv_store, "dest");
+
+    let result = process_sunionstore(&parts(&["SUNIONSTORE", "dest", "s1", "s2"]), &kv_store);
+    assert_eq!(result.unwrap(), b":2\r\n");
+
+    let kind = process_type(&parts(&["TYPE", "dest"]), &kv_store);
+    assert_eq!(kind.unwrap(), b"+set\r\n");
+}
+
+#[test]
+fn test_sdiffstore_can_use_destination_as_source() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b", "c"]);
+    insert_set(&kv_store, "s2", &["b"]);
+
+    let result = process_sdiffstore(&parts(&["SDIFFSTORE", "s1", "s1", "s2"]), &kv_store);
+    assert_eq!(result.unwrap(), b":2\r\n");
+
+    let members = process_smembers(&parts(&["SMEMBERS", "s1"]), &kv_store).unwrap();
+    assert_eq!(sorted_members(&members), vec!["a", "c"]);
+}
+
+#[test]
+fn test_store_empty_result_deletes_destination() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a"]);
+    insert_set(&kv_store, "dest", &["old"]);
+
+    let result = process_sinterstore(&parts(&["SINTERSTORE", "dest", "s1", "nokey"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+    assert!(kv_store.lock_all().get("dest").is_none());
+}
+
+#[test]
+fn test_store_wrong_type_source_leaves_destination() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "dest", &["old"]);
+    insert_string(&kv_store, "strkey");
+
+    let result = process_sunionstore(&parts(&["SUNIONSTORE", "dest", "strkey"]), &kv_store).unwrap();
+    assert_eq!(result, WRONGTYPE);
+    let card = process_scard(&parts(&["SCARD", "dest"]), &kv_store);
+    assert_eq!(card.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_store_missing_source_keys() {
+    let kv_store = new_kv_store();
+    assert!(process_sdiffstore(&parts(&["SDIFFSTORE", "dest"]), &kv_store).is_err());
+}
+
+// ==================== SINTERCARD Tests ====================
+
+#[test]
+fn test_sintercard_counts_intersection() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b", "c"]);
+    insert_set(&kv_store, "s2", &["b", "c", "d"]);
+
+    let result = process_sintercard(&parts(&["SINTERCARD", "2", "s1", "s2"]), &kv_store);
+    assert_eq!(result.unwrap(), b":2\r\n");
+}
+
+#[test]
+fn test_sintercard_limit() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a", "b", "c"]);
+
+    let limited = process_sintercard(&parts(&["SINTERCARD", "1", "s1", "LIMIT", "2"]), &kv_store);
+    assert_eq!(limited.unwrap(), b":2\r\n");
+    let unlimited = process_sintercard(&parts(&["SINTERCARD", "1", "s1", "LIMIT", "0"]), &kv_store);
+    assert_eq!(unlimited.unwrap(), b":3\r\n");
+}
+
+#[test]
+fn test_sintercard_missing_key_is_zero() {
+    let kv_store = new_kv_store();
+    insert_set(&kv_store, "s1", &["a"]);
+    let result = process_sintercard(&parts(&["SINTERCARD", "2", "s1", "nokey"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_sintercard_invalid_arguments() {
+    let kv_store = new_kv_store();
+    let zero_keys = process_sintercard(&parts(&["SINTERCARD", "0", "s1"]), &kv_store).unwrap();
+    assert_eq!(zero_keys, b"-ERR numkeys should be greater than 0\r\n");
+    // Even with nothing after it
+    let zero_keys = process_sintercard(&parts(&["SINTERCARD", "0"]), &kv_store).unwrap();
+    assert_eq!(zero_keys, b"-ERR numkeys should be greater than 0\r\n");
+    let too_many = process_sintercard(&parts(&["SINTERCARD", "3", "s1", "s2"]), &kv_store).unwrap();
+    assert!(too_many.starts_with(b"-ERR"));
+    let huge = process_sintercard(&parts(&["SINTERCARD", "18446744073709551615", "k"]), &kv_store).unwrap();
+    assert_eq!(huge, b"-ERR Number of keys can't be greater than number of args\r\n");
+    let bad_limit = process_sintercard(&parts(&["SINTERCARD", "1", "s1", "LIMIT", "-1"]), &kv_store).unwrap();
+    assert!(bad_limit.starts_with(b"-ERR"));
+    let bad_option = process_sintercard(&parts(&["SINTERCARD", "1", "s1", "COUNT", "1"]), &kv_store).unwrap();
+    assert!(bad_option.starts_with(b"-ERR syntax error"));
+}
+
+#[test]
+fn test_sintercard_wrong_type() {
+    let kv_store = new_kv_store();
+    insert_string(&kv_store, "strkey");
+    assert_eq!(process_sintercard(&parts(&["SINTERCARD", "1", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== TYPE Tests ====================
+
+#[test]
+fn test_type_set_created_by_sadd() {
+    let kv_store = new_kv_store();
+    process_sadd(&parts(&["SADD", "myset", "a"]), &kv_store).unwrap();
+
+    let result = process_type(&parts(&["TYPE", "myset"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+set\r\n");
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use redis_cache::models::{KvStore, ListDir, RedisData, RedisValue, Store, WaitingRoom};
+use redis_cache::commands::{
+    process_get, process_hget, process_hgetall, process_hset, process_incr, process_llen, process_lrange, process_push,
+    process_sadd, process_scard, process_sinter, process_sinterstore, process_smembers, process_ttl, process_xadd,
+    process_xrange, process_zadd, process_zcard, process_zrange, process_zscore, process_zunion,
+};
+
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
+}
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn string_value(value: &str) -> RedisValue {
+    RedisValue::new(RedisData::String(value.into()), None)
+}
+
+// Finds a key that hashes to a different shard than `other`
+fn key_on_other_shard(kv_store: &KvStore, other: &str) -> String {
+    (0..)
+        .map(|i| format!("key:{}", i))
+        .find(|key| kv_store.shard_of(key) != kv_store.shard_of(other))
+        .unwrap()
+}
+
+// ==================== Store Tests ====================
+
+#[test]
+fn test_store_insert_get_remove() {
+    let kv_store = new_kv_store();
+    assert!(kv_store.is_empty());
+
+    kv_store.insert("a".to_string(), string_value("1"));
+    kv_store.insert("b".to_string(), string_value("2"));
+    assert_eq!(kv_store.len(), 2);
+    assert!(kv_store.contains_key("a"));
+
+    assert!(kv_store.remove("a").is_some());
+    assert!(!kv_store.contains_key("a"));
+    assert_eq!(kv_store.len(), 1);
+}
+
+#[test]
+fn test_store_shard_of_is_stable() {
+    let kv_store = new_kv_store();
+    assert_eq!(kv_store.shard_count(), 16);
+    assert_eq!(kv_store.shard_of("mykey"), kv_store.shard_of("mykey"));
+    assert!(kv_store.shard_of("mykey") < kv_store.shard_count());
+}
+
+#[test]
+fn test_single_shard_store() {
+    let kv_store: KvStore = Arc::new(Store::with_shards(1));
+    kv_store.insert("a".to_string(), string_value("1"));
+    kv_store.insert("b".to_string(), string_value("2"));
+
+    let map = kv_store.lock_keys(&["a", "b"]);
+    assert_eq!(map.len(), 2);
+}
+
+#[test]
+fn test_lock_keys_spanning_shards() {
+    let kv_store = new_kv_store();
+    let other = key_on_other_shard(&kv_store, "a");
+
+    {
+        let mut map = kv_store.lock_keys(&["a", other.as_str()]);
+        map.insert("a".to_string(), string_value("1"));
+        map.entry(other.clone()).or_insert(string_value("2"));
+        assert!(map.get("a").is_some());
+        assert!(map.contains_key(&other));
+    }
+    assert_eq!(kv_store.len(), 2);
+}
+
+#[test]
+fn test_lock_all_iterates_every_shard() {
+    let kv_store = new_kv_store();
+    for i in 0..50 {
+        kv_store.insert(format!("key:{}", i), string_value("x"));
+    }
+
+    let map = kv_store.lock_all();
+    assert_eq!(map.len(), 50);
+    assert_eq!(map.iter().count(), 50);
+}
+
+#[test]
+fn test_multi_key_command_across_shards() {
+    let kv_store = new_kv_store();
+    let other = key_on_other_shard(&kv_store, "s1");
+    process_sadd(&parts(&["SADD", "s1", "a", "b"]), &kv_store).unwrap();
+    process_sadd(&parts(&["SADD", &other, "b", "c"]), &kv_store).unwrap();
+
+    let result = process_sinterstore(&parts(&["SINTERSTORE", "dest", "s1", &other]), &kv_store).unwrap();
+    assert_eq!(result, b":1\r\n");
+    let members = process_smembers(&parts(&["SMEMBERS", "dest"]), &kv_store).unwrap();
+    assert_eq!(members, b"*1\r\n$1\r\nb\r\n");
+}
+
+// ==================== Concurrency Tests ====================
+
+#[test]
+fn test_independent_keys_do_not_contend() {
+    let kv_store = new_kv_store();
+    let other = key_on_other_shard(&kv_store, "held");
+
+    // Hold the shard for "held" for the whole test; a key on another shard
+    // must still be writable while it's locked
+    let _held = kv_store.shard("held");
+
+    let (tx, rx) = mpsc::channel();
+    let store = Arc::clone(&kv_store);
+    let key = other.clone();
+    thread::spawn(move || {
+        let reply = process_incr(&parts(&["INCR", &key]), &store).unwrap();
+        tx.send(reply).unwrap();
+    });
+
+    let reply = rx.recv_timeout(Duration::from_secs(2))
+        .expect("INCR on an unrelated key blocked behind another shard's lock");
+    assert_eq!(reply, b":1\r\n");
+}
+
+#[test]
+fn test_incr_overflow_leaves_shard_usable() {
+    let kv_store = new_kv_store();
+    kv_store.insert("n".to_string(), string_value("9223372036854775807"));
+
+    let reply = process_incr(&parts(&["INCR", "n"]), &kv_store).unwrap();
+    assert_eq!(reply, b"-ERR increment or decrement would overflow\r\n");
+    // The shard's lock isn't poisoned and the value is untouched
+    let reply = process_get(&parts(&["GET", "n"]), &kv_store).unwrap();
+    assert_eq!(reply, b"$19\r\n9223372036854775807\r\n");
+}
+
+#[test]
+fn test_readers_share_a_shard() {
+    let kv_store = new_kv_store();
+    kv_store.insert("key".to_string(), string_value("v"));
+
+    // A reader holding the shard doesn't stop another read of the same key
+    let _reading = kv_store.shard_read("key");
+    let (tx, rx) = mpsc::channel();
+    let store = Arc::clone(&kv_store);
+    thread::spawn(move || {
+        tx.send(process_get(&parts(&["GET", "key"]), &store).unwrap()).unwrap();
+    });
+
+    let reply = rx.recv_timeout(Duration::from_secs(2))
+        .expect("GET blocked behind another reader");
+    assert_eq!(reply, b"$1\r\nv\r\n");
+}
+
+#[test]
+fn test_read_only_commands_share_a_shard() {
+    // One shard, so every key below sits behind the same lock
+    let kv_store: KvStore = Arc::new(Store::with_shards(1));
+    let waiting_room: WaitingRoom = Arc::new(Mutex::new(HashMap::new()));
+    process_hset(&parts(&["HSET", "hash", "f", "v"]), &kv_store).unwrap();
+    process_sadd(&parts(&["SADD", "set", "m"]), &kv_store).unwrap();
+    process_zadd(&parts(&["ZADD", "zset", "1", "m"]), &kv_store, &waiting_room).unwrap();
+    process_push(&parts(&["RPUSH", "list", "a"]), &kv_store, &waiting_room, ListDir::R).unwrap();
+    process_xadd(&parts(&["XADD", "stream", "1-1", "f", "v"]), &kv_store, &waiting_room).unwrap();
+
+    // Any of these asking for the write lock would wait for this reader to finish
+    let _reading = kv_store.shard_read("hash");
+    let (tx, rx) = mpsc::channel();
+    let store = Arc::clone(&kv_store);
+    thread::spawn(move || {
+        let replies = vec![
+            process_hget(&parts(&["HGET", "hash", "f"]), &store).unwrap(),
+            process_hgetall(&parts(&["HGETALL", "hash"]), &store, 2).unwrap(),
+            process_smembers(&parts(&["SMEMBERS", "set"]), &store).unwrap(),
+            process_sinter(&parts(&["SINTER", "set", "set"]), &store).unwrap(),
+            process_zscore(&parts(&["ZSCORE", "zset", "m"]), &store, 2).unwrap(),
+            process_zrange(&parts(&["ZRANGE", "zset", "0", "-1"]), &store).unwrap(),
+            process_zunion(&parts(&["ZUNION", "1", "zset"]), &store).unwrap(),
+            process_lrange(&parts(&["LRANGE", "list", "0", "-1"]), &store).unwrap(),
+            process_xrange(&parts(&["XRANGE", "stream", "-", "+"]), &store).unwrap(),
+            process_ttl(&parts(&["TTL", "hash"]), &store).unwrap(),
+        ];
+        tx.send(replies).unwrap();
+    });
+
+    let replies = rx.recv_timeout(Duration::from_secs(2))
+        .expect("a read-only command blocked behind another reader");
+    assert_eq!(replies[0], b"$1\r\nv\r\n");
+    assert_eq!(replies[4], b"$1\r\n1\r\n");
+    assert_eq!(replies[9], b":-1\r\n");
+}
+
+#[test]
+fn test_read_live_hides_and_removes_expired() {
+    let kv_store = new_kv_store();
+    kv_store.insert(
+        "dead".to_string(),
+        RedisValue::new(RedisData::String("v".into()), Some(Instant::now() - Duration::from_secs(1))),
+    );
+    kv_store.insert("live".to_string(), string_value("v"));
+
+    assert!(kv_store.read_live("dead", |value| value.is_none()));
+    assert!(!kv_store.contains_key("dead"));
+    assert!(kv_store.read_live("live", |value| value.is_some()));
+}
+
+#[test]
+fn test_collections_expire_lazily() {
+    let kv_store = new_kv_store();
+    let waiting_room: WaitingRoom = Arc::new(Mutex::new(HashMap::new()));
+    process_hset(&parts(&["HSET", "hash", "f", "v"]), &kv_store).unwrap();
+    process_sadd(&parts(&["SADD", "set", "m"]), &kv_store).unwrap();
+    process_zadd(&parts(&["ZADD", "zset", "1", "m"]), &kv_store, &waiting_room).unwrap();
+    process_push(&parts(&["RPUSH", "list", "a"]), &kv_store, &waiting_room, ListDir::R).unwrap();
+    process_xadd(&parts(&["XADD", "stream", "1-1", "f", "v"]), &kv_store, &waiting_room).unwrap();
+    let past = Instant::now() - Duration::from_secs(1);
+    for key in ["hash", "set", "zset", "list", "stream"] {
+        kv_store.shard(key).get_mut(key).unwrap().expires_at = Some(past);
+    }
+
+    assert_eq!(process_hget(&parts(&["HGET", "hash", "f"]), &kv_store).unwrap(), b"$-1\r\n");
+    assert_eq!(process_zcard(&parts(&["ZCARD", "zset"]), &kv_store).unwrap(), b":0\r\n");
+    assert_eq!(process_llen(&parts(&["LLEN", "list"]), &kv_store).unwrap(), b":0\r\n");
+    assert_eq!(process_xrange(&parts(&["XRANGE", "stream", "-", "+"]), &kv_store).unwrap(), b"*0\r\n");
+    // Multi-key commands see it gone too, whether they read or write
+    assert_eq!(process_sinter(&parts(&["SINTER", "set"]), &kv_store).unwrap(), b"*0\r\n");
+    assert!(!kv_store.contains_key("set"));
+    kv_store.insert("set".to_string(), RedisValue::new(RedisData::String("old".into()), Some(past)));
+    let stored = process_sinterstore(&parts(&["SINTERSTORE", "dst", "set"]), &kv_store).unwrap();
+    assert_eq!(stored, b":0\r\n");
+    for key in ["hash", "set", "zset", "list", "stream"] {
+        assert!(!kv_store.contains_key(key), "{} outlived its expiry", key);
+    }
+
+    // A write starts over rather than adding to what expired
+    kv_store.insert(
+        "set".to_string(),
+        RedisValue::new(RedisData::String("old".into()), Some(past)),
+    );
+    process_sadd(&parts(&["SADD", "set", "n"]), &kv_store).unwrap();
+    assert_eq!(process_scard(&parts(&["SCARD", "set"]), &kv_store).unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_concurrent_incr_on_independent_keys() {
+    const THREADS: usize = 8;
+    const OPS: usize = 5_000;
+    let kv_store = new_kv_store();
+
+    let start = Instant::now();
+    let handles: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let store = Arc::clone(&kv_store);
+            thread::spawn(move || {
+                let p = parts(&["INCR", &format!("counter:{}", t)]);
+                for _ in 0..OPS {
+                    process_incr(&p, &store).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    println!("{} INCRs across {} keys took {:?}", THREADS * OPS, THREADS, start.elapsed());
+
+    for t in 0..THREADS {
+        let reply = process_get(&parts(&["GET", &format!("counter:{}", t)]), &kv_store).unwrap();
+        assert_eq!(reply, format!("${}\r\n{}\r\n", OPS.to_string().len(), OPS).into_bytes());
+    }
+}
@@ -1,15 +1,14 @@
 use std::sync::{Arc, Mutex};
-use std::collections::{HashMap, VecDeque};
-use tokio::sync::mpsc;
+use std::collections::HashMap;
 
-use redis_cache::models::{RedisData, RedisValue};
-use redis_cache::commands::{process_xadd, process_xrange, process_xread};
+use redis_cache::models::{RedisData, RedisValue, Stream, KvStore, Store, WaitingRoom};
+use redis_cache::commands::{process_xadd, process_xrange, process_xrevrange, process_xread, process_xinfo, process_xsetid, process_xdel};
 
-fn new_kv_store() -> Arc<Mutex<HashMap<String, RedisValue>>> {
-    Arc::new(Mutex::new(HashMap::new()))
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
 }
 
-fn new_waiting_room() -> Arc<Mutex<HashMap<String, VecDeque<mpsc::Sender<String>>>>> {
+fn new_waiting_room() -> WaitingRoom {
     Arc::new(Mutex::new(HashMap::new()))
 }
 
@@ -41,10 +40,10 @@ fn test_xadd_multiple_fields() {
     let result = process_xadd(&p, &kv_store, &waiting_room);
     assert!(result.is_ok());
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stream = map.get("mystream").unwrap();
     match &stream.data {
-        RedisData::Stream(entries) => {
+        RedisData::Stream(Stream { entries, .. }) => {
             assert_eq!(entries.len(), 1);
             assert_eq!(entries[0].fields.len(), 2);
             assert_eq!(entries[0].fields.get("field1"), Some(&"value1".to_string()));
@@ -63,10 +62,10 @@ fn test_xadd_sequential_ids() {
     process_xadd(&parts(&["XADD", "mystream", "1-2", "b", "2"]), &kv_store, &waiting_room).unwrap();
     process_xadd(&parts(&["XADD", "mystream", "2-0", "c", "3"]), &kv_store, &waiting_room).unwrap();
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stream = map.get("mystream").unwrap();
     match &stream.data {
-        RedisData::Stream(entries) => {
+        RedisData::Stream(Stream { entries, .. }) => {
             assert_eq!(entries.len(), 3);
         }
         _ => panic!("Expected stream data"),
@@ -84,6 +83,8 @@ fn test_xadd_rejects_zero_id() {
     let bytes = result.unwrap();
     let response = String::from_utf8_lossy(&bytes);
     assert!(response.contains("ERR") && response.contains("greater than 0-0"));
+    // The rejected entry doesn't leave an empty stream behind
+    assert!(!kv_store.contains_key("mystream"));
 }
 
 #[test]
@@ -100,6 +101,8 @@ fn test_xadd_rejects_non_increasing_id() {
     let bytes = result.unwrap();
     let response = String::from_utf8_lossy(&bytes);
     assert!(response.contains("ERR") && response.contains("equal or smaller"));
+    // The stream that was already there stays
+    assert!(kv_store.contains_key("mystream"));
 }
 
 #[test]
@@ -177,6 +180,134 @@ fn test_xadd_partial_wildcard_different_ms() {
     assert!(response.contains("200-0"));
 }
 
+// ==================== XADD Tests - Full Wildcard (*) ====================
+
+// Pulls the ID out of XADD's bulk string reply
+fn xadd_reply_id(bytes: &[u8]) -> String {
+    let response = String::from_utf8_lossy(bytes);
+    response.split("\r\n").nth(1).unwrap().to_string()
+}
+
+fn split_id(id: &str) -> (u64, u64) {
+    let (ms, seq) = id.split_once('-').unwrap();
+    (ms.parse().unwrap(), seq.parse().unwrap())
+}
+
+#[test]
+fn test_xadd_full_wildcard_generates_id() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let p = parts(&["XADD", "mystream", "*", "field", "value"]);
+    let result = process_xadd(&p, &kv_store, &waiting_room);
+    assert!(result.is_ok());
+    let id = xadd_reply_id(&result.unwrap());
+    let (ms, _) = split_id(&id);
+    assert!(ms > 0);
+}
+
+#[test]
+fn test_xadd_full_wildcard_stored_id_matches_reply() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let p = parts(&["XADD", "mystream", "*", "field", "value"]);
+    let id = xadd_reply_id(&process_xadd(&p, &kv_store, &waiting_room).unwrap());
+
+    let map = kv_store.lock_all();
+    match &map.get("mystream").unwrap().data {
+        RedisData::Stream(stream) => {
+            assert_eq!(stream.entries.len(), 1);
+            assert_eq!(stream.entries[0].id, id);
+        },
+        _ => panic!("Expected a stream"),
+    }
+}
+
+#[test]
+fn test_xadd_full_wildcard_id_round_trips_through_xrange() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let reply = process_xadd(&parts(&["XADD", "s", "*", "field", "value"]), &kv_store, &waiting_room).unwrap();
+    let id = xadd_reply_id(&reply);
+
+    let range = process_xrange(&parts(&["XRANGE", "s", "-", "+"]), &kv_store).unwrap();
+    let expected_prefix = format!("*1\r\n*2\r\n${}\r\n{}\r\n", id.len(), id);
+    assert!(String::from_utf8_lossy(&range).starts_with(&expected_prefix));
+
+    // The stored ID must also work as an XRANGE bound
+    let exact = process_xrange(&parts(&["XRANGE", "s", &id, &id]), &kv_store).unwrap();
+    assert!(exact.starts_with(b"*1\r\n"));
+}
+
+#[test]
+fn test_xadd_full_wildcard_same_millisecond_increments_seq() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    // Back-to-back adds regularly land in the same millisecond; each must still succeed
+    let mut last = (0, 0);
+    for _ in 0..50 {
+        let p = parts(&["XADD", "mystream", "*", "field", "value"]);
+        let bytes = process_xadd(&p, &kv_store, &waiting_room).unwrap();
+        assert!(bytes.starts_with(b"$"), "XADD * was rejected: {:?}", String::from_utf8_lossy(&bytes));
+        let current = split_id(&xadd_reply_id(&bytes));
+        assert!(current > last);
+        if current.0 == last.0 {
+            assert_eq!(current.1, last.1 + 1);
+        } else {
+            assert_eq!(current.1, 0);
+        }
+        last = current;
+    }
+}
+
+#[test]
+fn test_xadd_full_wildcard_when_last_id_is_ahead_of_clock() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    // An explicit ID far in the future puts the stream ahead of the wall clock
+    let future_ms: u64 = 99_999_999_999_999;
+    let future_id = format!("{}-3", future_ms);
+    process_xadd(&parts(&["XADD", "mystream", &future_id, "a", "1"]), &kv_store, &waiting_room).unwrap();
+
+    let bytes = process_xadd(&parts(&["XADD", "mystream", "*", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    assert_eq!(xadd_reply_id(&bytes), format!("{}-4", future_ms));
+}
+
+#[test]
+fn test_xadd_wildcard_after_the_last_possible_id() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+    let max = u64::MAX;
+    let top = format!("{}-{}", max, max);
+    process_xsetid(&parts(&["XSETID", "mystream", &top]), &kv_store).unwrap();
+
+    let result = process_xadd(&parts(&["XADD", "mystream", "*", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    assert_eq!(result, b"-ERR The stream has exhausted the last possible ID, unable to add more items\r\n");
+    let pinned = format!("{}-*", max);
+    let result = process_xadd(&parts(&["XADD", "mystream", &pinned, "a", "1"]), &kv_store, &waiting_room).unwrap();
+    assert_eq!(result, b"-ERR The ID specified in XADD is equal or smaller than the target stream top item\r\n");
+
+    // The shard is still usable afterwards
+    let result = process_xadd(&parts(&["XADD", "mystream", "*", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    assert!(result.starts_with(b"-ERR The stream has exhausted"));
+}
+
+#[test]
+fn test_xadd_wildcard_rolls_over_to_the_next_millisecond() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+    let future_ms: u64 = 99_999_999_999_999;
+    let top = format!("{}-{}", future_ms, u64::MAX);
+    process_xsetid(&parts(&["XSETID", "mystream", &top]), &kv_store).unwrap();
+
+    let bytes = process_xadd(&parts(&["XADD", "mystream", "*", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    assert_eq!(xadd_reply_id(&bytes), format!("{}-0", future_ms + 1));
+}
+
 // ==================== XADD Tests - Wrong Type ====================
 
 #[test]
@@ -186,17 +317,16 @@ fn test_xadd_wrong_type() {
 
     // Create a string key
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mykey".to_string(),
-            RedisValue::new(RedisData::String("value".to_string()), None),
+            RedisValue::new(RedisData::String("value".into()), None),
         );
     }
 
     let p = parts(&["XADD", "mykey", "1-1", "field", "value"]);
-    let result = process_xadd(&p, &kv_store, &waiting_room);
-    assert!(result.is_err());
-    assert!(result.unwrap_err().contains("WRONGTYPE"));
+    let result = process_xadd(&p, &kv_store, &waiting_room).unwrap();
+    assert_eq!(result, b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n");
 }
 
 #[test]
@@ -315,6 +445,177 @@ fn test_xrange_plus_end() {
     assert!(response.starts_with(b"*1"));
 }
 
+#[test]
+fn test_xrange_count_limits_entries() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "3-0", "c", "3"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XRANGE", "mystream", "-", "+", "COUNT", "1"]);
+    let result = process_xrange(&p, &kv_store);
+    assert!(result.is_ok());
+    let bytes = result.unwrap();
+    assert!(bytes.starts_with(b"*1"));
+    let response = String::from_utf8_lossy(&bytes);
+    // The earliest entry is the one kept
+    assert!(response.contains("1-0"));
+    assert!(!response.contains("2-0"));
+}
+
+#[test]
+fn test_xrange_count_exceeding_length() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "3-0", "c", "3"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XRANGE", "mystream", "-", "+", "count", "10"]);
+    let result = process_xrange(&p, &kv_store);
+    assert!(result.is_ok());
+    assert!(result.unwrap().starts_with(b"*3"));
+}
+
+#[test]
+fn test_xrange_count_zero() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XRANGE", "mystream", "-", "+", "COUNT", "0"]);
+    let result = process_xrange(&p, &kv_store);
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_xrange_without_count_returns_full_range() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "3-0", "c", "3"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XRANGE", "mystream", "2-0", "+"]);
+    let result = process_xrange(&p, &kv_store);
+    assert!(result.is_ok());
+    assert!(result.unwrap().starts_with(b"*2"));
+}
+
+#[test]
+fn test_xrange_invalid_count() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+
+    let not_an_integer = b"-ERR value is not an integer or out of range\r\n";
+    for count in ["abc", "-1"] {
+        let p = parts(&["XRANGE", "mystream", "-", "+", "COUNT", count]);
+        assert_eq!(process_xrange(&p, &kv_store).unwrap(), not_an_integer);
+        let p = parts(&["XREVRANGE", "mystream", "+", "-", "COUNT", count]);
+        assert_eq!(process_xrevrange(&p, &kv_store).unwrap(), not_an_integer);
+    }
+    let p = parts(&["XRANGE", "mystream", "-", "+", "COUNT"]);
+    assert_eq!(process_xrange(&p, &kv_store).unwrap(), b"-ERR syntax error\r\n");
+}
+
+#[test]
+fn test_xrange_wrong_type() {
+    let kv_store = new_kv_store();
+    kv_store.insert("mykey".to_string(), RedisValue::new(RedisData::String("value".into()), None));
+
+    let p = parts(&["XRANGE", "mykey", "-", "+"]);
+    assert_eq!(
+        process_xrange(&p, &kv_store).unwrap(),
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+    );
+    let p = parts(&["XREVRANGE", "mykey", "+", "-"]);
+    assert_eq!(
+        process_xrevrange(&p, &kv_store).unwrap(),
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+    );
+}
+
+// ==================== XREVRANGE Tests ====================
+
+#[test]
+fn test_xrevrange_full_range_is_reversed() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XREVRANGE", "mystream", "+", "-"]);
+    let result = process_xrevrange(&p, &kv_store).unwrap();
+    assert_eq!(
+        result,
+        b"*2\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$1\r\nb\r\n$1\r\n2\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n"
+    );
+}
+
+#[test]
+fn test_xrevrange_partial_range() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "3-0", "c", "3"]), &kv_store, &waiting_room).unwrap();
+
+    // End bound comes first; a bare ms covers every sequence number in it
+    let p = parts(&["XREVRANGE", "mystream", "2", "1-0"]);
+    let response = process_xrevrange(&p, &kv_store).unwrap();
+    assert!(response.starts_with(b"*2\r\n*2\r\n$3\r\n2-0"));
+}
+
+#[test]
+fn test_xrevrange_count_takes_newest_entries() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "3-0", "c", "3"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XREVRANGE", "mystream", "+", "-", "COUNT", "1"]);
+    let response = process_xrevrange(&p, &kv_store).unwrap();
+    assert!(response.starts_with(b"*1\r\n*2\r\n$3\r\n3-0"));
+}
+
+#[test]
+fn test_xrevrange_bounds_in_xrange_order_is_empty() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XREVRANGE", "mystream", "-", "+"]);
+    assert_eq!(process_xrevrange(&p, &kv_store).unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_xrevrange_nonexistent_stream() {
+    let kv_store = new_kv_store();
+    let p = parts(&["XREVRANGE", "nokey", "+", "-"]);
+    assert_eq!(process_xrevrange(&p, &kv_store).unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_xrevrange_missing_arguments() {
+    let kv_store = new_kv_store();
+    let p = parts(&["XREVRANGE", "mystream", "+"]);
+    assert!(process_xrevrange(&p, &kv_store).is_err());
+}
+
 // ==================== XREAD Tests - Without BLOCK ====================
 
 #[tokio::test]
@@ -390,6 +691,110 @@ async fn test_xread_multiple_streams() {
     assert!(response.len() > 20);
 }
 
+// ==================== XREAD Tests - COUNT ====================
+
+#[tokio::test]
+async fn test_xread_count_limits_each_stream() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    for id in ["1-0", "2-0", "3-0"] {
+        process_xadd(&parts(&["XADD", "s1", id, "a", "1"]), &kv_store, &waiting_room).unwrap();
+        process_xadd(&parts(&["XADD", "s2", id, "b", "2"]), &kv_store, &waiting_room).unwrap();
+    }
+
+    let p = parts(&["XREAD", "COUNT", "2", "STREAMS", "s1", "s2", "0-0", "1-0"]);
+    let response = process_xread(&p, &kv_store, &waiting_room).await.unwrap();
+    let response = String::from_utf8_lossy(&response);
+    assert!(response.starts_with("*2\r\n*2\r\n$2\r\ns1\r\n*2\r\n"));
+    assert!(response.contains("$2\r\ns2\r\n*2\r\n"));
+    assert!(response.contains("$3\r\n1-0"));
+    assert!(!response.contains("$3\r\n3-0\r\n*2\r\n$1\r\na"));
+}
+
+#[tokio::test]
+async fn test_xread_count_with_block_returns_immediately() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XREAD", "COUNT", "1", "BLOCK", "0", "STREAMS", "mystream", "0"]);
+    let response = process_xread(&p, &kv_store, &waiting_room).await.unwrap();
+    assert!(response.starts_with(b"*1\r\n*2\r\n$8\r\nmystream\r\n*1\r\n*2\r\n$3\r\n1-0"));
+}
+
+#[tokio::test]
+async fn test_xread_count_zero_is_unlimited() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+
+    // Both entries come back, and with BLOCK the read still returns straight away
+    let p = parts(&["XREAD", "COUNT", "0", "BLOCK", "0", "STREAMS", "mystream", "0"]);
+    let response = tokio::time::timeout(
+        tokio::time::Duration::from_secs(5),
+        process_xread(&p, &kv_store, &waiting_room)
+    ).await.expect("XREAD COUNT 0 BLOCK 0 should not block on existing entries").unwrap();
+    assert!(response.starts_with(b"*1\r\n*2\r\n$8\r\nmystream\r\n*2\r\n"));
+}
+
+#[tokio::test]
+async fn test_xread_unbalanced_streams() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let p = parts(&["XREAD", "STREAMS", "s1", "s2", "0"]);
+    let result = process_xread(&p, &kv_store, &waiting_room).await.unwrap();
+    assert_eq!(
+        result,
+        b"-ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.\r\n"
+    );
+}
+
+#[tokio::test]
+async fn test_xread_invalid_count() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let p = parts(&["XREAD", "COUNT", "many", "STREAMS", "mystream", "0"]);
+    let result = process_xread(&p, &kv_store, &waiting_room).await.unwrap();
+    assert_eq!(result, b"-ERR value is not an integer or out of range\r\n");
+}
+
+#[tokio::test]
+async fn test_xread_rejects_bad_block_values() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let p = parts(&["XREAD", "BLOCK", "-1", "STREAMS", "mystream", "$"]);
+    assert_eq!(process_xread(&p, &kv_store, &waiting_room).await.unwrap(), b"-ERR timeout is negative\r\n");
+    let p = parts(&["XREAD", "BLOCK", "1.5", "STREAMS", "mystream", "$"]);
+    assert_eq!(
+        process_xread(&p, &kv_store, &waiting_room).await.unwrap(),
+        b"-ERR timeout is not an integer or out of range\r\n"
+    );
+    assert!(waiting_room.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_xread_wrong_type() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    kv_store.insert("mykey".to_string(), RedisValue::new(RedisData::String("value".into()), None));
+
+    // Even alongside a real stream, and even with BLOCK, it fails straight away
+    let p = parts(&["XREAD", "BLOCK", "0", "STREAMS", "mystream", "mykey", "0", "0"]);
+    assert_eq!(
+        process_xread(&p, &kv_store, &waiting_room).await.unwrap(),
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+    );
+}
+
 // ==================== XREAD Tests - With $ (Special ID) ====================
 
 #[tokio::test]
@@ -434,6 +839,8 @@ async fn test_xread_block_with_existing_data() {
     assert!(result.is_ok());
     let response = result.unwrap();
     assert!(response.len() > 10);
+    // It registered before reading, and left again without waiting
+    assert!(waiting_room.lock().unwrap().is_empty());
 }
 
 #[tokio::test]
@@ -451,6 +858,8 @@ async fn test_xread_block_timeout() {
     assert_eq!(result.unwrap(), b"*-1\r\n");
     // Should have waited approximately 100ms
     assert!(elapsed.as_millis() >= 90);
+    // And gone from the waiting room once it gave up
+    assert!(waiting_room.lock().unwrap().is_empty());
 }
 
 #[tokio::test]
@@ -578,6 +987,42 @@ async fn test_xread_multiple_blocked_readers() {
     }
 }
 
+#[tokio::test]
+async fn test_xread_dollar_readers_each_get_only_their_entry() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "seed", "0"]), &kv_store, &waiting_room).unwrap();
+
+    let spawn_reader = || {
+        let kv_clone = Arc::clone(&kv_store);
+        let room_clone = Arc::clone(&waiting_room);
+        tokio::spawn(async move {
+            let p = parts(&["XREAD", "BLOCK", "2000", "STREAMS", "mystream", "$"]);
+            process_xread(&p, &kv_clone, &room_clone).await
+        })
+    };
+
+    // First reader's baseline is 1-0
+    let first = spawn_reader();
+    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "n", "2"]), &kv_store, &waiting_room).unwrap();
+    let first = first.await.unwrap().unwrap();
+
+    // Second reader's baseline is 2-0
+    let second = spawn_reader();
+    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    process_xadd(&parts(&["XADD", "mystream", "3-0", "n", "3"]), &kv_store, &waiting_room).unwrap();
+    let second = second.await.unwrap().unwrap();
+
+    let expected = |id: &str, value: &str| format!(
+        "*1\r\n*2\r\n$8\r\nmystream\r\n*1\r\n*2\r\n$3\r\n{}\r\n*2\r\n$1\r\nn\r\n$1\r\n{}\r\n",
+        id, value
+    );
+    assert_eq!(String::from_utf8_lossy(&first), expected("2-0", "2"));
+    assert_eq!(String::from_utf8_lossy(&second), expected("3-0", "3"));
+}
+
 // ==================== Concurrent Stream Tests ====================
 
 #[tokio::test]
@@ -614,12 +1059,12 @@ async fn test_concurrent_xadd() {
         handle.await.unwrap();
     }
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stream = map.get("sharedstream").unwrap();
     match &stream.data {
-        RedisData::Stream(entries) => {
+        RedisData::Stream(Stream { entries, .. }) => {
             // Should have some entries (exact count depends on ordering)
-            assert!(entries.len() > 0);
+            assert!(!entries.is_empty());
         }
         _ => panic!("Expected stream"),
     }
@@ -659,6 +1104,300 @@ async fn test_xread_while_xadd() {
     assert!(response.len() > 10);
 }
 
+// ==================== XINFO Tests ====================
+
+#[test]
+fn test_xinfo_stream_reports_length_and_entries() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XINFO", "STREAM", "mystream"]);
+    let result = process_xinfo(&p, &kv_store);
+    assert!(result.is_ok());
+    let bytes = result.unwrap();
+    let response = String::from_utf8_lossy(&bytes);
+
+    assert!(bytes.starts_with(b"*18\r\n"));
+    assert!(response.contains("$6\r\nlength\r\n:2\r\n"));
+    assert!(response.contains("$17\r\nlast-generated-id\r\n$3\r\n2-0\r\n"));
+    assert!(response.contains("$6\r\ngroups\r\n:0\r\n"));
+    assert!(response.contains("$11\r\nfirst-entry\r\n*2\r\n$3\r\n1-0\r\n"));
+    assert!(response.contains("$10\r\nlast-entry\r\n*2\r\n$3\r\n2-0\r\n"));
+}
+
+#[test]
+fn test_xinfo_stream_empty_stream() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "emptystream".to_string(),
+            RedisValue::new(RedisData::Stream(Stream::new()), None),
+        );
+    }
+
+    let p = parts(&["XINFO", "STREAM", "emptystream"]);
+    let bytes = process_xinfo(&p, &kv_store).unwrap();
+    let response = String::from_utf8_lossy(&bytes);
+    assert!(response.contains("$6\r\nlength\r\n:0\r\n"));
+    assert!(response.contains("$11\r\nfirst-entry\r\n$-1\r\n"));
+    assert!(response.contains("$10\r\nlast-entry\r\n$-1\r\n"));
+}
+
+#[test]
+fn test_xinfo_stream_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let p = parts(&["XINFO", "STREAM", "nokey"]);
+    let result = process_xinfo(&p, &kv_store);
+    assert_eq!(result.unwrap(), b"-ERR no such key\r\n");
+}
+
+#[test]
+fn test_xinfo_stream_wrong_type() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+    let p = parts(&["XINFO", "STREAM", "strkey"]);
+    assert_eq!(process_xinfo(&p, &kv_store).unwrap(), b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n");
+}
+
+#[test]
+fn test_xinfo_unimplemented_subcommands() {
+    let kv_store = new_kv_store();
+    for sub in ["GROUPS", "CONSUMERS", "FULL"] {
+        let p = parts(&["XINFO", sub, "mystream"]);
+        let result = process_xinfo(&p, &kv_store);
+        assert_eq!(result.unwrap(), b"-ERR not implemented\r\n");
+    }
+}
+
+#[test]
+fn test_xinfo_missing_arguments() {
+    let kv_store = new_kv_store();
+    let p = parts(&["XINFO", "STREAM"]);
+    assert!(process_xinfo(&p, &kv_store).is_err());
+}
+
+// ==================== XSETID Tests ====================
+
+#[test]
+fn test_xsetid_moves_last_id_forward() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+
+    let result = process_xsetid(&parts(&["XSETID", "mystream", "5-0"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+OK\r\n");
+
+    // XADD must now respect the raised high-water mark
+    let rejected = process_xadd(&parts(&["XADD", "mystream", "3-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    assert!(rejected.starts_with(b"-ERR"));
+    let accepted = process_xadd(&parts(&["XADD", "mystream", "5-*", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    assert_eq!(accepted, b"$3\r\n5-1\r\n");
+}
+
+#[test]
+fn test_xsetid_creates_missing_stream() {
+    let kv_store = new_kv_store();
+
+    let result = process_xsetid(&parts(&["XSETID", "newstream", "10-5"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+OK\r\n");
+
+    let map = kv_store.lock_all();
+    match &map.get("newstream").unwrap().data {
+        RedisData::Stream(stream) => {
+            assert!(stream.entries.is_empty());
+            assert_eq!(stream.last_id, "10-5");
+        },
+        _ => panic!("Expected stream"),
+    }
+}
+
+#[test]
+fn test_xsetid_rejected_on_missing_stream_creates_nothing() {
+    let kv_store = new_kv_store();
+
+    let p = parts(&["XSETID", "newstream", "3-0", "MAXDELETEDENTRYID", "4-0"]);
+    assert!(process_xsetid(&p, &kv_store).unwrap().starts_with(b"-ERR"));
+    assert!(!kv_store.contains_key("newstream"));
+}
+
+#[test]
+fn test_xsetid_rejects_id_below_top_item() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "5-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+
+    let result = process_xsetid(&parts(&["XSETID", "mystream", "4-0"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"-ERR The ID specified in XSETID is smaller than the target stream top item"));
+}
+
+#[test]
+fn test_xsetid_rejects_moving_backwards_without_options() {
+    let kv_store = new_kv_store();
+
+    process_xsetid(&parts(&["XSETID", "mystream", "10-0"]), &kv_store).unwrap();
+    let result = process_xsetid(&parts(&["XSETID", "mystream", "5-0"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"-ERR"));
+
+    // The bookkeeping options allow rewinding to anything at or above the top entry
+    let result = process_xsetid(&parts(&["XSETID", "mystream", "5-0", "ENTRIESADDED", "0"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+OK\r\n");
+}
+
+#[test]
+fn test_xsetid_with_options_updates_xinfo() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["XSETID", "mystream", "9-0", "ENTRIESADDED", "7", "MAXDELETEDENTRYID", "8-0"]);
+    assert_eq!(process_xsetid(&p, &kv_store).unwrap(), b"+OK\r\n");
+
+    let bytes = process_xinfo(&parts(&["XINFO", "STREAM", "mystream"]), &kv_store).unwrap();
+    let response = String::from_utf8_lossy(&bytes);
+    assert!(response.contains("last-generated-id\r\n$3\r\n9-0\r\n"));
+    assert!(response.contains("max-deleted-entry-id\r\n$3\r\n8-0\r\n"));
+    assert!(response.contains("entries-added\r\n:7\r\n"));
+}
+
+#[test]
+fn test_xsetid_invalid_options() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+
+    // ENTRIESADDED can't be below the current length
+    let p = parts(&["XSETID", "mystream", "3-0", "ENTRIESADDED", "1"]);
+    assert!(process_xsetid(&p, &kv_store).unwrap().starts_with(b"-ERR"));
+
+    // MAXDELETEDENTRYID can't be above the new last ID
+    let p = parts(&["XSETID", "mystream", "3-0", "MAXDELETEDENTRYID", "4-0"]);
+    assert!(process_xsetid(&p, &kv_store).unwrap().starts_with(b"-ERR"));
+
+    let p = parts(&["XSETID", "mystream", "3-0", "BOGUS", "1"]);
+    assert!(process_xsetid(&p, &kv_store).unwrap().starts_with(b"-ERR syntax error"));
+
+    let p = parts(&["XSETID", "mystream", "abc"]);
+    assert!(process_xsetid(&p, &kv_store).unwrap().starts_with(b"-ERR Invalid stream ID"));
+
+    // A later check failing doesn't leave the earlier options applied
+    let p = parts(&["XSETID", "mystream", "3-0", "ENTRIESADDED", "9", "MAXDELETEDENTRYID", "4-0"]);
+    assert!(process_xsetid(&p, &kv_store).unwrap().starts_with(b"-ERR"));
+    let bytes = process_xinfo(&parts(&["XINFO", "STREAM", "mystream"]), &kv_store).unwrap();
+    assert!(String::from_utf8_lossy(&bytes).contains("entries-added\r\n:2\r\n"));
+}
+
+#[test]
+fn test_xsetid_wrong_type() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+    let result = process_xsetid(&parts(&["XSETID", "strkey", "1-0"]), &kv_store).unwrap();
+    assert_eq!(result, b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n");
+}
+
+#[test]
+fn test_xsetid_missing_arguments() {
+    let kv_store = new_kv_store();
+    assert!(process_xsetid(&parts(&["XSETID", "mystream"]), &kv_store).is_err());
+}
+
+// ==================== XDEL Tests ====================
+
+#[test]
+fn test_xdel_removes_matching_entries() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "2-0", "b", "2"]), &kv_store, &waiting_room).unwrap();
+    process_xadd(&parts(&["XADD", "mystream", "3-0", "c", "3"]), &kv_store, &waiting_room).unwrap();
+
+    let result = process_xdel(&parts(&["XDEL", "mystream", "1-0", "3-0"]), &kv_store);
+    assert_eq!(result.unwrap(), b":2\r\n");
+
+    let bytes = process_xrange(&parts(&["XRANGE", "mystream", "-", "+"]), &kv_store).unwrap();
+    assert!(bytes.starts_with(b"*1"));
+    let response = String::from_utf8_lossy(&bytes);
+    assert!(response.contains("2-0"));
+    assert!(!response.contains("1-0"));
+}
+
+#[test]
+fn test_xdel_skips_nonexistent_ids() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "1-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+
+    let result = process_xdel(&parts(&["XDEL", "mystream", "1-0", "9-9", "1-0"]), &kv_store);
+    assert_eq!(result.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_xdel_last_entry_keeps_last_id() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    process_xadd(&parts(&["XADD", "mystream", "5-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    process_xdel(&parts(&["XDEL", "mystream", "5-0"]), &kv_store).unwrap();
+
+    // The deleted ID can't be reused
+    let bytes = process_xadd(&parts(&["XADD", "mystream", "5-0", "a", "1"]), &kv_store, &waiting_room).unwrap();
+    assert!(bytes.starts_with(b"-ERR"));
+
+    let info = process_xinfo(&parts(&["XINFO", "STREAM", "mystream"]), &kv_store).unwrap();
+    let response = String::from_utf8_lossy(&info);
+    assert!(response.contains("max-deleted-entry-id\r\n$3\r\n5-0\r\n"));
+}
+
+#[test]
+fn test_xdel_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_xdel(&parts(&["XDEL", "nokey", "1-0"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_xdel_invalid_id() {
+    let kv_store = new_kv_store();
+    let result = process_xdel(&parts(&["XDEL", "mystream", "not-an-id"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"-ERR Invalid stream ID"));
+}
+
+#[test]
+fn test_xdel_wrong_type() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+    let result = process_xdel(&parts(&["XDEL", "strkey", "1-0"]), &kv_store).unwrap();
+    assert_eq!(result, b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n");
+}
+
 // ==================== Edge Cases ====================
 
 #[test]
@@ -710,10 +1449,10 @@ fn test_xrange_empty_stream() {
 
     // Create empty stream
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "emptystream".to_string(),
-            RedisValue::new(RedisData::Stream(vec![]), None),
+            RedisValue::new(RedisData::Stream(Stream::new()), None),
         );
     }
 
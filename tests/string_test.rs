@@ -1,12 +1,12 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
-use redis_cache::models::{RedisData, RedisValue};
-use redis_cache::commands::{process_set, process_get};
+use redis_cache::models::{RedisData, RedisValue, KvStore, Store};
+use redis_cache::commands::{process_set, process_get, process_getrange, process_setrange,
+    process_setnx, process_getset};
 
-fn new_kv_store() -> Arc<Mutex<HashMap<String, RedisValue>>> {
-    Arc::new(Mutex::new(HashMap::new()))
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
 }
 
 fn parts(args: &[&str]) -> Vec<String> {
@@ -24,10 +24,10 @@ fn test_set_basic() {
     assert_eq!(result.unwrap(), b"+OK\r\n");
 
     // Verify value was stored
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     match &stored.data {
-        RedisData::String(s) => assert_eq!(s, "value"),
+        RedisData::String(s) => assert_eq!(s, b"value"),
         _ => panic!("Expected string data"),
     }
 }
@@ -38,10 +38,10 @@ fn test_set_overwrites_existing() {
     process_set(&parts(&["SET", "key", "value1"]), &kv_store).unwrap();
     process_set(&parts(&["SET", "key", "value2"]), &kv_store).unwrap();
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     match &stored.data {
-        RedisData::String(s) => assert_eq!(s, "value2"),
+        RedisData::String(s) => assert_eq!(s, b"value2"),
         _ => panic!("Expected string data"),
     }
 }
@@ -53,7 +53,7 @@ fn test_set_with_ex_expiry() {
     let result = process_set(&p, &kv_store);
     assert!(result.is_ok());
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     assert!(stored.expires_at.is_some());
 
@@ -71,7 +71,7 @@ fn test_set_with_px_expiry() {
     let result = process_set(&p, &kv_store);
     assert!(result.is_ok());
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     assert!(stored.expires_at.is_some());
 
@@ -89,7 +89,7 @@ fn test_set_with_lowercase_ex() {
     let result = process_set(&p, &kv_store);
     assert!(result.is_ok());
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     assert!(stored.expires_at.is_some());
 }
@@ -101,7 +101,7 @@ fn test_set_with_lowercase_px() {
     let result = process_set(&p, &kv_store);
     assert!(result.is_ok());
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     assert!(stored.expires_at.is_some());
 }
@@ -121,10 +121,10 @@ fn test_set_empty_value() {
     let result = process_set(&p, &kv_store);
     assert!(result.is_ok());
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     match &stored.data {
-        RedisData::String(s) => assert_eq!(s, ""),
+        RedisData::String(s) => assert_eq!(s, b""),
         _ => panic!("Expected string data"),
     }
 }
@@ -136,10 +136,10 @@ fn test_set_with_spaces_in_value() {
     let result = process_set(&p, &kv_store);
     assert!(result.is_ok());
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     match &stored.data {
-        RedisData::String(s) => assert_eq!(s, "hello world"),
+        RedisData::String(s) => assert_eq!(s, b"hello world"),
         _ => panic!("Expected string data"),
     }
 }
@@ -158,7 +158,7 @@ fn test_set_without_expiry_has_none() {
     let p = parts(&["SET", "key", "value"]);
     process_set(&p, &kv_store).unwrap();
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     let stored = map.get("key").unwrap();
     assert!(stored.expires_at.is_none());
 }
@@ -169,10 +169,10 @@ fn test_set_without_expiry_has_none() {
 fn test_get_existing_key() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "mykey".to_string(),
-            RedisValue::new(RedisData::String("myvalue".to_string()), None),
+            RedisValue::new(RedisData::String("myvalue".into()), None),
         );
     }
 
@@ -195,11 +195,11 @@ fn test_get_nonexistent_key() {
 fn test_get_expired_key() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         let expired_time = Instant::now() - std::time::Duration::from_secs(10);
         map.insert(
             "expired".to_string(),
-            RedisValue::new(RedisData::String("value".to_string()), Some(expired_time)),
+            RedisValue::new(RedisData::String("value".into()), Some(expired_time)),
         );
     }
 
@@ -209,7 +209,7 @@ fn test_get_expired_key() {
     assert_eq!(result.unwrap(), b"$-1\r\n");
 
     // Verify key was removed
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     assert!(map.get("expired").is_none());
 }
 
@@ -217,7 +217,7 @@ fn test_get_expired_key() {
 fn test_get_wrong_type() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "listkey".to_string(),
             RedisValue::new(RedisData::List(vec!["item".to_string()]), None),
@@ -225,9 +225,8 @@ fn test_get_wrong_type() {
     }
 
     let p = parts(&["GET", "listkey"]);
-    let result = process_get(&p, &kv_store);
-    assert!(result.is_err());
-    assert!(result.unwrap_err().contains("WRONGTYPE"));
+    let result = process_get(&p, &kv_store).unwrap();
+    assert_eq!(result, b"-WRONGTYPE Operation against a key not holding a string\r\n");
 }
 
 #[test]
@@ -242,10 +241,10 @@ fn test_get_missing_key_argument() {
 fn test_get_empty_string_value() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         map.insert(
             "emptykey".to_string(),
-            RedisValue::new(RedisData::String("".to_string()), None),
+            RedisValue::new(RedisData::String("".into()), None),
         );
     }
 
@@ -259,11 +258,11 @@ fn test_get_empty_string_value() {
 fn test_get_not_yet_expired() {
     let kv_store = new_kv_store();
     {
-        let mut map = kv_store.lock().unwrap();
+        let mut map = kv_store.lock_all();
         let future_time = Instant::now() + std::time::Duration::from_secs(100);
         map.insert(
             "future".to_string(),
-            RedisValue::new(RedisData::String("stillvalid".to_string()), Some(future_time)),
+            RedisValue::new(RedisData::String("stillvalid".into()), Some(future_time)),
         );
     }
 
@@ -319,6 +318,214 @@ async fn test_set_with_expiry_then_wait_and_get() {
     assert_eq!(result.unwrap(), b"$-1\r\n");
 }
 
+// ==================== SETNX Tests ====================
+
+#[test]
+fn test_setnx_only_sets_missing_keys() {
+    let kv_store = new_kv_store();
+
+    assert_eq!(process_setnx(&parts(&["SETNX", "key", "first"]), &kv_store).unwrap(), b":1\r\n");
+    assert_eq!(process_setnx(&parts(&["SETNX", "key", "second"]), &kv_store).unwrap(), b":0\r\n");
+    assert_eq!(process_get(&parts(&["GET", "key"]), &kv_store).unwrap(), b"$5\r\nfirst\r\n");
+}
+
+#[test]
+fn test_setnx_ignores_expired_and_other_types() {
+    let kv_store = new_kv_store();
+    kv_store.insert(
+        "dead".to_string(),
+        RedisValue::new(RedisData::String("old".into()), Some(Instant::now() - std::time::Duration::from_secs(1))),
+    );
+    kv_store.insert("list".to_string(), RedisValue::new(RedisData::List(vec![]), None));
+
+    assert_eq!(process_setnx(&parts(&["SETNX", "dead", "new"]), &kv_store).unwrap(), b":1\r\n");
+    assert_eq!(process_get(&parts(&["GET", "dead"]), &kv_store).unwrap(), b"$3\r\nnew\r\n");
+    // Any existing key blocks SETNX, whatever its type
+    assert_eq!(process_setnx(&parts(&["SETNX", "list", "new"]), &kv_store).unwrap(), b":0\r\n");
+}
+
+// ==================== GETSET Tests ====================
+
+#[test]
+fn test_getset_returns_old_value() {
+    let kv_store = new_kv_store();
+
+    assert_eq!(process_getset(&parts(&["GETSET", "key", "one"]), &kv_store).unwrap(), b"$-1\r\n");
+    assert_eq!(process_getset(&parts(&["GETSET", "key", "two"]), &kv_store).unwrap(), b"$3\r\none\r\n");
+    assert_eq!(process_get(&parts(&["GET", "key"]), &kv_store).unwrap(), b"$3\r\ntwo\r\n");
+}
+
+#[test]
+fn test_getset_clears_ttl() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "old", "EX", "100"]), &kv_store).unwrap();
+
+    process_getset(&parts(&["GETSET", "key", "new"]), &kv_store).unwrap();
+    let map = kv_store.lock_all();
+    assert!(map.get("key").unwrap().expires_at.is_none());
+}
+
+#[test]
+fn test_getset_wrong_type_leaves_value() {
+    let kv_store = new_kv_store();
+    kv_store.insert("list".to_string(), RedisValue::new(RedisData::List(vec!["a".to_string()]), None));
+
+    let result = process_getset(&parts(&["GETSET", "list", "new"]), &kv_store).unwrap();
+    assert_eq!(result, b"-WRONGTYPE Operation against a key not holding a string\r\n");
+    let map = kv_store.lock_all();
+    assert!(matches!(map.get("list").unwrap().data, RedisData::List(_)));
+}
+
+// ==================== GETRANGE Tests ====================
+
+#[test]
+fn test_getrange_positive_and_negative_offsets() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "This is a string"]), &kv_store).unwrap();
+
+    let cases: [(&str, &str, &[u8]); 5] = [
+        ("0", "3", b"$4\r\nThis\r\n"),
+        ("-3", "-1", b"$3\r\ning\r\n"),
+        ("0", "-1", b"$16\r\nThis is a string\r\n"),
+        ("10", "100", b"$6\r\nstring\r\n"),
+        ("-100", "1", b"$2\r\nTh\r\n"),
+    ];
+    for (start, end, expected) in cases {
+        let result = process_getrange(&parts(&["GETRANGE", "key", start, end]), &kv_store).unwrap();
+        assert_eq!(result, expected, "GETRANGE key {} {}", start, end);
+    }
+}
+
+#[test]
+fn test_getrange_empty_ranges() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "hello"]), &kv_store).unwrap();
+
+    for (start, end) in [("3", "1"), ("5", "10"), ("-1", "-3")] {
+        let result = process_getrange(&parts(&["GETRANGE", "key", start, end]), &kv_store).unwrap();
+        assert_eq!(result, b"$0\r\n\r\n", "GETRANGE key {} {}", start, end);
+    }
+
+    let missing = process_getrange(&parts(&["GETRANGE", "nokey", "0", "-1"]), &kv_store).unwrap();
+    assert_eq!(missing, b"$0\r\n\r\n");
+}
+
+#[test]
+fn test_getrange_counts_bytes() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "héllo"]), &kv_store).unwrap();
+
+    let result = process_getrange(&parts(&["GETRANGE", "key", "0", "1"]), &kv_store).unwrap();
+    assert_eq!(result, b"$2\r\nh\xc3\r\n");
+}
+
+#[test]
+fn test_getrange_errors() {
+    let kv_store = new_kv_store();
+    kv_store.insert("list".to_string(), RedisValue::new(RedisData::List(vec![]), None));
+
+    let wrong_type = process_getrange(&parts(&["GETRANGE", "list", "0", "1"]), &kv_store).unwrap();
+    assert_eq!(wrong_type, b"-WRONGTYPE Operation against a key not holding a string\r\n");
+    let not_int = process_getrange(&parts(&["GETRANGE", "list", "a", "1"]), &kv_store).unwrap();
+    assert_eq!(not_int, b"-ERR value is not an integer or out of range\r\n");
+    assert!(process_getrange(&parts(&["GETRANGE", "list", "0"]), &kv_store).is_err());
+}
+
+#[test]
+fn test_getrange_expired_key() {
+    let kv_store = new_kv_store();
+    kv_store.insert(
+        "key".to_string(),
+        RedisValue::new(RedisData::String("value".into()), Some(Instant::now() - std::time::Duration::from_secs(1))),
+    );
+
+    let result = process_getrange(&parts(&["GETRANGE", "key", "0", "-1"]), &kv_store).unwrap();
+    assert_eq!(result, b"$0\r\n\r\n");
+    assert!(!kv_store.contains_key("key"));
+}
+
+// ==================== SETRANGE Tests ====================
+
+#[test]
+fn test_setrange_overwrites_in_place() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "Hello World"]), &kv_store).unwrap();
+
+    let result = process_setrange(&parts(&["SETRANGE", "key", "6", "Redis"]), &kv_store).unwrap();
+    assert_eq!(result, b":11\r\n");
+    assert_eq!(process_get(&parts(&["GET", "key"]), &kv_store).unwrap(), b"$11\r\nHello Redis\r\n");
+
+    // Writing past the end grows the string
+    let result = process_setrange(&parts(&["SETRANGE", "key", "6", "Redis!!"]), &kv_store).unwrap();
+    assert_eq!(result, b":13\r\n");
+}
+
+#[test]
+fn test_setrange_zero_pads_missing_key() {
+    let kv_store = new_kv_store();
+
+    let result = process_setrange(&parts(&["SETRANGE", "key", "3", "ab"]), &kv_store).unwrap();
+    assert_eq!(result, b":5\r\n");
+    assert_eq!(process_get(&parts(&["GET", "key"]), &kv_store).unwrap(), b"$5\r\n\0\0\0ab\r\n");
+}
+
+#[test]
+fn test_setrange_pads_existing_key() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "ab"]), &kv_store).unwrap();
+
+    let result = process_setrange(&parts(&["SETRANGE", "key", "4", "c"]), &kv_store).unwrap();
+    assert_eq!(result, b":5\r\n");
+    assert_eq!(process_get(&parts(&["GET", "key"]), &kv_store).unwrap(), b"$5\r\nab\0\0c\r\n");
+}
+
+#[test]
+fn test_setrange_counts_bytes_written() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "é"]), &kv_store).unwrap();
+
+    // Splits the two-byte 'é'; the bytes are kept as they are
+    let result = process_setrange(&parts(&["SETRANGE", "key", "1", "x"]), &kv_store).unwrap();
+    assert_eq!(result, b":2\r\n");
+    assert_eq!(process_get(&parts(&["GET", "key"]), &kv_store).unwrap(), b"$2\r\n\xc3x\r\n");
+}
+
+#[test]
+fn test_setrange_empty_value() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "hello"]), &kv_store).unwrap();
+
+    assert_eq!(process_setrange(&parts(&["SETRANGE", "key", "10", ""]), &kv_store).unwrap(), b":5\r\n");
+    assert_eq!(process_setrange(&parts(&["SETRANGE", "nokey", "10", ""]), &kv_store).unwrap(), b":0\r\n");
+    assert!(!kv_store.contains_key("nokey"));
+}
+
+#[test]
+fn test_setrange_keeps_ttl() {
+    let kv_store = new_kv_store();
+    process_set(&parts(&["SET", "key", "hello", "EX", "100"]), &kv_store).unwrap();
+
+    process_setrange(&parts(&["SETRANGE", "key", "0", "J"]), &kv_store).unwrap();
+    let map = kv_store.lock_all();
+    assert!(map.get("key").unwrap().expires_at.is_some());
+}
+
+#[test]
+fn test_setrange_errors() {
+    let kv_store = new_kv_store();
+    kv_store.insert("list".to_string(), RedisValue::new(RedisData::List(vec![]), None));
+
+    let wrong_type = process_setrange(&parts(&["SETRANGE", "list", "0", "a"]), &kv_store).unwrap();
+    assert_eq!(wrong_type, b"-WRONGTYPE Operation against a key not holding a string\r\n");
+    let negative = process_setrange(&parts(&["SETRANGE", "key", "-1", "a"]), &kv_store).unwrap();
+    assert_eq!(negative, b"-ERR offset is out of range\r\n");
+    let not_int = process_setrange(&parts(&["SETRANGE", "key", "x", "a"]), &kv_store).unwrap();
+    assert_eq!(not_int, b"-ERR value is not an integer or out of range\r\n");
+    let too_big = process_setrange(&parts(&["SETRANGE", "key", "536870912", "a"]), &kv_store).unwrap();
+    assert!(too_big.starts_with(b"-ERR string exceeds maximum allowed size"));
+    assert!(!kv_store.contains_key("key"));
+}
+
 // ==================== Concurrent Tests ====================
 
 #[tokio::test]
@@ -354,7 +561,7 @@ async fn test_concurrent_set_get_operations() {
         handle.await.unwrap();
     }
 
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     assert_eq!(map.len(), num_clients * ops_per_client);
 }
 
@@ -413,7 +620,7 @@ async fn test_concurrent_set_same_key() {
     }
 
     // Should have exactly one value (the last one to win)
-    let map = kv_store.lock().unwrap();
+    let map = kv_store.lock_all();
     assert_eq!(map.len(), 1);
     assert!(map.contains_key("shared_key"));
 }
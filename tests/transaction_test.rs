@@ -0,0 +1,196 @@
+use redis_cache::models::{ClientState, RedisData, ServerState};
+use redis_cache::commands::{
+    process_discard, process_exec, process_incr, process_multi, process_set, process_unwatch, process_watch
+};
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+// Queues `args` the way the parser does between MULTI and EXEC
+fn queue(client: &mut ClientState, args: &[&str]) {
+    let args = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+    client.command_queue.as_mut().expect("not inside MULTI").push_back(args);
+}
+
+// ==================== MULTI / EXEC Tests ====================
+
+#[tokio::test]
+async fn test_exec_runs_queued_commands_in_order() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    assert_eq!(process_multi(&mut client).unwrap(), b"+OK\r\n");
+    queue(&mut client, &["SET", "counter", "10"]);
+    queue(&mut client, &["INCR", "counter"]);
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"*2\r\n+OK\r\n:11\r\n");
+    assert!(client.command_queue.is_none());
+}
+
+#[tokio::test]
+async fn test_exec_and_multi_errors() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"-ERR EXEC without MULTI\r\n");
+
+    process_multi(&mut client).unwrap();
+    assert_eq!(process_multi(&mut client).unwrap(), b"-ERR MULTI calls can not be nested\r\n");
+}
+
+#[tokio::test]
+async fn test_exec_replies_to_every_failed_command() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    process_set(&parts(&["SET", "s", "x"]), &state.databases[0]).unwrap();
+
+    process_multi(&mut client).unwrap();
+    queue(&mut client, &["HSET", "s", "f", "v"]);
+    queue(&mut client, &["GET", "s"]);
+    queue(&mut client, &["HSET", "s"]);
+    let result = process_exec(&mut client, &state).await.unwrap();
+    // Three elements for three commands, the failures as error replies
+    assert_eq!(
+        result,
+        b"*3\r\n-WRONGTYPE Operation against a key holding the wrong kind of value\r\n$1\r\nx\r\n-ERR Malformed HSET\r\n".to_vec()
+    );
+}
+
+// ==================== EXECABORT Tests ====================
+
+#[tokio::test]
+async fn test_exec_after_queue_error_aborts() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    process_multi(&mut client).unwrap();
+    queue(&mut client, &["SET", "a", "1"]);
+    // What the parser does when a command can't be queued
+    client.transaction_dirty = true;
+
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"-EXECABORT Transaction discarded because of previous errors.\r\n");
+    assert!(!state.databases[0].contains_key("a"));
+    assert!(client.command_queue.is_none());
+    assert!(!client.transaction_dirty);
+}
+
+#[tokio::test]
+async fn test_multi_resets_queue_error() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+    client.transaction_dirty = true;
+
+    process_multi(&mut client).unwrap();
+    queue(&mut client, &["SET", "a", "1"]);
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"*1\r\n+OK\r\n");
+}
+
+// ==================== WATCH / UNWATCH Tests ====================
+
+#[tokio::test]
+async fn test_watch_unmodified_key_commits() {
+    let state = ServerState::default();
+    let kv_store = &state.databases[0];
+    let mut client = ClientState::new();
+    process_set(&parts(&["SET", "balance", "100"]), kv_store).unwrap();
+
+    assert_eq!(process_watch(&parts(&["WATCH", "balance"]), &mut client, kv_store).unwrap(), b"+OK\r\n");
+    process_multi(&mut client).unwrap();
+    queue(&mut client, &["INCR", "balance"]);
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"*1\r\n:101\r\n");
+    assert!(client.watched_keys.is_empty());
+}
+
+#[tokio::test]
+async fn test_watch_aborts_on_write() {
+    let state = ServerState::default();
+    let kv_store = &state.databases[0];
+    let mut client = ClientState::new();
+    process_set(&parts(&["SET", "balance", "100"]), kv_store).unwrap();
+
+    process_watch(&parts(&["WATCH", "balance"]), &mut client, kv_store).unwrap();
+    // Another connection writes the key between WATCH and EXEC
+    process_incr(&parts(&["INCR", "balance"]), kv_store).unwrap();
+
+    process_multi(&mut client).unwrap();
+    queue(&mut client, &["SET", "balance", "0"]);
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"*-1\r\n");
+
+    // Nothing queued ran, and the watch is over
+    let map = kv_store.lock_all();
+    match &map.get("balance").unwrap().data {
+        RedisData::String(s) => assert_eq!(s, b"101"),
+        _ => panic!("Expected string data"),
+    }
+    assert!(client.watched_keys.is_empty());
+}
+
+#[tokio::test]
+async fn test_watch_sees_value_replaced_by_its_clone() {
+    let state = ServerState::default();
+    let kv_store = &state.databases[0];
+    let mut client = ClientState::new();
+    process_set(&parts(&["SET", "key", "v"]), kv_store).unwrap();
+
+    process_watch(&parts(&["WATCH", "key"]), &mut client, kv_store).unwrap();
+    let copy = kv_store.shard("key").get("key").unwrap().clone();
+    kv_store.insert("key".to_string(), copy);
+
+    process_multi(&mut client).unwrap();
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"*-1\r\n");
+}
+
+#[tokio::test]
+async fn test_unwatch_forgets_keys() {
+    let state = ServerState::default();
+    let kv_store = &state.databases[0];
+    let mut client = ClientState::new();
+
+    process_watch(&parts(&["WATCH", "a", "b"]), &mut client, kv_store).unwrap();
+    assert_eq!(client.watched_keys.len(), 2);
+    assert_eq!(process_unwatch(&mut client).unwrap(), b"+OK\r\n");
+    assert!(client.watched_keys.is_empty());
+
+    // Writes after UNWATCH no longer abort the transaction
+    process_set(&parts(&["SET", "a", "1"]), kv_store).unwrap();
+    process_multi(&mut client).unwrap();
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"*0\r\n");
+}
+
+#[test]
+fn test_discard_clears_queue_and_watches() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    assert_eq!(process_discard(&mut client).unwrap(), b"-ERR DISCARD without MULTI\r\n");
+
+    process_watch(&parts(&["WATCH", "a"]), &mut client, &state.databases[0]).unwrap();
+    process_multi(&mut client).unwrap();
+    queue(&mut client, &["SET", "a", "1"]);
+    assert_eq!(process_discard(&mut client).unwrap(), b"+OK\r\n");
+    assert!(client.command_queue.is_none());
+    assert!(client.watched_keys.is_empty());
+    assert!(!state.databases[0].contains_key("a"));
+}
+
+#[tokio::test]
+async fn test_exec_after_discard_has_no_multi() {
+    let state = ServerState::default();
+    let mut client = ClientState::new();
+
+    process_multi(&mut client).unwrap();
+    queue(&mut client, &["SET", "a", "1"]);
+    process_discard(&mut client).unwrap();
+
+    let result = process_exec(&mut client, &state).await.unwrap();
+    assert_eq!(result, b"-ERR EXEC without MULTI\r\n");
+    assert!(!state.databases[0].contains_key("a"));
+}
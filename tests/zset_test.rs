@@ -0,0 +1,1268 @@
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+
+use redis_cache::models::{RedisData, RedisValue, KvStore, Store, WaitingRoom};
+use redis_cache::commands::{
+    process_zadd, process_zscore, process_zrange, process_zcard, process_zrank, process_zrevrange,
+    process_zrangebyscore, process_zrevrangebyscore, process_zrangebylex, process_zrevrangebylex,
+    process_zrem, process_zincrby, process_zcount, process_zpopmin, process_zpopmax, process_bzpopmin,
+    process_bzpopmax, process_bzpopmin_nowait, process_zmpop, process_bzmpop, process_zunion, process_zinter,
+    process_zdiff, process_zunionstore, process_zinterstore, process_zdiffstore, process_zintercard, process_sadd,
+    process_zrandmember, process_zscan, process_zmscore, process_type
+};
+
+fn new_kv_store() -> KvStore {
+    Arc::new(Store::new())
+}
+
+fn new_waiting_room() -> WaitingRoom {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+fn parts(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+const WRONGTYPE: &[u8] = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+
+// ==================== ZADD Tests ====================
+
+#[test]
+fn test_zadd_new_members() {
+    let kv_store = new_kv_store();
+    let p = parts(&["ZADD", "myzset", "1", "one", "2", "two"]);
+    let result = process_zadd(&p, &kv_store, &new_waiting_room());
+    assert!(result.is_ok());
+    assert_eq!(result.unwrap(), b":2\r\n");
+}
+
+#[test]
+fn test_zadd_existing_member_updates_score() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "one"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zadd(&parts(&["ZADD", "myzset", "5", "one", "2", "two"]), &kv_store, &new_waiting_room());
+    // Only "two" is new
+    assert_eq!(result.unwrap(), b":1\r\n");
+
+    let score = process_zscore(&parts(&["ZSCORE", "myzset", "one"]), &kv_store, 2).unwrap();
+    assert_eq!(score, b"$1\r\n5\r\n");
+}
+
+#[test]
+fn test_zadd_keeps_score_order() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "3", "c", "1", "a", "2", "b"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "0", "-1"]), &kv_store).unwrap();
+    assert_eq!(result, b"*3\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n");
+}
+
+#[test]
+fn test_zadd_equal_scores_sorted_by_member() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "b", "1", "a", "1", "c"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "0", "-1"]), &kv_store).unwrap();
+    assert_eq!(result, b"*3\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n");
+}
+
+#[test]
+fn test_zadd_invalid_score() {
+    let kv_store = new_kv_store();
+    let result = process_zadd(&parts(&["ZADD", "myzset", "abc", "one"]), &kv_store, &new_waiting_room()).unwrap();
+    assert_eq!(result, b"-ERR value is not a valid float\r\n");
+    let result = process_zadd(&parts(&["ZADD", "myzset", "nan", "one"]), &kv_store, &new_waiting_room()).unwrap();
+    assert_eq!(result, b"-ERR value is not a valid float\r\n");
+
+    // Nothing should have been created
+    let map = kv_store.lock_all();
+    assert!(map.get("myzset").is_none());
+}
+
+#[test]
+fn test_zadd_odd_arguments() {
+    let kv_store = new_kv_store();
+    let result = process_zadd(&parts(&["ZADD", "myzset", "1", "one", "2"]), &kv_store, &new_waiting_room());
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_zadd_wrong_type() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+    let result = process_zadd(&parts(&["ZADD", "strkey", "1", "one"]), &kv_store, &new_waiting_room()).unwrap();
+    assert_eq!(result, WRONGTYPE);
+    let result = process_zscore(&parts(&["ZSCORE", "strkey", "one"]), &kv_store, 2).unwrap();
+    assert_eq!(result, WRONGTYPE);
+}
+
+#[test]
+fn test_zadd_nx_only_adds_new_members() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "one"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zadd(&parts(&["ZADD", "myzset", "NX", "5", "one", "2", "two"]), &kv_store, &new_waiting_room());
+    assert_eq!(result.unwrap(), b":1\r\n");
+    let score = process_zscore(&parts(&["ZSCORE", "myzset", "one"]), &kv_store, 2).unwrap();
+    assert_eq!(score, b"$1\r\n1\r\n");
+}
+
+#[test]
+fn test_zadd_xx_only_updates_existing_members() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "one"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zadd(&parts(&["ZADD", "myzset", "xx", "5", "one", "2", "two"]), &kv_store, &new_waiting_room());
+    assert_eq!(result.unwrap(), b":0\r\n");
+    let score = process_zscore(&parts(&["ZSCORE", "myzset", "one"]), &kv_store, 2).unwrap();
+    assert_eq!(score, b"$1\r\n5\r\n");
+    let missing = process_zscore(&parts(&["ZSCORE", "myzset", "two"]), &kv_store, 2).unwrap();
+    assert_eq!(missing, b"$-1\r\n");
+}
+
+#[test]
+fn test_zadd_xx_does_not_create_key() {
+    let kv_store = new_kv_store();
+    let result = process_zadd(&parts(&["ZADD", "myzset", "XX", "1", "one"]), &kv_store, &new_waiting_room());
+    assert_eq!(result.unwrap(), b":0\r\n");
+    assert!(kv_store.lock_all().get("myzset").is_none());
+}
+
+#[test]
+fn test_zadd_gt_and_lt() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "5", "a", "5", "b"]), &kv_store, &new_waiting_room()).unwrap();
+
+    process_zadd(&parts(&["ZADD", "myzset", "GT", "3", "a", "7", "b"]), &kv_store, &new_waiting_room()).unwrap();
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "myzset", "a"]), &kv_store, 2).unwrap(), b"$1\r\n5\r\n");
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "myzset", "b"]), &kv_store, 2).unwrap(), b"$1\r\n7\r\n");
+
+    process_zadd(&parts(&["ZADD", "myzset", "LT", "3", "a", "9", "b"]), &kv_store, &new_waiting_room()).unwrap();
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "myzset", "a"]), &kv_store, 2).unwrap(), b"$1\r\n3\r\n");
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "myzset", "b"]), &kv_store, 2).unwrap(), b"$1\r\n7\r\n");
+
+    // GT still adds brand new members
+    let result = process_zadd(&parts(&["ZADD", "myzset", "GT", "1", "c"]), &kv_store, &new_waiting_room());
+    assert_eq!(result.unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_zadd_ch_counts_changed_members() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "a", "2", "b"]), &kv_store, &new_waiting_room()).unwrap();
+
+    // a changes, b keeps its score, c is new
+    let p = parts(&["ZADD", "myzset", "CH", "10", "a", "2", "b", "3", "c"]);
+    assert_eq!(process_zadd(&p, &kv_store, &new_waiting_room()).unwrap(), b":2\r\n");
+}
+
+#[test]
+fn test_zadd_repeated_member_counts_once() {
+    let kv_store = new_kv_store();
+    let p = parts(&["ZADD", "myzset", "CH", "1", "a", "2", "a"]);
+    assert_eq!(process_zadd(&p, &kv_store, &new_waiting_room()).unwrap(), b":1\r\n");
+
+    // The last score given for the member is the one kept
+    let result = process_zscore(&parts(&["ZSCORE", "myzset", "a"]), &kv_store, 2).unwrap();
+    assert_eq!(result, b"$1\r\n2\r\n");
+    assert_eq!(process_zcard(&parts(&["ZCARD", "myzset"]), &kv_store).unwrap(), b":1\r\n");
+}
+
+#[test]
+fn test_zadd_incompatible_options() {
+    let kv_store = new_kv_store();
+    let nx_xx = process_zadd(&parts(&["ZADD", "myzset", "NX", "XX", "1", "a"]), &kv_store, &new_waiting_room()).unwrap();
+    assert!(nx_xx.starts_with(b"-ERR XX and NX"));
+    let gt_lt = process_zadd(&parts(&["ZADD", "myzset", "GT", "LT", "1", "a"]), &kv_store, &new_waiting_room()).unwrap();
+    assert!(gt_lt.starts_with(b"-ERR GT, LT, and/or NX"));
+    let nx_gt = process_zadd(&parts(&["ZADD", "myzset", "NX", "GT", "1", "a"]), &kv_store, &new_waiting_room()).unwrap();
+    assert!(nx_gt.starts_with(b"-ERR GT, LT, and/or NX"));
+    assert!(process_zadd(&parts(&["ZADD", "myzset", "NX", "CH"]), &kv_store, &new_waiting_room()).is_err());
+}
+
+// ==================== ZCARD Tests ====================
+
+#[test]
+fn test_zcard() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "a", "2", "b"]), &kv_store, &new_waiting_room()).unwrap();
+
+    assert_eq!(process_zcard(&parts(&["ZCARD", "myzset"]), &kv_store).unwrap(), b":2\r\n");
+    assert_eq!(process_zcard(&parts(&["ZCARD", "nokey"]), &kv_store).unwrap(), b":0\r\n");
+}
+
+#[test]
+fn test_zcard_wrong_type() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+    assert_eq!(process_zcard(&parts(&["ZCARD", "strkey"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== ZRANK Tests ====================
+
+#[test]
+fn test_zrank_by_ascending_score() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "3", "c", "1", "a", "2", "b"]), &kv_store, &new_waiting_room()).unwrap();
+
+    assert_eq!(process_zrank(&parts(&["ZRANK", "myzset", "a"]), &kv_store).unwrap(), b":0\r\n");
+    assert_eq!(process_zrank(&parts(&["ZRANK", "myzset", "c"]), &kv_store).unwrap(), b":2\r\n");
+}
+
+#[test]
+fn test_zrank_with_score() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1.5", "a"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrank(&parts(&["ZRANK", "myzset", "a", "WITHSCORE"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*2\r\n:0\r\n$3\r\n1.5\r\n");
+}
+
+#[test]
+fn test_zrank_missing_member_or_key() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "a"]), &kv_store, &new_waiting_room()).unwrap();
+
+    assert_eq!(process_zrank(&parts(&["ZRANK", "myzset", "x"]), &kv_store).unwrap(), b"$-1\r\n");
+    assert_eq!(process_zrank(&parts(&["ZRANK", "nokey", "a"]), &kv_store).unwrap(), b"$-1\r\n");
+    let with_score = process_zrank(&parts(&["ZRANK", "nokey", "a", "WITHSCORE"]), &kv_store);
+    assert_eq!(with_score.unwrap(), b"*-1\r\n");
+}
+
+#[test]
+fn test_zrank_wrong_type() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+    assert_eq!(process_zrank(&parts(&["ZRANK", "strkey", "a"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== ZSCORE Tests ====================
+
+#[test]
+fn test_zscore_existing_member() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1.5", "one"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zscore(&parts(&["ZSCORE", "myzset", "one"]), &kv_store, 2);
+    assert_eq!(result.unwrap(), b"$3\r\n1.5\r\n");
+}
+
+#[test]
+fn test_zscore_missing_member() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "one"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zscore(&parts(&["ZSCORE", "myzset", "nope"]), &kv_store, 2);
+    assert_eq!(result.unwrap(), b"$-1\r\n");
+}
+
+#[test]
+fn test_zscore_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_zscore(&parts(&["ZSCORE", "nokey", "one"]), &kv_store, 2);
+    assert_eq!(result.unwrap(), b"$-1\r\n");
+}
+
+#[test]
+fn test_zscore_infinity() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "-inf", "low", "+inf", "high"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let low = process_zscore(&parts(&["ZSCORE", "myzset", "low"]), &kv_store, 2).unwrap();
+    assert_eq!(low, b"$4\r\n-inf\r\n");
+    let high = process_zscore(&parts(&["ZSCORE", "myzset", "high"]), &kv_store, 2).unwrap();
+    assert_eq!(high, b"$3\r\ninf\r\n");
+}
+
+#[test]
+fn test_zscore_formats_like_redis() {
+    let kv_store = new_kv_store();
+    let p = parts(&["ZADD", "myzset", "1e300", "huge", "-1e-5", "tiny", "1e16", "wide", "1e17", "wider", "0.1", "tenth"]);
+    process_zadd(&p, &kv_store, &new_waiting_room()).unwrap();
+
+    for (member, expected) in [
+        ("huge", "1e+300"),
+        ("tiny", "-1e-05"),
+        ("wide", "10000000000000000"),
+        ("wider", "1e+17"),
+        ("tenth", "0.1"),
+    ] {
+        let result = process_zscore(&parts(&["ZSCORE", "myzset", member]), &kv_store, 2).unwrap();
+        assert_eq!(result, format!("${}\r\n{}\r\n", expected.len(), expected).into_bytes());
+    }
+}
+
+#[test]
+fn test_zscore_resp3_is_a_double() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1.5", "one", "-inf", "low"]), &kv_store, &new_waiting_room()).unwrap();
+
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "myzset", "one"]), &kv_store, 3).unwrap(), b",1.5\r\n");
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "myzset", "low"]), &kv_store, 3).unwrap(), b",-inf\r\n");
+    process_zadd(&parts(&["ZADD", "myzset", "1e300", "huge"]), &kv_store, &new_waiting_room()).unwrap();
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "myzset", "huge"]), &kv_store, 3).unwrap(), b",1e+300\r\n");
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "myzset", "nope"]), &kv_store, 3).unwrap(), b"_\r\n");
+    assert_eq!(process_zscore(&parts(&["ZSCORE", "nokey", "one"]), &kv_store, 3).unwrap(), b"_\r\n");
+}
+
+// ==================== ZRANGE Tests ====================
+
+#[test]
+fn test_zrange_with_scores() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "one", "2", "two"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "0", "-1", "WITHSCORES"]), &kv_store);
+    assert_eq!(
+        result.unwrap(),
+        b"*4\r\n$3\r\none\r\n$1\r\n1\r\n$3\r\ntwo\r\n$1\r\n2\r\n"
+    );
+}
+
+#[test]
+fn test_zrange_negative_indices() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "a", "2", "b", "3", "c"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "-2", "-1"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*2\r\n$1\r\nb\r\n$1\r\nc\r\n");
+}
+
+#[test]
+fn test_zrange_out_of_bounds() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "a", "2", "b"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "5", "10"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*0\r\n");
+
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "0", "100"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*2\r\n$1\r\na\r\n$1\r\nb\r\n");
+}
+
+#[test]
+fn test_zrange_start_greater_than_stop() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "a", "2", "b"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "1", "0"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_zrange_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_zrange(&parts(&["ZRANGE", "nokey", "0", "-1"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_zrange_invalid_index() {
+    let kv_store = new_kv_store();
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "a", "1"]), &kv_store).unwrap();
+    assert_eq!(result, b"-ERR value is not an integer or out of range\r\n");
+}
+
+fn seed_zset(kv_store: &KvStore) {
+    let p = parts(&["ZADD", "myzset", "1", "a", "2", "b", "3", "c", "4", "d"]);
+    process_zadd(&p, kv_store, &new_waiting_room()).unwrap();
+}
+
+// Decodes the bulk strings of a flat array reply, in order
+fn elements(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes)
+        .split("\r\n")
+        .skip(1)
+        .filter(|line| !line.starts_with('$') && !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[test]
+fn test_zrange_rev() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zrange(&parts(&["ZRANGE", "myzset", "0", "1", "REV"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["d", "c"]);
+}
+
+#[test]
+fn test_zrange_byscore_with_limit_and_scores() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let p = parts(&["ZRANGE", "myzset", "(1", "+inf", "BYSCORE", "LIMIT", "1", "2", "WITHSCORES"]);
+    let result = process_zrange(&p, &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["c", "3", "d", "4"]);
+}
+
+#[test]
+fn test_zrange_byscore_rev_takes_max_first() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let p = parts(&["ZRANGE", "myzset", "3", "2", "BYSCORE", "REV"]);
+    let result = process_zrange(&p, &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["c", "b"]);
+}
+
+#[test]
+fn test_zrange_bylex() {
+    let kv_store = new_kv_store();
+    let p = parts(&["ZADD", "lex", "0", "apple", "0", "banana", "0", "cherry", "0", "date"]);
+    process_zadd(&p, &kv_store, &new_waiting_room()).unwrap();
+
+    let range = process_zrange(&parts(&["ZRANGE", "lex", "[b", "(d", "BYLEX"]), &kv_store).unwrap();
+    assert_eq!(elements(&range), vec!["banana", "cherry"]);
+    let open = process_zrange(&parts(&["ZRANGE", "lex", "+", "-", "BYLEX", "REV", "LIMIT", "0", "1"]), &kv_store).unwrap();
+    assert_eq!(elements(&open), vec!["date"]);
+}
+
+#[test]
+fn test_zrange_invalid_option_combinations() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let limit_by_rank = process_zrange(&parts(&["ZRANGE", "myzset", "0", "1", "LIMIT", "0", "1"]), &kv_store).unwrap();
+    assert!(limit_by_rank.starts_with(b"-ERR syntax error, LIMIT"));
+    let lex_scores = process_zrange(&parts(&["ZRANGE", "myzset", "-", "+", "BYLEX", "WITHSCORES"]), &kv_store).unwrap();
+    assert!(lex_scores.starts_with(b"-ERR syntax error, WITHSCORES"));
+    let bad_lex = process_zrange(&parts(&["ZRANGE", "myzset", "a", "+", "BYLEX"]), &kv_store).unwrap();
+    assert!(bad_lex.starts_with(b"-ERR min or max not valid string range item"));
+    let bad_score = process_zrange(&parts(&["ZRANGE", "myzset", "x", "1", "BYSCORE"]), &kv_store).unwrap();
+    assert!(bad_score.starts_with(b"-ERR min or max is not a float"));
+    let unknown = process_zrange(&parts(&["ZRANGE", "myzset", "0", "1", "BOGUS"]), &kv_store).unwrap();
+    assert!(unknown.starts_with(b"-ERR syntax error"));
+}
+
+// ==================== ZREVRANGE Tests ====================
+
+#[test]
+fn test_zrevrange() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zrevrange(&parts(&["ZREVRANGE", "myzset", "0", "-1"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["d", "c", "b", "a"]);
+    let scored = process_zrevrange(&parts(&["ZREVRANGE", "myzset", "-1", "-1", "WITHSCORES"]), &kv_store).unwrap();
+    assert_eq!(elements(&scored), vec!["a", "1"]);
+}
+
+#[test]
+fn test_zrevrange_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_zrevrange(&parts(&["ZREVRANGE", "nokey", "0", "-1"]), &kv_store);
+    assert_eq!(result.unwrap(), b"*0\r\n");
+}
+
+// ==================== ZRANGEBYSCORE Tests ====================
+
+#[test]
+fn test_zrangebyscore_inclusive_and_exclusive() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let inclusive = process_zrangebyscore(&parts(&["ZRANGEBYSCORE", "myzset", "2", "3"]), &kv_store).unwrap();
+    assert_eq!(elements(&inclusive), vec!["b", "c"]);
+    let exclusive = process_zrangebyscore(&parts(&["ZRANGEBYSCORE", "myzset", "(2", "(4"]), &kv_store).unwrap();
+    assert_eq!(elements(&exclusive), vec!["c"]);
+}
+
+#[test]
+fn test_zrangebyscore_infinite_bounds() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zrangebyscore(&parts(&["ZRANGEBYSCORE", "myzset", "-inf", "+inf"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn test_zrangebyscore_withscores_and_limit() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let p = parts(&["ZRANGEBYSCORE", "myzset", "-inf", "inf", "WITHSCORES", "LIMIT", "1", "-1"]);
+    let result = process_zrangebyscore(&p, &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["b", "2", "c", "3", "d", "4"]);
+
+    let negative_offset = parts(&["ZRANGEBYSCORE", "myzset", "-inf", "inf", "LIMIT", "-1", "2"]);
+    assert_eq!(process_zrangebyscore(&negative_offset, &kv_store).unwrap(), b"*0\r\n");
+}
+
+#[test]
+fn test_zrangebyscore_invalid_arguments() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let bad_bound = process_zrangebyscore(&parts(&["ZRANGEBYSCORE", "myzset", "((1", "2"]), &kv_store).unwrap();
+    assert!(bad_bound.starts_with(b"-ERR min or max is not a float"));
+    let bad_limit = process_zrangebyscore(&parts(&["ZRANGEBYSCORE", "myzset", "1", "2", "LIMIT", "x", "1"]), &kv_store).unwrap();
+    assert!(bad_limit.starts_with(b"-ERR"));
+    let short_limit = process_zrangebyscore(&parts(&["ZRANGEBYSCORE", "myzset", "1", "2", "LIMIT", "1"]), &kv_store).unwrap();
+    assert!(short_limit.starts_with(b"-ERR syntax error"));
+}
+
+#[test]
+fn test_zrangebyscore_wrong_type() {
+    let kv_store = new_kv_store();
+    {
+        let mut map = kv_store.lock_all();
+        map.insert(
+            "strkey".to_string(),
+            RedisValue::new(RedisData::String("value".into()), None),
+        );
+    }
+    assert_eq!(process_zrangebyscore(&parts(&["ZRANGEBYSCORE", "strkey", "0", "1"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== ZREVRANGEBYSCORE Tests ====================
+
+#[test]
+fn test_zrevrangebyscore() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let p = parts(&["ZREVRANGEBYSCORE", "myzset", "+inf", "(2", "WITHSCORES"]);
+    let result = process_zrevrangebyscore(&p, &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["d", "4", "c", "3"]);
+
+    let limited = parts(&["ZREVRANGEBYSCORE", "myzset", "4", "1", "LIMIT", "1", "2"]);
+    assert_eq!(elements(&process_zrevrangebyscore(&limited, &kv_store).unwrap()), vec!["c", "b"]);
+}
+
+// ==================== ZREM Tests ====================
+
+#[test]
+fn test_zrem_counts_removed_members() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zrem(&parts(&["ZREM", "myzset", "a", "c", "missing"]), &kv_store).unwrap();
+    assert_eq!(result, b":2\r\n");
+    let remaining = process_zrange(&parts(&["ZRANGE", "myzset", "0", "-1"]), &kv_store).unwrap();
+    assert_eq!(elements(&remaining), vec!["b", "d"]);
+}
+
+#[test]
+fn test_zrem_last_member_deletes_key() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "a"]), &kv_store, &new_waiting_room()).unwrap();
+
+    process_zrem(&parts(&["ZREM", "myzset", "a"]), &kv_store).unwrap();
+    assert!(!kv_store.contains_key("myzset"));
+}
+
+#[test]
+fn test_zrem_nonexistent_key() {
+    let kv_store = new_kv_store();
+    let result = process_zrem(&parts(&["ZREM", "nokey", "a"]), &kv_store);
+    assert_eq!(result.unwrap(), b":0\r\n");
+}
+
+// ==================== ZINCRBY Tests ====================
+
+#[test]
+fn test_zincrby_existing_member_reorders() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zincrby(&parts(&["ZINCRBY", "myzset", "2.5", "a"]), &kv_store, &new_waiting_room()).unwrap();
+    assert_eq!(result, b"$3\r\n3.5\r\n");
+    let order = process_zrange(&parts(&["ZRANGE", "myzset", "0", "-1"]), &kv_store).unwrap();
+    assert_eq!(elements(&order), vec!["b", "c", "a", "d"]);
+}
+
+#[test]
+fn test_zincrby_creates_key_and_member() {
+    let kv_store = new_kv_store();
+    let result = process_zincrby(&parts(&["ZINCRBY", "newzset", "-2", "m"]), &kv_store, &new_waiting_room()).unwrap();
+    assert_eq!(result, b"$2\r\n-2\r\n");
+    let score = process_zscore(&parts(&["ZSCORE", "newzset", "m"]), &kv_store, 2).unwrap();
+    assert_eq!(score, b"$2\r\n-2\r\n");
+}
+
+#[test]
+fn test_zincrby_invalid_increment() {
+    let kv_store = new_kv_store();
+    let result = process_zincrby(&parts(&["ZINCRBY", "myzset", "abc", "m"]), &kv_store, &new_waiting_room()).unwrap();
+    assert!(result.starts_with(b"-ERR value is not a valid float"));
+    assert!(!kv_store.contains_key("myzset"));
+}
+
+#[test]
+fn test_zincrby_nan_result() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "inf", "m"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zincrby(&parts(&["ZINCRBY", "myzset", "-inf", "m"]), &kv_store, &new_waiting_room()).unwrap();
+    assert!(result.starts_with(b"-ERR resulting score is not a number"));
+    let score = process_zscore(&parts(&["ZSCORE", "myzset", "m"]), &kv_store, 2).unwrap();
+    assert_eq!(score, b"$3\r\ninf\r\n");
+}
+
+// ==================== ZCOUNT Tests ====================
+
+#[test]
+fn test_zcount() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let inclusive = process_zcount(&parts(&["ZCOUNT", "myzset", "2", "4"]), &kv_store).unwrap();
+    assert_eq!(inclusive, b":3\r\n");
+    let exclusive = process_zcount(&parts(&["ZCOUNT", "myzset", "(2", "(4"]), &kv_store).unwrap();
+    assert_eq!(exclusive, b":1\r\n");
+    let everything = process_zcount(&parts(&["ZCOUNT", "myzset", "-inf", "+inf"]), &kv_store).unwrap();
+    assert_eq!(everything, b":4\r\n");
+}
+
+#[test]
+fn test_zcount_invalid_bounds_and_missing_key() {
+    let kv_store = new_kv_store();
+    let bad = process_zcount(&parts(&["ZCOUNT", "myzset", "low", "4"]), &kv_store).unwrap();
+    assert!(bad.starts_with(b"-ERR min or max is not a float"));
+    let missing = process_zcount(&parts(&["ZCOUNT", "nokey", "0", "1"]), &kv_store).unwrap();
+    assert_eq!(missing, b":0\r\n");
+}
+
+#[test]
+fn test_zrem_zincrby_zcount_wrong_type() {
+    let kv_store = new_kv_store();
+    process_sadd(&parts(&["SADD", "myset", "a"]), &kv_store).unwrap();
+    assert_eq!(process_zrem(&parts(&["ZREM", "myset", "a"]), &kv_store).unwrap(), WRONGTYPE);
+    let p = parts(&["ZINCRBY", "myset", "1", "a"]);
+    assert_eq!(process_zincrby(&p, &kv_store, &new_waiting_room()).unwrap(), WRONGTYPE);
+    assert_eq!(process_zcount(&parts(&["ZCOUNT", "myset", "0", "1"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== ZRANGEBYLEX Tests ====================
+
+fn seed_lex(kv_store: &KvStore) {
+    let p = parts(&["ZADD", "lex", "0", "a", "0", "b", "0", "c", "0", "d", "0", "e"]);
+    process_zadd(&p, kv_store, &new_waiting_room()).unwrap();
+}
+
+#[test]
+fn test_zrangebylex_bounds() {
+    let kv_store = new_kv_store();
+    seed_lex(&kv_store);
+
+    let all = process_zrangebylex(&parts(&["ZRANGEBYLEX", "lex", "-", "+"]), &kv_store).unwrap();
+    assert_eq!(elements(&all), vec!["a", "b", "c", "d", "e"]);
+    let inclusive = process_zrangebylex(&parts(&["ZRANGEBYLEX", "lex", "[b", "[d"]), &kv_store).unwrap();
+    assert_eq!(elements(&inclusive), vec!["b", "c", "d"]);
+    let exclusive = process_zrangebylex(&parts(&["ZRANGEBYLEX", "lex", "(b", "(d"]), &kv_store).unwrap();
+    assert_eq!(elements(&exclusive), vec!["c"]);
+}
+
+#[test]
+fn test_zrangebylex_limit() {
+    let kv_store = new_kv_store();
+    seed_lex(&kv_store);
+
+    let p = parts(&["ZRANGEBYLEX", "lex", "-", "+", "LIMIT", "1", "2"]);
+    assert_eq!(elements(&process_zrangebylex(&p, &kv_store).unwrap()), vec!["b", "c"]);
+}
+
+#[test]
+fn test_zrangebylex_invalid_arguments() {
+    let kv_store = new_kv_store();
+    seed_lex(&kv_store);
+
+    let bad_bound = process_zrangebylex(&parts(&["ZRANGEBYLEX", "lex", "b", "+"]), &kv_store).unwrap();
+    assert!(bad_bound.starts_with(b"-ERR min or max not valid string range item"));
+    let with_scores = process_zrangebylex(&parts(&["ZRANGEBYLEX", "lex", "-", "+", "WITHSCORES"]), &kv_store).unwrap();
+    assert!(with_scores.starts_with(b"-ERR syntax error"));
+}
+
+#[test]
+fn test_zrevrangebylex() {
+    let kv_store = new_kv_store();
+    seed_lex(&kv_store);
+
+    let result = process_zrevrangebylex(&parts(&["ZREVRANGEBYLEX", "lex", "[d", "(a"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["d", "c", "b"]);
+    let limited = parts(&["ZREVRANGEBYLEX", "lex", "+", "-", "LIMIT", "0", "2"]);
+    assert_eq!(elements(&process_zrevrangebylex(&limited, &kv_store).unwrap()), vec!["e", "d"]);
+}
+
+// ==================== ZPOPMIN / ZPOPMAX Tests ====================
+
+#[test]
+fn test_zpopmin_default_count() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zpopmin(&parts(&["ZPOPMIN", "myzset"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["a", "1"]);
+    let card = process_zcard(&parts(&["ZCARD", "myzset"]), &kv_store).unwrap();
+    assert_eq!(card, b":3\r\n");
+}
+
+#[test]
+fn test_zpopmax_with_count() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zpopmax(&parts(&["ZPOPMAX", "myzset", "2"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["d", "4", "c", "3"]);
+}
+
+#[test]
+fn test_zpopmin_count_past_end_deletes_key() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zpopmin(&parts(&["ZPOPMIN", "myzset", "10"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["a", "1", "b", "2", "c", "3", "d", "4"]);
+    assert!(!kv_store.contains_key("myzset"));
+}
+
+#[test]
+fn test_zpopmin_missing_key_and_bad_count() {
+    let kv_store = new_kv_store();
+    let missing = process_zpopmin(&parts(&["ZPOPMIN", "nokey"]), &kv_store).unwrap();
+    assert_eq!(missing, b"*0\r\n");
+
+    seed_zset(&kv_store);
+    let negative = process_zpopmin(&parts(&["ZPOPMIN", "myzset", "-1"]), &kv_store).unwrap();
+    assert!(negative.starts_with(b"-ERR value is out of range"));
+    let zero = process_zpopmin(&parts(&["ZPOPMIN", "myzset", "0"]), &kv_store).unwrap();
+    assert_eq!(zero, b"*0\r\n");
+}
+
+// ==================== BZPOPMIN / BZPOPMAX Tests ====================
+
+#[tokio::test]
+async fn test_bzpopmin_existing_zset() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+    seed_zset(&kv_store);
+
+    // The first non-empty key wins
+    let p = parts(&["BZPOPMIN", "nokey", "myzset", "0"]);
+    let result = process_bzpopmin(&p, &kv_store, &waiting_room).await.unwrap();
+    assert_eq!(elements(&result), vec!["myzset", "a", "1"]);
+}
+
+#[tokio::test]
+async fn test_bzpopmax_timeout() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let result = process_bzpopmax(&parts(&["BZPOPMAX", "nokey", "0.1"]), &kv_store, &waiting_room).await;
+    assert_eq!(result.unwrap(), b"*-1\r\n");
+}
+
+#[tokio::test]
+async fn test_bzpopmin_timeout_leaves_the_waiting_room() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let p = parts(&["BZPOPMIN", "a", "b", "0.05"]);
+    assert_eq!(process_bzpopmin(&p, &kv_store, &waiting_room).await.unwrap(), b"*-1\r\n");
+    assert!(waiting_room.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_bzpopmin_woken_by_zadd() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let kv_clone = Arc::clone(&kv_store);
+    let room_clone = Arc::clone(&waiting_room);
+    let handle = tokio::spawn(async move {
+        process_bzpopmin(&parts(&["BZPOPMIN", "myzset", "5"]), &kv_clone, &room_clone).await
+    });
+
+    // Give BZPOPMIN time to register
+    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    process_zadd(&parts(&["ZADD", "myzset", "7", "m"]), &kv_store, &waiting_room).unwrap();
+
+    let result = handle.await.unwrap().unwrap();
+    assert_eq!(elements(&result), vec!["myzset", "m", "7"]);
+    assert!(!kv_store.contains_key("myzset"));
+    assert!(waiting_room.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_bzpopmin_single_member_wakes_one_winner() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let mut handles = Vec::new();
+    for _ in 0..2 {
+        let kv_clone = Arc::clone(&kv_store);
+        let room_clone = Arc::clone(&waiting_room);
+        handles.push(tokio::spawn(async move {
+            process_bzpopmin(&parts(&["BZPOPMIN", "myzset", "0.3"]), &kv_clone, &room_clone).await
+        }));
+    }
+    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    process_zadd(&parts(&["ZADD", "myzset", "1", "only"]), &kv_store, &waiting_room).unwrap();
+
+    let mut replies = Vec::new();
+    for handle in handles {
+        replies.push(handle.await.unwrap().unwrap());
+    }
+    // One client gets the member, the other goes back to waiting and times out
+    assert_eq!(replies.iter().filter(|r| r.as_slice() == b"*-1\r\n").count(), 1);
+    assert!(replies.iter().any(|r| elements(r) == vec!["myzset", "only", "1"]));
+}
+
+#[tokio::test]
+async fn test_bzpopmin_nowait_and_bad_timeout() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let nowait = process_bzpopmin_nowait(&parts(&["BZPOPMIN", "nokey", "0"]), &kv_store).await;
+    assert_eq!(nowait.unwrap(), b"*-1\r\n");
+    let negative = process_bzpopmin(&parts(&["BZPOPMIN", "nokey", "-1"]), &kv_store, &waiting_room).await.unwrap();
+    assert!(negative.starts_with(b"-ERR timeout is negative"));
+    let not_float = process_bzpopmin(&parts(&["BZPOPMIN", "nokey", "soon"]), &kv_store, &waiting_room).await.unwrap();
+    assert!(not_float.starts_with(b"-ERR timeout is not a float"));
+    let huge = process_bzpopmin(&parts(&["BZPOPMIN", "nokey", "1e300"]), &kv_store, &waiting_room).await.unwrap();
+    assert_eq!(huge, b"-ERR timeout is out of range\r\n");
+}
+
+// ==================== ZMPOP / BZMPOP Tests ====================
+
+#[test]
+fn test_zmpop_min_and_max() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let min = process_zmpop(&parts(&["ZMPOP", "2", "nokey", "myzset", "MIN"]), &kv_store).unwrap();
+    assert_eq!(min, b"*2\r\n$6\r\nmyzset\r\n*1\r\n*2\r\n$1\r\na\r\n$1\r\n1\r\n");
+    let max = process_zmpop(&parts(&["ZMPOP", "1", "myzset", "MAX", "COUNT", "2"]), &kv_store).unwrap();
+    assert_eq!(
+        max,
+        b"*2\r\n$6\r\nmyzset\r\n*2\r\n*2\r\n$1\r\nd\r\n$1\r\n4\r\n*2\r\n$1\r\nc\r\n$1\r\n3\r\n"
+    );
+}
+
+#[test]
+fn test_zmpop_nothing_to_pop() {
+    let kv_store = new_kv_store();
+    let result = process_zmpop(&parts(&["ZMPOP", "1", "nokey", "MIN"]), &kv_store).unwrap();
+    assert_eq!(result, b"*-1\r\n");
+}
+
+#[tokio::test]
+async fn test_zpop_family_wrong_type() {
+    let kv_store = new_kv_store();
+    process_sadd(&parts(&["SADD", "myset", "a"]), &kv_store).unwrap();
+    assert_eq!(process_zpopmin(&parts(&["ZPOPMIN", "myset"]), &kv_store).unwrap(), WRONGTYPE);
+    assert_eq!(process_zmpop(&parts(&["ZMPOP", "1", "myset", "MAX"]), &kv_store).unwrap(), WRONGTYPE);
+    let result = process_bzpopmin(&parts(&["BZPOPMIN", "myset", "0"]), &kv_store, &new_waiting_room()).await;
+    assert_eq!(result.unwrap(), WRONGTYPE);
+    let result = process_bzmpop(&parts(&["BZMPOP", "0", "1", "myset", "MIN"]), &kv_store, &new_waiting_room()).await;
+    assert_eq!(result.unwrap(), WRONGTYPE);
+}
+
+#[test]
+fn test_zmpop_invalid_arguments() {
+    let kv_store = new_kv_store();
+    let bad_numkeys = process_zmpop(&parts(&["ZMPOP", "0", "k", "MIN"]), &kv_store).unwrap();
+    assert!(bad_numkeys.starts_with(b"-ERR numkeys should be greater than 0"));
+    let bad_side = process_zmpop(&parts(&["ZMPOP", "1", "k", "MIDDLE"]), &kv_store).unwrap();
+    assert!(bad_side.starts_with(b"-ERR syntax error"));
+    let bad_count = process_zmpop(&parts(&["ZMPOP", "1", "k", "MIN", "COUNT", "0"]), &kv_store).unwrap();
+    assert!(bad_count.starts_with(b"-ERR count should be greater than 0"));
+    let too_few_keys = process_zmpop(&parts(&["ZMPOP", "3", "k", "MIN"]), &kv_store).unwrap();
+    assert!(too_few_keys.starts_with(b"-ERR syntax error"));
+    let huge_numkeys = process_zmpop(&parts(&["ZMPOP", "18446744073709551615", "k", "MIN"]), &kv_store).unwrap();
+    assert!(huge_numkeys.starts_with(b"-ERR syntax error"));
+}
+
+#[tokio::test]
+async fn test_bzmpop_woken_by_zadd() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+
+    let kv_clone = Arc::clone(&kv_store);
+    let room_clone = Arc::clone(&waiting_room);
+    let handle = tokio::spawn(async move {
+        let p = parts(&["BZMPOP", "5", "2", "z1", "z2", "MAX", "COUNT", "5"]);
+        process_bzmpop(&p, &kv_clone, &room_clone).await
+    });
+
+    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    process_zadd(&parts(&["ZADD", "z2", "1", "x", "2", "y"]), &kv_store, &waiting_room).unwrap();
+
+    let result = handle.await.unwrap().unwrap();
+    assert_eq!(
+        result,
+        b"*2\r\n$2\r\nz2\r\n*2\r\n*2\r\n$1\r\ny\r\n$1\r\n2\r\n*2\r\n$1\r\nx\r\n$1\r\n1\r\n"
+    );
+}
+
+#[tokio::test]
+async fn test_bzmpop_timeout() {
+    let kv_store = new_kv_store();
+    let waiting_room = new_waiting_room();
+    let p = parts(&["BZMPOP", "0.1", "1", "nokey", "MIN"]);
+    assert_eq!(process_bzmpop(&p, &kv_store, &waiting_room).await.unwrap(), b"*-1\r\n");
+}
+
+// ==================== ZUNION / ZINTER / ZDIFF Tests ====================
+
+// z1 = {a:1, b:2, c:3}, z2 = {b:10, c:20, d:30}
+fn seed_two_zsets(kv_store: &KvStore) {
+    process_zadd(&parts(&["ZADD", "z1", "1", "a", "2", "b", "3", "c"]), kv_store, &new_waiting_room()).unwrap();
+    process_zadd(&parts(&["ZADD", "z2", "10", "b", "20", "c", "30", "d"]), kv_store, &new_waiting_room()).unwrap();
+}
+
+#[test]
+fn test_zunion_sums_scores() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+
+    let result = process_zunion(&parts(&["ZUNION", "2", "z1", "z2", "WITHSCORES"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["a", "1", "b", "12", "c", "23", "d", "30"]);
+}
+
+#[test]
+fn test_zunion_weights_and_aggregate() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+
+    let p = parts(&["ZUNION", "2", "z1", "z2", "WEIGHTS", "10", "1", "AGGREGATE", "MIN", "WITHSCORES"]);
+    let result = process_zunion(&p, &kv_store).unwrap();
+    // Ties on score fall back to member order
+    assert_eq!(elements(&result), vec!["a", "10", "b", "10", "c", "20", "d", "30"]);
+
+    let max = parts(&["ZUNION", "2", "z1", "z2", "AGGREGATE", "max"]);
+    assert_eq!(elements(&process_zunion(&max, &kv_store).unwrap()), vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn test_zunion_missing_keys_and_plain_sets() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+    process_sadd(&parts(&["SADD", "plain", "a", "z"]), &kv_store).unwrap();
+
+    let p = parts(&["ZUNION", "3", "z1", "nokey", "plain", "WITHSCORES"]);
+    let result = process_zunion(&p, &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["z", "1", "a", "2", "b", "2", "c", "3"]);
+}
+
+#[test]
+fn test_zinter() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+
+    let result = process_zinter(&parts(&["ZINTER", "2", "z1", "z2", "WITHSCORES"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["b", "12", "c", "23"]);
+    let max = process_zinter(&parts(&["ZINTER", "2", "z1", "z2", "AGGREGATE", "MAX"]), &kv_store).unwrap();
+    assert_eq!(elements(&max), vec!["b", "c"]);
+    let with_missing = process_zinter(&parts(&["ZINTER", "2", "z1", "nokey"]), &kv_store).unwrap();
+    assert_eq!(with_missing, b"*0\r\n");
+}
+
+#[test]
+fn test_zdiff() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+
+    let result = process_zdiff(&parts(&["ZDIFF", "2", "z1", "z2", "WITHSCORES"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["a", "1"]);
+    let first_missing = process_zdiff(&parts(&["ZDIFF", "2", "nokey", "z1"]), &kv_store).unwrap();
+    assert_eq!(first_missing, b"*0\r\n");
+}
+
+#[test]
+fn test_zset_op_invalid_arguments() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+
+    let zero = process_zunion(&parts(&["ZUNION", "0", "z1"]), &kv_store).unwrap();
+    assert!(zero.starts_with(b"-ERR at least 1 input key is needed for 'zunion' command"));
+    let short_weights = process_zunion(&parts(&["ZUNION", "2", "z1", "z2", "WEIGHTS", "1"]), &kv_store).unwrap();
+    assert!(short_weights.starts_with(b"-ERR syntax error"));
+    let bad_weight = process_zinter(&parts(&["ZINTER", "2", "z1", "z2", "WEIGHTS", "1", "x"]), &kv_store).unwrap();
+    assert!(bad_weight.starts_with(b"-ERR weight value is not a float"));
+    let bad_aggregate = process_zunion(&parts(&["ZUNION", "1", "z1", "AGGREGATE", "AVG"]), &kv_store).unwrap();
+    assert!(bad_aggregate.starts_with(b"-ERR syntax error"));
+    let diff_weights = process_zdiff(&parts(&["ZDIFF", "1", "z1", "WEIGHTS", "2"]), &kv_store).unwrap();
+    assert!(diff_weights.starts_with(b"-ERR syntax error"));
+}
+
+#[test]
+fn test_zset_op_wrong_type() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+    kv_store.insert("str".to_string(), RedisValue::new(RedisData::String("v".into()), None));
+
+    assert_eq!(process_zunion(&parts(&["ZUNION", "2", "z1", "str"]), &kv_store).unwrap(), WRONGTYPE);
+    assert_eq!(process_zintercard(&parts(&["ZINTERCARD", "2", "z1", "str"]), &kv_store).unwrap(), WRONGTYPE);
+    let store = process_zunionstore(&parts(&["ZUNIONSTORE", "dest", "2", "z1", "str"]), &kv_store, &new_waiting_room());
+    assert_eq!(store.unwrap(), WRONGTYPE);
+}
+
+// ==================== ZUNIONSTORE / ZINTERSTORE / ZDIFFSTORE Tests ====================
+
+#[test]
+fn test_zunionstore() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+    let waiting_room = new_waiting_room();
+
+    let p = parts(&["ZUNIONSTORE", "dest", "2", "z1", "z2", "WEIGHTS", "2", "1"]);
+    assert_eq!(process_zunionstore(&p, &kv_store, &waiting_room).unwrap(), b":4\r\n");
+    let stored = process_zrange(&parts(&["ZRANGE", "dest", "0", "-1", "WITHSCORES"]), &kv_store).unwrap();
+    assert_eq!(elements(&stored), vec!["a", "2", "b", "14", "c", "26", "d", "30"]);
+}
+
+#[test]
+fn test_zinterstore_overwrites_destination() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+    let waiting_room = new_waiting_room();
+
+    // The destination may also be an input
+    let p = parts(&["ZINTERSTORE", "z1", "2", "z1", "z2"]);
+    assert_eq!(process_zinterstore(&p, &kv_store, &waiting_room).unwrap(), b":2\r\n");
+    let stored = process_zrange(&parts(&["ZRANGE", "z1", "0", "-1", "WITHSCORES"]), &kv_store).unwrap();
+    assert_eq!(elements(&stored), vec!["b", "12", "c", "23"]);
+}
+
+#[test]
+fn test_zdiffstore_empty_result_deletes_destination() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+    let waiting_room = new_waiting_room();
+    process_zadd(&parts(&["ZADD", "dest", "1", "old"]), &kv_store, &waiting_room).unwrap();
+
+    let p = parts(&["ZDIFFSTORE", "dest", "2", "z1", "z1"]);
+    assert_eq!(process_zdiffstore(&p, &kv_store, &waiting_room).unwrap(), b":0\r\n");
+    assert!(!kv_store.contains_key("dest"));
+}
+
+#[test]
+fn test_zstore_rejects_withscores() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+    let p = parts(&["ZUNIONSTORE", "dest", "1", "z1", "WITHSCORES"]);
+    let result = process_zunionstore(&p, &kv_store, &new_waiting_room()).unwrap();
+    assert!(result.starts_with(b"-ERR syntax error"));
+}
+
+#[tokio::test]
+async fn test_zunionstore_wakes_bzpopmin() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+    let waiting_room = new_waiting_room();
+
+    let kv_clone = Arc::clone(&kv_store);
+    let room_clone = Arc::clone(&waiting_room);
+    let handle = tokio::spawn(async move {
+        process_bzpopmin(&parts(&["BZPOPMIN", "dest", "5"]), &kv_clone, &room_clone).await
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+    process_zunionstore(&parts(&["ZUNIONSTORE", "dest", "1", "z1"]), &kv_store, &waiting_room).unwrap();
+
+    let result = handle.await.unwrap().unwrap();
+    assert_eq!(elements(&result), vec!["dest", "a", "1"]);
+}
+
+// ==================== ZINTERCARD Tests ====================
+
+#[test]
+fn test_zintercard() {
+    let kv_store = new_kv_store();
+    seed_two_zsets(&kv_store);
+
+    let result = process_zintercard(&parts(&["ZINTERCARD", "2", "z1", "z2"]), &kv_store).unwrap();
+    assert_eq!(result, b":2\r\n");
+    let limited = process_zintercard(&parts(&["ZINTERCARD", "2", "z1", "z2", "LIMIT", "1"]), &kv_store).unwrap();
+    assert_eq!(limited, b":1\r\n");
+    let missing = process_zintercard(&parts(&["ZINTERCARD", "2", "z1", "nokey"]), &kv_store).unwrap();
+    assert_eq!(missing, b":0\r\n");
+}
+
+#[test]
+fn test_zintercard_invalid_arguments() {
+    let kv_store = new_kv_store();
+    let zero = process_zintercard(&parts(&["ZINTERCARD", "0", "z1"]), &kv_store).unwrap();
+    assert!(zero.starts_with(b"-ERR numkeys should be greater than 0"));
+    let too_many = process_zintercard(&parts(&["ZINTERCARD", "3", "z1"]), &kv_store).unwrap();
+    assert!(too_many.starts_with(b"-ERR Number of keys can't be greater than number of args"));
+    let huge = process_zintercard(&parts(&["ZINTERCARD", "18446744073709551615", "k"]), &kv_store).unwrap();
+    assert_eq!(huge, b"-ERR Number of keys can't be greater than number of args\r\n");
+    let negative = process_zintercard(&parts(&["ZINTERCARD", "1", "z1", "LIMIT", "-1"]), &kv_store).unwrap();
+    assert!(negative.starts_with(b"-ERR LIMIT can't be negative"));
+}
+
+
+// ==================== ZRANDMEMBER Tests ====================
+
+#[test]
+fn test_zrandmember_single() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zrandmember(&parts(&["ZRANDMEMBER", "myzset"]), &kv_store).unwrap();
+    let replies: Vec<&[u8]> = vec![b"$1\r\na\r\n", b"$1\r\nb\r\n", b"$1\r\nc\r\n", b"$1\r\nd\r\n"];
+    assert!(replies.contains(&result.as_slice()));
+    // Nothing is removed
+    let card = process_zcard(&parts(&["ZCARD", "myzset"]), &kv_store).unwrap();
+    assert_eq!(card, b":4\r\n");
+}
+
+#[test]
+fn test_zrandmember_positive_count_is_distinct() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zrandmember(&parts(&["ZRANDMEMBER", "myzset", "10"]), &kv_store).unwrap();
+    let mut members = elements(&result);
+    members.sort();
+    assert_eq!(members, vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn test_zrandmember_negative_count_may_repeat() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "only"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrandmember(&parts(&["ZRANDMEMBER", "myzset", "-3"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["only", "only", "only"]);
+}
+
+#[test]
+fn test_zrandmember_withscores() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1.5", "a"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zrandmember(&parts(&["ZRANDMEMBER", "myzset", "1", "WITHSCORES"]), &kv_store).unwrap();
+    assert_eq!(elements(&result), vec!["a", "1.5"]);
+}
+
+#[test]
+fn test_zrandmember_missing_key() {
+    let kv_store = new_kv_store();
+    let single = process_zrandmember(&parts(&["ZRANDMEMBER", "nokey"]), &kv_store).unwrap();
+    assert_eq!(single, b"$-1\r\n");
+    let counted = process_zrandmember(&parts(&["ZRANDMEMBER", "nokey", "-2"]), &kv_store).unwrap();
+    assert_eq!(counted, b"*0\r\n");
+}
+
+#[test]
+fn test_zrandmember_wrong_type() {
+    let kv_store = new_kv_store();
+    process_sadd(&parts(&["SADD", "myset", "a"]), &kv_store).unwrap();
+    assert_eq!(process_zrandmember(&parts(&["ZRANDMEMBER", "myset"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+#[test]
+fn test_zrandmember_invalid_arguments() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+    let count = process_zrandmember(&parts(&["ZRANDMEMBER", "myzset", "x"]), &kv_store).unwrap();
+    assert!(count.starts_with(b"-ERR value is not an integer"));
+    let option = process_zrandmember(&parts(&["ZRANDMEMBER", "myzset", "1", "WITHVALUES"]), &kv_store).unwrap();
+    assert!(option.starts_with(b"-ERR syntax error"));
+}
+
+#[test]
+fn test_zrandmember_rejects_huge_counts() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+    for count in ["-9223372036854775808", "9223372036854775807"] {
+        let result = process_zrandmember(&parts(&["ZRANDMEMBER", "myzset", count]), &kv_store).unwrap();
+        assert_eq!(result, b"-ERR value is out of range\r\n");
+    }
+}
+
+// ==================== ZSCAN Tests ====================
+
+#[test]
+fn test_zscan_returns_member_score_pairs() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zscan(&parts(&["ZSCAN", "myzset", "0"]), &kv_store).unwrap();
+    assert!(result.starts_with(b"*2\r\n$1\r\n0\r\n*8\r\n"));
+    let lines: Vec<String> = elements(&result).into_iter().filter(|l| !l.starts_with('*')).collect();
+    assert_eq!(lines, vec!["0", "a", "1", "b", "2", "c", "3", "d", "4"]);
+}
+
+#[test]
+fn test_zscan_match_pattern() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "one", "2", "two", "3", "three"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_zscan(&parts(&["ZSCAN", "myzset", "0", "MATCH", "t*", "COUNT", "5"]), &kv_store).unwrap();
+    let lines: Vec<String> = elements(&result).into_iter().filter(|l| !l.starts_with('*')).collect();
+    assert_eq!(lines, vec!["0", "two", "2", "three", "3"]);
+}
+
+#[test]
+fn test_zscan_missing_key_and_wrong_type() {
+    let kv_store = new_kv_store();
+    let empty = process_zscan(&parts(&["ZSCAN", "nokey", "0"]), &kv_store).unwrap();
+    assert_eq!(empty, b"*2\r\n$1\r\n0\r\n*0\r\n");
+    let cursor = process_zscan(&parts(&["ZSCAN", "nokey", "abc"]), &kv_store).unwrap();
+    assert!(cursor.starts_with(b"-ERR invalid cursor"));
+
+    process_sadd(&parts(&["SADD", "myset", "a"]), &kv_store).unwrap();
+    assert_eq!(process_zscan(&parts(&["ZSCAN", "myset", "0"]), &kv_store).unwrap(), WRONGTYPE);
+}
+
+// ==================== ZMSCORE Tests ====================
+
+#[test]
+fn test_zmscore_mixed_members() {
+    let kv_store = new_kv_store();
+    seed_zset(&kv_store);
+
+    let result = process_zmscore(&parts(&["ZMSCORE", "myzset", "a", "nope", "d"]), &kv_store).unwrap();
+    assert_eq!(result, b"*3\r\n$1\r\n1\r\n$-1\r\n$1\r\n4\r\n");
+}
+
+#[test]
+fn test_zmscore_missing_key() {
+    let kv_store = new_kv_store();
+    let result = process_zmscore(&parts(&["ZMSCORE", "nokey", "a", "b"]), &kv_store).unwrap();
+    assert_eq!(result, b"*2\r\n$-1\r\n$-1\r\n");
+}
+
+#[test]
+fn test_zmscore_wrong_type() {
+    let kv_store = new_kv_store();
+    process_sadd(&parts(&["SADD", "myset", "a"]), &kv_store).unwrap();
+    assert_eq!(process_zmscore(&parts(&["ZMSCORE", "myset", "a"]), &kv_store).unwrap(), WRONGTYPE);
+    assert!(process_zmscore(&parts(&["ZMSCORE", "myset"]), &kv_store).is_err());
+}
+
+// ==================== TYPE Tests ====================
+
+#[test]
+fn test_type_zset() {
+    let kv_store = new_kv_store();
+    process_zadd(&parts(&["ZADD", "myzset", "1", "one"]), &kv_store, &new_waiting_room()).unwrap();
+
+    let result = process_type(&parts(&["TYPE", "myzset"]), &kv_store);
+    assert_eq!(result.unwrap(), b"+zset\r\n");
+}